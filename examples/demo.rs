@@ -9,8 +9,8 @@ use {
     reedline::{
         default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
         ColumnarMenu, DefaultCompleter, DefaultHinter, DefaultPrompt, DefaultValidator,
-        EditCommand, EditMode, Emacs, ExampleHighlighter, Keybindings, ListMenu, Reedline,
-        ReedlineEvent, ReedlineMenu, Signal, Vi,
+        EditCommand, EditMode, Emacs, ExampleHighlighter, Keybindings, ListMenu, MenuSort,
+        Reedline, ReedlineEvent, ReedlineMenu, Signal, Vi,
     },
 };
 
@@ -99,7 +99,9 @@ fn main() -> std::io::Result<()> {
     // Adding default menus for the compiled reedline
     line_editor = line_editor
         .with_menu(ReedlineMenu::EngineCompleter(Box::new(
-            ColumnarMenu::default().with_name("completion_menu"),
+            ColumnarMenu::default()
+                .with_name("completion_menu")
+                .with_sort_orders(vec![MenuSort::None, MenuSort::Alphabetical]),
         )))
         .with_menu(ReedlineMenu::HistoryMenu(Box::new(
             ListMenu::default().with_name("history_menu"),
@@ -241,10 +243,17 @@ fn add_menu_keybindings(keybindings: &mut Keybindings) {
         ReedlineEvent::MenuPagePrevious,
     );
 
+    keybindings.add_binding(
+        KeyModifiers::ALT,
+        KeyCode::Char('s'),
+        ReedlineEvent::MenuToggleSort,
+    );
+
     keybindings.add_binding(
         KeyModifiers::NONE,
         KeyCode::Tab,
         ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::NextTabStop,
             ReedlineEvent::Menu("completion_menu".to_string()),
             ReedlineEvent::Edit(vec![EditCommand::Complete]),
         ]),
@@ -253,7 +262,10 @@ fn add_menu_keybindings(keybindings: &mut Keybindings) {
     keybindings.add_binding(
         KeyModifiers::SHIFT,
         KeyCode::BackTab,
-        ReedlineEvent::MenuPrevious,
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::MenuPrevious,
+            ReedlineEvent::PrevTabStop,
+        ]),
     );
 }
 
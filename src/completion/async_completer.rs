@@ -0,0 +1,210 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{utils::polling::poll_once, Completer, Span, Suggestion};
+
+/// Future returned by an [`AsyncCompleter`] that resolves to the suggestions
+pub type CompletionFuture = Pin<Box<dyn Future<Output = Vec<Suggestion>> + Send>>;
+
+/// A trait that defines how to compute completions without blocking the editor.
+///
+/// The returned future is polled by [`Reedline`](crate::Reedline) between key
+/// presses. While the future is pending the active menu keeps showing the previous
+/// values together with a spinner, and the new values are swapped in once the
+/// future resolves.
+///
+/// The waker given to the future is not used to schedule the polling, so the
+/// work has to progress on its own, e.g. in a thread or in an async runtime.
+///
+/// # Cancellation
+/// If the user keeps typing before the future resolves, the pending future is
+/// dropped and a new one is requested for the updated line. Dropping the future
+/// is the only cancellation signal, so implementations that spawn work should
+/// stop it when their future is dropped.
+pub trait AsyncCompleter: Send {
+    /// Starts computing the completions for the line at the given position
+    fn complete(&mut self, line: &str, pos: usize) -> CompletionFuture;
+}
+
+/// Bridge that lets a synchronous [`Completer`] be used as an [`AsyncCompleter`].
+///
+/// The completions are computed when the future is requested, so the completer
+/// still blocks the editor while it runs.
+pub struct BlockingCompleter<T: Completer> {
+    completer: T,
+}
+
+impl<T: Completer> BlockingCompleter<T> {
+    /// Wraps the synchronous completer
+    pub fn new(completer: T) -> Self {
+        Self { completer }
+    }
+}
+
+impl<T: Completer> AsyncCompleter for BlockingCompleter<T> {
+    fn complete(&mut self, line: &str, pos: usize) -> CompletionFuture {
+        Box::pin(std::future::ready(self.completer.complete(line, pos)))
+    }
+}
+
+struct PendingCompletion {
+    line: String,
+    pos: usize,
+    future: CompletionFuture,
+}
+
+// The PollingCompleter is used by the engine to present an AsyncCompleter to the
+// menus as a regular Completer. The futures are polled every time the menu asks
+// for values and the latest resolved values are returned while they are pending
+pub(crate) struct PollingCompleter {
+    completer: Box<dyn AsyncCompleter>,
+    pending: Option<PendingCompletion>,
+    resolved: Option<(String, usize)>,
+    values: Vec<Suggestion>,
+}
+
+impl PollingCompleter {
+    pub fn new(completer: Box<dyn AsyncCompleter>) -> Self {
+        Self {
+            completer,
+            pending: None,
+            resolved: None,
+            values: Vec::new(),
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(mut pending) = self.pending.take() {
+            match poll_once(pending.future.as_mut()) {
+                Some(values) => {
+                    self.values = values;
+                    self.resolved = Some((pending.line, pending.pos));
+                }
+                None => self.pending = Some(pending),
+            }
+        }
+    }
+}
+
+impl Completer for PollingCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let is_resolved = self
+            .resolved
+            .as_ref()
+            .map_or(false, |(resolved, resolved_pos)| {
+                resolved == line && *resolved_pos == pos
+            });
+
+        if is_resolved {
+            return self.values.clone();
+        }
+
+        let is_pending = self
+            .pending
+            .as_ref()
+            .map_or(false, |pending| pending.line == line && pending.pos == pos);
+
+        // A request for a different line replaces (and with it cancels) the pending one
+        if !is_pending {
+            self.pending = Some(PendingCompletion {
+                line: line.to_string(),
+                pos,
+                future: self.completer.complete(line, pos),
+            });
+        }
+
+        self.poll();
+
+        // The stale values were computed for another line, so their spans are
+        // moved to the current position to keep them inside the buffer
+        let is_stale = self.pending.is_some();
+        self.values
+            .iter()
+            .cloned()
+            .map(|mut suggestion| {
+                if is_stale {
+                    suggestion.span = Span::new(suggestion.span.start.min(pos), pos);
+                }
+                suggestion
+            })
+            .collect()
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::polling::Delayed, DefaultCompleter};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct SlowCompleter {
+        polls: usize,
+        drops: Arc<AtomicUsize>,
+    }
+
+    impl AsyncCompleter for SlowCompleter {
+        fn complete(&mut self, line: &str, _pos: usize) -> CompletionFuture {
+            let value = Suggestion::new(line.to_uppercase(), Span::new(0, 0));
+            Box::pin(Delayed::new(self.polls, vec![value]).with_drops(self.drops.clone()))
+        }
+    }
+
+    fn slow_completer(polls: usize) -> (PollingCompleter, Arc<AtomicUsize>) {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let completer = PollingCompleter::new(Box::new(SlowCompleter {
+            polls,
+            drops: drops.clone(),
+        }));
+
+        (completer, drops)
+    }
+
+    fn values(completer: &mut PollingCompleter, line: &str) -> Vec<String> {
+        completer
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[test]
+    fn blocking_completer_resolves_immediately() {
+        let completer = DefaultCompleter::new_with_wordlen(vec!["hello".into()], 2);
+        let mut completer = PollingCompleter::new(Box::new(BlockingCompleter::new(completer)));
+
+        assert_eq!(values(&mut completer, "he"), vec!["hello"]);
+        assert!(!completer.is_pending());
+    }
+
+    #[test]
+    fn stale_values_are_returned_while_pending() {
+        let (mut completer, _) = slow_completer(2);
+
+        assert!(values(&mut completer, "a").is_empty());
+        assert!(completer.is_pending());
+        assert!(values(&mut completer, "a").is_empty());
+        assert_eq!(values(&mut completer, "a"), vec!["A"]);
+        assert!(!completer.is_pending());
+
+        assert_eq!(values(&mut completer, "ab"), vec!["A"]);
+        assert!(completer.is_pending());
+    }
+
+    #[test]
+    fn new_request_cancels_pending_future() {
+        let (mut completer, drops) = slow_completer(5);
+
+        values(&mut completer, "a");
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        values(&mut completer, "ab");
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert!(completer.is_pending());
+    }
+}
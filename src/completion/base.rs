@@ -1,3 +1,5 @@
+use crate::StyledText;
+
 /// A span of source code, with positions in bytes
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Span {
@@ -22,6 +24,15 @@ impl Span {
 
         Span { start, end }
     }
+
+    /// The span limited to a buffer of `len` bytes, a span past the end becomes empty
+    /// at the end of the buffer
+    pub fn clamp_to(self, len: usize) -> Span {
+        Span {
+            start: self.start.min(len),
+            end: self.end.min(len),
+        }
+    }
 }
 
 /// A trait that defines how to convert some text and a position to a list of potential completions in that position.
@@ -49,8 +60,26 @@ pub trait Completer: Send {
     }
 
     /// number of available completions
-    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
-        self.complete(line, pos).len()
+    /// Completers that can't count their values cheaply return `None`, and the
+    /// menus keep pulling values until `partial_complete` runs out of them
+    fn total_completions(&mut self, line: &str, pos: usize) -> Option<usize> {
+        Some(self.complete(line, pos).len())
+    }
+
+    /// Completers that compute their values in the background return true while
+    /// the values of the last request are not available yet
+    fn is_pending(&self) -> bool {
+        false
+    }
+
+    /// Called after a suggestion of this completer was selected in a menu and applied to
+    /// the buffer, with the `line` as it is after the replacement. Completers can use it
+    /// to learn which suggestions are used, e.g. to rank them
+    fn on_accept(
+        &mut self,
+        #[allow(unused_variables)] accepted: &Suggestion,
+        #[allow(unused_variables)] line: &str,
+    ) {
     }
 }
 
@@ -59,14 +88,153 @@ pub trait Completer: Send {
 pub struct Suggestion {
     /// String replacement that will be introduced to the the buffer
     pub value: String,
-    /// Optional description for the replacement
-    pub description: Option<String>,
+    /// Optional description for the replacement, see [`Description`]
+    pub description: Option<Description>,
     /// Optional vector of strings in the suggestion. These can be used to
     /// represent examples coming from a suggestion
     pub extra: Option<Vec<String>>,
+    /// Optional glyph shown before the value, e.g. to indicate the type of the
+    /// suggestion. It is only displayed by menus with the glyph column enabled
+    pub glyph: Option<String>,
+    /// Optional name of the source of the suggestion, e.g. `files` or `commands`. The
+    /// [`ListMenu`](crate::ListMenu) can count the values of each source in its banner
+    pub source: Option<String>,
     /// Replacement span in the buffer
     pub span: Span,
     /// Whether to append a space after selecting this suggestion.
     /// This helps to avoid that a completer repeats the complete suggestion.
     pub append_whitespace: bool,
+    /// Text inserted instead of the value when the suggestion is accepted, with tab
+    /// stops written `$1` or `${1:placeholder}`. The cursor is put on the first
+    /// placeholder and [`ReedlineEvent::NextTabStop`](crate::ReedlineEvent::NextTabStop)
+    /// moves to the next one, typing replaces the placeholder. `$0` is the final cursor
+    /// position, by default the end of the snippet, and `\$` is a literal `$`
+    pub snippet: Option<String>,
+    /// Position of the cursor after the suggestion is accepted, in bytes from the start
+    /// of the inserted value, e.g. between the parentheses of `print()`. By default the
+    /// cursor is put after the value and the appended whitespace. It is ignored for a
+    /// [`Suggestion::snippet`], whose tab stops place the cursor
+    pub cursor_offset: Option<usize>,
+    /// Other spans of the buffer replaced together with the span of the value, e.g.
+    /// to correct the start of a path while completing its end. Edits overlapping
+    /// each other or the span of the value are dropped
+    pub extra_edits: Option<Vec<(Span, String)>>,
+}
+
+impl Suggestion {
+    /// Suggestion replacing the `span` of the buffer by the `value`
+    pub fn new(value: impl Into<String>, span: Span) -> Self {
+        Self {
+            value: value.into(),
+            span,
+            ..Self::default()
+        }
+    }
+
+    /// Builder setting the [`Suggestion::description`]
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<Description>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Builder setting the [`Suggestion::extra`] values
+    #[must_use]
+    pub fn with_extra(mut self, extra: Vec<String>) -> Self {
+        self.extra = Some(extra);
+        self
+    }
+
+    /// Builder setting the [`Suggestion::glyph`]
+    #[must_use]
+    pub fn with_glyph(mut self, glyph: impl Into<String>) -> Self {
+        self.glyph = Some(glyph.into());
+        self
+    }
+
+    /// Builder setting the [`Suggestion::source`]
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Builder setting whether a space is appended, see [`Suggestion::append_whitespace`]
+    #[must_use]
+    pub fn with_append_whitespace(mut self, append_whitespace: bool) -> Self {
+        self.append_whitespace = append_whitespace;
+        self
+    }
+
+    /// Builder setting the [`Suggestion::snippet`] inserted instead of the value
+    #[must_use]
+    pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
+        self.snippet = Some(snippet.into());
+        self
+    }
+
+    /// Builder setting the [`Suggestion::cursor_offset`]
+    #[must_use]
+    pub fn with_cursor_offset(mut self, cursor_offset: usize) -> Self {
+        self.cursor_offset = Some(cursor_offset);
+        self
+    }
+
+    /// Builder setting the [`Suggestion::extra_edits`]
+    #[must_use]
+    pub fn with_extra_edits(mut self, extra_edits: Vec<(Span, String)>) -> Self {
+        self.extra_edits = Some(extra_edits);
+        self
+    }
+}
+
+/// Description of a [`Suggestion`]
+///
+/// The description panes of the menus paint the lines of the description wrapped to
+/// their width, the menus showing the description next to the value join its lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Description {
+    /// Text painted in the description style of the menu
+    Text(String),
+    /// Text keeping its styles, the parts with the default style are painted in the
+    /// description style of the menu
+    Styled(StyledText),
+}
+
+impl Description {
+    /// The text of the description without its styles
+    pub fn text(&self) -> String {
+        match self {
+            Description::Text(text) => text.clone(),
+            Description::Styled(styled_text) => styled_text.raw_string(),
+        }
+    }
+
+    /// Whether the description has no text
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Description::Text(text) => text.is_empty(),
+            Description::Styled(styled_text) => {
+                styled_text.buffer.iter().all(|(_, text)| text.is_empty())
+            }
+        }
+    }
+}
+
+impl From<String> for Description {
+    fn from(text: String) -> Self {
+        Description::Text(text)
+    }
+}
+
+impl From<&str> for Description {
+    fn from(text: &str) -> Self {
+        Description::Text(text.to_string())
+    }
+}
+
+impl From<StyledText> for Description {
+    fn from(styled_text: StyledText) -> Self {
+        Description::Styled(styled_text)
+    }
 }
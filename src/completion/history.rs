@@ -33,7 +33,7 @@ impl<'menu> Completer for HistoryCompleter<'menu> {
 
     // TODO: Implement `fn partial_complete()`
 
-    fn total_completions(&mut self, line: &str, _pos: usize) -> usize {
+    fn total_completions(&mut self, line: &str, _pos: usize) -> Option<usize> {
         let parsed = parse_selection_char(line, SELECTION_CHAR);
         let count = self
             .0
@@ -41,7 +41,7 @@ impl<'menu> Completer for HistoryCompleter<'menu> {
                 parsed.remainder.to_string(),
             ))
             .expect("todo: error handling");
-        count as usize
+        Some(count as usize)
     }
 }
 
@@ -62,6 +62,7 @@ impl<'menu> HistoryCompleter<'menu> {
             extra: None,
             span,
             append_whitespace: false,
+            ..Suggestion::default()
         }
     }
 }
@@ -1,6 +1,9 @@
+mod async_completer;
 mod base;
 mod default;
 pub(crate) mod history;
 
-pub use base::{Completer, Span, Suggestion};
+pub(crate) use async_completer::PollingCompleter;
+pub use async_completer::{AsyncCompleter, BlockingCompleter, CompletionFuture};
+pub use base::{Completer, Description, Span, Suggestion};
 pub use default::DefaultCompleter;
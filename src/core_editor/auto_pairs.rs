@@ -0,0 +1,44 @@
+/// Pairs of characters the [`Editor`](crate::Editor) inserts together, see
+/// [`Reedline::with_auto_pairs`](crate::Reedline::with_auto_pairs)
+///
+/// Typing the opening character also inserts the closing one behind the cursor,
+/// typing the closing character in front of it just moves over it. An opening
+/// character typed with an active selection wraps the selection in the pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoPairConfig {
+    pairs: Vec<(char, char)>,
+}
+
+impl Default for AutoPairConfig {
+    /// Brackets and quotes: `()`, `[]`, `{}`, `""`, `''` and ` `` `
+    fn default() -> Self {
+        AutoPairConfig::new(vec![
+            ('(', ')'),
+            ('[', ']'),
+            ('{', '}'),
+            ('"', '"'),
+            ('\'', '\''),
+            ('`', '`'),
+        ])
+    }
+}
+
+impl AutoPairConfig {
+    /// Create a configuration with the `(open, close)` pairs
+    pub fn new(pairs: Vec<(char, char)>) -> Self {
+        AutoPairConfig { pairs }
+    }
+
+    /// The closing character of the pair opened by `c`
+    pub fn closing_char(&self, c: char) -> Option<char> {
+        self.pairs
+            .iter()
+            .find(|(open, _)| *open == c)
+            .map(|(_, close)| *close)
+    }
+
+    /// Whether `c` closes one of the pairs
+    pub fn is_closing_char(&self, c: char) -> bool {
+        self.pairs.iter().any(|(_, close)| *close == c)
+    }
+}
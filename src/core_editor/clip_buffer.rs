@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 /// Defines an interface to interact with a Clipboard for cut and paste.
 ///
 /// Mutable reference requirements are stricter than always necessary, but the currently used system clipboard API demands them for exclusive access.
@@ -13,6 +15,36 @@ pub trait Clipboard: Send {
     fn len(&mut self) -> usize {
         self.get().0.len()
     }
+
+    /// Store the text cut from the buffer on the given side of the insertion point.
+    ///
+    /// `consecutive` is set if the previous command did a cut in the same direction.
+    /// By default the content is replaced like with [`Clipboard::set`].
+    fn cut(
+        &mut self,
+        content: &str,
+        mode: ClipboardMode,
+        _direction: CutDirection,
+        _consecutive: bool,
+    ) {
+        self.set(content, mode);
+    }
+
+    /// Switch to the entry before the one returned by [`Clipboard::get`] and return its text.
+    ///
+    /// Returns `None` for clipboards holding a single entry.
+    fn rotate(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Side of the insertion point the text of a cut was removed from
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CutDirection {
+    /// The text was left of the insertion point
+    Left,
+    /// The text was right of the insertion point
+    Right,
 }
 
 /// Determines how the content in the clipboard should be inserted
@@ -50,6 +82,74 @@ impl Clipboard for LocalClipboard {
     }
 }
 
+/// Clipboard keeping the last entries that were cut or copied like the emacs kill ring.
+///
+/// Consecutive cuts in the same direction are combined into a single entry and
+/// [`Clipboard::rotate`] cycles through the older entries.
+pub struct KillRing {
+    entries: VecDeque<(String, ClipboardMode)>,
+    capacity: usize,
+    yank_index: usize,
+}
+
+impl KillRing {
+    /// Create a kill ring holding up to `capacity` entries (at least one)
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        KillRing {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            yank_index: 0,
+        }
+    }
+}
+
+impl Clipboard for KillRing {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        self.entries.push_front((content.to_owned(), mode));
+        self.entries.truncate(self.capacity);
+        self.yank_index = 0;
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        self.entries
+            .get(self.yank_index)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.yank_index = 0;
+    }
+
+    fn cut(
+        &mut self,
+        content: &str,
+        mode: ClipboardMode,
+        direction: CutDirection,
+        consecutive: bool,
+    ) {
+        match self.entries.front_mut() {
+            Some((previous, _)) if consecutive => match direction {
+                CutDirection::Left => previous.insert_str(0, content),
+                CutDirection::Right => previous.push_str(content),
+            },
+            _ => self.set(content, mode),
+        }
+        self.yank_index = 0;
+    }
+
+    fn rotate(&mut self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.yank_index = (self.yank_index + 1) % self.entries.len();
+
+        Some(self.get().0)
+    }
+}
+
 #[cfg(feature = "system_clipboard")]
 pub use system_clipboard::SystemClipboard;
 
@@ -78,18 +178,23 @@ mod system_clipboard {
     use super::*;
     use arboard::Clipboard as Arboard;
 
-    /// Wrapper around [`clipboard`](https://docs.rs/clipboard) crate
+    /// Wrapper around [`arboard`](https://docs.rs/arboard) crate
     ///
-    /// Requires that the feature `system_clipboard` is enabled
+    /// Requires that the feature `system_clipboard` is enabled. Falls back to a
+    /// clipboard local to the instance if the system clipboard is unavailable, e.g. in
+    /// a headless session or over SSH.
     pub struct SystemClipboard {
-        cb: Arboard,
+        cb: Option<Arboard>,
         local_copy: String,
         mode: ClipboardMode,
     }
 
     impl SystemClipboard {
         pub fn new() -> Self {
-            let cb = Arboard::new().unwrap();
+            Self::with_system_clipboard(Arboard::new().ok())
+        }
+
+        fn with_system_clipboard(cb: Option<Arboard>) -> Self {
             SystemClipboard {
                 cb,
                 local_copy: String::new(),
@@ -101,12 +206,19 @@ mod system_clipboard {
     impl Clipboard for SystemClipboard {
         fn set(&mut self, content: &str, mode: ClipboardMode) {
             self.local_copy = content.to_owned();
-            let _ = self.cb.set_text(content);
+            if let Some(cb) = self.cb.as_mut() {
+                let _ = cb.set_text(content);
+            }
             self.mode = mode;
         }
 
         fn get(&mut self) -> (String, ClipboardMode) {
-            let system_content = self.cb.get_text().unwrap_or_default();
+            let system_content = match self.cb.as_mut().map(|cb| cb.get_text()) {
+                Some(Ok(system_content)) => system_content,
+                // Without a system clipboard the last yank is pasted
+                None => return (self.local_copy.clone(), self.mode),
+                Some(Err(_)) => String::new(),
+            };
             if system_content == self.local_copy {
                 // We assume the content was yanked inside the line editor and the last yank determined the mode.
                 (system_content, self.mode)
@@ -116,11 +228,24 @@ mod system_clipboard {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn falls_back_to_the_local_clipboard() {
+            let mut cb = SystemClipboard::with_system_clipboard(None);
+            cb.set("line", ClipboardMode::Lines);
+
+            assert!(matches!(cb.get(), (content, ClipboardMode::Lines) if content == "line"));
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_default_clipboard, Clipboard, ClipboardMode};
+    use super::{get_default_clipboard, Clipboard, ClipboardMode, CutDirection, KillRing};
     #[test]
     fn reads_back() {
         let mut cb = get_default_clipboard();
@@ -138,4 +263,32 @@ mod tests {
 
         cb.set(&previous_state, ClipboardMode::Normal);
     }
+
+    #[test]
+    fn kill_ring_keeps_the_last_entries() {
+        let mut kill_ring = KillRing::new(2);
+        kill_ring.set("one", ClipboardMode::Normal);
+        kill_ring.set("two", ClipboardMode::Normal);
+        kill_ring.set("three", ClipboardMode::Normal);
+
+        assert_eq!(kill_ring.get().0, "three");
+        assert_eq!(kill_ring.rotate().as_deref(), Some("two"));
+        assert_eq!(kill_ring.rotate().as_deref(), Some("three"));
+
+        kill_ring.clear();
+        assert_eq!(kill_ring.rotate(), None);
+        assert_eq!(kill_ring.get().0, "");
+    }
+
+    #[test]
+    fn kill_ring_combines_consecutive_cuts() {
+        let mut kill_ring = KillRing::new(10);
+        kill_ring.cut("foo", ClipboardMode::Normal, CutDirection::Right, false);
+        kill_ring.cut(" bar", ClipboardMode::Normal, CutDirection::Right, true);
+        kill_ring.cut("baz ", ClipboardMode::Normal, CutDirection::Left, true);
+        kill_ring.cut("new", ClipboardMode::Normal, CutDirection::Left, false);
+
+        assert_eq!(kill_ring.get().0, "new");
+        assert_eq!(kill_ring.rotate().as_deref(), Some("baz foo bar"));
+    }
 }
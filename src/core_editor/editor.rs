@@ -1,6 +1,10 @@
-use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
-use crate::enums::{EditType, UndoBehavior};
-use crate::{core_editor::get_default_clipboard, EditCommand};
+use super::{
+    undo_tree::UndoTree, AutoPairConfig, Clipboard, ClipboardMode, CutDirection, LineBuffer,
+    TabStops, WordBoundary,
+};
+use crate::enums::{EditType, UndoBehavior, UndoCoalescing};
+use crate::{core_editor::get_default_clipboard, EditCommand, TextObject};
+use std::{collections::HashMap, ops::Range, time::Instant};
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -9,9 +13,30 @@ use crate::{core_editor::get_default_clipboard, EditCommand};
 pub struct Editor {
     line_buffer: LineBuffer,
     cut_buffer: Box<dyn Clipboard>,
-    edit_stack: EditStack<LineBuffer>,
+    undo_tree: UndoTree<LineBuffer>,
     last_undo_behavior: UndoBehavior,
+    last_undo_time: Option<Instant>,
+    undo_coalescing: UndoCoalescing,
     selection_anchor: Option<usize>,
+    registers: HashMap<char, (String, ClipboardMode)>,
+    kill_state: Option<KillState>,
+    previous_kill_state: Option<KillState>,
+    auto_pairs: Option<AutoPairConfig>,
+    goal_column: Option<usize>,
+    // The last edit command could not do anything, e.g. undo without any edits
+    command_failed: bool,
+    // Placeholders of the snippet inserted by the last completion
+    tab_stops: Option<TabStops>,
+    // Accepted completions with spans outside of the buffer panic instead of being clamped
+    strict_completions: bool,
+}
+
+/// Cut or paste done by the last edit command, to combine consecutive cuts and to
+/// replace the pasted text with [`EditCommand::YankPop`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum KillState {
+    Cut(CutDirection),
+    Paste(Range<usize>),
 }
 
 impl Default for Editor {
@@ -19,9 +44,19 @@ impl Default for Editor {
         Editor {
             line_buffer: LineBuffer::new(),
             cut_buffer: Box::new(get_default_clipboard()),
-            edit_stack: EditStack::new(),
+            undo_tree: UndoTree::new(),
             last_undo_behavior: UndoBehavior::CreateUndoPoint,
+            last_undo_time: None,
+            undo_coalescing: UndoCoalescing::default(),
             selection_anchor: None,
+            registers: HashMap::new(),
+            kill_state: None,
+            previous_kill_state: None,
+            auto_pairs: None,
+            goal_column: None,
+            command_failed: false,
+            tab_stops: None,
+            strict_completions: false,
         }
     }
 }
@@ -35,12 +70,124 @@ impl Editor {
     /// Set the current [`LineBuffer`].
     /// [`UndoBehavior`] specifies how this change should be reflected on the undo stack.
     pub(crate) fn set_line_buffer(&mut self, line_buffer: LineBuffer, undo_behavior: UndoBehavior) {
-        self.line_buffer = line_buffer;
+        if matches!(undo_behavior, UndoBehavior::Completion) {
+            // Undoing the completion goes back to exactly the state before it
+            self.undo_tree.replace_current(self.line_buffer.clone());
+        }
+        self.replace_line_buffer(line_buffer);
+        self.kill_state = None;
+        self.goal_column = None;
+        self.tab_stops = None;
         self.update_undo_state(undo_behavior);
     }
 
+    /// Start visiting the placeholders of an inserted snippet, the last range is the
+    /// final cursor position. The cursor is put on the first placeholder
+    pub(crate) fn start_tab_stops(&mut self, ranges: Vec<std::ops::Range<usize>>) {
+        self.tab_stops = TabStops::new(ranges);
+        self.select_tab_stop();
+    }
+
+    /// Moves to the next or the previous placeholder of the snippet, returns false
+    /// when there is no snippet to visit
+    pub(crate) fn goto_tab_stop(&mut self, forward: bool) -> bool {
+        match self.tab_stops.as_mut() {
+            Some(tab_stops) => {
+                tab_stops.step(forward);
+                self.select_tab_stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The placeholder of the active tab stop is selected, so typing replaces it
+    fn select_tab_stop(&mut self) {
+        if let Some(tab_stops) = &self.tab_stops {
+            let range = tab_stops.active();
+            self.selection_anchor = (!range.is_empty()).then_some(range.start);
+            self.line_buffer.set_insertion_point(range.end);
+            if tab_stops.is_finished() {
+                self.tab_stops = None;
+            }
+        }
+    }
+
+    // Moves the tab stops by the change of the buffer between the two states, the
+    // snippet is left when the change isn't inside of a placeholder
+    fn update_tab_stops(&mut self, before: &LineBuffer) {
+        if let Some(tab_stops) = self.tab_stops.as_mut() {
+            let (old, new) = (before.get_buffer(), self.line_buffer.get_buffer());
+            if old == new {
+                return;
+            }
+            // The edit is at the cursor, even if the text next to it is the same
+            let max_prefix = old
+                .len()
+                .min(new.len())
+                .min(before.insertion_point())
+                .min(self.line_buffer.insertion_point());
+            let prefix = common_prefix_len(old, new).min(max_prefix);
+            let suffix = common_prefix_len_rev(&old[prefix..], &new[prefix..]);
+
+            if !tab_stops.update(prefix, old.len() - suffix, new.len() - suffix) {
+                self.tab_stops = None;
+            }
+        }
+    }
+
+    pub(crate) fn set_strict_completions(&mut self, strict_completions: bool) {
+        self.strict_completions = strict_completions;
+    }
+
+    /// Whether the spans of the accepted completions are checked against the buffer
+    pub(crate) fn strict_completions(&self) -> bool {
+        self.strict_completions
+    }
+
+    /// Replace the clipboard used to cut and paste
+    pub(crate) fn set_cut_buffer(&mut self, cut_buffer: Box<dyn Clipboard>) {
+        self.cut_buffer = cut_buffer;
+    }
+
+    /// Set the definition of the words used by the word movements and edits
+    pub(crate) fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.line_buffer.set_word_boundary(word_boundary);
+    }
+
+    /// Set how consecutive edits are grouped into one undo step
+    pub(crate) fn set_undo_coalescing(&mut self, undo_coalescing: UndoCoalescing) {
+        self.undo_coalescing = undo_coalescing;
+    }
+
+    /// Set the pairs of characters inserted together, `None` disables the auto-pairing
+    pub(crate) fn set_auto_pairs(&mut self, auto_pairs: Option<AutoPairConfig>) {
+        self.auto_pairs = auto_pairs;
+    }
+
+    /// Whether the last edit command could not do anything, e.g. an undo without any
+    /// edits or a move to a bracket without a match
+    pub(crate) fn command_failed(&self) -> bool {
+        self.command_failed
+    }
+
     pub(crate) fn run_edit_command(&mut self, command: &EditCommand) {
+        let before = self.tab_stops.is_some().then(|| self.line_buffer.clone());
+        self.run_command(command);
+        if let Some(before) = before {
+            self.update_tab_stops(&before);
+        }
+    }
+
+    fn run_command(&mut self, command: &EditCommand) {
+        self.previous_kill_state = self.kill_state.take();
+        self.goal_column = None;
+        self.command_failed = false;
         match command {
+            _ if self.selection_anchor.is_some() && cuts_text(command) => self.cut_selection(),
+            EditCommand::CopyTextObject(_) if self.selection_anchor.is_some() => {
+                self.copy_selection()
+            }
             EditCommand::MoveToStart { select } => self.move_to_start(*select),
             EditCommand::MoveToLineStart { select } => self.move_to_line_start(*select),
             EditCommand::MoveToEnd { select } => self.move_to_end(*select),
@@ -59,6 +206,7 @@ impl Editor {
             }
             EditCommand::MoveWordRightEnd { select } => self.move_word_right_end(*select),
             EditCommand::MoveBigWordRightEnd { select } => self.move_big_word_right_end(*select),
+            EditCommand::MoveToMatchingBracket { select } => self.move_to_matching_bracket(*select),
             EditCommand::InsertChar(c) => self.insert_char(*c),
             EditCommand::Complete => {}
             EditCommand::InsertString(str) => self.insert_str(str),
@@ -70,6 +218,8 @@ impl Editor {
             EditCommand::CutChar => self.cut_char(),
             EditCommand::BackspaceWord => self.line_buffer.delete_word_left(),
             EditCommand::DeleteWord => self.line_buffer.delete_word_right(),
+            EditCommand::DeleteToMatchingBracket => self.delete_brackets(false),
+            EditCommand::DeleteInsideBrackets => self.delete_brackets(true),
             EditCommand::Clear => self.line_buffer.clear(),
             EditCommand::ClearToLineEnd => self.line_buffer.clear_to_line_end(),
             EditCommand::CutCurrentLine => self.cut_current_line(),
@@ -85,14 +235,20 @@ impl Editor {
             EditCommand::CutBigWordRightToNext => self.cut_big_word_right_to_next(),
             EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
             EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
+            EditCommand::YankPop => self.yank_pop(),
             EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
             EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
+            EditCommand::CapitalizeWord => self.line_buffer.capitalize_word(),
+            EditCommand::ToggleCase => self.toggle_case(),
             EditCommand::SwitchcaseChar => self.line_buffer.switchcase_char(),
             EditCommand::CapitalizeChar => self.line_buffer.capitalize_char(),
             EditCommand::SwapWords => self.line_buffer.swap_words(),
             EditCommand::SwapGraphemes => self.line_buffer.swap_graphemes(),
+            EditCommand::TransposeWords => self.line_buffer.transpose_words(),
             EditCommand::Undo => self.undo(),
             EditCommand::Redo => self.redo(),
+            EditCommand::UndoTreeBranchNext => self.undo_tree_branch(true),
+            EditCommand::UndoTreeBranchPrev => self.undo_tree_branch(false),
             EditCommand::CutRightUntil(c) => self.cut_right_until_char(*c, false, true),
             EditCommand::CutRightBefore(c) => self.cut_right_until_char(*c, true, true),
             EditCommand::MoveRightUntil { c, select } => {
@@ -110,8 +266,48 @@ impl Editor {
                 self.move_left_until_char(*c, true, true, *select)
             }
             EditCommand::SelectAll => self.select_all(),
+            EditCommand::ClearSelection => self.selection_anchor = None,
+            EditCommand::SwapCursorAndAnchor => self.swap_cursor_and_anchor(),
+            EditCommand::MoveToSelectionStart => self.move_to_selection_bound(false),
+            EditCommand::MoveToSelectionEnd => self.move_to_selection_bound(true),
             EditCommand::CutSelection => self.cut_selection(),
             EditCommand::CopySelection => self.copy_selection(),
+            EditCommand::CutTextObject(text_object) => self.cut_text_object(*text_object),
+            EditCommand::CopyTextObject(text_object) => self.copy_text_object(*text_object),
+            EditCommand::CopyCurrentLine => self.copy_current_line(),
+            EditCommand::CopyFromLineStart => self.copy_from_line_start(),
+            EditCommand::CopyToLineEnd => self.copy_to_line_end(),
+            EditCommand::CopyWordLeft => {
+                self.copy_left_of(self.line_buffer.word_left_index());
+            }
+            EditCommand::CopyBigWordLeft => {
+                self.copy_left_of(self.line_buffer.big_word_left_index());
+            }
+            EditCommand::CopyWordRight => {
+                self.copy_right_of(self.line_buffer.word_right_index());
+            }
+            EditCommand::CopyBigWordRight => {
+                self.copy_right_of(self.line_buffer.next_whitespace());
+            }
+            EditCommand::CopyWordRightToNext => {
+                self.copy_right_of(self.line_buffer.word_right_start_index());
+            }
+            EditCommand::CopyBigWordRightToNext => {
+                self.copy_right_of(self.line_buffer.big_word_right_start_index());
+            }
+            EditCommand::CopyRightUntil(c) => self.copy_right_until_char(*c, false),
+            EditCommand::CopyRightBefore(c) => self.copy_right_until_char(*c, true),
+            EditCommand::CopyLeftUntil(c) => self.copy_left_until_char(*c, false),
+            EditCommand::CopyLeftBefore(c) => self.copy_left_until_char(*c, true),
+            EditCommand::CopyToRegister(register) => self.copy_to_register(*register),
+            EditCommand::PasteFromRegister(register) => self.paste_from_register(*register, false),
+            EditCommand::PasteFromRegisterAfter(register) => {
+                self.paste_from_register(*register, true);
+            }
+            // Macros are recorded and replayed by the engine
+            EditCommand::StartRecordingMacro(_)
+            | EditCommand::StopRecordingMacro
+            | EditCommand::ReplayMacro(_) => {}
         }
         if !matches!(command.edit_type(), EditType::MoveCursor { select: true }) {
             self.selection_anchor = None;
@@ -122,11 +318,11 @@ impl Editor {
             (_, EditType::MoveCursor { .. }) => UndoBehavior::MoveCursor,
             (EditCommand::InsertChar(c), EditType::EditText) => UndoBehavior::InsertCharacter(*c),
             (EditCommand::Delete, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_right().chars().next();
+                let deleted_char = self.undo_tree.current().grapheme_right().chars().next();
                 UndoBehavior::Delete(deleted_char)
             }
             (EditCommand::Backspace, EditType::EditText) => {
-                let deleted_char = self.edit_stack.current().grapheme_left().chars().next();
+                let deleted_char = self.undo_tree.current().grapheme_left().chars().next();
                 UndoBehavior::Backspace(deleted_char)
             }
             (_, EditType::UndoRedo) => UndoBehavior::UndoRedo,
@@ -149,15 +345,26 @@ impl Editor {
     }
 
     pub(crate) fn move_line_up(&mut self) {
-        self.line_buffer.move_line_up();
+        let column = self.goal_column();
+        self.line_buffer.move_line_up_to_column(column);
         self.update_undo_state(UndoBehavior::MoveCursor);
     }
 
     pub(crate) fn move_line_down(&mut self) {
-        self.line_buffer.move_line_down();
+        let column = self.goal_column();
+        self.line_buffer.move_line_down_to_column(column);
         self.update_undo_state(UndoBehavior::MoveCursor);
     }
 
+    /// Column to keep for consecutive vertical moves, even through shorter lines.
+    /// Every edit command resets it to the column of the insertion point.
+    fn goal_column(&mut self) -> usize {
+        let line_buffer = &self.line_buffer;
+        *self
+            .goal_column
+            .get_or_insert_with(|| line_buffer.current_column())
+    }
+
     /// Get the text of the current [`LineBuffer`]
     pub fn get_buffer(&self) -> &str {
         self.line_buffer.get_buffer()
@@ -176,9 +383,31 @@ impl Editor {
     /// Insertion point update to the end of the buffer.
     pub(crate) fn set_buffer(&mut self, buffer: String, undo_behavior: UndoBehavior) {
         self.line_buffer.set_buffer(buffer);
+        self.kill_state = None;
+        self.goal_column = None;
+        self.tab_stops = None;
         self.update_undo_state(undo_behavior);
     }
 
+    /// Set the text the edit starts from, it is the initial state of the undo history.
+    ///
+    /// The cursor is put at the byte offset `cursor`, by default at the end of the buffer.
+    pub(crate) fn set_initial_buffer(&mut self, buffer: String, cursor: Option<usize>) {
+        let mut cursor = cursor.unwrap_or(buffer.len()).min(buffer.len());
+        while !buffer.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+
+        self.line_buffer.set_buffer(buffer);
+        self.line_buffer.set_insertion_point(cursor);
+        self.selection_anchor = None;
+        self.kill_state = None;
+        self.goal_column = None;
+        self.tab_stops = None;
+        self.last_undo_behavior = UndoBehavior::CreateUndoPoint;
+        self.undo_tree.reset_to(self.line_buffer.clone());
+    }
+
     pub(crate) fn insertion_point(&self) -> usize {
         self.line_buffer.insertion_point()
     }
@@ -200,7 +429,17 @@ impl Editor {
     }
 
     pub(crate) fn reset_undo_stack(&mut self) {
-        self.edit_stack.reset();
+        self.undo_tree.reset();
+    }
+
+    /// Overwrite the buffer and every state of the undo history before clearing them,
+    /// the clipboard and the registers are kept
+    pub(crate) fn clear_secret(&mut self) {
+        self.line_buffer.zeroize();
+        self.undo_tree.reset_with(LineBuffer::zeroize);
+        self.selection_anchor = None;
+        self.kill_state = None;
+        self.goal_column = None;
     }
 
     pub(crate) fn move_to_start(&mut self, select: bool) {
@@ -224,13 +463,31 @@ impl Editor {
     }
 
     fn undo(&mut self) {
-        let val = self.edit_stack.undo();
-        self.line_buffer = val.clone();
+        self.command_failed = !self.undo_tree.can_undo();
+        let val = self.undo_tree.undo().clone();
+        self.replace_line_buffer(val);
     }
 
     fn redo(&mut self) {
-        let val = self.edit_stack.redo();
-        self.line_buffer = val.clone();
+        self.command_failed = !self.undo_tree.can_redo();
+        let val = self.undo_tree.redo().clone();
+        self.replace_line_buffer(val);
+    }
+
+    fn undo_tree_branch(&mut self, next: bool) {
+        let val = if next {
+            self.undo_tree.next_branch()
+        } else {
+            self.undo_tree.previous_branch()
+        }
+        .clone();
+        self.replace_line_buffer(val);
+    }
+
+    // Replace the buffer, keeping the configured word boundary
+    fn replace_line_buffer(&mut self, mut line_buffer: LineBuffer) {
+        line_buffer.set_word_boundary(self.line_buffer.word_boundary());
+        self.line_buffer = line_buffer;
     }
 
     pub(crate) fn update_undo_state(&mut self, undo_behavior: UndoBehavior) {
@@ -238,11 +495,32 @@ impl Editor {
             self.last_undo_behavior = UndoBehavior::UndoRedo;
             return;
         }
-        if !undo_behavior.create_undo_point_after(&self.last_undo_behavior) {
-            self.edit_stack.undo();
+        let now = Instant::now();
+        if self.create_undo_point(&undo_behavior, now) {
+            self.undo_tree.insert(self.line_buffer.clone());
+        } else {
+            self.undo_tree.replace_current(self.line_buffer.clone());
         }
-        self.edit_stack.insert(self.line_buffer.clone());
         self.last_undo_behavior = undo_behavior;
+        self.last_undo_time = Some(now);
+    }
+
+    // Whether the change starts a new undo step, depending on the undo coalescing
+    fn create_undo_point(&self, undo_behavior: &UndoBehavior, now: Instant) -> bool {
+        use UndoBehavior as UB;
+        let repeated_edit = matches!(
+            (&self.last_undo_behavior, undo_behavior),
+            (UB::InsertCharacter(_), UB::InsertCharacter(_))
+                | (UB::Backspace(_), UB::Backspace(_))
+                | (UB::Delete(_), UB::Delete(_))
+        );
+        match self.undo_coalescing {
+            UndoCoalescing::PerEdit if repeated_edit => true,
+            UndoCoalescing::PerPause(pause) if repeated_edit => self
+                .last_undo_time
+                .map_or(true, |last| now.duration_since(last) >= pause),
+            _ => undo_behavior.create_undo_point_after(&self.last_undo_behavior),
+        }
     }
 
     fn cut_current_line(&mut self) {
@@ -250,7 +528,11 @@ impl Editor {
 
         let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Lines);
+            self.store_cut(
+                deletion_range.clone(),
+                ClipboardMode::Lines,
+                CutDirection::Right,
+            );
             self.line_buffer.set_insertion_point(deletion_range.start);
             self.line_buffer.clear_range(deletion_range);
         }
@@ -259,9 +541,10 @@ impl Editor {
     fn cut_from_start(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         if insertion_offset > 0 {
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[..insertion_offset],
+            self.store_cut(
+                0..insertion_offset,
                 ClipboardMode::Normal,
+                CutDirection::Left,
             );
             self.line_buffer.clear_to_insertion_point();
         }
@@ -273,24 +556,28 @@ impl Editor {
         let deletion_range = self.line_buffer.insertion_point()..previous_offset;
         let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.store_cut(
+                deletion_range.clone(),
+                ClipboardMode::Normal,
+                CutDirection::Left,
+            );
             self.line_buffer.clear_range(deletion_range);
         }
     }
 
     fn cut_from_end(&mut self) {
-        let cut_slice = &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..];
-        if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+        let cut_range = self.line_buffer.insertion_point()..self.line_buffer.len();
+        if !cut_range.is_empty() {
+            self.store_cut(cut_range, ClipboardMode::Normal, CutDirection::Right);
             self.line_buffer.clear_to_end();
         }
     }
 
     fn cut_to_line_end(&mut self) {
-        let cut_slice = &self.line_buffer.get_buffer()
-            [self.line_buffer.insertion_point()..self.line_buffer.find_current_line_end()];
-        if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+        let cut_range =
+            self.line_buffer.insertion_point()..self.line_buffer.find_current_line_end();
+        if !cut_range.is_empty() {
+            self.store_cut(cut_range, ClipboardMode::Normal, CutDirection::Right);
             self.line_buffer.clear_to_line_end();
         }
     }
@@ -300,10 +587,7 @@ impl Editor {
         let left_index = self.line_buffer.word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
-                ClipboardMode::Normal,
-            );
+            self.store_cut(cut_range.clone(), ClipboardMode::Normal, CutDirection::Left);
             self.line_buffer.clear_range(cut_range);
             self.line_buffer.set_insertion_point(left_index);
         }
@@ -314,10 +598,7 @@ impl Editor {
         let left_index = self.line_buffer.big_word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
-                ClipboardMode::Normal,
-            );
+            self.store_cut(cut_range.clone(), ClipboardMode::Normal, CutDirection::Left);
             self.line_buffer.clear_range(cut_range);
             self.line_buffer.set_insertion_point(left_index);
         }
@@ -328,9 +609,10 @@ impl Editor {
         let right_index = self.line_buffer.word_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
+            self.store_cut(
+                cut_range.clone(),
                 ClipboardMode::Normal,
+                CutDirection::Right,
             );
             self.line_buffer.clear_range(cut_range);
         }
@@ -341,9 +623,10 @@ impl Editor {
         let right_index = self.line_buffer.next_whitespace();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
+            self.store_cut(
+                cut_range.clone(),
                 ClipboardMode::Normal,
+                CutDirection::Right,
             );
             self.line_buffer.clear_range(cut_range);
         }
@@ -354,9 +637,10 @@ impl Editor {
         let right_index = self.line_buffer.word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
+            self.store_cut(
+                cut_range.clone(),
                 ClipboardMode::Normal,
+                CutDirection::Right,
             );
             self.line_buffer.clear_range(cut_range);
         }
@@ -367,9 +651,10 @@ impl Editor {
         let right_index = self.line_buffer.big_word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
+            self.store_cut(
+                cut_range.clone(),
                 ClipboardMode::Normal,
+                CutDirection::Right,
             );
             self.line_buffer.clear_range(cut_range);
         }
@@ -380,9 +665,10 @@ impl Editor {
         let right_index = self.line_buffer.grapheme_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
-                &self.line_buffer.get_buffer()[cut_range.clone()],
+            self.store_cut(
+                cut_range.clone(),
                 ClipboardMode::Normal,
+                CutDirection::Right,
             );
             self.line_buffer.clear_range(cut_range);
         }
@@ -390,14 +676,34 @@ impl Editor {
 
     fn insert_cut_buffer_before(&mut self) {
         self.delete_selection();
-        match self.cut_buffer.get() {
-            (content, ClipboardMode::Normal) => {
-                self.line_buffer.insert_str(&content);
+        let (content, mode) = self.cut_buffer.get();
+        self.paste(content, mode, false);
+    }
+
+    fn insert_cut_buffer_after(&mut self) {
+        self.delete_selection();
+        let (content, mode) = self.cut_buffer.get();
+        self.paste(content, mode, true);
+    }
+
+    /// Insert the content at the insertion point, or as a new line above or below the
+    /// current line for [`ClipboardMode::Lines`]
+    fn paste(&mut self, mut content: String, mode: ClipboardMode, after: bool) {
+        match mode {
+            ClipboardMode::Normal => {
+                if after {
+                    self.line_buffer.move_right();
+                }
+                self.insert_pasted_str(&content);
             }
-            (mut content, ClipboardMode::Lines) => {
+            ClipboardMode::Lines => {
                 // TODO: Simplify that?
                 self.line_buffer.move_to_line_start();
-                self.line_buffer.move_line_up();
+                if after {
+                    self.line_buffer.move_line_down();
+                } else {
+                    self.line_buffer.move_line_up();
+                }
                 if !content.ends_with('\n') {
                     // TODO: Make sure platform requirements are met
                     content.push('\n');
@@ -407,26 +713,96 @@ impl Editor {
         }
     }
 
-    fn insert_cut_buffer_after(&mut self) {
-        self.delete_selection();
-        match self.cut_buffer.get() {
-            (content, ClipboardMode::Normal) => {
-                self.line_buffer.move_right();
-                self.line_buffer.insert_str(&content);
-            }
-            (mut content, ClipboardMode::Lines) => {
-                // TODO: Simplify that?
-                self.line_buffer.move_to_line_start();
-                self.line_buffer.move_line_down();
-                if !content.ends_with('\n') {
-                    // TODO: Make sure platform requirements are met
-                    content.push('\n');
+    fn insert_pasted_str(&mut self, content: &str) {
+        let start = self.insertion_point();
+        self.line_buffer.insert_str(content);
+        self.kill_state = Some(KillState::Paste(start..self.insertion_point()));
+    }
+
+    fn yank_pop(&mut self) {
+        if let Some(KillState::Paste(pasted_range)) = self.previous_kill_state.take() {
+            match self.cut_buffer.rotate() {
+                Some(content) => {
+                    self.line_buffer
+                        .replace_range(pasted_range.clone(), &content);
+                    self.line_buffer
+                        .set_insertion_point(pasted_range.start + content.len());
+                    self.kill_state = Some(KillState::Paste(
+                        pasted_range.start..pasted_range.start + content.len(),
+                    ));
                 }
-                self.line_buffer.insert_str(&content);
+                None => self.kill_state = Some(KillState::Paste(pasted_range)),
             }
         }
     }
 
+    /// Store the text in the range of the buffer in the clipboard, it is combined with
+    /// the cut of the previous command if that was done in the same direction
+    fn store_cut(&mut self, range: Range<usize>, mode: ClipboardMode, direction: CutDirection) {
+        let consecutive = self.previous_kill_state == Some(KillState::Cut(direction));
+        self.cut_buffer.cut(
+            &self.line_buffer.get_buffer()[range],
+            mode,
+            direction,
+            consecutive,
+        );
+        self.kill_state = Some(KillState::Cut(direction));
+    }
+
+    /// Store the text in the range of the buffer in the clipboard, without combining it
+    /// with a previous cut
+    fn store_copy(&mut self, range: Range<usize>, mode: ClipboardMode) {
+        self.cut_buffer
+            .set(&self.line_buffer.get_buffer()[range], mode);
+    }
+
+    fn copy_current_line(&mut self) {
+        let range = self.line_buffer.current_line_range();
+        if !range.is_empty() {
+            self.store_copy(range, ClipboardMode::Lines);
+        }
+    }
+
+    fn copy_from_line_start(&mut self) {
+        let insertion_offset = self.line_buffer.insertion_point();
+        let line_start = self.get_buffer()[..insertion_offset]
+            .rfind('\n')
+            .map_or(0, |offset| offset + 1);
+        self.copy_left_of(line_start);
+    }
+
+    fn copy_to_line_end(&mut self) {
+        self.copy_right_of(self.line_buffer.find_current_line_end());
+    }
+
+    fn copy_left_of(&mut self, left_index: usize) {
+        let insertion_offset = self.line_buffer.insertion_point();
+        if left_index < insertion_offset {
+            self.store_copy(left_index..insertion_offset, ClipboardMode::Normal);
+        }
+    }
+
+    fn copy_right_of(&mut self, right_index: usize) {
+        let insertion_offset = self.line_buffer.insertion_point();
+        if right_index > insertion_offset {
+            self.store_copy(insertion_offset..right_index, ClipboardMode::Normal);
+        }
+    }
+
+    fn copy_right_until_char(&mut self, c: char, before_char: bool) {
+        if let Some(index) = self.line_buffer.find_char_right(c, true) {
+            let extra = if before_char { 0 } else { c.len_utf8() };
+            self.copy_right_of(index + extra);
+        }
+    }
+
+    fn copy_left_until_char(&mut self, c: char, before_char: bool) {
+        if let Some(index) = self.line_buffer.find_char_left(c, true) {
+            let extra = if before_char { c.len_utf8() } else { 0 };
+            self.copy_left_of(index + extra);
+        }
+    }
+
     fn move_right_until_char(
         &mut self,
         c: char,
@@ -462,11 +838,10 @@ impl Editor {
             // Saving the section of the string that will be deleted to be
             // stored into the buffer
             let extra = if before_char { 0 } else { c.len_utf8() };
-            let cut_slice =
-                &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..index + extra];
+            let cut_range = self.line_buffer.insertion_point()..index + extra;
 
-            if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            if !cut_range.is_empty() {
+                self.store_cut(cut_range, ClipboardMode::Normal, CutDirection::Right);
 
                 if before_char {
                     self.line_buffer.delete_right_before_char(c, current_line);
@@ -482,11 +857,10 @@ impl Editor {
             // Saving the section of the string that will be deleted to be
             // stored into the buffer
             let extra = if before_char { c.len_utf8() } else { 0 };
-            let cut_slice =
-                &self.line_buffer.get_buffer()[index + extra..self.line_buffer.insertion_point()];
+            let cut_range = index + extra..self.line_buffer.insertion_point();
 
-            if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            if !cut_range.is_empty() {
+                self.store_cut(cut_range, ClipboardMode::Normal, CutDirection::Left);
 
                 if before_char {
                     self.line_buffer.delete_left_before_char(c, current_line);
@@ -526,10 +900,32 @@ impl Editor {
         self.line_buffer.move_to_end();
     }
 
+    fn swap_cursor_and_anchor(&mut self) {
+        if let Some(anchor) = self.selection_anchor {
+            self.selection_anchor = Some(self.insertion_point());
+            self.line_buffer.set_insertion_point(anchor);
+        }
+    }
+
+    fn move_to_selection_bound(&mut self, end: bool) {
+        if let Some((start, stop)) = self.get_selection() {
+            self.line_buffer
+                .set_insertion_point(if end { stop } else { start });
+            self.selection_anchor = None;
+        }
+    }
+
+    fn toggle_case(&mut self) {
+        if let Some((start, end)) = self.get_selection() {
+            self.line_buffer.toggle_case_range(start..end);
+        } else {
+            self.line_buffer.toggle_case();
+        }
+    }
+
     fn cut_selection(&mut self) {
         if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.store_cut(start..end, ClipboardMode::Normal, CutDirection::Right);
             self.line_buffer.clear_range_safe(start, end);
             self.selection_anchor = None;
         }
@@ -537,8 +933,59 @@ impl Editor {
 
     fn copy_selection(&mut self) {
         if let Some((start, end)) = self.get_selection() {
-            let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.store_copy(start..end, ClipboardMode::Normal);
+        }
+    }
+
+    fn text_object_range(&self, text_object: TextObject) -> Option<Range<usize>> {
+        match text_object {
+            TextObject::Word { inner } => Some(self.line_buffer.word_object_range(inner)),
+            TextObject::Delimited { open, close, inner } => {
+                self.line_buffer.delimited_range(open, close, inner)
+            }
+        }
+    }
+
+    fn cut_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.text_object_range(text_object) {
+            if !range.is_empty() {
+                self.store_cut(range.clone(), ClipboardMode::Normal, CutDirection::Right);
+                self.line_buffer.clear_range(range.clone());
+            }
+            self.line_buffer.set_insertion_point(range.start);
+        }
+    }
+
+    fn copy_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.text_object_range(text_object) {
+            if !range.is_empty() {
+                self.store_copy(range, ClipboardMode::Normal);
+            }
+        }
+    }
+
+    fn copy_to_register(&mut self, register: char) {
+        let (content, mode) = self.cut_buffer.get();
+        if register.is_uppercase() {
+            let (previous, previous_mode) = self
+                .registers
+                .entry(register.to_ascii_lowercase())
+                .or_default();
+            previous.push_str(&content);
+            if matches!(mode, ClipboardMode::Lines) {
+                *previous_mode = mode;
+            }
+        } else {
+            self.registers.insert(register, (content, mode));
+        }
+    }
+
+    fn paste_from_register(&mut self, register: char, after: bool) {
+        if let Some((content, mode)) = self.registers.get(&register.to_ascii_lowercase()).cloned() {
+            self.delete_selection();
+            self.paste(content, mode, after);
+            // Only the pastes of the clipboard are replaced by the kill ring entries
+            self.kill_state = None;
         }
     }
 
@@ -565,10 +1012,27 @@ impl Editor {
         if self.selection_anchor.is_some() {
             self.delete_selection();
         } else {
+            if self.is_inside_empty_pair() {
+                self.line_buffer.delete_right_grapheme();
+            }
             self.line_buffer.delete_left_grapheme();
         }
     }
 
+    fn is_inside_empty_pair(&self) -> bool {
+        let auto_pairs = match &self.auto_pairs {
+            Some(auto_pairs) => auto_pairs,
+            None => return false,
+        };
+        let mut left = self.line_buffer.grapheme_left().chars();
+        let mut right = self.line_buffer.grapheme_right().chars();
+
+        match (left.next(), left.next(), right.next(), right.next()) {
+            (Some(open), None, Some(close), None) => auto_pairs.closing_char(open) == Some(close),
+            _ => false,
+        }
+    }
+
     fn delete(&mut self) {
         if self.selection_anchor.is_some() {
             self.delete_selection();
@@ -577,6 +1041,36 @@ impl Editor {
         }
     }
 
+    fn move_to_matching_bracket(&mut self, select: bool) {
+        let position = self.insertion_point();
+        let bracket = self.line_buffer.matching_bracket(position).or_else(|| {
+            // Also match the bracket left of the cursor, e.g. right after typing it
+            let left = self.line_buffer.grapheme_left_index();
+            (left < position)
+                .then(|| self.line_buffer.matching_bracket(left))
+                .flatten()
+        });
+        match bracket {
+            Some(bracket) => self.move_to_position(bracket, select),
+            None => self.command_failed = true,
+        }
+    }
+
+    fn delete_brackets(&mut self, inner: bool) {
+        match self.line_buffer.enclosing_brackets() {
+            Some((open, close)) => {
+                let range = if inner {
+                    open + 1..close
+                } else {
+                    open..close + 1
+                };
+                self.line_buffer.clear_range(range.clone());
+                self.line_buffer.set_insertion_point(range.start);
+            }
+            None => self.command_failed = true,
+        }
+    }
+
     fn move_word_left(&mut self, select: bool) {
         self.move_to_position(self.line_buffer.word_left_index(), select);
     }
@@ -606,10 +1100,48 @@ impl Editor {
     }
 
     fn insert_char(&mut self, c: char) {
+        if let Some(auto_pairs) = &self.auto_pairs {
+            let closing_char = auto_pairs.closing_char(c);
+            let types_over = auto_pairs.is_closing_char(c)
+                && self.selection_anchor.is_none()
+                && self.line_buffer.grapheme_right() == c.to_string();
+
+            match (closing_char, self.get_selection()) {
+                (Some(close), Some((start, end))) => {
+                    // Wrap the selection in the pair
+                    self.line_buffer.replace_range(end..end, &close.to_string());
+                    self.line_buffer.replace_range(start..start, &c.to_string());
+                    self.line_buffer
+                        .set_insertion_point(end + c.len_utf8() + close.len_utf8());
+                    self.selection_anchor = None;
+                    return;
+                }
+                _ if types_over => {
+                    self.line_buffer.move_right();
+                    return;
+                }
+                (Some(close), None) if close != c || !self.is_after_word_char() => {
+                    self.line_buffer.insert_char(c);
+                    self.line_buffer.insert_char(close);
+                    self.line_buffer.move_left();
+                    return;
+                }
+                _ => {}
+            }
+        }
         self.delete_selection();
         self.line_buffer.insert_char(c);
     }
 
+    // Quotes typed after a word are apostrophes or close a string
+    fn is_after_word_char(&self) -> bool {
+        self.line_buffer
+            .grapheme_left()
+            .chars()
+            .last()
+            .map_or(false, char::is_alphanumeric)
+    }
+
     fn insert_str(&mut self, str: &str) {
         self.delete_selection();
         self.line_buffer.insert_str(str);
@@ -621,11 +1153,55 @@ impl Editor {
     }
 }
 
+// Length of the common start of the strings, on a char boundary
+fn common_prefix_len(lhs: &str, rhs: &str) -> usize {
+    lhs.char_indices()
+        .zip(rhs.chars())
+        .find(|((_, lhs), rhs)| lhs != rhs)
+        .map_or(lhs.len().min(rhs.len()), |((index, _), _)| index)
+}
+
+// Length of the common end of the strings, on a char boundary
+fn common_prefix_len_rev(lhs: &str, rhs: &str) -> usize {
+    lhs.chars()
+        .rev()
+        .zip(rhs.chars().rev())
+        .take_while(|(lhs, rhs)| lhs == rhs)
+        .map(|(c, _)| c.len_utf8())
+        .sum()
+}
+
+// Commands cutting a range of the buffer, they cut the selection instead when there is one
+fn cuts_text(command: &EditCommand) -> bool {
+    matches!(
+        command,
+        EditCommand::CutChar
+            | EditCommand::CutCurrentLine
+            | EditCommand::CutFromStart
+            | EditCommand::CutFromLineStart
+            | EditCommand::CutToEnd
+            | EditCommand::CutToLineEnd
+            | EditCommand::CutWordLeft
+            | EditCommand::CutBigWordLeft
+            | EditCommand::CutWordRight
+            | EditCommand::CutBigWordRight
+            | EditCommand::CutWordRightToNext
+            | EditCommand::CutBigWordRightToNext
+            | EditCommand::CutRightUntil(_)
+            | EditCommand::CutRightBefore(_)
+            | EditCommand::CutLeftUntil(_)
+            | EditCommand::CutLeftBefore(_)
+            | EditCommand::CutTextObject(_)
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::core_editor::KillRing;
     use pretty_assertions::assert_eq;
     use rstest::rstest;
+    use std::time::Duration;
 
     fn editor_with(buffer: &str) -> Editor {
         let mut editor = Editor::default();
@@ -726,6 +1302,63 @@ mod test {
         assert_eq!(editor.get_buffer(), "This is  a test");
     }
 
+    #[rstest]
+    #[case(UndoCoalescing::PerEdit, "ls -la")]
+    #[case(UndoCoalescing::PerWord, "ls")]
+    #[case(UndoCoalescing::PerPause(Duration::from_secs(60)), "")]
+    #[case(UndoCoalescing::PerPause(Duration::ZERO), "ls -la")]
+    fn test_undo_coalescing(#[case] undo_coalescing: UndoCoalescing, #[case] expected: &str) {
+        let mut editor = editor_with("ls");
+        editor.set_undo_coalescing(undo_coalescing);
+        for cmd in str_to_edit_commands(" -la x") {
+            editor.run_edit_command(&cmd);
+        }
+
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[test]
+    fn test_cursor_jump_ends_the_pause_coalescing() {
+        let mut editor = editor_with("ls");
+        editor.set_undo_coalescing(UndoCoalescing::PerPause(Duration::from_secs(60)));
+        for cmd in str_to_edit_commands(" -la") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        for cmd in str_to_edit_commands("sudo ") {
+            editor.run_edit_command(&cmd);
+        }
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls -la");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+    }
+
+    #[test]
+    fn test_edit_after_undo_keeps_the_redo_branch() {
+        let mut editor = editor_with("ls");
+        for cmd in str_to_edit_commands(" -a") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        for cmd in str_to_edit_commands(" -l") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "ls -l");
+
+        editor.run_edit_command(&EditCommand::UndoTreeBranchPrev);
+        assert_eq!(editor.get_buffer(), "ls -a");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "ls -a");
+        editor.run_edit_command(&EditCommand::UndoTreeBranchNext);
+        assert_eq!(editor.get_buffer(), "ls -l");
+    }
+
     #[test]
     fn test_undo_backspace_works_on_word_boundaries() {
         let mut editor = editor_with("This is  a test");
@@ -829,4 +1462,528 @@ mod test {
         editor.run_edit_command(&EditCommand::Undo);
         assert_eq!(editor.get_buffer(), "This \r\n is a test");
     }
+
+    #[rstest]
+    #[case("echo \"abc\" x", 2, "echo \"\" x", 6)]
+    #[case("echo \"abc\" x", 7, "echo \"\" x", 6)]
+    #[case("echo \"abc\" x", 11, "echo \"abc\" x", 11)]
+    fn test_cut_inner_quotes(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CutTextObject(TextObject::Delimited {
+            open: '"',
+            close: '"',
+            inner: true,
+        }));
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("f(a)", 1, 3)]
+    #[case("f(a)", 4, 1)]
+    #[case("f(a) ", 5, 5)]
+    #[case("f(a", 1, 1)]
+    fn test_move_to_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::MoveToMatchingBracket { select: false });
+
+        assert_eq!(editor.insertion_point(), expected_position);
+        assert_eq!(editor.command_failed(), position == expected_position);
+    }
+
+    #[rstest]
+    #[case("(a (b) c)", 1, "()", 1)]
+    #[case("(a (b) c)", 0, "()", 1)]
+    #[case("(a (b) c)", 3, "(a () c)", 4)]
+    #[case("(a (b) c)", 4, "(a () c)", 4)]
+    #[case("(a (b) c)", 5, "(a () c)", 4)]
+    #[case("(a (b) c)", 7, "()", 1)]
+    #[case("(a \")\" c)", 2, "()", 1)]
+    #[case("a (b) c", 6, "a (b) c", 6)]
+    fn test_delete_inside_brackets(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::DeleteInsideBrackets);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        assert_eq!(editor.command_failed(), input == expected);
+    }
+
+    #[rstest]
+    #[case("(a (b) c)", 4, "(a  c)", 3)]
+    #[case("(a (b) c)", 5, "(a  c)", 3)]
+    #[case("(a (b) c)", 1, "", 0)]
+    #[case("a (b) c", 0, "a (b) c", 0)]
+    fn test_delete_to_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::DeleteToMatchingBracket);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        assert_eq!(editor.command_failed(), input == expected);
+    }
+
+    fn editor_with_kill_ring(buffer: &str) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.set_cut_buffer(Box::new(KillRing::new(10)));
+        editor
+    }
+
+    #[test]
+    fn test_consecutive_cuts_are_combined() {
+        let mut editor = editor_with_kill_ring("one two three four");
+
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::CutToEnd);
+        assert_eq!(editor.get_buffer(), "");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "one two ");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "three four");
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_through_entries() {
+        let mut editor = editor_with_kill_ring("a b c");
+
+        for _ in 0..3 {
+            editor.run_edit_command(&EditCommand::CutWordLeft);
+            editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        }
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        assert_eq!(editor.get_buffer(), "  ");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "  a");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "  b");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "  c");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "  a");
+        assert_eq!(editor.insertion_point(), 3);
+
+        // Only directly after a paste
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "  a");
+    }
+
+    #[test]
+    fn test_text_object_cuts_go_through_the_kill_ring() {
+        let mut editor = editor_with_kill_ring("one two three");
+        editor.line_buffer.set_insertion_point(0);
+        let word = TextObject::Word { inner: false };
+
+        editor.run_edit_command(&EditCommand::CutTextObject(word));
+        editor.run_edit_command(&EditCommand::CutTextObject(word));
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        assert_eq!(editor.get_buffer(), "");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "three");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "one two ");
+    }
+
+    #[test]
+    fn test_text_object_copies_go_through_the_kill_ring() {
+        let mut editor = editor_with_kill_ring("one two");
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::CopyTextObject(TextObject::Word {
+            inner: true,
+        }));
+        editor.run_edit_command(&EditCommand::CopyToRegister('a'));
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "twotwo");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "twoone ");
+        editor.run_edit_command(&EditCommand::PasteFromRegister('a'));
+        assert_eq!(editor.get_buffer(), "twoone two");
+    }
+
+    // Clipboard standing for the system clipboard, the content is shared with the test
+    struct MockClipboard(std::sync::Arc<std::sync::Mutex<String>>);
+
+    impl Clipboard for MockClipboard {
+        fn set(&mut self, content: &str, _mode: ClipboardMode) {
+            *self.0.lock().unwrap() = content.to_string();
+        }
+
+        fn get(&mut self) -> (String, ClipboardMode) {
+            (self.0.lock().unwrap().clone(), ClipboardMode::Normal)
+        }
+    }
+
+    #[test]
+    fn cut_copy_and_paste_go_through_the_clipboard() {
+        let content = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+        let mut editor = editor_with("hello world");
+        editor.set_cut_buffer(Box::new(MockClipboard(content.clone())));
+
+        editor.run_edit_command(&EditCommand::MoveWordLeft { select: true });
+        editor.run_edit_command(&EditCommand::CopySelection);
+        assert_eq!(*content.lock().unwrap(), "world");
+
+        editor.run_edit_command(&EditCommand::MoveToStart { select: true });
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(*content.lock().unwrap(), "hello ");
+        assert_eq!(editor.get_buffer(), "world");
+
+        editor.run_edit_command(&EditCommand::CutToEnd);
+        assert_eq!(*content.lock().unwrap(), "world");
+        assert_eq!(editor.get_buffer(), "");
+
+        // Text copied by another application
+        *content.lock().unwrap() = "there".to_string();
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "there");
+    }
+
+    fn editor_with_auto_pairs(buffer: &str, position: usize) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.set_auto_pairs(Some(AutoPairConfig::default()));
+        editor.line_buffer.set_insertion_point(position);
+        editor
+    }
+
+    #[rstest]
+    #[case("f", 1, '(', "f()", 2)]
+    #[case("f()", 2, ')', "f()", 3)]
+    #[case("f(a)", 3, ']', "f(a])", 4)]
+    #[case("echo ", 5, '"', "echo \"\"", 6)]
+    #[case("echo \"a\"", 7, '"', "echo \"a\"", 8)]
+    #[case("don", 3, '\'', "don'", 4)]
+    #[case("f", 1, '<', "f<", 2)]
+    fn test_auto_pairs_insert_char(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] c: char,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with_auto_pairs(input, position);
+
+        editor.run_edit_command(&EditCommand::InsertChar(c));
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[test]
+    fn test_auto_pairs_wrap_selection() {
+        let mut editor = editor_with_auto_pairs("echo abc", 5);
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: true });
+
+        editor.run_edit_command(&EditCommand::InsertChar('['));
+
+        assert_eq!(editor.get_buffer(), "echo [abc]");
+        assert_eq!(editor.insertion_point(), 10);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(EditCommand::SelectAll, Some((0, 11)))]
+    #[case(EditCommand::MoveToLineStart { select: true }, Some((8, 9)))]
+    #[case(EditCommand::MoveToStart { select: true }, Some((0, 9)))]
+    #[case(EditCommand::MoveWordLeft { select: true }, Some((8, 9)))]
+    #[case(EditCommand::MoveToEnd { select: true }, Some((9, 11)))]
+    #[case(EditCommand::MoveToEnd { select: false }, None)]
+    fn test_selection_range(
+        #[case] command: EditCommand,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        let mut editor = editor_with("abc def\nghi");
+        editor.line_buffer.set_insertion_point(9);
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_selection(), expected);
+    }
+
+    #[test]
+    fn test_selection_range_follows_the_cursor_across_lines() {
+        let mut editor = editor_with("abc def\nghi");
+        editor.line_buffer.set_insertion_point(5);
+
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: true });
+        assert_eq!(editor.get_selection(), Some((5, 11)));
+        editor.run_edit_command(&EditCommand::MoveToStart { select: true });
+        assert_eq!(editor.get_selection(), Some((0, 5)));
+        editor.run_edit_command(&EditCommand::SwapCursorAndAnchor);
+        assert_eq!(editor.get_selection(), Some((0, 5)));
+        assert_eq!(editor.insertion_point(), 5);
+    }
+
+    #[rstest]
+    #[case(EditCommand::CutWordLeft)]
+    #[case(EditCommand::CutToLineEnd)]
+    #[case(EditCommand::CutChar)]
+    fn test_cuts_take_the_selection(#[case] command: EditCommand) {
+        let mut editor = editor_with("abc def ghi");
+        editor.line_buffer.set_insertion_point(4);
+        editor.run_edit_command(&EditCommand::MoveToPosition {
+            position: 7,
+            select: true,
+        });
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), "abc  ghi");
+        assert_eq!(editor.cut_buffer.get().0, "def");
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn test_copy_takes_the_selection() {
+        let mut editor = editor_with("abc def ghi");
+        editor.run_edit_command(&EditCommand::MoveToStart { select: true });
+
+        editor.run_edit_command(&EditCommand::CopyTextObject(TextObject::Word {
+            inner: true,
+        }));
+
+        assert_eq!(editor.get_buffer(), "abc def ghi");
+        assert_eq!(editor.cut_buffer.get().0, "abc def ghi");
+    }
+
+    #[rstest]
+    #[case("f()", 2, "f", 1)]
+    #[case("f(a)", 3, "f()", 2)]
+    #[case("f(]", 2, "f]", 1)]
+    fn test_auto_pairs_backspace(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with_auto_pairs(input, position);
+
+        editor.run_edit_command(&EditCommand::Backspace);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[test]
+    fn test_auto_pairs_disabled_by_default() {
+        let mut editor = editor_with("f");
+
+        editor.run_edit_command(&EditCommand::InsertChar('('));
+
+        assert_eq!(editor.get_buffer(), "f(");
+    }
+
+    #[test]
+    fn test_word_boundary_is_kept_by_undo() {
+        let mut editor = Editor::default();
+        editor.set_word_boundary(WordBoundary::Unix);
+        editor.run_edit_command(&EditCommand::InsertString("cd foo-bar".to_string()));
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::Redo);
+
+        editor.run_edit_command(&EditCommand::BackspaceWord);
+
+        assert_eq!(editor.get_buffer(), "cd ");
+    }
+
+    #[test]
+    fn test_vertical_moves_keep_goal_column() {
+        let mut editor = editor_with("long line\nab\nanother line");
+        editor.run_edit_command(&EditCommand::MoveToPosition {
+            position: 7,
+            select: false,
+        });
+
+        editor.move_line_down();
+        assert_eq!(editor.insertion_point(), 12);
+        editor.move_line_down();
+        assert_eq!(editor.insertion_point(), 20);
+        editor.move_line_up();
+        editor.move_line_up();
+        assert_eq!(editor.insertion_point(), 7);
+
+        // A horizontal move sets the new goal column
+        editor.move_line_down();
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        editor.move_line_up();
+        assert_eq!(editor.insertion_point(), 1);
+    }
+
+    #[rstest]
+    #[case(None, 5)]
+    #[case(Some(2), 2)]
+    #[case(Some(10), 5)]
+    #[case(Some(4), 3)]
+    fn test_set_initial_buffer_cursor(#[case] cursor: Option<usize>, #[case] expected: usize) {
+        let mut editor = Editor::default();
+
+        editor.set_initial_buffer("ls é".to_string(), cursor);
+
+        assert_eq!(editor.get_buffer(), "ls é");
+        assert_eq!(editor.insertion_point(), expected);
+    }
+
+    #[test]
+    fn test_initial_buffer_is_not_undone() {
+        let mut editor = Editor::default();
+        editor.set_initial_buffer("ls".to_string(), None);
+
+        editor.run_edit_command(&EditCommand::InsertString(" -la".to_string()));
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+    }
+
+    #[test]
+    fn test_clear_secret_drops_the_undo_history() {
+        let mut editor = editor_with("secret");
+        editor.run_edit_command(&EditCommand::InsertString("!".to_string()));
+
+        editor.clear_secret();
+        editor.run_edit_command(&EditCommand::Undo);
+
+        assert_eq!(editor.get_buffer(), "");
+        assert_eq!(editor.insertion_point(), 0);
+    }
+
+    #[test]
+    fn test_copy_text_object_keeps_buffer() {
+        let mut editor = editor_with("f(a, b)");
+        editor.line_buffer.set_insertion_point(3);
+
+        editor.run_edit_command(&EditCommand::CopyTextObject(TextObject::Delimited {
+            open: '(',
+            close: ')',
+            inner: false,
+        }));
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+
+        assert_eq!(editor.get_buffer(), "f(a, b)(a, b)");
+    }
+
+    #[test]
+    fn test_register_round_trip() {
+        let mut editor = editor_with("foo bar");
+        editor.line_buffer.set_insertion_point(0);
+        let word = TextObject::Word { inner: true };
+
+        editor.run_edit_command(&EditCommand::CopyTextObject(word));
+        editor.run_edit_command(&EditCommand::CopyToRegister('a'));
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::CopyTextObject(word));
+        editor.run_edit_command(&EditCommand::CopyToRegister('b'));
+
+        editor.run_edit_command(&EditCommand::PasteFromRegister('a'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        editor.run_edit_command(&EditCommand::PasteFromRegister('b'));
+
+        assert_eq!(editor.get_buffer(), "foo barfoobarbar");
+    }
+
+    #[test]
+    fn test_uppercase_register_appends() {
+        let mut editor = editor_with("foo bar");
+        editor.line_buffer.set_insertion_point(0);
+        let word = TextObject::Word { inner: true };
+
+        editor.run_edit_command(&EditCommand::CopyTextObject(word));
+        editor.run_edit_command(&EditCommand::CopyToRegister('a'));
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::CopyTextObject(word));
+        editor.run_edit_command(&EditCommand::CopyToRegister('A'));
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::PasteFromRegister('a'));
+
+        assert_eq!(editor.get_buffer(), "foobarfoo bar");
+    }
+
+    #[test]
+    fn test_paste_from_empty_register() {
+        let mut editor = editor_with("foo");
+
+        editor.run_edit_command(&EditCommand::PasteFromRegister('z'));
+
+        assert_eq!(editor.get_buffer(), "foo");
+    }
+
+    #[test]
+    fn test_swap_cursor_and_anchor() {
+        let mut editor = editor_with("foo bar");
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::MoveWordRightStart { select: true });
+        editor.run_edit_command(&EditCommand::SwapCursorAndAnchor);
+
+        assert_eq!(editor.insertion_point(), 0);
+        assert_eq!(editor.get_selection(), Some((0, 4)));
+    }
+
+    #[rstest]
+    #[case(EditCommand::MoveToSelectionStart, 0)]
+    #[case(EditCommand::MoveToSelectionEnd, 4)]
+    #[case(EditCommand::ClearSelection, 4)]
+    fn test_collapse_selection(#[case] command: EditCommand, #[case] expected: usize) {
+        let mut editor = editor_with("foo bar");
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::MoveWordRightStart { select: true });
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.insertion_point(), expected);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn test_toggle_case_of_selection() {
+        let mut editor = editor_with("Foo bar");
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::MoveWordRightStart { select: true });
+        editor.run_edit_command(&EditCommand::ToggleCase);
+
+        assert_eq!(editor.get_buffer(), "fOO bar");
+        assert_eq!(editor.insertion_point(), 0);
+        assert_eq!(editor.get_selection(), None);
+    }
 }
@@ -1,16 +1,31 @@
 use {
+    super::{CharClass, WordBoundary},
+    crate::utils::text_manipulation,
     itertools::Itertools,
     std::{convert::From, ops::Range},
-    unicode_segmentation::UnicodeSegmentation,
+    unicode_segmentation::{GraphemeCursor, UnicodeSegmentation},
 };
 
 /// In memory representation of the entered line(s) including a cursor position to facilitate cursor based editing.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+///
+/// The words used by the word based movements and edits are defined by its
+/// [`WordBoundary`], by default the Unicode word boundaries.
+#[derive(Debug, Clone, Default)]
 pub struct LineBuffer {
     lines: String,
     insertion_point: usize,
+    word_boundary: WordBoundary,
 }
 
+// The word boundary is a setting of the buffer, not part of its state
+impl PartialEq for LineBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.lines == other.lines && self.insertion_point == other.insertion_point
+    }
+}
+
+impl Eq for LineBuffer {}
+
 impl From<&str> for LineBuffer {
     fn from(input: &str) -> Self {
         let mut line_buffer = LineBuffer::new();
@@ -72,6 +87,16 @@ impl LineBuffer {
         self.insertion_point = offset;
     }
 
+    /// The definition of the words used by the word movements and edits
+    pub fn word_boundary(&self) -> WordBoundary {
+        self.word_boundary
+    }
+
+    /// Set the definition of the words used by the word movements and edits
+    pub fn set_word_boundary(&mut self, word_boundary: WordBoundary) {
+        self.word_boundary = word_boundary;
+    }
+
     /// Output the current line in the multiline buffer
     pub fn get_buffer(&self) -> &str {
         &self.lines
@@ -95,6 +120,54 @@ impl LineBuffer {
         self.lines.split('\n').count()
     }
 
+    /// Number of lines in the buffer, same as [`LineBuffer::num_lines`]
+    ///
+    /// A trailing newline starts an empty last line, e.g. `"a\n"` has two lines.
+    pub fn line_count(&self) -> usize {
+        self.num_lines()
+    }
+
+    /// Line and grapheme column of the insertion point, both zero-based
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        (self.line(), self.current_column())
+    }
+
+    /// Byte range of the zero-based `line` without the terminating `\n` or `\r\n`
+    ///
+    /// Lines past the last one are the empty range at the end of the buffer.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = if line == 0 {
+            0
+        } else {
+            match self.lines.match_indices('\n').nth(line - 1) {
+                Some((i, _)) => i + 1,
+                None => return self.lines.len()..self.lines.len(),
+            }
+        };
+        let end = self.lines[start..]
+            .find('\n')
+            .map_or(self.lines.len(), |i| start + i);
+        let end = if self.lines[start..end].ends_with('\r') {
+            end - 1
+        } else {
+            end
+        };
+
+        start..end
+    }
+
+    /// Byte offset of the grapheme at column `col` of the zero-based `line`
+    ///
+    /// The line is clamped to the last line and the column to the end of the line.
+    pub fn offset_of(&self, line: usize, col: usize) -> usize {
+        let range = self.line_range(line.min(self.line_count() - 1));
+
+        self.lines[range.clone()]
+            .grapheme_indices(true)
+            .nth(col)
+            .map_or(range.end, |(i, _)| range.start + i)
+    }
+
     /// Checks to see if the buffer ends with a given character
     pub fn ends_with(&self, c: char) -> bool {
         self.lines.ends_with(c)
@@ -152,27 +225,29 @@ impl LineBuffer {
 
     /// Cursor position *behind* the next unicode grapheme to the right
     pub fn grapheme_right_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .grapheme_indices(true)
-            .nth(1)
-            .map(|(i, _)| self.insertion_point + i)
-            .unwrap_or_else(|| self.lines.len())
+        // The boundaries are searched in the whole buffer, a cursor inside of a
+        // cluster (e.g. a ZWJ sequence) would split it in the text after it
+        GraphemeCursor::new(self.insertion_point, self.lines.len(), true)
+            .next_boundary(&self.lines, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(self.lines.len())
     }
 
     /// Cursor position *in front of* the next unicode grapheme to the left
     pub fn grapheme_left_index(&self) -> usize {
-        self.lines[..self.insertion_point]
-            .grapheme_indices(true)
-            .last()
-            .map(|(i, _)| i)
+        GraphemeCursor::new(self.insertion_point, self.lines.len(), true)
+            .prev_boundary(&self.lines, 0)
+            .ok()
+            .flatten()
             .unwrap_or(0)
     }
 
     /// Cursor position *behind* the next word to the right
     pub fn word_right_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
-            .find(|(_, word)| !is_whitespace_str(word))
+        self.split_words(&self.lines[self.insertion_point..])
+            .into_iter()
+            .find(|(_, word)| !self.is_whitespace_word(word))
             .map(|(i, word)| self.insertion_point + i + word.len())
             .unwrap_or_else(|| self.lines.len())
     }
@@ -193,13 +268,13 @@ impl LineBuffer {
 
     /// Cursor position *at end of* the next word to the right
     pub fn word_right_end_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.split_words(&self.lines[self.insertion_point..])
+            .into_iter()
             .find_map(|(i, word)| {
                 word.grapheme_indices(true)
                     .next_back()
                     .map(|x| self.insertion_point + x.0 + i)
-                    .filter(|x| !is_whitespace_str(word) && *x != self.insertion_point)
+                    .filter(|x| !self.is_whitespace_word(word) && *x != self.insertion_point)
             })
             .unwrap_or_else(|| {
                 self.lines
@@ -237,9 +312,9 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the right
     pub fn word_right_start_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
-            .find(|(i, word)| *i != 0 && !is_whitespace_str(word))
+        self.split_words(&self.lines[self.insertion_point..])
+            .into_iter()
+            .find(|(i, word)| *i != 0 && !self.is_whitespace_word(word))
             .map(|(i, _)| self.insertion_point + i)
             .unwrap_or_else(|| self.lines.len())
     }
@@ -260,9 +335,9 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the left
     pub fn word_left_index(&self) -> usize {
-        self.lines[..self.insertion_point]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
+        self.split_words(&self.lines[..self.insertion_point])
+            .into_iter()
+            .filter(|(_, word)| !self.is_whitespace_word(word))
             .last()
             .map(|(i, _)| i)
             .unwrap_or(0)
@@ -377,6 +452,12 @@ impl LineBuffer {
         self.insertion_point = 0;
     }
 
+    /// Overwrite the text in memory before clearing it, for buffers holding a secret
+    pub fn zeroize(&mut self) {
+        text_manipulation::zeroize(&mut self.lines);
+        self.insertion_point = 0;
+    }
+
     /// Clear everything beginning at the cursor to the right/end.
     /// Keeps the cursor at the end.
     pub fn clear_to_end(&mut self) {
@@ -459,9 +540,10 @@ impl LineBuffer {
     /// Gets the range of the word the current edit position is pointing to
     pub fn current_word_range(&self) -> Range<usize> {
         let right_index = self.word_right_index();
-        let left_index = self.lines[..right_index]
-            .split_word_bound_indices()
-            .filter(|(_, word)| !is_whitespace_str(word))
+        let left_index = self
+            .split_words(&self.lines[..right_index])
+            .into_iter()
+            .filter(|(_, word)| !self.is_whitespace_word(word))
             .last()
             .map(|(i, _)| i)
             .unwrap_or(0);
@@ -485,20 +567,71 @@ impl LineBuffer {
         left_index..right_index
     }
 
-    /// Uppercases the current word
+    /// Range from the insertion point to the end of the next word, the text changed
+    /// by the word case commands
+    fn case_word_range(&self) -> Range<usize> {
+        let right_index = self
+            .split_words(&self.lines[self.insertion_point..])
+            .into_iter()
+            .find(|(_, word)| self.word_boundary.is_word(word))
+            .map(|(i, word)| self.insertion_point + i + word.len())
+            .unwrap_or_else(|| self.lines.len());
+
+        self.insertion_point..right_index
+    }
+
+    /// Replaces the text in `range` with its case changed by `change_case` and moves
+    /// the insertion point behind it
+    fn change_case(&mut self, range: Range<usize>, change_case: impl FnOnce(&str) -> String) {
+        let changed = change_case(&self.lines[range.clone()]);
+        self.replace_range(range.clone(), &changed);
+        self.insertion_point = range.start + changed.len();
+    }
+
+    /// Uppercases the text from the insertion point to the end of the word and moves
+    /// the insertion point behind the word (Emacs `M-u`)
     pub fn uppercase_word(&mut self) {
-        let change_range = self.current_word_range();
-        let uppercased = self.get_buffer()[change_range.clone()].to_uppercase();
-        self.replace_range(change_range, &uppercased);
-        self.move_word_right();
+        self.change_case(self.case_word_range(), str::to_uppercase);
     }
 
-    /// Lowercases the current word
+    /// Lowercases the text from the insertion point to the end of the word and moves
+    /// the insertion point behind the word (Emacs `M-l`)
     pub fn lowercase_word(&mut self) {
-        let change_range = self.current_word_range();
-        let uppercased = self.get_buffer()[change_range.clone()].to_lowercase();
-        self.replace_range(change_range, &uppercased);
-        self.move_word_right();
+        self.change_case(self.case_word_range(), str::to_lowercase);
+    }
+
+    /// Uppercases the first letter from the insertion point and lowercases the rest of
+    /// the word, then moves the insertion point behind the word (Emacs `M-c`)
+    pub fn capitalize_word(&mut self) {
+        self.change_case(self.case_word_range(), |text| {
+            let mut capitalized = String::with_capacity(text.len());
+            let mut graphemes = text.graphemes(true);
+            for grapheme in graphemes.by_ref() {
+                if grapheme.chars().any(char::is_alphanumeric) {
+                    capitalized.push_str(&grapheme.to_uppercase());
+                    break;
+                }
+                capitalized.push_str(grapheme);
+            }
+            capitalized.push_str(&graphemes.as_str().to_lowercase());
+            capitalized
+        });
+    }
+
+    /// Switches the case of the grapheme at the insertion point and moves the insertion
+    /// point behind it (vi `~`)
+    pub fn toggle_case(&mut self) {
+        self.change_case(
+            self.insertion_point..self.grapheme_right_index(),
+            toggle_case,
+        );
+    }
+
+    /// Switches the case of the text in `range` and moves the insertion point to its
+    /// start
+    pub fn toggle_case_range(&mut self, range: Range<usize>) {
+        self.change_case(range.clone(), toggle_case);
+        self.insertion_point = range.start;
     }
 
     /// Switches the ASCII case of the current char
@@ -590,6 +723,42 @@ impl LineBuffer {
         }
     }
 
+    /// Transposes the word around or before the insertion point with the following word
+    /// like emacs and moves the insertion point behind both of them.
+    ///
+    /// The text in between (whitespace and punctuation) stays in place. At the end of the
+    /// buffer the last two words are transposed.
+    pub fn transpose_words(&mut self) {
+        let words: Vec<Range<usize>> = self
+            .split_words(&self.lines)
+            .into_iter()
+            .filter(|(_, word)| self.word_boundary.is_word(word))
+            .map(|(i, word)| i..i + word.len())
+            .collect();
+
+        let current = words
+            .iter()
+            .rposition(|word| word.start < self.insertion_point)
+            .unwrap_or(0);
+        let first = if current + 1 < words.len() {
+            current
+        } else if words.len() >= 2 {
+            words.len() - 2
+        } else {
+            return;
+        };
+
+        let (word_1, word_2) = (words[first].clone(), words[first + 1].clone());
+        let transposed = format!(
+            "{}{}{}",
+            &self.lines[word_2.clone()],
+            &self.lines[word_1.end..word_2.start],
+            &self.lines[word_1.clone()]
+        );
+        self.replace_range(word_1.start..word_2.end, &transposed);
+        self.insertion_point = word_2.end;
+    }
+
     /// Swaps current grapheme with grapheme on right
     pub fn swap_graphemes(&mut self) {
         let initial_offset = self.insertion_point();
@@ -617,13 +786,14 @@ impl LineBuffer {
 
     /// Moves one line up
     pub fn move_line_up(&mut self) {
+        self.move_line_up_to_column(self.current_column());
+    }
+
+    /// Moves one line up onto the grapheme at `grapheme_col` or the end of a shorter line
+    pub fn move_line_up_to_column(&mut self, grapheme_col: usize) {
         if !self.is_cursor_at_first_line() {
             let old_range = self.current_line_range();
 
-            let grapheme_col = self.lines[old_range.start..self.insertion_point()]
-                .graphemes(true)
-                .count();
-
             // Platform independent way to jump to the previous line.
             // Doesn't matter if `\n` or `\r\n` terminated line.
             // Maybe replace with more explicit implementation.
@@ -643,13 +813,14 @@ impl LineBuffer {
 
     /// Moves one line down
     pub fn move_line_down(&mut self) {
+        self.move_line_down_to_column(self.current_column());
+    }
+
+    /// Moves one line down onto the grapheme at `grapheme_col` or the end of a shorter line
+    pub fn move_line_down_to_column(&mut self, grapheme_col: usize) {
         if !self.is_cursor_at_last_line() {
             let old_range = self.current_line_range();
 
-            let grapheme_col = self.lines[old_range.start..self.insertion_point()]
-                .graphemes(true)
-                .count();
-
             // Exclusive range, thus guaranteed to be in the next line
             self.set_insertion_point(old_range.end);
 
@@ -669,6 +840,13 @@ impl LineBuffer {
         }
     }
 
+    /// Number of graphemes between the start of the current line and the insertion point
+    pub fn current_column(&self) -> usize {
+        self.lines[self.current_line_range().start..self.insertion_point()]
+            .graphemes(true)
+            .count()
+    }
+
     /// Checks to see if the cursor is on the first line of the buffer
     pub fn is_cursor_at_first_line(&self) -> bool {
         !self.get_buffer()[0..self.insertion_point()].contains('\n')
@@ -767,6 +945,232 @@ impl LineBuffer {
             self.insertion_point = index + c.len_utf8();
         }
     }
+
+    /// Position of the bracket balancing the one of `()[]{}` at `pos`
+    ///
+    /// Nested pairs are skipped and brackets inside of quoted strings are ignored.
+    /// Returns `None` if there is no bracket at `pos` or it is unbalanced.
+    pub fn matching_bracket(&self, pos: usize) -> Option<usize> {
+        self.bracket_pairs().into_iter().find_map(|(open, close)| {
+            if open == pos {
+                Some(close)
+            } else if close == pos {
+                Some(open)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Positions of the innermost pair of `()[]{}` enclosing the cursor or of the pair
+    /// of the bracket the cursor is on
+    ///
+    /// Like for [`LineBuffer::matching_bracket`] brackets inside of quoted strings are
+    /// ignored.
+    pub fn enclosing_brackets(&self) -> Option<(usize, usize)> {
+        let position = self.cursor_char_index();
+
+        self.bracket_pairs()
+            .into_iter()
+            .filter(|(open, close)| *open <= position && position <= *close)
+            .max_by_key(|(open, _)| *open)
+    }
+
+    // Positions of all balanced bracket pairs outside of quoted strings, in the order
+    // of their closing brackets
+    fn bracket_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        let mut open_brackets: Vec<(usize, char)> = Vec::new();
+        let mut quote = None;
+        let mut escaped = false;
+
+        for (index, c) in self.lines.char_indices() {
+            if let Some(quote_char) = quote {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && quote_char == '"' {
+                    escaped = true;
+                } else if c == quote_char {
+                    quote = None;
+                }
+                continue;
+            }
+
+            match c {
+                '"' | '\'' | '`' => quote = Some(c),
+                '(' | '[' | '{' => open_brackets.push((index, c)),
+                ')' | ']' | '}' => {
+                    let open = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    // Unbalanced closing brackets are skipped
+                    if let Some(&(open_index, _)) =
+                        open_brackets.last().filter(|(_, bracket)| *bracket == open)
+                    {
+                        open_brackets.pop();
+                        pairs.push((open_index, index));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        pairs
+    }
+
+    /// Position of the character the cursor is on in vi normal mode,
+    /// i.e. the last character if the insertion point is at the end of the buffer
+    fn cursor_char_index(&self) -> usize {
+        if self.insertion_point == self.lines.len() {
+            self.grapheme_left_index()
+        } else {
+            self.insertion_point
+        }
+    }
+
+    /// Range of the word text object the cursor is on (vim's `iw` and `aw`)
+    ///
+    /// Words are runs of alphanumeric characters or of punctuation, the whitespace
+    /// between them counts as a word for `inner`. Otherwise the range includes the
+    /// whitespace following the word or, if there is none, the whitespace before it.
+    pub fn word_object_range(&self, inner: bool) -> Range<usize> {
+        let position = self.cursor_char_index();
+        let class = match self.lines[position..].chars().next() {
+            Some(c) => self.word_boundary.char_class(c),
+            None => return position..position,
+        };
+        if class == CharClass::Newline {
+            return position..position;
+        }
+
+        let start = self.class_run_start(position, class);
+        let end = self.class_run_end(position, class);
+        if inner {
+            return start..end;
+        }
+
+        let next_class = self.lines[end..]
+            .chars()
+            .next()
+            .map(|c| self.word_boundary.char_class(c));
+        match (class, next_class) {
+            // The whitespace is extended by the following word
+            (CharClass::Whitespace, Some(next @ (CharClass::Word | CharClass::Punctuation))) => {
+                start..self.class_run_end(end, next)
+            }
+            (CharClass::Whitespace, _) => start..end,
+            (_, Some(CharClass::Whitespace)) => {
+                start..self.class_run_end(end, CharClass::Whitespace)
+            }
+            _ => self.class_run_start(start, CharClass::Whitespace)..end,
+        }
+    }
+
+    // Words and whitespace of the text, as defined by the word boundary
+    fn split_words<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        self.word_boundary.split(text)
+    }
+
+    fn is_whitespace_word(&self, word: &str) -> bool {
+        self.word_boundary.is_whitespace(word)
+    }
+
+    fn class_run_start(&self, position: usize, class: CharClass) -> usize {
+        self.lines[..position]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| self.word_boundary.char_class(*c) == class)
+            .last()
+            .map_or(position, |(i, _)| i)
+    }
+
+    fn class_run_end(&self, position: usize, class: CharClass) -> usize {
+        self.lines[position..]
+            .char_indices()
+            .find(|(_, c)| self.word_boundary.char_class(*c) != class)
+            .map_or(self.lines.len(), |(i, _)| position + i)
+    }
+
+    /// Range of the text object delimited by `open` and `close` around the cursor
+    /// (vim's `i(`, `a(`, `i"`, `a"`, ...)
+    ///
+    /// For brackets the innermost pair enclosing the cursor is used, taking nested
+    /// brackets into account. Identical delimiters like quotes are paired from the start
+    /// of the current line and, like in vim, the next pair on the line is used if the
+    /// cursor is not inside of one. With `inner` the delimiters are not part of the range.
+    ///
+    /// Returns `None` if there is no matching pair.
+    pub fn delimited_range(&self, open: char, close: char, inner: bool) -> Option<Range<usize>> {
+        let (start, end) = if open == close {
+            self.quoted_pair(open)?
+        } else {
+            self.bracket_pair(open, close)?
+        };
+
+        if inner {
+            Some(start + open.len_utf8()..end)
+        } else {
+            Some(start..end + close.len_utf8())
+        }
+    }
+
+    // Positions of the quotes around the cursor or of the next quotes on the line
+    fn quoted_pair(&self, quote: char) -> Option<(usize, usize)> {
+        let position = self.cursor_char_index();
+        let line_range = self.current_line_range();
+        let quotes: Vec<usize> = self.lines[line_range.clone()]
+            .char_indices()
+            .filter(|(_, c)| *c == quote)
+            .map(|(i, _)| line_range.start + i)
+            .collect();
+
+        // An odd number of quotes before the cursor means that it is inside of a pair
+        // or on its closing quote, otherwise the pair starts at or after the cursor
+        let before = quotes.iter().take_while(|i| **i < position).count();
+        let first = before - before % 2;
+
+        Some((*quotes.get(first)?, *quotes.get(first + 1)?))
+    }
+
+    // Positions of the innermost brackets enclosing the cursor
+    fn bracket_pair(&self, open: char, close: char) -> Option<(usize, usize)> {
+        let position = self.cursor_char_index();
+        let start = if self.lines[position..].starts_with(open) {
+            position
+        } else {
+            let mut depth = 0;
+            let (start, _) = self.lines[..position].char_indices().rev().find(|(_, c)| {
+                if *c == close {
+                    depth += 1;
+                } else if *c == open {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            })?;
+            start
+        };
+
+        let content_start = start + open.len_utf8();
+        let mut depth = 0;
+        let (end, _) = self.lines[content_start..].char_indices().find(|(_, c)| {
+            if *c == open {
+                depth += 1;
+            } else if *c == close {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        })?;
+
+        Some((start, content_start + end))
+    }
 }
 
 /// Match any sequence of characters that are considered a word boundary
@@ -774,6 +1178,22 @@ fn is_whitespace_str(s: &str) -> bool {
     s.chars().all(char::is_whitespace)
 }
 
+/// Switches the case of every letter with an upper and lower case, the mapping may
+/// change the length of the text (e.g. `ß` to `SS`)
+fn toggle_case(text: &str) -> String {
+    let mut toggled = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_uppercase() {
+            toggled.extend(c.to_lowercase());
+        } else if c.is_lowercase() {
+            toggled.extend(c.to_uppercase());
+        } else {
+            toggled.push(c);
+        }
+    }
+    toggled
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -878,6 +1298,55 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    const FAMILY: &str = "👨\u{200d}👩\u{200d}👧";
+
+    #[rstest]
+    #[case(FAMILY)]
+    #[case("🇩🇪")]
+    #[case("👍🏽")]
+    #[case("e\u{301}")]
+    fn one_move_passes_the_whole_cluster(#[case] cluster: &str) {
+        let mut line_buffer = buffer_with(&format!("a{cluster}b"));
+        line_buffer.move_left();
+
+        line_buffer.move_left();
+        assert_eq!(line_buffer.insertion_point(), 1);
+        line_buffer.assert_valid();
+
+        line_buffer.move_right();
+        assert_eq!(line_buffer.insertion_point(), 1 + cluster.len());
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case(5, 1, 1 + FAMILY.len())] // After the man
+    #[case(8, 1, 1 + FAMILY.len())] // After the first joiner
+    fn moves_inside_of_a_cluster_land_on_its_boundaries(
+        #[case] position: usize,
+        #[case] left: usize,
+        #[case] right: usize,
+    ) {
+        let mut line_buffer = buffer_with(&format!("a{FAMILY}b"));
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.grapheme_left_index(), left);
+        assert_eq!(line_buffer.grapheme_right_index(), right);
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_the_whole_cluster() {
+        let mut line_buffer = buffer_with(&format!("a{FAMILY}{FAMILY}"));
+
+        line_buffer.delete_left_grapheme();
+        assert_eq!(line_buffer.get_buffer(), format!("a{FAMILY}"));
+        line_buffer.assert_valid();
+
+        line_buffer.move_left();
+        line_buffer.delete_right_grapheme();
+        assert_eq!(line_buffer.get_buffer(), "a");
+        line_buffer.assert_valid();
+    }
+
     #[test]
     fn delete_word_left_works() {
         let mut line_buffer = buffer_with("This is a test");
@@ -930,6 +1399,31 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case(WordBoundary::Unicode, "foo-bar baz", 3, 4)]
+    #[case(WordBoundary::Unix, "foo-bar baz", 7, 0)]
+    #[case(WordBoundary::Alphanumeric, "foo-bar baz", 3, 4)]
+    #[case(WordBoundary::Alphanumeric, "don't", 3, 4)]
+    #[case(WordBoundary::Unix, "a/b c/d", 3, 4)]
+    fn word_motions_follow_the_word_boundary(
+        #[case] word_boundary: WordBoundary,
+        #[case] input: &str,
+        #[case] right_index: usize,
+        #[case] left_index: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_word_boundary(word_boundary);
+
+        line_buffer.set_insertion_point(0);
+        line_buffer.move_word_right();
+        assert_eq!(line_buffer.insertion_point(), right_index);
+
+        line_buffer.set_insertion_point(5);
+        line_buffer.move_word_left();
+        assert_eq!(line_buffer.insertion_point(), left_index);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("This is a test", 13, "This is a tesT", 14)]
     #[case("This is a test", 10, "This is a Test", 11)]
@@ -952,11 +1446,14 @@ mod test {
     }
 
     #[rstest]
-    #[case("This is a test", 13, "This is a TEST", 14)]
+    #[case("This is a test", 13, "This is a tesT", 14)]
     #[case("This is a test", 10, "This is a TEST", 14)]
+    #[case("This is a test", 4, "This IS a test", 7)]
     #[case("", 0, "", 0)]
     #[case("This", 0, "THIS", 4)]
-    #[case("This", 4, "THIS", 4)]
+    #[case("This", 4, "This", 4)]
+    #[case("été là", 0, "ÉTÉ là", 5)]
+    #[case("-- straße", 0, "-- STRASSE", 10)]
     fn uppercase_word_works(
         #[case] input: &str,
         #[case] in_location: usize,
@@ -975,11 +1472,12 @@ mod test {
     }
 
     #[rstest]
-    #[case("This is a TEST", 13, "This is a test", 14)]
+    #[case("This is a TEST", 13, "This is a TESt", 14)]
     #[case("This is a TEST", 10, "This is a test", 14)]
     #[case("", 0, "", 0)]
     #[case("THIS", 0, "this", 4)]
-    #[case("THIS", 4, "this", 4)]
+    #[case("THIS", 4, "THIS", 4)]
+    #[case("ÉTÉ LÀ", 5, "ÉTÉ là", 9)]
     fn lowercase_word_works(
         #[case] input: &str,
         #[case] in_location: usize,
@@ -997,6 +1495,68 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("", 0, "", 0)]
+    #[case("this is a TEST", 0, "This is a TEST", 4)]
+    #[case("this is a TEST", 4, "this Is a TEST", 7)]
+    #[case("this is a TEST", 10, "this is a Test", 14)]
+    #[case("mIDDLE", 1, "mIddle", 6)]
+    #[case("(élan)", 0, "(Élan)", 6)]
+    fn capitalize_word_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.capitalize_word();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("", 0, "", 0)]
+    #[case("a test", 2, "a Test", 3)]
+    #[case("Test", 0, "test", 1)]
+    #[case("a test", 1, "a test", 2)]
+    #[case("été", 0, "Été", 2)]
+    #[case("Été", 2, "ÉTé", 3)]
+    #[case("e\u{301}t", 0, "E\u{301}t", 3)]
+    #[case("ß", 0, "SS", 2)]
+    fn toggle_case_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.toggle_case();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn toggle_case_range_moves_to_its_start() {
+        let mut line_buffer = buffer_with("Grüße aus");
+        line_buffer.toggle_case_range(0..7);
+
+        let mut expected = buffer_with("gRÜSSE aus");
+        expected.set_insertion_point(0);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("", 0, "", 0)]
     #[case("a test", 2, "a Test", 3)]
@@ -1068,6 +1628,36 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("foo bar", 3, "bar foo", 7)]
+    #[case("foo bar", 0, "bar foo", 7)]
+    #[case("foo bar", 4, "bar foo", 7)]
+    #[case("foo bar", 7, "bar foo", 7)]
+    #[case("foo bar baz", 5, "foo baz bar", 11)]
+    #[case("foo bar baz ", 12, "foo baz bar ", 11)]
+    #[case("foo.  bar!", 1, "bar.  foo!", 9)]
+    #[case("über straße", 2, "straße über", 13)]
+    #[case("foo", 1, "foo", 1)]
+    #[case("  -- ", 2, "  -- ", 2)]
+    #[case("", 0, "", 0)]
+    fn transpose_words_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.transpose_words();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("line 1\nline 2", 7, 0)]
     #[case("line 1\nline 2", 8, 1)]
@@ -1597,4 +2187,123 @@ mod test {
 
         assert_eq!(index, expected);
     }
+
+    #[rstest]
+    #[case("abc def ghi", 5, true, 4..7)]
+    #[case("abc def ghi", 5, false, 4..8)]
+    #[case("abc def", 5, false, 3..7)]
+    #[case("abc   def", 4, true, 3..6)]
+    #[case("abc   def", 4, false, 3..9)]
+    #[case("foo.bar", 1, true, 0..3)]
+    #[case("foo.bar", 3, true, 3..4)]
+    #[case("abc", 3, true, 0..3)]
+    #[case("", 0, true, 0..0)]
+    #[case("über straße", 8, true, 6..13)]
+    fn test_word_object_range(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] inner: bool,
+        #[case] expected: Range<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.word_object_range(inner), expected);
+    }
+
+    #[rstest]
+    #[case("echo \"abc\"", 7, '"', '"', true, Some(6..9))]
+    #[case("echo \"abc\"", 7, '"', '"', false, Some(5..10))]
+    #[case("echo \"abc\"", 0, '"', '"', true, Some(6..9))]
+    #[case("echo \"abc\" x", 11, '"', '"', true, None)]
+    #[case("echo \"abc\"", 9, '"', '"', true, Some(6..9))]
+    #[case("\"a\" b \"c\"", 4, '"', '"', true, Some(7..8))]
+    #[case("f(a, (b), c)", 10, '(', ')', true, Some(2..11))]
+    #[case("f(a, (b), c)", 6, '(', ')', true, Some(6..7))]
+    #[case("f(a, (b), c)", 5, '(', ')', false, Some(5..8))]
+    #[case("f(a, (b), c)", 0, '(', ')', true, None)]
+    #[case("f(a", 2, '(', ')', true, None)]
+    #[case("[ä, ö]", 3, '[', ']', true, Some(1..7))]
+    #[case("'né'", 2, '\'', '\'', false, Some(0..5))]
+    fn test_delimited_range(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] open: char,
+        #[case] close: char,
+        #[case] inner: bool,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.delimited_range(open, close, inner), expected);
+    }
+
+    #[rstest]
+    #[case("f(a)", 1, Some(3))]
+    #[case("f(a)", 3, Some(1))]
+    #[case("{ls | each { |x| $x }}", 0, Some(21))]
+    #[case("{ls | each { |x| $x }}", 11, Some(20))]
+    #[case("{ls | each { |x| $x }}", 20, Some(11))]
+    #[case("[a, (b], c)", 0, None)]
+    #[case("[a, (b], c)", 4, Some(10))]
+    #[case("(a))", 3, None)]
+    #[case("((a)", 0, None)]
+    #[case("f(a)", 0, None)]
+    #[case("(\")\" ')')", 0, Some(8))]
+    #[case("(\"\\\")\")", 0, Some(6))]
+    #[case("(ä)", 0, Some(3))]
+    fn test_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let line_buffer = buffer_with(input);
+
+        assert_eq!(line_buffer.matching_bracket(position), expected);
+    }
+
+    #[rstest]
+    #[case("ab\ncd\nef", 3)]
+    #[case("ab\n\n", 3)]
+    #[case("", 1)]
+    fn line_count_counts_the_empty_last_line(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(buffer_with(input).line_count(), expected);
+    }
+
+    #[rstest]
+    #[case("ab\ncd\nef", 0, 0..2)]
+    #[case("ab\ncd\nef", 1, 3..5)]
+    #[case("ab\ncd\nef", 2, 6..8)]
+    #[case("ab\r\n\ncd\n", 0, 0..2)]
+    #[case("ab\r\n\ncd\n", 1, 4..4)]
+    #[case("ab\r\n\ncd\n", 3, 8..8)]
+    #[case("ab\ncd", 5, 5..5)]
+    fn line_range_excludes_the_line_ending(
+        #[case] input: &str,
+        #[case] line: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        assert_eq!(buffer_with(input).line_range(line), expected);
+    }
+
+    #[test]
+    fn offsets_round_trip_through_lines_and_columns() {
+        let mut line_buffer = buffer_with("añb\n\nc🧑‍🔬d\n");
+
+        for offset in 0..=line_buffer.len() {
+            line_buffer.set_insertion_point(offset);
+            if !line_buffer.is_valid() {
+                continue;
+            }
+            let (line, col) = line_buffer.cursor_line_col();
+            assert_eq!(line_buffer.offset_of(line, col), offset);
+        }
+
+        assert_eq!(line_buffer.offset_of(0, 2), 3);
+        assert_eq!(line_buffer.offset_of(1, 3), 5);
+        assert_eq!(line_buffer.offset_of(2, 2), line_buffer.len() - 2);
+        assert_eq!(line_buffer.offset_of(2, 9), line_buffer.len() - 1);
+        assert_eq!(line_buffer.offset_of(9, 0), line_buffer.len());
+    }
 }
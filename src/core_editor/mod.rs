@@ -1,8 +1,16 @@
+mod auto_pairs;
 mod clip_buffer;
-mod edit_stack;
 mod editor;
 mod line_buffer;
+mod snippet;
+mod undo_tree;
+mod word_boundary;
 
-pub(crate) use clip_buffer::{get_default_clipboard, Clipboard, ClipboardMode};
+pub use auto_pairs::AutoPairConfig;
+pub(crate) use clip_buffer::{
+    get_default_clipboard, Clipboard, ClipboardMode, CutDirection, KillRing, LocalClipboard,
+};
 pub use editor::Editor;
 pub use line_buffer::LineBuffer;
+pub(crate) use snippet::{Snippet, TabStops};
+pub use word_boundary::{CharClass, WordBoundary};
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+/// Text of a snippet with its tab stops, see [`Suggestion::snippet`](crate::Suggestion::snippet)
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Snippet {
+    pub text: String,
+    /// Ranges of the placeholders in the order they are visited, ending with the
+    /// final cursor position
+    pub tab_stops: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    /// Parses the tab stops `$1` or `${1:placeholder}` of the body, `$0` is the final
+    /// cursor position, by default the end of the text. `\$` is a literal `$`
+    pub fn parse(body: &str) -> Self {
+        let mut text = String::with_capacity(body.len());
+        let mut tab_stops: Vec<(usize, Range<usize>)> = Vec::new();
+        let mut rest = body;
+
+        while let Some(index) = rest.find(['$', '\\']) {
+            text.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            if let Some(escaped) = rest.strip_prefix("\\$") {
+                text.push('$');
+                rest = escaped;
+            } else if let Some((number, placeholder, remainder)) = parse_tab_stop(rest) {
+                let start = text.len();
+                text.push_str(placeholder);
+                tab_stops.push((number, start..text.len()));
+                rest = remainder;
+            } else {
+                // Not a tab stop, the character is kept
+                let c = rest.chars().next().unwrap_or_default();
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        text.push_str(rest);
+
+        if !tab_stops.iter().any(|(number, _)| *number == 0) {
+            tab_stops.push((0, text.len()..text.len()));
+        }
+        // The tab stops are visited by their number, the final position comes last
+        tab_stops.sort_by_key(|(number, _)| if *number == 0 { usize::MAX } else { *number });
+        let tab_stops = tab_stops.into_iter().map(|(_, range)| range).collect();
+
+        Self { text, tab_stops }
+    }
+}
+
+// Number, placeholder and remaining text of the tab stop at the start of the text
+fn parse_tab_stop(text: &str) -> Option<(usize, &str, &str)> {
+    let text = text.strip_prefix('$')?;
+    if let Some(braced) = text.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let (number, placeholder) = match braced[..end].split_once(':') {
+            Some((number, placeholder)) => (number, placeholder),
+            None => (&braced[..end], ""),
+        };
+        Some((number.parse().ok()?, placeholder, &braced[end + 1..]))
+    } else {
+        let digits = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        Some((text[..digits].parse().ok()?, "", &text[digits..]))
+    }
+}
+
+/// Tab stops of an inserted snippet, kept in place while the buffer is edited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TabStops {
+    ranges: Vec<Range<usize>>,
+    active: usize,
+}
+
+impl TabStops {
+    pub fn new(ranges: Vec<Range<usize>>) -> Option<Self> {
+        (!ranges.is_empty()).then_some(Self { ranges, active: 0 })
+    }
+
+    /// Range of the placeholder the cursor is on
+    pub fn active(&self) -> Range<usize> {
+        self.ranges[self.active].clone()
+    }
+
+    /// Whether the active tab stop is the final cursor position
+    pub fn is_finished(&self) -> bool {
+        self.active + 1 == self.ranges.len()
+    }
+
+    /// Activates the next or the previous tab stop, the first one stays active
+    pub fn step(&mut self, forward: bool) {
+        self.active = if forward {
+            (self.active + 1).min(self.ranges.len() - 1)
+        } else {
+            self.active.saturating_sub(1)
+        };
+    }
+
+    /// Moves the tab stops after an edit changing the text at `start..old_end` into
+    /// `start..new_end`. The placeholder containing the edit grows or shrinks with it.
+    /// Returns false if the edit overlaps a placeholder without being inside of it
+    pub fn update(&mut self, start: usize, old_end: usize, new_end: usize) -> bool {
+        let active = self.active;
+        let contains = |range: &Range<usize>| range.start <= start && old_end <= range.end;
+        // Adjacent placeholders all contain an edit at their common bound, the
+        // active one gets it
+        let growing = if contains(&self.ranges[active]) {
+            Some(active)
+        } else {
+            self.ranges.iter().position(contains)
+        };
+
+        for (index, range) in self.ranges.iter_mut().enumerate() {
+            if Some(index) == growing {
+                range.end = range.end + new_end - old_end;
+            } else if range.start >= old_end {
+                *range = range.start + new_end - old_end..range.end + new_end - old_end;
+            } else if range.end > start {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("cp ${1:src} ${2:dst}", "cp src dst", vec![3..6, 7..10, 10..10])]
+    #[case("echo $1; $0ls", "echo ; ls", vec![5..5, 7..7])]
+    #[case("a ${2:b} ${1:c}", "a b c", vec![4..5, 2..3, 5..5])]
+    #[case("cost \\$5 ${1}", "cost $5 ", vec![8..8, 8..8])]
+    #[case("$HOME ${x}", "$HOME ${x}", vec![10..10])]
+    fn snippets_are_parsed(
+        #[case] body: &str,
+        #[case] text: &str,
+        #[case] tab_stops: Vec<Range<usize>>,
+    ) {
+        assert_eq!(
+            Snippet::parse(body),
+            Snippet {
+                text: text.to_string(),
+                tab_stops
+            }
+        );
+    }
+
+    #[test]
+    fn edits_move_the_following_tab_stops() {
+        let mut tab_stops = TabStops::new(vec![3..6, 7..10, 10..10]).unwrap();
+
+        // "src" replaced by "a"
+        assert!(tab_stops.update(3, 6, 4));
+        assert_eq!(tab_stops.ranges, vec![3..4, 5..8, 8..8]);
+
+        tab_stops.step(true);
+        // Typing at the end of "dst"
+        assert!(tab_stops.update(8, 8, 9));
+        assert_eq!(tab_stops.ranges, vec![3..4, 5..9, 9..9]);
+
+        // Deleting across the placeholders
+        assert!(!tab_stops.update(2, 6, 2));
+    }
+}
@@ -0,0 +1,233 @@
+#[derive(Debug, PartialEq, Eq)]
+struct UndoNode<T> {
+    value: T,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Position in `children` of the branch a redo follows
+    active_child: usize,
+}
+
+impl<T> UndoNode<T> {
+    const fn new(value: T, parent: Option<usize>) -> Self {
+        UndoNode {
+            value,
+            parent,
+            children: Vec::new(),
+            active_child: 0,
+        }
+    }
+}
+
+/// Undo history keeping every state as a node of a tree.
+///
+/// An insert after going back with undo starts a new branch next to the states that
+/// could be redone before. Those are kept as sibling branches and can be switched to.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UndoTree<T> {
+    nodes: Vec<UndoNode<T>>,
+    index: usize,
+}
+
+impl<T> UndoTree<T> {
+    pub fn new() -> Self
+    where
+        T: Default,
+    {
+        UndoTree {
+            nodes: vec![UndoNode::new(T::default(), None)],
+            index: 0,
+        }
+    }
+}
+
+impl<T> UndoTree<T>
+where
+    T: Default + Clone + Send,
+{
+    /// Go back to the parent state. If present on the initial state do nothing
+    pub(super) fn undo(&mut self) -> &T {
+        if let Some(parent) = self.nodes[self.index].parent {
+            self.index = parent;
+        }
+        &self.nodes[self.index].value
+    }
+
+    /// Whether there is a parent state to go back to
+    pub(super) fn can_undo(&self) -> bool {
+        self.nodes[self.index].parent.is_some()
+    }
+
+    /// Whether there is a child state on the active branch to go forward to
+    pub(super) fn can_redo(&self) -> bool {
+        let node = &self.nodes[self.index];
+        node.children.get(node.active_child).is_some()
+    }
+
+    /// Go forward to the child state on the active branch. If present on the last edit do
+    /// nothing
+    pub(super) fn redo(&mut self) -> &T {
+        let node = &self.nodes[self.index];
+        if let Some(&child) = node.children.get(node.active_child) {
+            self.index = child;
+        }
+        &self.nodes[self.index].value
+    }
+
+    /// Insert a new state as child of the current one, keeping the existing children as
+    /// other branches
+    pub(super) fn insert(&mut self, value: T) {
+        let new_index = self.nodes.len();
+        self.nodes.push(UndoNode::new(value, Some(self.index)));
+
+        let current = &mut self.nodes[self.index];
+        current.children.push(new_index);
+        current.active_child = current.children.len() - 1;
+        self.index = new_index;
+    }
+
+    /// Replace the value of the current state, on the initial state a new one is inserted
+    pub(super) fn replace_current(&mut self, value: T) {
+        if self.index == 0 {
+            self.insert(value);
+        } else {
+            self.nodes[self.index].value = value;
+        }
+    }
+
+    /// Switch to the next sibling branch of the current state. If there is none do nothing
+    pub(super) fn next_branch(&mut self) -> &T {
+        self.switch_branch(true)
+    }
+
+    /// Switch to the previous sibling branch of the current state. If there is none do
+    /// nothing
+    pub(super) fn previous_branch(&mut self) -> &T {
+        self.switch_branch(false)
+    }
+
+    fn switch_branch(&mut self, next: bool) -> &T {
+        if let Some(parent) = self.nodes[self.index].parent {
+            let parent = &mut self.nodes[parent];
+            let position = parent
+                .children
+                .iter()
+                .position(|child| *child == self.index)
+                .unwrap_or(parent.active_child);
+            let new_position = if next {
+                (position + 1).min(parent.children.len() - 1)
+            } else {
+                position.saturating_sub(1)
+            };
+            parent.active_child = new_position;
+            self.index = parent.children[new_position];
+        }
+        &self.nodes[self.index].value
+    }
+
+    /// Reset the tree to the initial state
+    pub(super) fn reset(&mut self) {
+        self.reset_to(T::default());
+    }
+
+    /// Reset the tree with `value` as the initial state
+    pub(super) fn reset_to(&mut self, value: T) {
+        self.index = 0;
+        self.nodes = vec![UndoNode::new(value, None)];
+    }
+
+    /// Reset the tree to the initial state, passing every dropped state to `clear` first
+    pub(super) fn reset_with(&mut self, clear: impl FnMut(&mut T)) {
+        self.nodes
+            .iter_mut()
+            .map(|node| &mut node.value)
+            .for_each(clear);
+        self.reset();
+    }
+
+    /// Return the entry currently being pointed to
+    pub(super) fn current(&mut self) -> &T {
+        &self.nodes[self.index].value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    // Linear history starting at the default value `0`, positioned on `index`
+    fn undo_tree(values: &[isize], index: usize) -> UndoTree<isize> {
+        let mut tree = UndoTree::new();
+        for value in values {
+            tree.insert(*value);
+        }
+        for _ in index..values.len() {
+            tree.undo();
+        }
+        tree
+    }
+
+    #[rstest]
+    #[case(undo_tree(&[1, 2, 3], 3), 2)]
+    #[case(undo_tree(&[], 0), 0)]
+    fn undo_works(#[case] tree: UndoTree<isize>, #[case] value_after_undo: isize) {
+        let mut tree = tree;
+
+        let value = tree.undo();
+        assert_eq!(*value, value_after_undo);
+    }
+
+    #[rstest]
+    #[case(undo_tree(&[1, 2, 3], 1), 2)]
+    #[case(undo_tree(&[1, 2, 3], 3), 3)]
+    #[case(undo_tree(&[], 0), 0)]
+    fn redo_works(#[case] tree: UndoTree<isize>, #[case] value_after_redo: isize) {
+        let mut tree = tree;
+
+        let value = tree.redo();
+        assert_eq!(*value, value_after_redo);
+    }
+
+    #[test]
+    fn insert_after_undo_keeps_the_branch() {
+        let mut tree = undo_tree(&[1, 2, 3], 1);
+
+        tree.insert(4);
+        assert_eq!(*tree.redo(), 4);
+        assert_eq!(*tree.previous_branch(), 2);
+        assert_eq!(*tree.redo(), 3);
+        assert_eq!(*tree.redo(), 3);
+
+        tree.undo();
+        assert_eq!(*tree.next_branch(), 4);
+        assert_eq!(*tree.next_branch(), 4);
+        assert_eq!(*tree.undo(), 1);
+        assert_eq!(*tree.redo(), 4);
+    }
+
+    #[test]
+    fn reset_to_sets_the_initial_state() {
+        let mut tree = undo_tree(&[1, 2], 2);
+
+        tree.reset_to(5);
+        assert_eq!(*tree.current(), 5);
+        assert_eq!(*tree.undo(), 5);
+
+        tree.insert(6);
+        assert_eq!(*tree.undo(), 5);
+    }
+
+    #[test]
+    fn replace_current_keeps_the_initial_state() {
+        let mut tree = undo_tree(&[1], 1);
+        tree.replace_current(2);
+        assert_eq!(*tree.current(), 2);
+        assert_eq!(*tree.undo(), 0);
+
+        tree.replace_current(3);
+        assert_eq!(*tree.current(), 3);
+        assert_eq!(*tree.undo(), 0);
+        assert_eq!(*tree.previous_branch(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Classes of characters that make up words, see [`WordBoundary`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    /// Separates words, except for newlines
+    Whitespace,
+    /// Ends a line, it is never part of a word
+    Newline,
+    /// Letters, digits and other characters of words
+    Word,
+    /// Characters forming words of their own between words
+    Punctuation,
+}
+
+impl CharClass {
+    /// Class of the character like in vim: letters, digits and `_` make up words
+    pub fn of(c: char) -> Self {
+        if c == '\n' {
+            CharClass::Newline
+        } else if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if c.is_alphanumeric() || c == '_' {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// Definition of the words used by the word movements and edits of the
+/// [`LineBuffer`](crate::LineBuffer), e.g. [`EditCommand::MoveWordLeft`](crate::EditCommand::MoveWordLeft)
+/// or [`EditCommand::BackspaceWord`](crate::EditCommand::BackspaceWord).
+///
+/// The big word (`WORD`) variants always end at whitespace, so in vi mode `w`
+/// uses the configured words and `W` the words separated by whitespace.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum WordBoundary {
+    /// The Unicode word boundaries, e.g. `foo-bar` is three words and `don't` one
+    #[default]
+    Unicode,
+    /// Words are separated by whitespace, like the unix line discipline:
+    /// `foo-bar` is one word
+    Unix,
+    /// Runs of letters, digits and `_` are words and every run of other characters
+    /// is a word of its own: `foo-bar` is three words
+    Alphanumeric,
+    /// Words are the runs of characters of the same class
+    Custom(fn(char) -> CharClass),
+}
+
+impl WordBoundary {
+    /// Class of the character for these words
+    pub fn char_class(&self, c: char) -> CharClass {
+        match self {
+            WordBoundary::Unicode | WordBoundary::Alphanumeric => CharClass::of(c),
+            WordBoundary::Unix => match CharClass::of(c) {
+                CharClass::Punctuation => CharClass::Word,
+                class => class,
+            },
+            WordBoundary::Custom(char_class) => char_class(c),
+        }
+    }
+
+    /// Splits the text into words and the whitespace between them, with their byte
+    /// offsets
+    pub(crate) fn split<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        if let WordBoundary::Unicode = self {
+            return text.split_word_bound_indices().collect();
+        }
+
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut previous_class = None;
+        for (index, c) in text.char_indices() {
+            let class = self.char_class(c);
+            if let Some(previous_class) = previous_class {
+                if class != previous_class || class == CharClass::Newline {
+                    segments.push((start, &text[start..index]));
+                    start = index;
+                }
+            }
+            previous_class = Some(class);
+        }
+        if start < text.len() {
+            segments.push((start, &text[start..]));
+        }
+
+        segments
+    }
+
+    /// Whether the segment consists of whitespace only
+    pub(crate) fn is_whitespace(&self, segment: &str) -> bool {
+        segment.chars().all(|c| {
+            matches!(
+                self.char_class(c),
+                CharClass::Whitespace | CharClass::Newline
+            )
+        })
+    }
+
+    /// Whether the segment contains a word character
+    pub(crate) fn is_word(&self, segment: &str) -> bool {
+        segment
+            .chars()
+            .any(|c| self.char_class(c) == CharClass::Word)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn path_separators(c: char) -> CharClass {
+        match c {
+            '/' | ' ' => CharClass::Whitespace,
+            _ => CharClass::Word,
+        }
+    }
+
+    #[rstest]
+    #[case(WordBoundary::Unicode, "foo-bar", vec!["foo", "-", "bar"])]
+    #[case(WordBoundary::Unix, "foo-bar", vec!["foo-bar"])]
+    #[case(WordBoundary::Alphanumeric, "foo-bar", vec!["foo", "-", "bar"])]
+    #[case(WordBoundary::Unicode, "don't", vec!["don't"])]
+    #[case(WordBoundary::Alphanumeric, "don't --all", vec!["don", "'", "t", " ", "--", "all"])]
+    #[case(WordBoundary::Unix, "a  b\n\nc", vec!["a", "  ", "b", "\n", "\n", "c"])]
+    #[case(WordBoundary::Custom(path_separators), "/usr/a-b", vec!["/", "usr", "/", "a-b"])]
+    #[case(WordBoundary::Alphanumeric, "", vec![])]
+    fn split_works(
+        #[case] word_boundary: WordBoundary,
+        #[case] text: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        let segments: Vec<&str> = word_boundary
+            .split(text)
+            .into_iter()
+            .map(|(_, segment)| segment)
+            .collect();
+
+        assert_eq!(segments, expected);
+    }
+
+    #[test]
+    fn custom_whitespace_separates_words() {
+        let word_boundary = WordBoundary::Custom(path_separators);
+
+        assert!(word_boundary.is_whitespace("/ /"));
+        assert!(!word_boundary.is_word("/"));
+        assert!(word_boundary.is_word("a-b"));
+    }
+}
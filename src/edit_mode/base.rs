@@ -7,10 +7,21 @@ use crate::{
 /// Available default options:
 /// - Emacs
 /// - Vi
+/// - Kakoune
 pub trait EditMode: Send {
     /// Translate the given user input event into what the `LineEditor` understands
     fn parse_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent;
 
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
+
+    /// Whether the keys of a started key sequence are held back
+    fn has_pending_keys(&self) -> bool {
+        false
+    }
+
+    /// Process the held back keys after the key sequence timed out
+    fn flush_pending_keys(&mut self) -> ReedlineEvent {
+        ReedlineEvent::None
+    }
 }
@@ -2,7 +2,8 @@ use crate::{
     edit_mode::{
         keybindings::{
             add_common_control_bindings, add_common_edit_bindings, add_common_navigation_bindings,
-            add_common_selection_bindings, edit_bind, Keybindings,
+            add_common_selection_bindings, edit_bind, KeyCombination, KeySequenceBuffer,
+            Keybindings, ResolvedKey,
         },
         EditMode,
     },
@@ -43,7 +44,14 @@ pub fn default_emacs_keybindings() -> Keybindings {
         ]),
     );
     // Undo/Redo
-    kb.add_binding(KM::CONTROL, KC::Char('g'), edit_bind(EC::Redo));
+    kb.add_binding(
+        KM::CONTROL,
+        KC::Char('g'),
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::CancelHistorySearch,
+            edit_bind(EC::Redo),
+        ]),
+    );
     kb.add_binding(KM::CONTROL, KC::Char('z'), edit_bind(EC::Undo));
     // Cutting
     kb.add_binding(
@@ -55,6 +63,7 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::CutToEnd));
     kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('y'), edit_bind(EC::YankPop));
     // Edits
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
 
@@ -89,6 +98,7 @@ pub fn default_emacs_keybindings() -> Keybindings {
     // Edits
     kb.add_binding(KM::ALT, KC::Delete, edit_bind(EC::DeleteWord));
     kb.add_binding(KM::ALT, KC::Backspace, edit_bind(EC::BackspaceWord));
+    kb.add_binding(KM::ALT, KC::Char('t'), edit_bind(EC::TransposeWords));
     kb.add_binding(
         KM::ALT,
         KC::Char('m'),
@@ -97,20 +107,41 @@ pub fn default_emacs_keybindings() -> Keybindings {
     // Case changes
     kb.add_binding(KM::ALT, KC::Char('u'), edit_bind(EC::UppercaseWord));
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
-    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
+    kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeWord));
+
+    // *** Function keys ***
+    // Macros
+    kb.add_binding(
+        KM::NONE,
+        KC::F(3),
+        edit_bind(EC::StartRecordingMacro(EMACS_MACRO_REGISTER)),
+    );
+    kb.add_binding(
+        KM::NONE,
+        KC::F(4),
+        ReedlineEvent::UntilFound(vec![
+            edit_bind(EC::StopRecordingMacro),
+            edit_bind(EC::ReplayMacro(EMACS_MACRO_REGISTER)),
+        ]),
+    );
 
     kb
 }
 
+/// Register of the keyboard macro recorded with `F3` and ended or replayed with `F4`
+const EMACS_MACRO_REGISTER: char = 'e';
+
 /// This parses the incoming Events like a emacs style-editor
 pub struct Emacs {
     keybindings: Keybindings,
+    pending_keys: KeySequenceBuffer,
 }
 
 impl Default for Emacs {
     fn default() -> Self {
         Emacs {
             keybindings: default_emacs_keybindings(),
+            pending_keys: KeySequenceBuffer::default(),
         }
     }
 }
@@ -120,69 +151,105 @@ impl EditMode for Emacs {
         match event.into() {
             Event::Key(KeyEvent {
                 code, modifiers, ..
-            }) => match (modifiers, code) {
-                (modifier, KeyCode::Char(c)) => {
-                    // Note. The modifier can also be a combination of modifiers, for
-                    // example:
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-                    //
-                    // Mixed modifiers are used by non american keyboards that have extra
-                    // keys like 'alt gr'. Keep this in mind if in the future there are
-                    // cases where an event is not being captured
-                    let c = match modifier {
-                        KeyModifiers::NONE => c,
-                        _ => c.to_ascii_lowercase(),
-                    };
-
-                    self.keybindings
-                        .find_binding(modifier, KeyCode::Char(c))
-                        .unwrap_or_else(|| {
-                            if modifier == KeyModifiers::NONE
-                                || modifier == KeyModifiers::SHIFT
-                                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
-                                || modifier
-                                    == KeyModifiers::CONTROL
-                                        | KeyModifiers::ALT
-                                        | KeyModifiers::SHIFT
-                            {
-                                ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                                    if modifier == KeyModifiers::SHIFT {
-                                        c.to_ascii_uppercase()
-                                    } else {
-                                        c
-                                    },
-                                )])
-                            } else {
-                                ReedlineEvent::None
-                            }
-                        })
-                }
-                _ => self
-                    .keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
-            },
-
-            Event::Mouse(_) => ReedlineEvent::Mouse,
+            }) => {
+                // Note. The modifier can also be a combination of modifiers, for
+                // example:
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                //
+                // Mixed modifiers are used by non american keyboards that have extra
+                // keys like 'alt gr'. Keep this in mind if in the future there are
+                // cases where an event is not being captured
+                let key_code = match (modifiers, code) {
+                    (KeyModifiers::NONE, code) => code,
+                    (_, KeyCode::Char(c)) => KeyCode::Char(c.to_ascii_lowercase()),
+                    (_, code) => code,
+                };
+                let key = KeyCombination {
+                    modifier: modifiers,
+                    key_code,
+                };
+
+                let resolved = self.pending_keys.push(&self.keybindings, key);
+                self.resolved_keys_event(resolved)
+            }
+
+            Event::Mouse(mouse) => ReedlineEvent::Mouse(mouse.into()),
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
             Event::FocusGained => ReedlineEvent::None,
             Event::FocusLost => ReedlineEvent::None,
-            Event::Paste(body) => ReedlineEvent::Edit(vec![EditCommand::InsertString(
-                body.replace("\r\n", "\n").replace('\r', "\n"),
-            )]),
+            Event::Paste(body) => ReedlineEvent::Paste(body),
         }
     }
 
     fn edit_mode(&self) -> PromptEditMode {
         PromptEditMode::Emacs
     }
+
+    fn has_pending_keys(&self) -> bool {
+        self.pending_keys.is_pending()
+    }
+
+    fn flush_pending_keys(&mut self) -> ReedlineEvent {
+        let resolved = self.pending_keys.flush(&self.keybindings);
+        self.resolved_keys_event(resolved)
+    }
 }
 
 impl Emacs {
     /// Emacs style input parsing constructor if you want to use custom keybindings
     pub const fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+        Emacs {
+            keybindings,
+            pending_keys: KeySequenceBuffer::new(),
+        }
+    }
+
+    fn resolved_keys_event(&self, resolved: Vec<ResolvedKey>) -> ReedlineEvent {
+        let mut events: Vec<ReedlineEvent> = resolved
+            .into_iter()
+            .map(|resolved| match resolved {
+                ResolvedKey::Key(key) => self.key_event(key),
+                ResolvedKey::Sequence(event) => event,
+            })
+            .collect();
+
+        match events.len() {
+            0 => ReedlineEvent::None,
+            1 => events.remove(0),
+            _ => ReedlineEvent::Multiple(events),
+        }
+    }
+
+    fn key_event(&self, key: KeyCombination) -> ReedlineEvent {
+        let KeyCombination { modifier, key_code } = key;
+        match key_code {
+            KeyCode::Char(c) => self
+                .keybindings
+                .find_binding(modifier, key_code)
+                .unwrap_or_else(|| {
+                    if modifier == KeyModifiers::NONE
+                        || modifier == KeyModifiers::SHIFT
+                        || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                        || modifier
+                            == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                    {
+                        ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                            if modifier == KeyModifiers::SHIFT {
+                                c.to_ascii_uppercase()
+                            } else {
+                                c
+                            },
+                        )])
+                    } else {
+                        ReedlineEvent::None
+                    }
+                }),
+            _ => self
+                .keybindings
+                .find_binding(modifier, key_code)
+                .unwrap_or(ReedlineEvent::None),
+        }
     }
 }
 
@@ -204,6 +271,16 @@ mod test {
         assert_eq!(result, ReedlineEvent::ClearScreen);
     }
 
+    #[test]
+    fn pasted_newlines_do_not_submit() {
+        let mut emacs = Emacs::default();
+        let paste =
+            ReedlineRawEvent::convert_from(Event::Paste("ls\r\necho a\n".to_string())).unwrap();
+        let result = emacs.parse_event(paste);
+
+        assert_eq!(result, ReedlineEvent::Paste("ls\r\necho a\n".to_string()));
+    }
+
     #[test]
     fn overriding_default_keybindings_works() {
         let mut keybindings = default_emacs_keybindings();
@@ -288,4 +365,46 @@ mod test {
             ReedlineEvent::Edit(vec![EditCommand::InsertChar('😀')])
         );
     }
+
+    #[test]
+    fn key_sequence_is_resolved() {
+        let ctrl = |c| KeyCombination {
+            modifier: KeyModifiers::CONTROL,
+            key_code: KeyCode::Char(c),
+        };
+        let key = |modifiers, c| {
+            ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers)))
+                .unwrap()
+        };
+        // Ctrl-x on its own cuts the selection
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_sequence(&[ctrl('x'), ctrl('s')], ReedlineEvent::Submit);
+        let mut emacs = Emacs::new(keybindings);
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'x')),
+            ReedlineEvent::None
+        );
+        assert!(emacs.has_pending_keys());
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 's')),
+            ReedlineEvent::Submit
+        );
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'a')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::CutSelection]),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')])
+            ])
+        );
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            emacs.flush_pending_keys(),
+            ReedlineEvent::Edit(vec![EditCommand::CutSelection])
+        );
+        assert!(!emacs.has_pending_keys());
+    }
 }
@@ -0,0 +1,327 @@
+use crate::{
+    edit_mode::{
+        keybindings::{
+            add_common_control_bindings, add_common_edit_bindings, add_common_navigation_bindings,
+            add_common_selection_bindings, edit_bind, Keybindings,
+        },
+        EditMode,
+    },
+    enums::{EditCommand, ReedlineEvent, ReedlineRawEvent},
+    PromptEditMode,
+};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+/// Returns the current default Kakoune normal mode keybindings
+///
+/// Movements replace the selection with the text they move over and extend it when
+/// combined with shift, the operators act on the current selection.
+pub fn default_kakoune_normal_keybindings() -> Keybindings {
+    use EditCommand as EC;
+    use KeyCode as KC;
+    use KeyModifiers as KM;
+
+    let mut kb = Keybindings::new();
+    add_common_control_bindings(&mut kb);
+    add_common_navigation_bindings(&mut kb);
+    add_common_selection_bindings(&mut kb);
+
+    kb.add_binding(KM::NONE, KC::Enter, ReedlineEvent::Enter);
+
+    // Selecting movements
+    add_selecting_movement(&mut kb, 'h', EC::MoveLeft { select: true });
+    add_selecting_movement(&mut kb, 'l', EC::MoveRight { select: true });
+    add_selecting_movement(&mut kb, 'w', EC::MoveWordRightStart { select: true });
+    add_selecting_movement(&mut kb, 'e', EC::MoveWordRightEnd { select: true });
+    add_selecting_movement(&mut kb, 'b', EC::MoveWordLeft { select: true });
+    kb.add_binding(
+        KM::NONE,
+        KC::Char('j'),
+        ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuDown, ReedlineEvent::Down]),
+    );
+    kb.add_binding(
+        KM::NONE,
+        KC::Char('k'),
+        ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuUp, ReedlineEvent::Up]),
+    );
+    kb.add_binding(
+        KM::NONE,
+        KC::Char('x'),
+        ReedlineEvent::Edit(vec![
+            EC::MoveToLineStart { select: false },
+            EC::MoveToLineEnd { select: true },
+        ]),
+    );
+    kb.add_binding(KM::NONE, KC::Char('%'), edit_bind(EC::SelectAll));
+    kb.add_binding(KM::SHIFT, KC::Char('%'), edit_bind(EC::SelectAll));
+    kb.add_binding(KM::NONE, KC::Char(';'), edit_bind(EC::ClearSelection));
+    kb.add_binding(KM::ALT, KC::Char(';'), edit_bind(EC::SwapCursorAndAnchor));
+
+    // Operators
+    kb.add_binding(KM::NONE, KC::Char('d'), edit_bind(EC::CutSelection));
+    kb.add_binding(KM::NONE, KC::Char('y'), edit_bind(EC::CopySelection));
+    kb.add_binding(
+        KM::NONE,
+        KC::Char('p'),
+        ReedlineEvent::Edit(vec![EC::MoveToSelectionEnd, EC::PasteCutBufferBefore]),
+    );
+    kb.add_binding(
+        KM::SHIFT,
+        KC::Char('p'),
+        ReedlineEvent::Edit(vec![EC::MoveToSelectionStart, EC::PasteCutBufferBefore]),
+    );
+    kb.add_binding(
+        KM::SHIFT,
+        KC::Char('r'),
+        edit_bind(EC::PasteCutBufferBefore),
+    );
+    kb.add_binding(KM::NONE, KC::Char('u'), edit_bind(EC::Undo));
+    kb.add_binding(KM::SHIFT, KC::Char('u'), edit_bind(EC::Redo));
+
+    kb
+}
+
+// The movement replaces the selection, with shift it extends the selection
+fn add_selecting_movement(kb: &mut Keybindings, c: char, movement: EditCommand) {
+    kb.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Char(c),
+        ReedlineEvent::Edit(vec![EditCommand::ClearSelection, movement.clone()]),
+    );
+    kb.add_binding(KeyModifiers::SHIFT, KeyCode::Char(c), edit_bind(movement));
+}
+
+/// Returns the current default Kakoune insert mode keybindings
+pub fn default_kakoune_insert_keybindings() -> Keybindings {
+    let mut kb = Keybindings::new();
+    add_common_control_bindings(&mut kb);
+    add_common_navigation_bindings(&mut kb);
+    add_common_edit_bindings(&mut kb);
+    add_common_selection_bindings(&mut kb);
+
+    kb.add_binding(KeyModifiers::NONE, KeyCode::Enter, ReedlineEvent::Enter);
+
+    kb
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum KakouneMode {
+    Normal,
+    Insert,
+}
+
+/// This parses the incoming Events like the selection-first Kakoune editor
+///
+/// In normal mode the movements select text, `i`, `a` and `c` enter the insert mode
+/// before the selection, after it or by replacing it. `Esc` returns to normal mode.
+pub struct Kakoune {
+    normal_keybindings: Keybindings,
+    insert_keybindings: Keybindings,
+    mode: KakouneMode,
+}
+
+impl Default for Kakoune {
+    fn default() -> Self {
+        Kakoune {
+            normal_keybindings: default_kakoune_normal_keybindings(),
+            insert_keybindings: default_kakoune_insert_keybindings(),
+            mode: KakouneMode::Insert,
+        }
+    }
+}
+
+impl Kakoune {
+    /// A builder that replaces the keybindings of the normal mode
+    #[must_use]
+    pub fn with_normal_keybindings(mut self, keybindings: Keybindings) -> Self {
+        self.normal_keybindings = keybindings;
+        self
+    }
+
+    /// A builder that replaces the keybindings of the insert mode
+    #[must_use]
+    pub fn with_insert_keybindings(mut self, keybindings: Keybindings) -> Self {
+        self.insert_keybindings = keybindings;
+        self
+    }
+
+    fn enter_insert_mode(&mut self, modifier: KeyModifiers, c: char) -> ReedlineEvent {
+        let command = match (modifier, c) {
+            (KeyModifiers::NONE, 'i') => EditCommand::MoveToSelectionStart,
+            (KeyModifiers::NONE, 'a') => EditCommand::MoveToSelectionEnd,
+            (KeyModifiers::NONE, 'c') => EditCommand::CutSelection,
+            _ => return ReedlineEvent::None,
+        };
+        self.mode = KakouneMode::Insert;
+
+        ReedlineEvent::Multiple(vec![
+            ReedlineEvent::Edit(vec![command]),
+            ReedlineEvent::Repaint,
+        ])
+    }
+}
+
+impl EditMode for Kakoune {
+    fn parse_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent {
+        match event.into() {
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => match (self.mode, modifiers, code) {
+                (_, KeyModifiers::NONE, KeyCode::Esc) => {
+                    self.mode = KakouneMode::Normal;
+                    ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+                }
+                (KakouneMode::Normal, modifier, KeyCode::Char(c)) => {
+                    let c = c.to_ascii_lowercase();
+
+                    self.normal_keybindings
+                        .find_binding(modifier, KeyCode::Char(c))
+                        .unwrap_or_else(|| self.enter_insert_mode(modifier, c))
+                }
+                (KakouneMode::Insert, modifier, KeyCode::Char(c)) => {
+                    // The modifier can also be a combination of modifiers used by
+                    // keyboards with extra keys like 'alt gr', see the Emacs mode.
+                    let c = match modifier {
+                        KeyModifiers::NONE => c,
+                        _ => c.to_ascii_lowercase(),
+                    };
+
+                    self.insert_keybindings
+                        .find_binding(modifier, KeyCode::Char(c))
+                        .unwrap_or_else(|| {
+                            if modifier == KeyModifiers::NONE
+                                || modifier == KeyModifiers::SHIFT
+                                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                                || modifier
+                                    == KeyModifiers::CONTROL
+                                        | KeyModifiers::ALT
+                                        | KeyModifiers::SHIFT
+                            {
+                                ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                                    if modifier == KeyModifiers::SHIFT {
+                                        c.to_ascii_uppercase()
+                                    } else {
+                                        c
+                                    },
+                                )])
+                            } else {
+                                ReedlineEvent::None
+                            }
+                        })
+                }
+                (KakouneMode::Normal, _, _) => self
+                    .normal_keybindings
+                    .find_binding(modifiers, code)
+                    .unwrap_or(ReedlineEvent::None),
+                (KakouneMode::Insert, _, _) => self
+                    .insert_keybindings
+                    .find_binding(modifiers, code)
+                    .unwrap_or(ReedlineEvent::None),
+            },
+
+            Event::Mouse(mouse) => ReedlineEvent::Mouse(mouse.into()),
+            Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
+            Event::FocusGained => ReedlineEvent::None,
+            Event::FocusLost => ReedlineEvent::None,
+            Event::Paste(body) => ReedlineEvent::Paste(body),
+        }
+    }
+
+    fn edit_mode(&self) -> PromptEditMode {
+        match self.mode {
+            KakouneMode::Normal => PromptEditMode::Custom("normal".to_string()),
+            KakouneMode::Insert => PromptEditMode::Custom("insert".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(modifiers: KeyModifiers, c: char) -> ReedlineRawEvent {
+        ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers)))
+            .unwrap()
+    }
+
+    fn normal_mode() -> Kakoune {
+        Kakoune {
+            mode: KakouneMode::Normal,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn movement_replaces_selection() {
+        let mut kakoune = normal_mode();
+
+        assert_eq!(
+            kakoune.parse_event(key(KeyModifiers::NONE, 'w')),
+            ReedlineEvent::Edit(vec![
+                EditCommand::ClearSelection,
+                EditCommand::MoveWordRightStart { select: true }
+            ])
+        );
+    }
+
+    #[test]
+    fn shifted_movement_extends_selection() {
+        let mut kakoune = normal_mode();
+
+        assert_eq!(
+            kakoune.parse_event(key(KeyModifiers::SHIFT, 'W')),
+            ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart { select: true }])
+        );
+    }
+
+    #[test]
+    fn change_enters_insert_mode() {
+        let mut kakoune = normal_mode();
+
+        assert_eq!(
+            kakoune.parse_event(key(KeyModifiers::NONE, 'c')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::CutSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(kakoune.mode, KakouneMode::Insert);
+        assert_eq!(
+            kakoune.parse_event(key(KeyModifiers::NONE, 'c')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('c')])
+        );
+    }
+
+    #[test]
+    fn esc_leads_to_normal_mode() {
+        let mut kakoune = Kakoune::default();
+        let esc = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+
+        kakoune.parse_event(esc);
+
+        assert_eq!(kakoune.mode, KakouneMode::Normal);
+    }
+
+    #[test]
+    fn customized_keybindings_are_used() {
+        let mut keybindings = default_kakoune_normal_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Char('w'),
+            ReedlineEvent::ClearScreen,
+        );
+        let mut kakoune = Kakoune {
+            mode: KakouneMode::Normal,
+            ..Kakoune::default().with_normal_keybindings(keybindings)
+        };
+
+        assert_eq!(
+            kakoune.parse_event(key(KeyModifiers::NONE, 'w')),
+            ReedlineEvent::ClearScreen
+        );
+    }
+}
@@ -5,17 +5,27 @@ use {
     std::collections::HashMap,
 };
 
+/// A key together with its modifiers, as used by the bindings
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombination {
+    /// Modifiers like `Ctrl` pressed together with the key
     pub modifier: KeyModifiers,
+    /// The pressed key
     pub key_code: KeyCode,
 }
 
 /// Main definition of editor keybindings
+///
+/// Created with [`Keybindings::new`] and filled with [`Keybindings::add_binding`] and
+/// [`Keybindings::add_sequence`]. The key sequences are kept apart from the public
+/// `bindings`, so the struct can't be built as a literal anymore.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Keybindings {
     /// Defines a keybinding for a reedline event
     pub bindings: HashMap<KeyCombination, ReedlineEvent>,
+    /// Bindings of the sequences of at least two keys
+    #[serde(default)]
+    sequences: HashMap<Vec<KeyCombination>, ReedlineEvent>,
 }
 
 impl Default for Keybindings {
@@ -29,6 +39,7 @@ impl Keybindings {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            sequences: HashMap::new(),
         }
     }
 
@@ -59,6 +70,44 @@ impl Keybindings {
         self.bindings.insert(key_combo, command);
     }
 
+    /// Adds a binding for a sequence of keys, like `Ctrl-x Ctrl-s`
+    ///
+    /// The keys of a started sequence are held back until the sequence is complete.
+    /// If another key arrives or the sequence times out, they are processed one by one,
+    /// which also resolves a sequence that is the start of a longer sequence.
+    ///
+    /// Sequences are only supported by the [`Emacs`](crate::Emacs) edit mode. The
+    /// [`Vi`](crate::Vi) edit mode ignores them and only uses the bindings of single keys,
+    /// as its normal mode already parses multi-key commands like `dw` itself.
+    ///
+    /// # Panics
+    ///
+    /// If `sequence` is empty or `command` is an empty [`ReedlineEvent::UntilFound`]
+    pub fn add_sequence(&mut self, sequence: &[KeyCombination], command: ReedlineEvent) {
+        assert!(
+            !sequence.is_empty(),
+            "A key sequence needs at least one key"
+        );
+
+        if let [key_combo] = sequence {
+            self.add_binding(key_combo.modifier, key_combo.key_code, command);
+        } else {
+            if let ReedlineEvent::UntilFound(subcommands) = &command {
+                assert!(
+                    !subcommands.is_empty(),
+                    "UntilFound should contain a series of potential events to handle"
+                );
+            }
+            self.sequences.insert(sequence.to_vec(), command);
+        }
+    }
+
+    fn is_sequence_prefix(&self, keys: &[KeyCombination]) -> bool {
+        self.sequences
+            .keys()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+
     /// Find a keybinding based on the modifier and keycode
     pub fn find_binding(&self, modifier: KeyModifiers, key_code: KeyCode) -> Option<ReedlineEvent> {
         let key_combo = KeyCombination { modifier, key_code };
@@ -83,6 +132,81 @@ impl Keybindings {
     }
 }
 
+/// Keys of a sequence resolved by the [`KeySequenceBuffer`]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ResolvedKey {
+    /// The key is processed on its own
+    Key(KeyCombination),
+    /// The event bound to the completed sequence
+    Sequence(ReedlineEvent),
+}
+
+/// Holds back the keys of a started key sequence
+#[derive(Default)]
+pub(crate) struct KeySequenceBuffer {
+    keys: Vec<KeyCombination>,
+}
+
+impl KeySequenceBuffer {
+    pub const fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Adds the key to the sequence, returns nothing while the sequence can still continue
+    pub fn push(&mut self, keybindings: &Keybindings, key: KeyCombination) -> Vec<ResolvedKey> {
+        self.keys.push(key);
+        if keybindings.is_sequence_prefix(&self.keys) {
+            return vec![];
+        }
+        if let Some(event) = keybindings.sequences.get(&self.keys) {
+            self.keys.clear();
+            return vec![ResolvedKey::Sequence(event.clone())];
+        }
+
+        // No sequence continues with the key, but it can start a new one
+        let key = self.keys.pop().expect("the key was just pushed");
+        if self.keys.is_empty() {
+            return vec![ResolvedKey::Key(key)];
+        }
+        let mut resolved = self.flush(keybindings);
+        resolved.extend(self.push(keybindings, key));
+
+        resolved
+    }
+
+    /// Resolves the held back keys, using the longest complete sequences they start with
+    pub fn flush(&mut self, keybindings: &Keybindings) -> Vec<ResolvedKey> {
+        let keys = std::mem::take(&mut self.keys);
+        let mut keys = keys.as_slice();
+        let mut resolved = vec![];
+
+        while let Some(first) = keys.first() {
+            let sequence = (2..=keys.len()).rev().find_map(|len| {
+                keybindings
+                    .sequences
+                    .get(&keys[..len])
+                    .map(|event| (len, event.clone()))
+            });
+            match sequence {
+                Some((len, event)) => {
+                    resolved.push(ResolvedKey::Sequence(event));
+                    keys = &keys[len..];
+                }
+                None => {
+                    resolved.push(ResolvedKey::Key(first.clone()));
+                    keys = &keys[1..];
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
 pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
     ReedlineEvent::Edit(vec![command])
 }
@@ -101,6 +225,11 @@ pub fn add_common_control_bindings(kb: &mut Keybindings) {
     kb.add_binding(KM::CONTROL, KC::Char('d'), ReedlineEvent::CtrlD);
     kb.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
     kb.add_binding(KM::CONTROL, KC::Char('r'), ReedlineEvent::SearchHistory);
+    kb.add_binding(
+        KM::CONTROL,
+        KC::Char('s'),
+        ReedlineEvent::SearchHistoryForward,
+    );
     kb.add_binding(KM::CONTROL, KC::Char('o'), ReedlineEvent::OpenEditor);
 }
 /// Add the arrow navigation and its `Ctrl` variants
@@ -270,3 +399,84 @@ pub fn add_common_selection_bindings(kb: &mut Keybindings) {
     );
     kb.add_binding(KM::CONTROL, KC::Char('a'), edit_bind(EC::SelectAll));
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn ctrl(c: char) -> KeyCombination {
+        KeyCombination {
+            modifier: KeyModifiers::CONTROL,
+            key_code: KeyCode::Char(c),
+        }
+    }
+
+    fn keybindings() -> Keybindings {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_sequence(&[ctrl('x')], ReedlineEvent::CtrlC);
+        keybindings.add_sequence(&[ctrl('x'), ctrl('s')], ReedlineEvent::Submit);
+        keybindings.add_sequence(&[ctrl('x'), ctrl('s'), ctrl('s')], ReedlineEvent::Enter);
+        keybindings
+    }
+
+    #[test]
+    fn complete_sequence_is_resolved() {
+        let keybindings = keybindings();
+        let mut buffer = KeySequenceBuffer::default();
+
+        assert_eq!(buffer.push(&keybindings, ctrl('x')), vec![]);
+        assert_eq!(buffer.push(&keybindings, ctrl('s')), vec![]);
+        assert_eq!(
+            buffer.push(&keybindings, ctrl('s')),
+            vec![ResolvedKey::Sequence(ReedlineEvent::Enter)]
+        );
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn prefix_sequence_is_resolved_by_other_key() {
+        let keybindings = keybindings();
+        let mut buffer = KeySequenceBuffer::default();
+
+        buffer.push(&keybindings, ctrl('x'));
+        buffer.push(&keybindings, ctrl('s'));
+
+        assert_eq!(
+            buffer.push(&keybindings, ctrl('a')),
+            vec![
+                ResolvedKey::Sequence(ReedlineEvent::Submit),
+                ResolvedKey::Key(ctrl('a'))
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix_key_is_resolved_on_timeout() {
+        let keybindings = keybindings();
+        let mut buffer = KeySequenceBuffer::default();
+
+        buffer.push(&keybindings, ctrl('x'));
+        assert!(buffer.is_pending());
+
+        assert_eq!(
+            buffer.flush(&keybindings),
+            vec![ResolvedKey::Key(ctrl('x'))]
+        );
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn other_key_can_start_new_sequence() {
+        let keybindings = keybindings();
+        let mut buffer = KeySequenceBuffer::default();
+
+        buffer.push(&keybindings, ctrl('x'));
+
+        assert_eq!(
+            buffer.push(&keybindings, ctrl('x')),
+            vec![ResolvedKey::Key(ctrl('x'))]
+        );
+        assert!(buffer.is_pending());
+    }
+}
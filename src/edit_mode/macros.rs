@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::enums::{EditCommand, ReedlineEvent};
+
+/// Maximum number of macros replaying each other (or themselves) at the same time
+const MAX_REPLAY_DEPTH: usize = 16;
+
+/// Records the events dispatched by the engine into named registers and provides them
+/// again to replay them.
+///
+/// The events are recorded after the edit mode parsed them, so a replay has the same
+/// effect on the buffer but does not change the state of the edit mode itself.
+#[derive(Default)]
+pub(crate) struct MacroRecorder {
+    registers: HashMap<char, Vec<ReedlineEvent>>,
+    recording: Option<(char, Vec<ReedlineEvent>)>,
+    replay_depth: usize,
+}
+
+impl MacroRecorder {
+    /// Start recording into the register, discarding a recording in progress
+    pub fn start(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Store the recording in its register, returns false if nothing was recorded
+    pub fn stop(&mut self) -> bool {
+        match self.recording.take() {
+            Some((register, events)) => {
+                self.registers.insert(register, events);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Append the event to the recording, unless it is dispatched by a replay
+    pub fn record(&mut self, event: &ReedlineEvent) {
+        if self.replay_depth > 0 || matches!(event, ReedlineEvent::Resize(..)) {
+            return;
+        }
+        if let Some((_, events)) = self.recording.as_mut() {
+            events.push(event.clone());
+        }
+    }
+
+    /// Get the events to replay from the register, has to be followed by
+    /// [`MacroRecorder::end_replay`] once they are dispatched.
+    ///
+    /// Returns `None` if the register is empty or too many replays are nested.
+    pub fn begin_replay(&mut self, register: char) -> Option<Vec<ReedlineEvent>> {
+        if self.replay_depth >= MAX_REPLAY_DEPTH {
+            return None;
+        }
+        let events = self.registers.get(&register)?.clone();
+        self.replay_depth += 1;
+
+        Some(events)
+    }
+
+    pub fn end_replay(&mut self) {
+        self.replay_depth = self.replay_depth.saturating_sub(1);
+    }
+}
+
+/// Commands that are handled by the [`MacroRecorder`] instead of the editor
+pub(crate) fn is_macro_command(command: &EditCommand) -> bool {
+    matches!(
+        command,
+        EditCommand::StartRecordingMacro(_)
+            | EditCommand::StopRecordingMacro
+            | EditCommand::ReplayMacro(_)
+    )
+}
+
+pub(crate) fn contains_macro_command(event: &ReedlineEvent) -> bool {
+    match event {
+        ReedlineEvent::Edit(commands) => commands.iter().any(is_macro_command),
+        ReedlineEvent::Multiple(events) | ReedlineEvent::UntilFound(events) => {
+            events.iter().any(contains_macro_command)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn insert(c: char) -> ReedlineEvent {
+        ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])
+    }
+
+    #[test]
+    fn recorded_events_are_replayed() {
+        let mut macros = MacroRecorder::default();
+
+        macros.record(&insert('x'));
+        macros.start('a');
+        macros.record(&insert('a'));
+        macros.record(&ReedlineEvent::Resize(80, 24));
+        macros.record(&insert('b'));
+        assert!(macros.stop());
+        macros.record(&insert('c'));
+
+        assert_eq!(
+            macros.begin_replay('a'),
+            Some(vec![insert('a'), insert('b')])
+        );
+        assert_eq!(macros.begin_replay('b'), None);
+        assert!(!macros.stop());
+    }
+
+    #[test]
+    fn replayed_events_are_not_recorded() {
+        let mut macros = MacroRecorder::default();
+        macros.start('a');
+        macros.record(&insert('a'));
+        macros.stop();
+
+        macros.start('b');
+        let events = macros.begin_replay('a').unwrap();
+        for event in &events {
+            macros.record(event);
+        }
+        macros.end_replay();
+        macros.stop();
+
+        assert_eq!(macros.begin_replay('b'), Some(vec![]));
+    }
+
+    #[test]
+    fn nested_replay_is_limited() {
+        let mut macros = MacroRecorder::default();
+        macros.start('a');
+        macros.stop();
+
+        let replays = std::iter::from_fn(|| macros.begin_replay('a')).count();
+
+        assert_eq!(replays, MAX_REPLAY_DEPTH);
+    }
+
+    #[test]
+    fn macro_commands_are_found_in_nested_events() {
+        let event = ReedlineEvent::Multiple(vec![
+            insert('a'),
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::Edit(vec![
+                EditCommand::StopRecordingMacro,
+            ])]),
+        ]);
+
+        assert!(contains_macro_command(&event));
+        assert!(!contains_macro_command(&insert('a')));
+    }
+}
@@ -1,11 +1,17 @@
 mod base;
 mod cursors;
 mod emacs;
+mod kakoune;
 mod keybindings;
+mod macros;
 mod vi;
 
 pub use base::EditMode;
 pub use cursors::CursorConfig;
 pub use emacs::{default_emacs_keybindings, Emacs};
-pub use keybindings::Keybindings;
+pub use kakoune::{
+    default_kakoune_insert_keybindings, default_kakoune_normal_keybindings, Kakoune,
+};
+pub use keybindings::{KeyCombination, Keybindings};
+pub(crate) use macros::{contains_macro_command, is_macro_command, MacroRecorder};
 pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
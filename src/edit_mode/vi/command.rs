@@ -11,6 +11,10 @@ where
             let _ = input.next();
             Some(Command::Delete)
         }
+        Some('y') => {
+            let _ = input.next();
+            Some(Command::Yank)
+        }
         Some('p') => {
             let _ = input.next();
             Some(Command::PasteAfter)
@@ -46,6 +50,20 @@ where
                 None => Some(Command::Incomplete),
             }
         }
+        Some('q') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::RecordMacro(*c)),
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('@') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::ReplayMacro(*c)),
+                None => Some(Command::Incomplete),
+            }
+        }
         Some('s') => {
             let _ = input.next();
             Some(Command::SubstituteCharWithInsert)
@@ -90,6 +108,7 @@ where
 pub enum Command {
     Incomplete,
     Delete,
+    Yank,
     DeleteChar,
     ReplaceChar(char),
     SubstituteCharWithInsert,
@@ -107,6 +126,8 @@ pub enum Command {
     HistorySearch,
     Switchcase,
     RepeatLastAction,
+    RecordMacro(char),
+    ReplayMacro(char),
 }
 
 impl Command {
@@ -114,12 +135,13 @@ impl Command {
         match self {
             Command::Delete => Some('d'),
             Command::Change => Some('c'),
+            Command::Yank => Some('y'),
             _ => None,
         }
     }
 
     pub fn requires_motion(&self) -> bool {
-        matches!(self, Command::Delete | Command::Change)
+        matches!(self, Command::Delete | Command::Change | Command::Yank)
     }
 
     pub fn to_reedline(&self, vi_state: &mut Vi) -> Vec<ReedlineOption> {
@@ -146,9 +168,20 @@ impl Command {
             }
             Self::SubstituteCharWithInsert => vec![ReedlineOption::Edit(EditCommand::CutChar)],
             Self::HistorySearch => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
-            Self::Switchcase => vec![ReedlineOption::Edit(EditCommand::SwitchcaseChar)],
+            Self::Switchcase => vec![ReedlineOption::Edit(EditCommand::ToggleCase)],
             // Mark a command as incomplete whenever a motion is required to finish the command
-            Self::Delete | Self::Change | Self::Incomplete => vec![ReedlineOption::Incomplete],
+            Self::Delete | Self::Change | Self::Yank | Self::Incomplete => {
+                vec![ReedlineOption::Incomplete]
+            }
+            Self::RecordMacro(register) => {
+                vi_state.recording_macro = true;
+                vec![ReedlineOption::Edit(EditCommand::StartRecordingMacro(
+                    *register,
+                ))]
+            }
+            Self::ReplayMacro(register) => {
+                vec![ReedlineOption::Edit(EditCommand::ReplayMacro(*register))]
+            }
             Command::RepeatLastAction => match &vi_state.previous {
                 Some(event) => vec![ReedlineOption::Event(event.clone())],
                 None => vec![],
@@ -200,6 +233,7 @@ impl Command {
                 Motion::Right => Some(vec![ReedlineOption::Edit(EditCommand::Delete)]),
                 Motion::Up => None,
                 Motion::Down => None,
+                Motion::MatchingBracket => None,
                 Motion::ReplayCharSearch => vi_state
                     .last_char_search
                     .as_ref()
@@ -208,6 +242,9 @@ impl Command {
                     .last_char_search
                     .as_ref()
                     .map(|char_search| vec![ReedlineOption::Edit(char_search.reverse().to_cut())]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutTextObject(*text_object),
+                )]),
             },
             Self::Change => {
                 let op = match motion {
@@ -255,6 +292,7 @@ impl Command {
                     Motion::Right => Some(vec![ReedlineOption::Edit(EditCommand::Delete)]),
                     Motion::Up => None,
                     Motion::Down => None,
+                    Motion::MatchingBracket => None,
                     Motion::ReplayCharSearch => vi_state
                         .last_char_search
                         .as_ref()
@@ -264,6 +302,9 @@ impl Command {
                             vec![ReedlineOption::Edit(char_search.reverse().to_cut())]
                         })
                     }
+                    Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                        EditCommand::CutTextObject(*text_object),
+                    )]),
                 };
                 // Semihack: Append `Repaint` to ensure the mode change gets displayed
                 op.map(|mut vec| {
@@ -271,6 +312,57 @@ impl Command {
                     vec
                 })
             }
+            Self::Yank => match motion {
+                Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CopyToLineEnd)]),
+                Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)]),
+                Motion::NextWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRightToNext)])
+                }
+                Motion::NextBigWord => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyBigWordRightToNext,
+                )]),
+                Motion::NextWordEnd => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRight)]),
+                Motion::NextBigWordEnd => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordRight)])
+                }
+                Motion::PreviousWord => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordLeft)]),
+                Motion::PreviousBigWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordLeft)])
+                }
+                Motion::RightUntil(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::ToRight(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightUntil(*c))])
+                }
+                Motion::RightBefore(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::TillRight(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightBefore(*c))])
+                }
+                Motion::LeftUntil(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::ToLeft(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftUntil(*c))])
+                }
+                Motion::LeftBefore(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::TillLeft(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftBefore(*c))])
+                }
+                Motion::Start => Some(vec![ReedlineOption::Edit(EditCommand::CopyFromLineStart)]),
+                Motion::Left => None,
+                Motion::Right => None,
+                Motion::Up => None,
+                Motion::Down => None,
+                Motion::MatchingBracket => None,
+                Motion::ReplayCharSearch => vi_state
+                    .last_char_search
+                    .as_ref()
+                    .map(|char_search| vec![ReedlineOption::Edit(char_search.to_copy())]),
+                Motion::ReverseCharSearch => vi_state
+                    .last_char_search
+                    .as_ref()
+                    .map(|char_search| vec![ReedlineOption::Edit(char_search.reverse().to_copy())]),
+                Motion::TextObject(text_object) => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyTextObject(*text_object),
+                )]),
+            },
             _ => None,
         }
     }
@@ -30,6 +30,8 @@ pub struct Vi {
     previous: Option<ReedlineEvent>,
     // last f, F, t, T motion for ; and ,
     last_char_search: Option<ViCharSearch>,
+    // a single q stops the recording instead of starting a new one
+    recording_macro: bool,
 }
 
 impl Default for Vi {
@@ -41,6 +43,7 @@ impl Default for Vi {
             mode: ViMode::Insert,
             previous: None,
             last_char_search: None,
+            recording_macro: false,
         }
     }
 }
@@ -70,6 +73,13 @@ impl EditMode for Vi {
                         .find_binding(modifiers, KeyCode::Char(c))
                     {
                         event
+                    } else if modifier == KeyModifiers::NONE
+                        && c == 'q'
+                        && self.recording_macro
+                        && self.cache.is_empty()
+                    {
+                        self.recording_macro = false;
+                        ReedlineEvent::Edit(vec![EditCommand::StopRecordingMacro])
                     } else if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT {
                         self.cache.push(if modifier == KeyModifiers::SHIFT {
                             c.to_ascii_uppercase()
@@ -153,13 +163,11 @@ impl EditMode for Vi {
                     .unwrap_or(ReedlineEvent::None),
             },
 
-            Event::Mouse(_) => ReedlineEvent::Mouse,
+            Event::Mouse(mouse) => ReedlineEvent::Mouse(mouse.into()),
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
             Event::FocusGained => ReedlineEvent::None,
             Event::FocusLost => ReedlineEvent::None,
-            Event::Paste(body) => ReedlineEvent::Edit(vec![EditCommand::InsertString(
-                body.replace("\r\n", "\n").replace('\r', "\n"),
-            )]),
+            Event::Paste(body) => ReedlineEvent::Paste(body),
         }
     }
 
@@ -174,7 +182,9 @@ impl EditMode for Vi {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{core_editor::Editor, UndoBehavior};
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
     fn esc_leads_to_normal_mode_test() {
@@ -193,6 +203,107 @@ mod test {
         assert!(matches!(vi.mode, ViMode::Normal));
     }
 
+    #[test]
+    fn q_stops_macro_recording_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut press = |c| {
+            let key = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )))
+            .unwrap();
+            vi.parse_event(key)
+        };
+
+        assert_eq!(press('q'), ReedlineEvent::None);
+        assert_eq!(
+            press('a'),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::StartRecordingMacro('a')
+            ])])
+        );
+        assert_eq!(
+            press('q'),
+            ReedlineEvent::Edit(vec![EditCommand::StopRecordingMacro])
+        );
+        assert_eq!(press('q'), ReedlineEvent::None);
+    }
+
+    // Runs the edit commands produced by the keys typed in normal mode on the editor
+    fn run_normal_keys(vi: &mut Vi, editor: &mut Editor, keys: &str) {
+        fn run(event: ReedlineEvent, editor: &mut Editor) {
+            match event {
+                ReedlineEvent::Multiple(events) => {
+                    events.into_iter().for_each(|event| run(event, editor))
+                }
+                ReedlineEvent::Edit(commands) => commands
+                    .iter()
+                    .for_each(|command| editor.run_edit_command(command)),
+                _ => {}
+            }
+        }
+
+        for c in keys.chars() {
+            let modifiers = if c.is_ascii_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            };
+            let key = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                modifiers,
+            )))
+            .unwrap();
+            run(vi.parse_event(key), editor);
+        }
+    }
+
+    #[rstest]
+    #[case("foo\nbar", 0, "\"ayy\"ap", "foo\nfoo\nbar")]
+    #[case("foo bar", 0, "\"ayw$\"ap", "foo barfoo ")]
+    #[case("foo bar", 0, "\"ay$\"aP", "foo barfoo bar")]
+    #[case("foo bar", 4, "\"ayb0\"aP", "foo foo bar")]
+    fn yank_into_register_round_trip(
+        #[case] buffer: &str,
+        #[case] position: usize,
+        #[case] keys: &str,
+        #[case] expected: &str,
+    ) {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor.run_edit_command(&EditCommand::MoveToPosition {
+            position,
+            select: false,
+        });
+
+        run_normal_keys(&mut vi, &mut editor, keys);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[test]
+    fn yank_does_not_change_the_buffer() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut editor = Editor::default();
+        editor.set_buffer("foo bar".to_string(), UndoBehavior::CreateUndoPoint);
+
+        run_normal_keys(&mut vi, &mut editor, "\"ayy");
+
+        assert_eq!(editor.get_buffer(), "foo bar");
+        run_normal_keys(&mut vi, &mut editor, "\"ap");
+        assert_eq!(editor.get_buffer(), "foo bar\nfoo bar");
+    }
+
     #[test]
     fn keybinding_without_modifier_test() {
         let mut keybindings = default_vi_normal_keybindings();
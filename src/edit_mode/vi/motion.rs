@@ -1,6 +1,6 @@
 use std::iter::Peekable;
 
-use crate::{EditCommand, ReedlineEvent, Vi};
+use crate::{EditCommand, ReedlineEvent, TextObject, Vi};
 
 use super::parser::{ParseResult, ReedlineOption};
 
@@ -60,6 +60,10 @@ where
             let _ = input.next();
             ParseResult::Valid(Motion::End)
         }
+        Some('%') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::MatchingBracket)
+        }
         Some('f') => {
             let _ = input.next();
             match input.peek() {
@@ -108,6 +112,23 @@ where
             let _ = input.next();
             ParseResult::Valid(Motion::ReverseCharSearch)
         }
+        Some(&&scope @ ('i' | 'a')) if command_char.is_some() => {
+            let _ = input.next();
+            let inner = scope == 'i';
+            let delimited = |open, close| TextObject::Delimited { open, close, inner };
+            let text_object = match input.peek() {
+                Some('w') => TextObject::Word { inner },
+                Some(&&quote @ ('"' | '\'' | '`')) => delimited(quote, quote),
+                Some('(' | ')' | 'b') => delimited('(', ')'),
+                Some('[' | ']') => delimited('[', ']'),
+                Some('{' | '}' | 'B') => delimited('{', '}'),
+                Some('<' | '>') => delimited('<', '>'),
+                Some(_) => return ParseResult::Invalid,
+                None => return ParseResult::Incomplete,
+            };
+            let _ = input.next();
+            ParseResult::Valid(Motion::TextObject(text_object))
+        }
         ch if ch == command_char.as_ref().as_ref() && command_char.is_some() => {
             let _ = input.next();
             ParseResult::Valid(Motion::Line)
@@ -132,12 +153,14 @@ pub enum Motion {
     Line,
     Start,
     End,
+    MatchingBracket,
     RightUntil(char),
     RightBefore(char),
     LeftUntil(char),
     LeftBefore(char),
     ReplayCharSearch,
     ReverseCharSearch,
+    TextObject(TextObject),
 }
 
 impl Motion {
@@ -181,12 +204,18 @@ impl Motion {
                 select: false,
             })],
             Motion::Line => vec![], // Placeholder as unusable standalone motion
+            Motion::TextObject(_) => vec![], // Text objects are only usable after a command
             Motion::Start => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart {
                 select: false,
             })],
             Motion::End => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd {
                 select: false,
             })],
+            Motion::MatchingBracket => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToMatchingBracket {
+                    select: false,
+                })]
+            }
             Motion::RightUntil(ch) => {
                 vi_state.last_char_search = Some(ViCharSearch::ToRight(*ch));
                 vec![ReedlineOption::Edit(EditCommand::MoveRightUntil {
@@ -286,4 +315,13 @@ impl ViCharSearch {
             ViCharSearch::TillLeft(c) => EditCommand::CutLeftBefore(*c),
         }
     }
+
+    pub fn to_copy(&self) -> EditCommand {
+        match self {
+            ViCharSearch::ToRight(c) => EditCommand::CopyRightUntil(*c),
+            ViCharSearch::ToLeft(c) => EditCommand::CopyLeftUntil(*c),
+            ViCharSearch::TillRight(c) => EditCommand::CopyRightBefore(*c),
+            ViCharSearch::TillLeft(c) => EditCommand::CopyLeftBefore(*c),
+        }
+    }
 }
@@ -39,6 +39,7 @@ impl<T> ParseResult<T> {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsedViSequence {
+    register: Option<ParseResult<char>>,
     multiplier: Option<usize>,
     command: Option<Command>,
     count: Option<usize>,
@@ -47,10 +48,13 @@ pub struct ParsedViSequence {
 
 impl ParsedViSequence {
     pub fn is_valid(&self) -> bool {
-        !self.motion.is_invalid()
+        !self.motion.is_invalid() && !matches!(self.register, Some(ParseResult::Invalid))
     }
 
     pub fn is_complete(&self) -> bool {
+        if matches!(self.register, Some(ParseResult::Incomplete)) {
+            return false;
+        }
         match (&self.command, &self.motion) {
             (None, ParseResult::Valid(_)) => true,
             (Some(Command::Incomplete), _) => false,
@@ -91,6 +95,36 @@ impl ParsedViSequence {
         }
     }
 
+    /// Let the commands yank into or paste from the named register
+    ///
+    /// The yanking commands still fill the cut buffer, which is then copied into the
+    /// register like vim also keeps the last yank in the unnamed register
+    fn apply_register(
+        &self,
+        raw_events: Option<Vec<ReedlineOption>>,
+    ) -> Option<Vec<ReedlineOption>> {
+        let register = match self.register {
+            Some(ParseResult::Valid(register)) => register,
+            _ => return raw_events,
+        };
+
+        match self.command {
+            Some(Command::PasteAfter) => Some(vec![ReedlineOption::Edit(
+                EditCommand::PasteFromRegisterAfter(register),
+            )]),
+            Some(Command::PasteBefore) => Some(vec![ReedlineOption::Edit(
+                EditCommand::PasteFromRegister(register),
+            )]),
+            Some(Command::Delete | Command::Change | Command::Yank | Command::DeleteChar) => {
+                raw_events.map(|mut events| {
+                    events.push(ReedlineOption::Edit(EditCommand::CopyToRegister(register)));
+                    events
+                })
+            }
+            _ => raw_events,
+        }
+    }
+
     pub fn enters_insert_mode(&self) -> bool {
         matches!(
             (&self.command, &self.motion),
@@ -112,7 +146,8 @@ impl ParsedViSequence {
     pub fn to_reedline_event(&self, vi_state: &mut Vi) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
             (_, Some(command), None, ParseResult::Incomplete) => {
-                let events = self.apply_multiplier(Some(command.to_reedline(vi_state)));
+                let events =
+                    self.apply_multiplier(self.apply_register(Some(command.to_reedline(vi_state))));
                 match &events {
                     ReedlineEvent::None => {}
                     event => vi_state.previous = Some(event.clone()),
@@ -121,8 +156,9 @@ impl ParsedViSequence {
             }
             // This case handles all combinations of commands and motions that could exist
             (_, Some(command), _, ParseResult::Valid(motion)) => {
-                let events =
-                    self.apply_multiplier(command.to_reedline_with_motion(motion, vi_state));
+                let events = self.apply_multiplier(
+                    self.apply_register(command.to_reedline_with_motion(motion, vi_state)),
+                );
                 match &events {
                     ReedlineEvent::None => {}
                     event => vi_state.previous = Some(event.clone()),
@@ -161,16 +197,35 @@ where
     }
 }
 
+fn parse_register<'iter, I>(input: &mut Peekable<I>) -> Option<ParseResult<char>>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    match input.peek() {
+        Some('"') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) if c.is_ascii_alphabetic() => Some(ParseResult::Valid(*c)),
+                Some(_) => Some(ParseResult::Invalid),
+                None => Some(ParseResult::Incomplete),
+            }
+        }
+        _ => None,
+    }
+}
+
 pub fn parse<'iter, I>(input: &mut Peekable<I>) -> ParsedViSequence
 where
     I: Iterator<Item = &'iter char>,
 {
+    let register = parse_register(input);
     let multiplier = parse_number(input);
     let command = parse_command(input);
     let count = parse_number(input);
     let motion = parse_motion(input, command.as_ref().and_then(Command::whole_line_char));
 
     ParsedViSequence {
+        register,
         multiplier,
         command,
         count,
@@ -181,6 +236,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::TextObject;
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
@@ -196,6 +252,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Delete),
                 count: None,
@@ -214,6 +271,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -232,6 +290,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(2),
@@ -250,6 +309,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(20),
@@ -268,6 +328,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -286,6 +347,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Delete),
                 count: None,
@@ -304,6 +366,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -321,6 +384,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Incomplete),
                 count: None,
@@ -340,6 +404,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: None,
                 count: None,
@@ -358,6 +423,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::ReplaceChar('k')),
                 count: None,
@@ -377,6 +443,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: None,
                 count: None,
@@ -395,6 +462,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: None,
                 count: None,
@@ -405,6 +473,23 @@ mod tests {
         assert_eq!(output.is_complete(), true);
     }
 
+    #[rstest]
+    #[case(&['"'], true, false)]
+    #[case(&['"', 'a'], true, false)]
+    #[case(&['"', 'a', 'd'], true, false)]
+    #[case(&['"', 'a', 'p'], true, true)]
+    #[case(&['"', '1'], false, false)]
+    fn test_register_parsing(
+        #[case] input: &[char],
+        #[case] is_valid: bool,
+        #[case] is_complete: bool,
+    ) {
+        let output = vi_parse(input);
+
+        assert_eq!(output.is_valid(), is_valid);
+        assert_eq!(output.is_complete(), is_complete);
+    }
+
     #[rstest]
     #[case(&['2', 'k'], ReedlineEvent::Multiple(vec![ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuUp,
@@ -438,6 +523,7 @@ mod tests {
             ])]))]
     #[case(&['0'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart{select:false}])]))]
     #[case(&['$'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd{select:false}])]))]
+    #[case(&['%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMatchingBracket{select:false}])]))]
     #[case(&['i'], ReedlineEvent::Multiple(vec![ReedlineEvent::Repaint]))]
     #[case(&['p'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
     #[case(&['2', 'p'], ReedlineEvent::Multiple(vec![
@@ -456,6 +542,35 @@ mod tests {
     #[case(&['d', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRight])]))]
     #[case(&['d', 'b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordLeft])]))]
     #[case(&['d', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordLeft])]))]
+    #[case(&['c', 'i', '"'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutTextObject(TextObject::Delimited { open: '"', close: '"', inner: true })]),
+        ReedlineEvent::Repaint])) ]
+    #[case(&['d', 'a', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutTextObject(TextObject::Word { inner: false })])]))]
+    #[case(&['y', 'i', 'b'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyTextObject(TextObject::Delimited { open: '(', close: ')', inner: true })])]))]
+    #[case(&['d', 'i', 'x'], ReedlineEvent::None)]
+    #[case(&['"', 'a', 'y', 'i', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyTextObject(TextObject::Word { inner: true })]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyToRegister('a')])]))]
+    #[case(&['"', 'A', 'd', 'd'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyToRegister('A')])]))]
+    #[case(&['"', 'a', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::PasteFromRegisterAfter('a')])]))]
+    #[case(&['y', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['y', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyWordRightToNext])]))]
+    #[case(&['y', '$'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyToLineEnd])]))]
+    #[case(&['"', 'a', 'y', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyToRegister('a')])]))]
+    #[case(&['3', '@', 'a'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ReplayMacro('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::ReplayMacro('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::ReplayMacro('a')])]))]
+    #[case(&['"', 'a', 'P'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::PasteFromRegister('a')])]))]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let mut vi = Vi::default();
         let res = vi_parse(input);
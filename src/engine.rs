@@ -17,23 +17,37 @@ use {
 };
 use {
     crate::{
-        completion::{Completer, DefaultCompleter},
-        core_editor::Editor,
-        edit_mode::{EditMode, Emacs},
-        enums::{EventStatus, ReedlineEvent},
+        completion::{AsyncCompleter, Completer, DefaultCompleter, PollingCompleter},
+        core_editor::{
+            get_default_clipboard, AutoPairConfig, Editor, KillRing, LocalClipboard, WordBoundary,
+        },
+        edit_mode::{
+            contains_macro_command, is_macro_command, EditMode, Emacs, KeyCombination,
+            MacroRecorder,
+        },
+        enums::{
+            CtrlCBehavior, CtrlDBehavior, EventStatus, LineOverflow, ReedlineEvent, UndoCoalescing,
+        },
         highlighter::SimpleMatchHighlighter,
-        hinter::Hinter,
+        hinter::{get_first_token, Hinter},
         history::{
             FileBackedHistory, History, HistoryCursor, HistoryItem, HistoryItemId,
             HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchQuery,
         },
-        painting::{Painter, PromptLines},
-        prompt::{PromptEditMode, PromptHistorySearchStatus},
+        painting::{expand_tabs, line_width, Painter, PromptLines},
+        prompt::{
+            AsyncPrompt, CollapsedPrompt, PendingPrompt, PromptEditMode, PromptHistorySearchStatus,
+        },
         result::{ReedlineError, ReedlineErrorVariants},
-        terminal_extensions::{bracketed_paste::BracketedPasteGuard, kitty::KittyProtocolGuard},
+        terminal_extensions::{
+            bracketed_paste::BracketedPasteGuard, kitty::KittyProtocolGuard,
+            mouse::MouseCaptureGuard,
+        },
         utils::text_manipulation,
-        EditCommand, ExampleHighlighter, Highlighter, LineBuffer, Menu, MenuEvent, Prompt,
-        PromptHistorySearch, ReedlineMenu, Signal, UndoBehavior, ValidationResult, Validator,
+        validator::PendingValidation,
+        AsyncValidator, EditCommand, ExampleHighlighter, Highlighter, LineBuffer, Menu,
+        MenuAcceptBehavior, MenuEvent, MenuTheme, MouseAction, Prompt, PromptHistorySearch,
+        ReedlineMenu, Signal, Suggestion, TrimMode, UndoBehavior, ValidationResult, Validator,
     },
     crossterm::{
         cursor::{SetCursorStyle, Show},
@@ -42,7 +56,17 @@ use {
         terminal, QueueableCommand,
     },
     std::{
-        fs::File, io, io::Result, io::Write, process::Command, time::Duration, time::SystemTime,
+        collections::hash_map::RandomState,
+        collections::HashMap,
+        fs::{File, OpenOptions},
+        hash::{BuildHasher, Hasher},
+        io,
+        io::Result,
+        io::Write,
+        process::Command,
+        time::Duration,
+        time::Instant,
+        time::SystemTime,
     },
 };
 
@@ -57,6 +81,30 @@ const POLL_WAIT: u64 = 10;
 // a paste. 10 events in 10 milliseconds is conservative enough (unlikely somebody
 // will type more than 10 characters in 10 milliseconds)
 const EVENTS_THRESHOLD: usize = 10;
+// While an asynchronous completion is pending, the menu is refreshed every
+// ASYNC_COMPLETION_WAIT milliseconds if no events arrive
+const ASYNC_COMPLETION_WAIT: u64 = 100;
+// While an asynchronous prompt is pending, it is polled every ASYNC_PROMPT_WAIT
+// milliseconds if no events arrive
+const ASYNC_PROMPT_WAIT: u64 = 50;
+// While an asynchronous validation is pending, it is polled every
+// ASYNC_VALIDATION_WAIT milliseconds if no events arrive
+const ASYNC_VALIDATION_WAIT: u64 = 50;
+// The keys of a started key sequence are processed on their own if the sequence
+// is not continued within KEY_SEQUENCE_TIMEOUT milliseconds
+const KEY_SEQUENCE_TIMEOUT: u64 = 1000;
+// The screen flashed by the visual bell is restored after VISUAL_BELL_DURATION
+// milliseconds, or as soon as a new event arrives
+const VISUAL_BELL_DURATION: u64 = 100;
+// Frames of the spinner shown in the menu indicator while a completion is pending
+const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Callback observing the history entries once their context is complete, see
+/// [`Reedline::with_history_session_callback`]
+pub type HistorySessionCallback = Box<dyn Fn(&HistoryItem) + Send>;
+
+/// Transformation of the accepted lines, see [`Reedline::with_submit_transform`]
+pub type SubmitTransform = Box<dyn Fn(&str) -> Option<String> + Send>;
 
 /// Determines if inputs should be used to extend the regular line buffer,
 /// traverse the history in the standard prompt or edit the search string in the
@@ -107,23 +155,55 @@ pub struct Reedline {
     history_exclusion_prefix: Option<String>,
     history_excluded_item: Option<HistoryItem>,
     history_cursor_on_excluded: bool,
+    prefix_history_search: bool,
+    // Line the prefix history search started from, restored when leaving the matches
+    prefix_search_line: Option<LineBuffer>,
+    history_session_callback: Option<HistorySessionCallback>,
+    // Entry of the last submitted line, passed to the callback once its context is complete
+    history_callback_entry: Option<HistoryItem>,
+    submit_transform: Option<SubmitTransform>,
+    trim_on_submit: TrimMode,
     input_mode: InputMode,
 
     // Validator
     validator: Option<Box<dyn Validator>>,
+    async_validator: Option<Box<dyn AsyncValidator>>,
+    pending_validation: Option<PendingValidation>,
+    auto_indent: bool,
+    forced_submit: bool,
+    newline_binding: Option<KeyCombination>,
 
     // Stdout
     painter: Painter,
 
     transient_prompt: Option<Box<dyn Prompt>>,
 
+    // Prompt computed in the background for every line
+    async_prompt: Option<Box<dyn AsyncPrompt>>,
+
     // Edit Mode: Vi, Emacs
     edit_mode: Box<dyn EditMode>,
+    // The edit mode changed since the last repaint, the prompt indicator is outdated
+    edit_mode_changed: bool,
+
+    // Recorded macros
+    macros: MacroRecorder,
 
     // Provides the tab completions
     completer: Box<dyn Completer>,
     quick_completions: bool,
     partial_completions: bool,
+    spinner_frame: usize,
+    // Edits within the debounce window query the completer of the active menu once
+    completion_debounce: Option<Duration>,
+    // Time of the last edit whose completer query was postponed
+    debounced_query: Option<Instant>,
+
+    // Signal the edit commands that could not do anything
+    visual_bell: bool,
+    audible_bell: bool,
+    // An edit command failed since the last repaint
+    bell: bool,
 
     // Highlight the edit buffer
     highlighter: Box<dyn Highlighter>,
@@ -131,9 +211,31 @@ pub struct Reedline {
     // Style used for visual selection
     visual_selection_style: Style,
 
+    // Columns between the tab stops the tabs of the buffer are expanded to
+    tab_width: Option<usize>,
+    line_overflow: LineOverflow,
+
     // Showcase hints based on various strategies (history, language-completion, spellcheck, etc)
     hinter: Option<Box<dyn Hinter>>,
     hide_hints: bool,
+    // Hint the top suggestion of the completer, with the line it was computed for
+    completion_preview: bool,
+    completion_preview_style: Style,
+    // Previews of the lines edited since the start of the current line
+    preview_cache: HashMap<String, String>,
+
+    // Words expanded when followed by a space or enter, only the first word of the
+    // line unless `abbreviations_anywhere`
+    abbreviations: HashMap<String, String>,
+    abbreviations_anywhere: bool,
+
+    // Signal keys
+    ctrl_c_behavior: CtrlCBehavior,
+    ctrl_d_behavior: CtrlDBehavior,
+
+    // Painted for every grapheme of a secret, see `read_secret`
+    secret_mask: Option<char>,
+    reading_secret: bool,
 
     // Use ansi coloring or not
     use_ansi_coloring: bool,
@@ -141,6 +243,10 @@ pub struct Reedline {
     // Engine Menus
     menus: Vec<ReedlineMenu>,
 
+    // Name of the menu kept open by `read_pick` and the value accepted in it
+    picker_menu: Option<String>,
+    picked: Option<Suggestion>,
+
     // Text editor used to open the line buffer for editing
     buffer_editor: Option<BufferEditor>,
 
@@ -153,6 +259,9 @@ pub struct Reedline {
     // Manage optional kitty protocol
     kitty_protocol: KittyProtocolGuard,
 
+    // Manage optional mouse capture
+    mouse_capture: MouseCaptureGuard,
+
     #[cfg(feature = "external_printer")]
     external_printer: Option<ExternalPrinter<String>>,
 }
@@ -162,8 +271,52 @@ struct BufferEditor {
     temp_file: PathBuf,
 }
 
+impl BufferEditor {
+    /// The editor of the `$VISUAL` or `$EDITOR` environment variable, which can contain
+    /// arguments like `code --wait`. It edits a new temporary file
+    fn from_env() -> Result<Option<Self>> {
+        let editor = match std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")) {
+            Ok(editor) => editor,
+            Err(_) => return Ok(None),
+        };
+        let mut words = editor.split_whitespace();
+        let mut command = match words.next() {
+            Some(program) => Command::new(program),
+            None => return Ok(None),
+        };
+        command.args(words);
+
+        let temp_file = create_temp_file()?;
+        command.arg(&temp_file);
+        Ok(Some(Self { command, temp_file }))
+    }
+}
+
+// Creates an empty file with a random name in the temporary directory, so that no other
+// process can prepare it or read it through a known path. On unix only the user can read it
+fn create_temp_file() -> Result<PathBuf> {
+    let mut attempts = 0;
+    loop {
+        let random = RandomState::new().build_hasher().finish();
+        let path = std::env::temp_dir().join(format!("reedline_buffer_{random:016x}.txt"));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(_) => return Ok(path),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempts < 100 => {
+                attempts += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 impl Drop for Reedline {
     fn drop(&mut self) {
+        self.notify_history_callback();
+
         if self.cursor_shapes.is_some() {
             let _ignore = terminal::enable_raw_mode();
             let mut stdout = std::io::stdout();
@@ -206,24 +359,58 @@ impl Reedline {
             history_exclusion_prefix: None,
             history_excluded_item: None,
             history_cursor_on_excluded: false,
+            prefix_history_search: false,
+            prefix_search_line: None,
+            history_session_callback: None,
+            history_callback_entry: None,
+            submit_transform: None,
+            trim_on_submit: TrimMode::default(),
             input_mode: InputMode::Regular,
             painter,
             transient_prompt: None,
+            async_prompt: None,
             edit_mode,
+            edit_mode_changed: false,
+            macros: MacroRecorder::default(),
             completer,
             quick_completions: false,
             partial_completions: false,
+            spinner_frame: 0,
+            completion_debounce: None,
+            debounced_query: None,
+            visual_bell: false,
+            audible_bell: false,
+            bell: false,
             highlighter: buffer_highlighter,
             visual_selection_style,
+            tab_width: None,
+            line_overflow: LineOverflow::default(),
             hinter,
             hide_hints: false,
+            completion_preview: false,
+            completion_preview_style: Style::new().fg(Color::LightGray),
+            preview_cache: HashMap::new(),
+            abbreviations: HashMap::new(),
+            abbreviations_anywhere: false,
+            ctrl_c_behavior: CtrlCBehavior::default(),
+            ctrl_d_behavior: CtrlDBehavior::default(),
+            secret_mask: None,
+            reading_secret: false,
             validator,
+            async_validator: None,
+            pending_validation: None,
+            auto_indent: false,
+            forced_submit: false,
+            newline_binding: None,
             use_ansi_coloring: true,
             menus: Vec::new(),
+            picker_menu: None,
+            picked: None,
             buffer_editor: None,
             cursor_shapes: None,
             bracketed_paste: BracketedPasteGuard::default(),
             kitty_protocol: KittyProtocolGuard::default(),
+            mouse_capture: MouseCaptureGuard::default(),
             #[cfg(feature = "external_printer")]
             external_printer: None,
         }
@@ -253,6 +440,32 @@ impl Reedline {
         self
     }
 
+    /// A builder that toggles bracketed paste, see [`Reedline::use_bracketed_paste`]
+    ///
+    /// Pasted text arrives as [`ReedlineEvent::Paste`] and is inserted as one edit that
+    /// can be undone at once. Its newlines don't submit the line and the control characters
+    /// around it are stripped.
+    #[must_use]
+    pub fn with_bracketed_paste(self, enable: bool) -> Self {
+        self.use_bracketed_paste(enable)
+    }
+
+    /// Toggle whether cutting and pasting goes through the clipboard of the system
+    ///
+    /// Requires the `system_clipboard` feature, without it the clipboard is always local
+    /// to the instance. Falls back to the local clipboard if the system clipboard is
+    /// unavailable, e.g. over SSH. Enabled by default with the feature. Replaces a kill
+    /// ring set with [`Reedline::with_kill_ring`].
+    pub fn use_system_clipboard(mut self, enable: bool) -> Self {
+        if enable {
+            self.editor
+                .set_cut_buffer(Box::new(get_default_clipboard()));
+        } else {
+            self.editor.set_cut_buffer(Box::new(LocalClipboard::new()));
+        }
+        self
+    }
+
     /// Toggle whether reedline uses the kitty keyboard enhancement protocol
     ///
     /// This allows us to disambiguate more events than the traditional standard
@@ -266,6 +479,17 @@ impl Reedline {
         self
     }
 
+    /// Toggle whether reedline captures the mouse to select the values of the menus
+    ///
+    /// A click selects the value under the mouse and a second click on the same value
+    /// accepts it. The scroll wheel moves through the values. While the mouse is
+    /// captured the terminal doesn't handle text selection with the mouse.
+    #[must_use]
+    pub fn with_mouse(mut self, enable: bool) -> Self {
+        self.mouse_capture.set(enable);
+        self
+    }
+
     /// Return the previously generated history session id
     pub fn get_history_session_id(&self) -> Option<HistorySessionId> {
         self.history_session_id
@@ -308,6 +532,48 @@ impl Reedline {
         self
     }
 
+    /// A builder that shows the rest of the first suggestion of the completer for the
+    /// typed word as a hint, when the [`Hinter`] has none. It is accepted like a hint,
+    /// e.g. with [`ReedlineEvent::HistoryHintComplete`].
+    ///
+    /// The completer is asked once for every line that is typed, and not while a
+    /// menu is open
+    #[must_use]
+    pub fn with_completion_preview(mut self, completion_preview: bool) -> Self {
+        self.completion_preview = completion_preview;
+        self
+    }
+
+    /// A builder that sets the style of the completion preview, see
+    /// [`Reedline::with_completion_preview`]. By default it is painted like the hints
+    /// of the [`DefaultHinter`](crate::DefaultHinter)
+    #[must_use]
+    pub fn with_completion_preview_style(mut self, style: Style) -> Self {
+        self.completion_preview_style = style;
+        self
+    }
+
+    /// A builder that sets abbreviations expanded in place when they are followed by a
+    /// space or submitted, like the `abbr` of fish
+    ///
+    /// The word before the cursor is replaced by its expansion if it is one of the
+    /// keys. The expansion is undone on its own, back to the abbreviation. By default
+    /// only the first word of the line is expanded, see
+    /// [`Reedline::with_abbreviations_anywhere`].
+    #[must_use]
+    pub fn with_abbreviations(mut self, abbreviations: HashMap<String, String>) -> Self {
+        self.abbreviations = abbreviations;
+        self
+    }
+
+    /// A builder that expands the abbreviations set with [`Reedline::with_abbreviations`]
+    /// wherever they are typed, not only as the first word of the line
+    #[must_use]
+    pub fn with_abbreviations_anywhere(mut self, anywhere: bool) -> Self {
+        self.abbreviations_anywhere = anywhere;
+        self
+    }
+
     /// A builder to configure the tab completion
     /// # Example
     /// ```rust
@@ -331,6 +597,18 @@ impl Reedline {
         self
     }
 
+    /// A builder to configure a tab completion that computes its values without
+    /// blocking the editor
+    ///
+    /// While the completions are pending the menu shows the previous values with a
+    /// spinner in its indicator. See [`AsyncCompleter`] for how pending completions
+    /// are cancelled
+    #[must_use]
+    pub fn with_async_completer(mut self, completer: Box<dyn AsyncCompleter>) -> Self {
+        self.completer = Box::new(PollingCompleter::new(completer));
+        self
+    }
+
     /// Turn on quick completions. These completions will auto-select if the completer
     /// ever narrows down to a single entry.
     #[must_use]
@@ -339,6 +617,27 @@ impl Reedline {
         self
     }
 
+    /// A builder that coalesces the completer queries of an active menu while typing
+    ///
+    /// The menu is only updated once no edit arrived for the `debounce` duration, or
+    /// before any other event is handled, so that the values match the buffer when they
+    /// are selected. Until then the menu keeps showing the values of the last query.
+    #[must_use]
+    pub fn with_completion_debounce(mut self, debounce: Duration) -> Self {
+        self.completion_debounce = Some(debounce);
+        self
+    }
+
+    /// A builder that panics when an accepted completion has a span outside of the
+    /// buffer, to catch the mistakes of a [`Completer`] while writing it
+    ///
+    /// By default such spans are clamped to the buffer with [`Span::clamp_to`](crate::Span::clamp_to).
+    #[must_use]
+    pub fn with_strict_completions(mut self, strict_completions: bool) -> Self {
+        self.editor.set_strict_completions(strict_completions);
+        self
+    }
+
     /// Turn on partial completions. These completions will fill the buffer with the
     /// smallest common string from all the options
     #[must_use]
@@ -355,6 +654,55 @@ impl Reedline {
         self
     }
 
+    /// A builder that writes the OSC 133 semantic prompt markers, which terminals like
+    /// WezTerm, iTerm2 or the one of VS Code use to jump between the commands and to
+    /// fold their output
+    ///
+    /// The start of the prompt and of the input are marked on every repaint, the start
+    /// of the output once the line is submitted. Report the exit code of the command
+    /// with [`Reedline::report_command_finished`] to mark the end of its output.
+    #[must_use]
+    pub fn with_shell_integration(mut self, shell_integration: bool) -> Self {
+        self.painter.set_shell_integration(shell_integration);
+        self
+    }
+
+    /// A builder that briefly flashes the terminal when an edit command can't do
+    /// anything, e.g. an undo without any edits or a move to a matching bracket without
+    /// a match
+    ///
+    /// The colors of the terminal are inverted until the next event arrives or a
+    /// moment has passed.
+    #[must_use]
+    pub fn with_visual_bell(mut self, visual_bell: bool) -> Self {
+        self.visual_bell = visual_bell;
+        self
+    }
+
+    /// A builder that rings the bell of the terminal when an edit command can't do
+    /// anything, like [`Reedline::with_visual_bell`]
+    #[must_use]
+    pub fn with_audible_bell(mut self, audible_bell: bool) -> Self {
+        self.audible_bell = audible_bell;
+        self
+    }
+
+    /// A builder that paints the line editor to the `writer` instead of the
+    /// terminal, e.g. to embed it in a TUI or to capture the painted lines.
+    ///
+    /// The terminal is not queried for its size and the cursor position. The lines are
+    /// painted for 80 columns and 24 rows until a [`ReedlineEvent::Resize`] changes
+    /// the size, and every line starts where the previous one left the cursor.
+    /// [`Reedline::read_line`] still reads the keys from the terminal, see
+    /// [`Reedline::process_events`] to run the events without it.
+    #[must_use]
+    pub fn with_buffer(mut self, writer: impl Write + Send + 'static) -> Self {
+        let shell_integration = self.painter.shell_integration();
+        self.painter = Painter::detached(writer);
+        self.painter.set_shell_integration(shell_integration);
+        self
+    }
+
     /// A builder that configures the highlighter for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -378,12 +726,78 @@ impl Reedline {
     }
 
     /// A builder that configures the style used for visual selection
+    ///
+    /// The style is layered over the highlighting, so a style only setting the
+    /// background keeps the colors of the highlighter
     #[must_use]
     pub fn with_visual_selection_style(mut self, style: Style) -> Self {
         self.visual_selection_style = style;
         self
     }
 
+    /// A builder that paints the tabs of the buffer as the spaces up to the next tab
+    /// stop, every `tab_width` columns from the start of the line
+    ///
+    /// The buffer keeps the tabs, only their display changes. Without it the tabs are
+    /// written out and the terminal expands them to its own tab stops.
+    #[must_use]
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = Some(tab_width.max(1));
+        self
+    }
+
+    /// A builder that sets how a line wider than the terminal is painted, see
+    /// [`LineOverflow`]
+    ///
+    /// Default is [`LineOverflow::Wrap`]. Buffers of several lines always wrap.
+    #[must_use]
+    pub fn with_line_overflow(mut self, line_overflow: LineOverflow) -> Self {
+        self.line_overflow = line_overflow;
+        self
+    }
+
+    /// A builder that replaces the clipboard with a kill ring keeping the last `size` cuts
+    ///
+    /// Consecutive cuts in the same direction are combined into one entry and
+    /// [`EditCommand::YankPop`] replaces the pasted text with the previous entries.
+    /// The kill ring is local to the instance, even with the `system_clipboard` feature.
+    #[must_use]
+    pub fn with_kill_ring(mut self, size: usize) -> Self {
+        self.editor.set_cut_buffer(Box::new(KillRing::new(size)));
+        self
+    }
+
+    /// A builder that enables the insertion of the closing bracket or quote together
+    /// with the opening one, see [`AutoPairConfig`]
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn with_auto_pairs(mut self, auto_pairs: AutoPairConfig) -> Self {
+        self.editor.set_auto_pairs(Some(auto_pairs));
+        self
+    }
+
+    /// A builder that sets how consecutive edits are grouped into one undo step, see
+    /// [`UndoCoalescing`]
+    ///
+    /// Default is [`UndoCoalescing::PerWord`].
+    #[must_use]
+    pub fn with_undo_coalescing(mut self, undo_coalescing: UndoCoalescing) -> Self {
+        self.editor.set_undo_coalescing(undo_coalescing);
+        self
+    }
+
+    /// A builder that sets the definition of the words used by the word movements and
+    /// edits, see [`WordBoundary`]
+    ///
+    /// In vi mode `w` uses these words while `W` always uses the words separated by
+    /// whitespace. Default is [`WordBoundary::Unicode`].
+    #[must_use]
+    pub fn with_word_boundary(mut self, word_boundary: WordBoundary) -> Self {
+        self.editor.set_word_boundary(word_boundary);
+        self
+    }
+
     /// A builder which configures the history for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -425,6 +839,16 @@ impl Reedline {
         self
     }
 
+    /// A builder that sets the character painted for every character of a secret read
+    /// with [`Reedline::read_secret`]
+    ///
+    /// By default nothing is shown while a secret is typed.
+    #[must_use]
+    pub fn with_secret_mask(mut self, mask: char) -> Self {
+        self.secret_mask = Some(mask);
+        self
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -441,10 +865,67 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures a validator running without blocking the editor, it
+    /// replaces the validator set with [`Reedline::with_validator`]
+    ///
+    /// See [`AsyncValidator`] for how the line is shown and submitted while the
+    /// validation is pending
+    #[must_use]
+    pub fn with_async_validator(mut self, validator: Box<dyn AsyncValidator>) -> Self {
+        self.async_validator = Some(validator);
+        self
+    }
+
+    /// A builder that toggles the indentation of continuation lines
+    ///
+    /// When the validator finds the input incomplete, the new line starts with the
+    /// indentation given by [`Validator::indent_for`]. It is inserted as regular text.
+    #[must_use]
+    pub fn with_auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// A builder that makes [`ReedlineEvent::Enter`] submit the line even if the
+    /// validator finds it incomplete, the lines are then started with the key set by
+    /// [`Reedline::with_newline_binding`]
+    #[must_use]
+    pub fn with_forced_submit(mut self, forced_submit: bool) -> Self {
+        self.forced_submit = forced_submit;
+        self
+    }
+
+    /// A builder that binds a key inserting a newline without submitting the line,
+    /// whatever the validator says. The key is handled before the keybindings of the
+    /// edit mode.
+    ///
+    /// # Example
+    /// ```rust
+    /// // Enter submits and Alt+Enter starts a new line
+    ///
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{DefaultValidator, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_validator(Box::new(DefaultValidator))
+    ///     .with_forced_submit(true)
+    ///     .with_newline_binding(KeyModifiers::ALT, KeyCode::Enter);
+    /// ```
+    #[must_use]
+    pub fn with_newline_binding(mut self, modifier: KeyModifiers, key_code: KeyCode) -> Self {
+        self.newline_binding = Some(KeyCombination { modifier, key_code });
+        self
+    }
+
     /// A builder that configures the alternate text editor used to edit the line buffer
     ///
     /// You are responsible for providing a file path that is unique to this reedline session
     ///
+    /// [`ReedlineEvent::OpenEditor`] writes the buffer to the file and runs the editor
+    /// outside of the raw mode. The edited file replaces the buffer once the editor exits
+    /// successfully, with the cursor at the end. Without a configured editor the one of
+    /// the `$VISUAL` or `$EDITOR` environment variable is used.
+    ///
     /// # Example
     /// ```rust,no_run
     /// // Create a reedline object with vim as editor
@@ -488,6 +969,17 @@ impl Reedline {
         self
     }
 
+    /// A builder to compute the prompt of every line without blocking the editor
+    ///
+    /// The prompt given to [`Reedline::read_line`] is shown as placeholder until the
+    /// computed prompt arrives. See [`AsyncPrompt`] for how the computation is
+    /// cancelled when the line is submitted before
+    #[must_use]
+    pub fn with_async_prompt(mut self, prompt: Box<dyn AsyncPrompt>) -> Self {
+        self.async_prompt = Some(prompt);
+        self
+    }
+
     /// A builder which configures the edit mode for your instance of the Reedline engine
     #[must_use]
     pub fn with_edit_mode(mut self, edit_mode: Box<dyn EditMode>) -> Self {
@@ -516,9 +1008,98 @@ impl Reedline {
         self
     }
 
+    /// A builder that configures what Ctrl-C ([`ReedlineEvent::CtrlC`]) does, see
+    /// [`CtrlCBehavior`]
+    ///
+    /// Default is returning [`Signal::CtrlC`].
+    #[must_use]
+    pub fn with_ctrl_c_behavior(mut self, behavior: CtrlCBehavior) -> Self {
+        self.ctrl_c_behavior = behavior;
+        self
+    }
+
+    /// A builder that configures what Ctrl-D ([`ReedlineEvent::CtrlD`]) does, see
+    /// [`CtrlDBehavior`]
+    ///
+    /// Default is deleting a character, or returning [`Signal::CtrlD`] on an empty line.
+    #[must_use]
+    pub fn with_ctrl_d_behavior(mut self, behavior: CtrlDBehavior) -> Self {
+        self.ctrl_d_behavior = behavior;
+        self
+    }
+
+    /// A builder that sets a callback observing every saved history entry, with the id
+    /// given by the history, e.g. to log the commands to another place
+    ///
+    /// It is called once per submitted line, but not for lines read with
+    /// [`Reedline::read_secret`] or excluded by [`Reedline::with_history_exclusion_prefix`].
+    /// The entry is passed once its context is complete, when the next line is read or
+    /// the engine is dropped, so it includes the duration or exit status set with
+    /// [`Reedline::update_last_command_context`].
+    #[must_use]
+    pub fn with_history_session_callback(mut self, callback: HistorySessionCallback) -> Self {
+        self.history_session_callback = Some(callback);
+        self
+    }
+
+    /// A builder that sets a transformation of the accepted lines, e.g. to expand aliases
+    ///
+    /// It is called with the line once it is complete according to the validator.
+    /// Returning `Some` replaces the line that is returned and saved in the history,
+    /// `None` keeps it unchanged. The new line is not validated again and the line
+    /// shown in the terminal stays the one that was typed. Lines read with
+    /// [`Reedline::read_secret`] are not transformed.
+    #[must_use]
+    pub fn with_submit_transform(mut self, transform: SubmitTransform) -> Self {
+        self.submit_transform = Some(transform);
+        self
+    }
+
+    /// A builder that removes the whitespace of the accepted lines, see [`TrimMode`]
+    ///
+    /// The line is trimmed once it is complete according to the validator, before the
+    /// [`Reedline::with_submit_transform`]. The trimmed line is returned and saved in
+    /// the history. Default is [`TrimMode::None`].
+    #[must_use]
+    pub fn with_trim_on_submit(mut self, trim_mode: TrimMode) -> Self {
+        self.trim_on_submit = trim_mode;
+        self
+    }
+
+    /// A builder that makes up and down search the history for the entries starting with
+    /// the text before the cursor, like `history-beginning-search-backward` in zsh
+    ///
+    /// The prefix is kept while stepping through the matches and the cursor stays at its
+    /// end. Going down past the most recent match restores the line. With an empty
+    /// buffer the whole history is traversed. By default the history is only searched
+    /// for the whole line when the cursor is at its end.
+    #[must_use]
+    pub fn with_prefix_history_search(mut self, prefix_history_search: bool) -> Self {
+        self.prefix_history_search = prefix_history_search;
+        self
+    }
+
     /// A builder that enables reedline changing the cursor shape based on the current edit mode.
-    /// The current implementation sets the cursor shape when drawing the prompt.
+    /// The current implementation sets the cursor shape when drawing the prompt in a new mode,
+    /// and restores the default shape of the terminal once the line is submitted.
     /// Do not use this if the cursor shape is set elsewhere, e.g. in the terminal settings or by ansi escape sequences.
+    ///
+    /// Terminals without support for the cursor shapes ignore them.
+    /// # Example
+    /// ```rust
+    /// use crossterm::cursor::SetCursorStyle;
+    /// use reedline::{CursorConfig, Reedline, Vi};
+    ///
+    /// let shapes = CursorConfig {
+    ///     vi_insert: Some(SetCursorStyle::BlinkingBar),
+    ///     vi_normal: Some(SetCursorStyle::SteadyBlock),
+    ///     emacs: None,
+    /// };
+    /// let line_editor = Reedline::create()
+    ///     .with_edit_mode(Box::new(Vi::default()))
+    ///     .with_cursor_config(shapes);
+    /// ```
+    #[must_use]
     pub fn with_cursor_config(mut self, cursor_shapes: CursorConfig) -> Self {
         self.cursor_shapes = Some(cursor_shapes);
         self
@@ -581,6 +1162,13 @@ impl Reedline {
         &*self.history
     }
 
+    /// Applies the theme to all the menus, it is shown from the next repaint
+    pub fn apply_menu_theme(&mut self, theme: &MenuTheme) {
+        for menu in self.menus.iter_mut() {
+            menu.apply_theme(theme);
+        }
+    }
+
     /// Mutable view of the history
     pub fn history_mut(&mut self) -> &mut dyn History {
         &mut *self.history
@@ -610,7 +1198,12 @@ impl Reedline {
                 self.history_excluded_item = Some(f(self.history_excluded_item.take().unwrap()));
                 Ok(())
             }
-            Some(r) => self.history.update(*r, f),
+            Some(r) => {
+                if let Some(entry) = self.history_callback_entry.take() {
+                    self.history_callback_entry = Some(f(entry));
+                }
+                self.history.update(*r, f)
+            }
             None => Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
                 "No command run",
             ))),
@@ -625,15 +1218,147 @@ impl Reedline {
         terminal::enable_raw_mode()?;
         self.bracketed_paste.enter();
         self.kitty_protocol.enter();
+        self.mouse_capture.enter();
 
         let result = self.read_line_helper(prompt);
 
         self.bracketed_paste.exit();
         self.kitty_protocol.exit();
+        self.mouse_capture.exit();
         terminal::disable_raw_mode()?;
         result
     }
 
+    /// Wait for input like [`Reedline::read_line`], starting with the buffer filled with
+    /// `initial` to edit an existing command.
+    ///
+    /// The cursor is placed at the byte offset `cursor`, by default at the end of the
+    /// text. The initial text is where the undo history starts, so it can't be undone.
+    pub fn read_line_with_initial(
+        &mut self,
+        prompt: &dyn Prompt,
+        initial: &str,
+        cursor: Option<usize>,
+    ) -> Result<Signal> {
+        self.editor.set_initial_buffer(initial.to_string(), cursor);
+
+        self.read_line(prompt)
+    }
+
+    /// Wait for a secret like a password and provide the user with a specified [`Prompt`].
+    ///
+    /// Instead of the input, the mask set with [`Reedline::with_secret_mask`] is painted for
+    /// every typed character, without a mask nothing is shown. The highlighter, the hinter,
+    /// the menus and the history navigation are not used and the input is never added to
+    /// the [`History`]. The returned [`Signal::Success`] contains the real input.
+    ///
+    /// Once the read is done the buffer and its undo history are overwritten in memory.
+    /// Text cut while typing the secret stays in the clipboard.
+    pub fn read_secret(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        self.reading_secret = true;
+
+        let result = self.read_line(prompt);
+
+        self.editor.clear_secret();
+        self.reading_secret = false;
+        result
+    }
+
+    /// Runs the menu named `menu_name` as a picker, see [`crate::pick`].
+    ///
+    /// The menu stays open while the buffer filters its values. The read ends with
+    /// the accepted value, or with `None` when the pick is cancelled.
+    pub(crate) fn read_pick(
+        &mut self,
+        prompt: &dyn Prompt,
+        menu_name: &str,
+    ) -> Result<Option<Suggestion>> {
+        self.picker_menu = Some(menu_name.to_string());
+        self.picked = None;
+        self.open_picker_menu();
+
+        let result = self.read_line(prompt);
+
+        self.picker_menu = None;
+        self.deactivate_menus();
+        result.map(|_| self.picked.take())
+    }
+
+    /// Runs the `events` like [`Reedline::read_line`] runs the events of the keys
+    /// pressed, without reading from the terminal, e.g. to test keybindings with the
+    /// events of [`EditMode::parse_event`] or to script edits.
+    ///
+    /// Returns the [`Signal`] of the first event ending the line, the remaining events
+    /// are dropped. Returns `None` if the line is still edited, its state is available
+    /// with [`Reedline::current_buffer_contents`] and
+    /// [`Reedline::current_insertion_point`].
+    ///
+    /// The line is painted with the [`DefaultPrompt`](crate::DefaultPrompt), to
+    /// update the menus as on the screen. Nothing is written to the terminal: the
+    /// painting goes to the writer of [`Reedline::with_buffer`] or is discarded.
+    ///
+    /// ```
+    /// use reedline::{EditCommand, Reedline, ReedlineEvent, Signal};
+    ///
+    /// let mut line_editor = Reedline::create();
+    /// let signal = line_editor.process_events(vec![
+    ///     ReedlineEvent::Edit(vec![EditCommand::InsertString("ls".to_string())]),
+    ///     ReedlineEvent::Enter,
+    /// ])?;
+    /// assert!(matches!(signal, Some(Signal::Success(line)) if line == "ls"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn process_events(&mut self, events: Vec<ReedlineEvent>) -> Result<Option<Signal>> {
+        let prompt = crate::DefaultPrompt::default();
+        let terminal_painter = if self.painter.is_terminal() {
+            Some(std::mem::replace(
+                &mut self.painter,
+                Painter::detached(io::sink()),
+            ))
+        } else {
+            None
+        };
+
+        let result = self.process_events_helper(&prompt, events);
+
+        if let Some(terminal_painter) = terminal_painter {
+            self.painter = terminal_painter;
+        }
+        result
+    }
+
+    fn process_events_helper(
+        &mut self,
+        prompt: &dyn Prompt,
+        events: Vec<ReedlineEvent>,
+    ) -> Result<Option<Signal>> {
+        self.painter.initialize_prompt_position()?;
+        self.repaint(prompt)?;
+
+        for event in events {
+            // The flash of the visual bell ends with the next event
+            if self.painter.reverse_video() {
+                self.painter.set_reverse_video(false)?;
+            }
+            match self.handle_event(prompt, event)? {
+                EventStatus::Exits(signal) => {
+                    self.painter.finish_input()?;
+                    return Ok(Some(signal));
+                }
+                EventStatus::Handled => self.repaint(prompt)?,
+                EventStatus::Inapplicable => {}
+            }
+        }
+        if self.debounced_query.is_some() {
+            self.flush_completion_query();
+            self.repaint(prompt)?;
+        } else if self.edit_mode_changed {
+            self.repaint(prompt)?;
+        }
+
+        Ok(None)
+    }
+
     /// Returns the current insertion point of the input buffer.
     pub fn current_insertion_point(&self) -> usize {
         self.editor.insertion_point()
@@ -649,6 +1374,12 @@ impl Reedline {
         self.painter.paint_line(msg)
     }
 
+    /// Marks the end of the output of the last submitted command with its exit code, see
+    /// [`Reedline::with_shell_integration`]. Does nothing without the shell integration.
+    pub fn report_command_finished(&mut self, exit_code: i32) -> Result<()> {
+        self.painter.finish_command(exit_code)
+    }
+
     /// Clear the screen by printing enough whitespace to start the prompt or
     /// other output back at the first line of the terminal.
     pub fn clear_screen(&mut self) -> Result<()> {
@@ -666,9 +1397,21 @@ impl Reedline {
 
     /// Helper implementing the logic for [`Reedline::read_line()`] to be wrapped
     /// in a `raw_mode` context.
+    ///
+    /// With an [`AsyncPrompt`] the given prompt is the placeholder until the
+    /// computed one arrives
     fn read_line_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
+        self.notify_history_callback();
         self.painter.initialize_prompt_position()?;
         self.hide_hints = false;
+        self.pending_validation = None;
+        self.preview_cache.clear();
+
+        let mut pending_prompt = self
+            .async_prompt
+            .as_mut()
+            .map(|async_prompt| PendingPrompt::new(async_prompt.prompt()));
+        let mut computed_prompt: Option<Box<dyn Prompt>> = None;
 
         self.repaint(prompt)?;
 
@@ -678,6 +1421,22 @@ impl Reedline {
         loop {
             let mut paste_enter_state = false;
 
+            // The computed prompt replaces the placeholder without touching the buffer
+            if let Some(resolved_prompt) = Self::wait_for_prompt(&mut pending_prompt)? {
+                self.repaint(resolved_prompt.as_ref())?;
+                computed_prompt = Some(resolved_prompt);
+            }
+            let prompt = computed_prompt.as_deref().unwrap_or(prompt);
+
+            match self.wait_for_validation(prompt)? {
+                Some(EventStatus::Exits(signal)) => {
+                    self.painter.finish_input()?;
+                    return Ok(signal);
+                }
+                Some(_) => self.repaint(prompt)?,
+                None => {}
+            }
+
             #[cfg(feature = "external_printer")]
             if let Some(ref external_printer) = self.external_printer {
                 // get messages from printer as crlf separated "lines"
@@ -693,42 +1452,75 @@ impl Reedline {
                 }
             }
 
+            // The flash of the visual bell ends after a moment or with the next event
+            if self.painter.reverse_video() {
+                event::poll(Duration::from_millis(VISUAL_BELL_DURATION))?;
+                self.painter.set_reverse_video(false)?;
+            }
+
+            // The postponed completer query is sent once the user pauses typing
+            while let Some(wait) = self.completion_debounce_wait() {
+                if event::poll(wait)? {
+                    break;
+                }
+                self.flush_completion_query();
+                self.repaint(prompt)?;
+            }
+
+            // While the completer computes its values in the background, the menu is
+            // refreshed until they arrive or the user sends a new event
+            while self.completion_pending()
+                && !event::poll(Duration::from_millis(ASYNC_COMPLETION_WAIT))?
+            {
+                self.refresh_completion_menu();
+                self.repaint(prompt)?;
+            }
+
             let mut latest_resize = None;
-            loop {
-                match event::read()? {
-                    Event::Resize(x, y) => {
-                        latest_resize = Some((x, y));
-                    }
-                    enter @ Event::Key(KeyEvent {
-                        code: KeyCode::Enter,
-                        modifiers: KeyModifiers::NONE,
-                        ..
-                    }) => {
-                        let enter = ReedlineRawEvent::convert_from(enter);
-                        if let Some(enter) = enter {
-                            crossterm_events.push(enter);
-                            // Break early to check if the input is complete and
-                            // can be send to the hosting application. If
-                            // multiple complete entries are submitted, events
-                            // are still in the crossterm queue for us to
-                            // process.
-                            paste_enter_state = crossterm_events.len() > EVENTS_THRESHOLD;
-                            break;
+            // A started key sequence that is not continued in time is processed key by key
+            if self.edit_mode.has_pending_keys()
+                && !event::poll(Duration::from_millis(KEY_SEQUENCE_TIMEOUT))?
+            {
+                let edit_mode = self.prompt_edit_mode();
+                reedline_events.push(self.edit_mode.flush_pending_keys());
+                self.edit_mode_changed |= self.prompt_edit_mode() != edit_mode;
+            } else {
+                loop {
+                    match event::read()? {
+                        Event::Resize(x, y) => {
+                            latest_resize = Some((x, y));
                         }
-                    }
-                    x => {
-                        let raw_event = ReedlineRawEvent::convert_from(x);
-                        if let Some(evt) = raw_event {
-                            crossterm_events.push(evt);
+                        enter @ Event::Key(KeyEvent {
+                            code: KeyCode::Enter,
+                            modifiers: KeyModifiers::NONE,
+                            ..
+                        }) => {
+                            let enter = ReedlineRawEvent::convert_from(enter);
+                            if let Some(enter) = enter {
+                                crossterm_events.push(enter);
+                                // Break early to check if the input is complete and
+                                // can be send to the hosting application. If
+                                // multiple complete entries are submitted, events
+                                // are still in the crossterm queue for us to
+                                // process.
+                                paste_enter_state = crossterm_events.len() > EVENTS_THRESHOLD;
+                                break;
+                            }
+                        }
+                        x => {
+                            let raw_event = ReedlineRawEvent::convert_from(x);
+                            if let Some(evt) = raw_event {
+                                crossterm_events.push(evt);
+                            }
                         }
                     }
-                }
 
-                // There could be multiple events queued up!
-                // pasting text, resizes, blocking this thread (e.g. during debugging)
-                // We should be able to handle all of them as quickly as possible without causing unnecessary output steps.
-                if !event::poll(Duration::from_millis(POLL_WAIT))? {
-                    break;
+                    // There could be multiple events queued up!
+                    // pasting text, resizes, blocking this thread (e.g. during debugging)
+                    // We should be able to handle all of them as quickly as possible without causing unnecessary output steps.
+                    if !event::poll(Duration::from_millis(POLL_WAIT))? {
+                        break;
+                    }
                 }
             }
 
@@ -741,7 +1533,7 @@ impl Reedline {
             // (Text should only be `EditCommand::InsertChar`s)
             let mut last_edit_commands = None;
             for event in crossterm_events.drain(..) {
-                match (&mut last_edit_commands, self.edit_mode.parse_event(event)) {
+                match (&mut last_edit_commands, self.parse_event(event)) {
                     (None, ReedlineEvent::Edit(ec)) => {
                         last_edit_commands = Some(ec);
                     }
@@ -766,7 +1558,7 @@ impl Reedline {
                 match self.handle_event(prompt, event)? {
                     EventStatus::Exits(signal) => {
                         // Move the cursor below the input area, for external commands or new read_line call
-                        self.painter.move_cursor_to_end()?;
+                        self.painter.finish_input()?;
                         return Ok(signal);
                     }
                     EventStatus::Handled => {
@@ -779,22 +1571,203 @@ impl Reedline {
                     }
                 }
             }
+            // The indicator shows the new edit mode even if the events changed nothing
+            if self.edit_mode_changed && !paste_enter_state {
+                self.repaint(prompt)?;
+            }
+        }
+    }
+
+    // The newline binding takes precedence over the keybindings of the edit mode
+    fn parse_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent {
+        if self.newline_binding.is_some() && event.key_combination() == self.newline_binding {
+            return ReedlineEvent::Edit(vec![EditCommand::InsertNewline]);
+        }
+
+        let edit_mode = self.prompt_edit_mode();
+        let event = self.edit_mode.parse_event(event);
+        self.edit_mode_changed |= self.prompt_edit_mode() != edit_mode;
+        event
+    }
+
+    // Polls the pending prompt until it resolves or the user sends a new event
+    fn wait_for_prompt(
+        pending_prompt: &mut Option<PendingPrompt>,
+    ) -> Result<Option<Box<dyn Prompt>>> {
+        while let Some(pending) = pending_prompt {
+            if let Some(prompt) = pending.poll() {
+                *pending_prompt = None;
+                return Ok(Some(prompt));
+            }
+            if event::poll(Duration::from_millis(ASYNC_PROMPT_WAIT))? {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Polls the pending validation until it resolves or the user sends a new event.
+    // The validation is dropped if the line was edited or a menu was opened since
+    fn wait_for_validation(&mut self, prompt: &dyn Prompt) -> Result<Option<EventStatus>> {
+        let is_stale = self.pending_validation.as_ref().map_or(false, |pending| {
+            pending.line() != self.editor.get_buffer()
+                || self.menus.iter().any(|menu| menu.is_active())
+        });
+        if is_stale {
+            self.pending_validation = None;
+        }
+
+        while let Some(pending) = self.pending_validation.as_mut() {
+            if let Some(result) = pending.poll() {
+                self.pending_validation = None;
+                return self.apply_validation(prompt, Some(result)).map(Some);
+            }
+            if event::poll(Duration::from_millis(ASYNC_VALIDATION_WAIT))? {
+                break;
+            }
         }
+
+        Ok(None)
     }
 
     fn handle_event(&mut self, prompt: &dyn Prompt, event: ReedlineEvent) -> Result<EventStatus> {
-        if self.input_mode == InputMode::HistorySearch {
-            self.handle_history_search_event(event)
+        if contains_macro_command(&event) {
+            return self.handle_macro_event(prompt, event);
+        }
+
+        let event = if self.reading_secret {
+            secret_event(event)
+        } else {
+            self.macros.record(&event);
+            event
+        };
+        // The other events may use the values of the menu
+        if !matches!(event, ReedlineEvent::Edit(_)) {
+            self.flush_completion_query();
+        }
+        let status = if self.input_mode == InputMode::HistorySearch {
+            self.handle_history_search_event(prompt, event)
         } else {
             self.handle_editor_event(prompt, event)
+        };
+        self.open_picker_menu();
+        status
+    }
+
+    // Splits events containing macro commands, so that the macro commands are not
+    // recorded themselves and the remaining events are dispatched as usual
+    fn handle_macro_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> Result<EventStatus> {
+        match event {
+            ReedlineEvent::Edit(commands) if commands.len() > 1 => {
+                let events = commands
+                    .into_iter()
+                    .group_by(is_macro_command)
+                    .into_iter()
+                    .flat_map(|(is_macro, commands)| {
+                        if is_macro {
+                            commands.map(|command| vec![command]).collect()
+                        } else {
+                            vec![commands.collect()]
+                        }
+                    })
+                    .map(ReedlineEvent::Edit)
+                    .collect();
+                self.handle_events(prompt, events)
+            }
+            ReedlineEvent::Edit(mut commands) => match commands.pop() {
+                Some(command) => self.run_macro_command(prompt, command),
+                None => Ok(EventStatus::Inapplicable),
+            },
+            ReedlineEvent::Multiple(events) => self.handle_events(prompt, events),
+            ReedlineEvent::UntilFound(events) => {
+                for event in events {
+                    match self.handle_event(prompt, event)? {
+                        EventStatus::Inapplicable => {
+                            // Try again with the next event handler
+                        }
+                        success => {
+                            return Ok(success);
+                        }
+                    }
+                }
+                Ok(EventStatus::Inapplicable)
+            }
+            event => self.handle_event(prompt, event),
+        }
+    }
+
+    fn handle_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        events: Vec<ReedlineEvent>,
+    ) -> Result<EventStatus> {
+        let mut latest_signal = EventStatus::Inapplicable;
+        for event in events {
+            match self.handle_event(prompt, event)? {
+                EventStatus::Handled => {
+                    latest_signal = EventStatus::Handled;
+                }
+                EventStatus::Inapplicable => {
+                    // NO OP
+                }
+                EventStatus::Exits(signal) => {
+                    return Ok(EventStatus::Exits(signal));
+                }
+            }
+        }
+
+        Ok(latest_signal)
+    }
+
+    fn run_macro_command(
+        &mut self,
+        prompt: &dyn Prompt,
+        command: EditCommand,
+    ) -> Result<EventStatus> {
+        match command {
+            EditCommand::StartRecordingMacro(register) => {
+                self.macros.start(register);
+                Ok(EventStatus::Handled)
+            }
+            EditCommand::StopRecordingMacro => Ok(if self.macros.stop() {
+                EventStatus::Handled
+            } else {
+                EventStatus::Inapplicable
+            }),
+            EditCommand::ReplayMacro(register) => {
+                // Like in vim the replay itself is recorded, so that a macro can
+                // replay other macros or even itself
+                self.macros
+                    .record(&ReedlineEvent::Edit(vec![EditCommand::ReplayMacro(
+                        register,
+                    )]));
+                match self.macros.begin_replay(register) {
+                    Some(events) => {
+                        let status = self.handle_events(prompt, events);
+                        self.macros.end_replay();
+                        status
+                    }
+                    None => Ok(EventStatus::Inapplicable),
+                }
+            }
+            command => self.handle_event(prompt, ReedlineEvent::Edit(vec![command])),
         }
     }
 
-    fn handle_history_search_event(&mut self, event: ReedlineEvent) -> io::Result<EventStatus> {
+    fn handle_history_search_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> io::Result<EventStatus> {
         match event {
             ReedlineEvent::UntilFound(events) => {
                 for event in events {
-                    match self.handle_history_search_event(event)? {
+                    match self.handle_history_search_event(prompt, event)? {
                         EventStatus::Inapplicable => {
                             // Try again with the next event handler
                         }
@@ -807,9 +1780,9 @@ impl Reedline {
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::CtrlD => {
-                if self.editor.is_empty() {
+                if self.is_eof() {
                     self.input_mode = InputMode::Regular;
-                    self.editor.reset_undo_stack();
+                    self.clear_buffer();
                     Ok(EventStatus::Exits(Signal::CtrlD))
                 } else {
                     self.run_history_commands(&[EditCommand::Delete]);
@@ -818,7 +1791,12 @@ impl Reedline {
             }
             ReedlineEvent::CtrlC => {
                 self.input_mode = InputMode::Regular;
-                Ok(EventStatus::Exits(Signal::CtrlC))
+                if self.cancels_line() {
+                    self.interrupt(prompt)
+                } else {
+                    // The search only edited its query, the line from before is kept
+                    Ok(EventStatus::Exits(Signal::CtrlC))
+                }
             }
             ReedlineEvent::ClearScreen => {
                 self.painter.clear_screen()?;
@@ -848,7 +1826,13 @@ impl Reedline {
                 self.run_history_commands(&commands);
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::Mouse => Ok(EventStatus::Handled),
+            ReedlineEvent::Mouse(_) => Ok(EventStatus::Handled),
+            ReedlineEvent::Paste(text) => {
+                self.run_history_commands(&[EditCommand::InsertString(
+                    text_manipulation::clean_pasted_text(&text),
+                )]);
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Inapplicable)
@@ -863,7 +1847,9 @@ impl Reedline {
                     .expect("todo: error handling");
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::NextHistory | ReedlineEvent::Down => {
+            ReedlineEvent::NextHistory
+            | ReedlineEvent::Down
+            | ReedlineEvent::SearchHistoryForward => {
                 self.history_cursor
                     .forward(self.history.as_ref())
                     .expect("todo: error handling");
@@ -875,7 +1861,7 @@ impl Reedline {
                 }
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::Esc => {
+            ReedlineEvent::Esc | ReedlineEvent::CancelHistorySearch => {
                 self.input_mode = InputMode::Regular;
                 Ok(EventStatus::Handled)
             }
@@ -894,7 +1880,13 @@ impl Reedline {
             | ReedlineEvent::MenuLeft
             | ReedlineEvent::MenuRight
             | ReedlineEvent::MenuPageNext
-            | ReedlineEvent::MenuPagePrevious => Ok(EventStatus::Inapplicable),
+            | ReedlineEvent::MenuPagePrevious
+            | ReedlineEvent::MenuPageGoto(_)
+            | ReedlineEvent::MenuAccept
+            | ReedlineEvent::MenuAcceptAndContinue
+            | ReedlineEvent::MenuToggleSort
+            | ReedlineEvent::NextTabStop
+            | ReedlineEvent::PrevTabStop => Ok(EventStatus::Inapplicable),
         }
     }
 
@@ -993,6 +1985,42 @@ impl Reedline {
                         Ok(EventStatus::Handled)
                     })
             }
+            ReedlineEvent::MenuPageGoto(page) => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::GotoPage(page));
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            ReedlineEvent::MenuToggleSort => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::ToggleSort);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            ReedlineEvent::MenuAccept => {
+                match self.menus.iter_mut().find(|menu| menu.is_active()) {
+                    Some(menu) => {
+                        menu.accept_selection(&mut self.editor, self.completer.as_mut());
+                        menu.menu_event(MenuEvent::Deactivate);
+                        Ok(EventStatus::Handled)
+                    }
+                    None => Ok(EventStatus::Inapplicable),
+                }
+            }
+            ReedlineEvent::MenuAcceptAndContinue => {
+                match self.menus.iter_mut().find(|menu| menu.is_active()) {
+                    Some(menu) => {
+                        menu.accept_selection(&mut self.editor, self.completer.as_mut());
+                        // Activating the menu again selects the first value and
+                        // queries the completer at the new cursor position
+                        menu.menu_event(MenuEvent::Activate(false));
+                        Ok(EventStatus::Handled)
+                    }
+                    None => Ok(EventStatus::Inapplicable),
+                }
+            }
             ReedlineEvent::HistoryHintComplete => {
                 if let Some(hinter) = self.hinter.as_mut() {
                     let current_hint = hinter.complete_hint();
@@ -1005,6 +2033,11 @@ impl Reedline {
                         return Ok(EventStatus::Handled);
                     }
                 }
+                let preview = self.completion_preview();
+                if !preview.is_empty() {
+                    self.run_edit_commands(&[EditCommand::InsertString(preview)]);
+                    return Ok(EventStatus::Handled);
+                }
                 Ok(EventStatus::Inapplicable)
             }
             ReedlineEvent::HistoryHintWordComplete => {
@@ -1019,15 +2052,23 @@ impl Reedline {
                         return Ok(EventStatus::Handled);
                     }
                 }
+                let preview_part = get_first_token(&self.completion_preview());
+                if !preview_part.is_empty() {
+                    self.run_edit_commands(&[EditCommand::InsertString(preview_part)]);
+                    return Ok(EventStatus::Handled);
+                }
                 Ok(EventStatus::Inapplicable)
             }
+            ReedlineEvent::Esc | ReedlineEvent::CtrlC if self.picker_menu.is_some() => {
+                self.end_pick(prompt, None)
+            }
             ReedlineEvent::Esc => {
                 self.deactivate_menus();
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::CtrlD => {
-                if self.editor.is_empty() {
-                    self.editor.reset_undo_stack();
+                if self.is_eof() {
+                    self.clear_buffer();
                     Ok(EventStatus::Exits(Signal::CtrlD))
                 } else {
                     self.run_edit_commands(&[EditCommand::Delete]);
@@ -1036,9 +2077,7 @@ impl Reedline {
             }
             ReedlineEvent::CtrlC => {
                 self.deactivate_menus();
-                self.run_edit_commands(&[EditCommand::Clear]);
-                self.editor.reset_undo_stack();
-                Ok(EventStatus::Exits(Signal::CtrlC))
+                self.interrupt(prompt)
             }
             ReedlineEvent::ClearScreen => {
                 self.deactivate_menus();
@@ -1050,12 +2089,25 @@ impl Reedline {
                 self.painter.clear_scrollback()?;
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::Enter | ReedlineEvent::Submit | ReedlineEvent::SubmitOrNewline
+                if self.picker_menu.is_some() =>
+            {
+                match self.active_menu().and_then(|menu| menu.selected_value()) {
+                    Some(suggestion) => self.end_pick(prompt, Some(suggestion)),
+                    None => Ok(EventStatus::Inapplicable),
+                }
+            }
             ReedlineEvent::Enter | ReedlineEvent::Submit | ReedlineEvent::SubmitOrNewline
                 if self.menus.iter().any(|menu| menu.is_active()) =>
             {
                 for menu in self.menus.iter_mut() {
                     if menu.is_active() {
-                        menu.replace_in_buffer(&mut self.editor);
+                        if menu.accept_behavior() == MenuAcceptBehavior::SubmitPassthrough {
+                            // Without the menu the buffer is submitted as it is
+                            menu.menu_event(MenuEvent::Deactivate);
+                            return self.handle_editor_event(prompt, event);
+                        }
+                        menu.accept_selection(&mut self.editor, self.completer.as_mut());
                         menu.menu_event(MenuEvent::Deactivate);
 
                         return Ok(EventStatus::Handled);
@@ -1064,22 +2116,16 @@ impl Reedline {
                 unreachable!()
             }
             ReedlineEvent::Enter => {
+                self.expand_abbreviation();
                 #[cfg(feature = "bashisms")]
                 if let Some(event) = self.parse_bang_command() {
                     return self.handle_editor_event(prompt, event);
                 }
 
-                let buffer = self.editor.get_buffer().to_string();
-                match self.validator.as_mut().map(|v| v.validate(&buffer)) {
-                    None | Some(ValidationResult::Complete) => Ok(self.submit_buffer(prompt)?),
-                    Some(ValidationResult::Incomplete) => {
-                        self.run_edit_commands(&[EditCommand::InsertNewline]);
-
-                        Ok(EventStatus::Handled)
-                    }
-                }
+                self.validate_buffer(prompt)
             }
             ReedlineEvent::Submit => {
+                self.expand_abbreviation();
                 #[cfg(feature = "bashisms")]
                 if let Some(event) = self.parse_bang_command() {
                     return self.handle_editor_event(prompt, event);
@@ -1087,6 +2133,7 @@ impl Reedline {
                 Ok(self.submit_buffer(prompt)?)
             }
             ReedlineEvent::SubmitOrNewline => {
+                self.expand_abbreviation();
                 #[cfg(feature = "bashisms")]
                 if let Some(event) = self.parse_bang_command() {
                     return self.handle_editor_event(prompt, event);
@@ -1097,23 +2144,26 @@ impl Reedline {
                     self.run_edit_commands(&[EditCommand::InsertNewline]);
                     return Ok(EventStatus::Handled);
                 }
-                match self.validator.as_mut().map(|v| v.validate(&buffer)) {
-                    None | Some(ValidationResult::Complete) => Ok(self.submit_buffer(prompt)?),
-                    Some(ValidationResult::Incomplete) => {
-                        self.run_edit_commands(&[EditCommand::InsertNewline]);
-
-                        Ok(EventStatus::Handled)
-                    }
-                }
+                self.validate_buffer(prompt)
             }
             ReedlineEvent::ExecuteHostCommand(host_command) => {
                 // TODO: Decide if we need to do something special to have a nicer painter state on the next go
                 Ok(EventStatus::Exits(Signal::Success(host_command)))
             }
             ReedlineEvent::Edit(commands) => {
+                // A menu with its own filter line keeps the typed text out of the buffer
+                if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
+                    if menu.edit_filter(&commands) {
+                        menu.menu_event(MenuEvent::Edit(false));
+                        return Ok(EventStatus::Handled);
+                    }
+                }
                 self.run_edit_commands(&commands);
+                // Completions requested with the keyboard are not postponed
+                let debounce = self.completion_debounce.is_some()
+                    && !matches!(commands.first(), Some(&EditCommand::Complete));
                 if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
-                    if self.quick_completions && menu.can_quick_complete() {
+                    if self.quick_completions && menu.can_quick_complete() && !debounce {
                         match commands.first() {
                             Some(&EditCommand::Backspace)
                             | Some(&EditCommand::BackspaceWord)
@@ -1147,6 +2197,9 @@ impl Reedline {
                     }
                     if self.editor.line_buffer().get_buffer().is_empty() {
                         menu.menu_event(MenuEvent::Deactivate);
+                        self.debounced_query = None;
+                    } else if debounce {
+                        self.debounced_query = Some(Instant::now());
                     } else {
                         menu.menu_event(MenuEvent::Edit(self.quick_completions));
                     }
@@ -1178,18 +2231,38 @@ impl Reedline {
                 self.down_command();
                 Ok(EventStatus::Handled)
             }
+            // A plain arrow collapses the selection to its side instead of moving
             ReedlineEvent::Left => {
-                self.run_edit_commands(&[EditCommand::MoveLeft { select: false }]);
+                let command = match self.editor.get_selection() {
+                    Some(_) => EditCommand::MoveToSelectionStart,
+                    None => EditCommand::MoveLeft { select: false },
+                };
+                self.run_edit_commands(&[command]);
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::Right => {
-                self.run_edit_commands(&[EditCommand::MoveRight { select: false }]);
+                let command = match self.editor.get_selection() {
+                    Some(_) => EditCommand::MoveToSelectionEnd,
+                    None => EditCommand::MoveRight { select: false },
+                };
+                self.run_edit_commands(&[command]);
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::SearchHistory => {
                 self.enter_history_search();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::SearchHistoryForward | ReedlineEvent::CancelHistorySearch => {
+                Ok(EventStatus::Inapplicable)
+            }
+            ReedlineEvent::NextTabStop | ReedlineEvent::PrevTabStop => {
+                let forward = matches!(event, ReedlineEvent::NextTabStop);
+                if self.editor.goto_tab_stop(forward) {
+                    Ok(EventStatus::Handled)
+                } else {
+                    Ok(EventStatus::Inapplicable)
+                }
+            }
             ReedlineEvent::Multiple(events) => {
                 let mut latest_signal = EventStatus::Inapplicable;
                 for event in events {
@@ -1225,7 +2298,102 @@ impl Reedline {
                 // Exhausting the event handlers is still considered handled
                 Ok(EventStatus::Inapplicable)
             }
-            ReedlineEvent::None | ReedlineEvent::Mouse => Ok(EventStatus::Inapplicable),
+            ReedlineEvent::Mouse(action) => self.handle_mouse_action(prompt, action),
+            ReedlineEvent::Paste(text) => {
+                // Inserted without updating the menus, so the pasted text doesn't
+                // trigger completions
+                self.run_edit_commands(&[EditCommand::InsertString(
+                    text_manipulation::clean_pasted_text(&text),
+                )]);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::None => Ok(EventStatus::Inapplicable),
+        }
+    }
+
+    fn handle_mouse_action(
+        &mut self,
+        prompt: &dyn Prompt,
+        action: MouseAction,
+    ) -> io::Result<EventStatus> {
+        match action {
+            MouseAction::Click { row, .. } => {
+                let menu_start_row = self.painter.menu_start_row();
+                let accept = match (self.active_menu(), row.checked_sub(menu_start_row)) {
+                    (Some(menu), Some(line)) => menu.select_line(line),
+                    _ => return Ok(EventStatus::Inapplicable),
+                };
+
+                if accept {
+                    self.handle_editor_event(prompt, ReedlineEvent::Enter)
+                } else {
+                    Ok(EventStatus::Handled)
+                }
+            }
+            MouseAction::ScrollUp => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::PreviousElement);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            MouseAction::ScrollDown => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::NextElement);
+                        Ok(EventStatus::Handled)
+                    })
+            }
+            MouseAction::Other => Ok(EventStatus::Inapplicable),
+        }
+    }
+
+    /// Time left until the postponed completer query is sent
+    fn completion_debounce_wait(&self) -> Option<Duration> {
+        let debounce = self.completion_debounce?;
+        self.debounced_query
+            .map(|edited| debounce.saturating_sub(edited.elapsed()))
+    }
+
+    /// Sends the postponed completer query for the current buffer
+    fn flush_completion_query(&mut self) {
+        if self.debounced_query.take().is_none() {
+            return;
+        }
+
+        if let Some(menu) = self.menus.iter_mut().find(|menu| menu.is_active()) {
+            // The working details are updated right away for the events using the values
+            menu.menu_event(MenuEvent::Edit(false));
+            menu.update_working_details(
+                &mut self.editor,
+                self.completer.as_mut(),
+                self.history.as_ref(),
+                &self.painter,
+            );
+        }
+    }
+
+    fn completion_pending(&self) -> bool {
+        // The values of a pending completion are outdated by the postponed query
+        self.debounced_query.is_none()
+            && self.completer.is_pending()
+            && self
+                .menus
+                .iter()
+                .any(|menu| menu.is_active() && matches!(menu, ReedlineMenu::EngineCompleter(_)))
+    }
+
+    /// Polls the pending completion and advances the spinner of the active menu
+    fn refresh_completion_menu(&mut self) {
+        self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+
+        // Only the engine completer computes its values in the background
+        if let Some(menu) = self
+            .menus
+            .iter_mut()
+            .find(|menu| menu.is_active() && matches!(menu, ReedlineMenu::EngineCompleter(_)))
+        {
+            menu.refresh_values(&mut self.editor, self.completer.as_mut());
         }
     }
 
@@ -1233,6 +2401,35 @@ impl Reedline {
         self.menus.iter_mut().find(|menu| menu.is_active())
     }
 
+    // Keeps the menu of the picker open, it is closed e.g. when the buffer is emptied
+    fn open_picker_menu(&mut self) {
+        if let Some(name) = &self.picker_menu {
+            if let Some(menu) = self.menus.iter_mut().find(|menu| menu.name() == name) {
+                if !menu.is_active() {
+                    menu.menu_event(MenuEvent::Activate(false));
+                }
+            }
+        }
+    }
+
+    // Ends the pick with the accepted value. The menu is closed first so it is not
+    // left on the screen
+    fn end_pick(
+        &mut self,
+        prompt: &dyn Prompt,
+        picked: Option<Suggestion>,
+    ) -> io::Result<EventStatus> {
+        let signal = match &picked {
+            Some(suggestion) => Signal::Success(suggestion.value.clone()),
+            None => Signal::CtrlC,
+        };
+        self.picker_menu = None;
+        self.picked = picked;
+        self.deactivate_menus();
+        self.repaint(prompt)?;
+        Ok(EventStatus::Exits(signal))
+    }
+
     fn deactivate_menus(&mut self) {
         self.menus
             .iter_mut()
@@ -1249,6 +2446,9 @@ impl Reedline {
                 self.get_history_navigation_based_on_line_buffer(),
                 self.get_history_session_id(),
             );
+            self.prefix_search_line = self
+                .prefix_history_search
+                .then(|| self.editor.line_buffer().clone());
 
             if self.history_excluded_item.is_some() {
                 self.history_cursor_on_excluded = true;
@@ -1261,12 +2461,14 @@ impl Reedline {
                 .expect("todo: error handling");
         }
         self.update_buffer_from_history();
-        self.editor.move_to_start(false);
-        self.editor
-            .update_undo_state(UndoBehavior::HistoryNavigation);
-        self.editor.move_to_line_end(false);
-        self.editor
-            .update_undo_state(UndoBehavior::HistoryNavigation);
+        if !self.move_to_history_prefix() {
+            self.editor.move_to_start(false);
+            self.editor
+                .update_undo_state(UndoBehavior::HistoryNavigation);
+            self.editor.move_to_line_end(false);
+            self.editor
+                .update_undo_state(UndoBehavior::HistoryNavigation);
+        }
     }
 
     fn next_history(&mut self) {
@@ -1276,6 +2478,9 @@ impl Reedline {
                 self.get_history_navigation_based_on_line_buffer(),
                 self.get_history_session_id(),
             );
+            self.prefix_search_line = self
+                .prefix_history_search
+                .then(|| self.editor.line_buffer().clone());
         }
 
         if self.history_cursor_on_excluded {
@@ -1298,16 +2503,42 @@ impl Reedline {
             self.input_mode = InputMode::Regular;
         }
         self.update_buffer_from_history();
-        self.editor.move_to_end(false);
-        self.editor
-            .update_undo_state(UndoBehavior::HistoryNavigation)
+        if !self.move_to_history_prefix() {
+            self.editor.move_to_end(false);
+            self.editor
+                .update_undo_state(UndoBehavior::HistoryNavigation);
+        }
+    }
+
+    // Puts the cursor at the end of the prefix of the prefix history search, returns
+    // false if the history is not searched for a prefix of the buffer
+    fn move_to_history_prefix(&mut self) -> bool {
+        if !self.prefix_history_search {
+            return false;
+        }
+        match self.history_cursor.get_navigation() {
+            HistoryNavigationQuery::PrefixSearch(prefix)
+                if self.editor.get_buffer().starts_with(&prefix) =>
+            {
+                let mut line_buffer = self.editor.line_buffer().clone();
+                line_buffer.set_insertion_point(prefix.len());
+                self.editor
+                    .set_line_buffer(line_buffer, UndoBehavior::HistoryNavigation);
+                true
+            }
+            _ => false,
+        }
     }
 
     /// Enable the search and navigation through the history from the line buffer prompt
     ///
     /// Enables either prefix search with output in the line buffer or simple traversal
     fn get_history_navigation_based_on_line_buffer(&self) -> HistoryNavigationQuery {
-        if self.editor.is_empty() || !self.editor.is_cursor_at_buffer_end() {
+        if self.prefix_history_search && !self.editor.is_empty() {
+            // The text after the cursor is not part of the prefix
+            let prefix = &self.editor.get_buffer()[..self.editor.insertion_point()];
+            HistoryNavigationQuery::PrefixSearch(prefix.to_string())
+        } else if self.editor.is_empty() || !self.editor.is_cursor_at_buffer_end() {
             // Perform bash-style basic up/down entry walking
             HistoryNavigationQuery::Normal(
                 // Hack: Tight coupling point to be able to restore previously typed input
@@ -1327,6 +2558,9 @@ impl Reedline {
     /// Switch into reverse history search mode
     ///
     /// This mode uses a separate prompt and handles keybindings slightly differently!
+    /// [`ReedlineEvent::SearchHistory`] and [`ReedlineEvent::SearchHistoryForward`] step
+    /// through the matches, enter accepts the match into the buffer and
+    /// [`ReedlineEvent::CancelHistorySearch`] leaves the buffer as it was.
     fn enter_history_search(&mut self) {
         self.history_cursor = HistoryCursor::new(
             HistoryNavigationQuery::SubstringSearch("".to_string()),
@@ -1386,6 +2620,11 @@ impl Reedline {
     /// When using the up/down traversal or fish/zsh style prefix search update the main line buffer accordingly.
     /// Not used for the separate modal reverse search!
     fn update_buffer_from_history(&mut self) {
+        if let Some(id) = self.history_cursor.id_at_cursor() {
+            if !self.history_cursor_on_excluded {
+                self.history.mark_used(id);
+            }
+        }
         match self.history_cursor.get_navigation() {
             _ if self.history_cursor_on_excluded => self.editor.set_buffer(
                 self.history_excluded_item
@@ -1409,6 +2648,9 @@ impl Reedline {
                 if let Some(prefix_result) = self.history_cursor.string_at_cursor() {
                     self.editor
                         .set_buffer(prefix_result, UndoBehavior::HistoryNavigation);
+                } else if let Some(line_buffer) = self.prefix_search_line.clone() {
+                    self.editor
+                        .set_line_buffer(line_buffer, UndoBehavior::HistoryNavigation);
                 } else {
                     self.editor
                         .set_buffer(prefix, UndoBehavior::HistoryNavigation);
@@ -1435,7 +2677,37 @@ impl Reedline {
 
         // Run the commands over the edit buffer
         for command in commands {
+            // The abbreviation is expanded before the space following it is inserted
+            if let EditCommand::InsertChar(' ') = command {
+                self.expand_abbreviation();
+            }
             self.editor.run_edit_command(command);
+            if self.editor.command_failed() && (self.visual_bell || self.audible_bell) {
+                self.bell = true;
+            }
+        }
+    }
+
+    // Replaces the word before the cursor by its abbreviation, as a separate undo step
+    fn expand_abbreviation(&mut self) {
+        if self.abbreviations.is_empty() || self.reading_secret {
+            return;
+        }
+        let insertion_point = self.editor.insertion_point();
+        let before_cursor = &self.editor.get_buffer()[..insertion_point];
+        let start = before_cursor
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .len();
+        if !self.abbreviations_anywhere && !before_cursor[..start].trim().is_empty() {
+            return;
+        }
+
+        if let Some(expansion) = self.abbreviations.get(&before_cursor[start..]) {
+            let mut line_buffer = self.editor.line_buffer().clone();
+            line_buffer.replace_range(start..insertion_point, expansion);
+            line_buffer.set_insertion_point(start + expansion.len());
+            self.editor
+                .set_line_buffer(line_buffer, UndoBehavior::Completion);
         }
     }
 
@@ -1459,13 +2731,60 @@ impl Reedline {
         }
     }
 
+    /// Rest of the first suggestion of the completer for the word before the cursor at
+    /// the end of the line, the completer is only asked once for every line
+    fn completion_preview(&mut self) -> String {
+        if !self.completion_preview
+            || !self.hints_active()
+            || self.editor.is_empty()
+            || !self.editor.is_cursor_at_buffer_end()
+            || self.menus.iter().any(|menu| menu.is_active())
+        {
+            return String::new();
+        }
+
+        let line = self.editor.get_buffer();
+        if let Some(preview) = self.preview_cache.get(line) {
+            return preview.clone();
+        }
+
+        let pos = line.len();
+        let preview = self
+            .completer
+            .complete(line, pos)
+            .into_iter()
+            .next()
+            // Nothing is previewed before a word is started
+            .filter(|suggestion| suggestion.span.start < pos && suggestion.span.end == pos)
+            .and_then(|suggestion| {
+                let typed = line.get(suggestion.span.start..pos)?;
+                suggestion
+                    .value
+                    .strip_prefix(typed)
+                    .map(|rest| rest.to_string())
+            })
+            .unwrap_or_default();
+        self.preview_cache.insert(line.to_string(), preview.clone());
+
+        preview
+    }
+
     /// Checks if hints should be displayed and are able to be completed
     fn hints_active(&self) -> bool {
-        !self.hide_hints && matches!(self.input_mode, InputMode::Regular)
+        !self.hide_hints && !self.reading_secret && matches!(self.input_mode, InputMode::Regular)
     }
 
     /// Repaint of either the buffer or the parts for reverse history search
     fn repaint(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        self.edit_mode_changed = false;
+        if std::mem::take(&mut self.bell) {
+            if self.audible_bell {
+                self.painter.ring_bell()?;
+            }
+            if self.visual_bell {
+                self.painter.set_reverse_video(true)?;
+            }
+        }
         // Repainting
         if self.input_mode == InputMode::HistorySearch {
             self.history_search_paint(prompt)
@@ -1477,6 +2796,9 @@ impl Reedline {
     #[cfg(feature = "bashisms")]
     /// Parses the ! command to replace entries from the history
     fn parse_bang_command(&mut self) -> Option<ReedlineEvent> {
+        if self.reading_secret {
+            return None;
+        }
         let buffer = self.editor.get_buffer();
         let parsed = parse_selection_char(buffer, '!');
 
@@ -1576,37 +2898,61 @@ impl Reedline {
         }
     }
 
+    /// Edits the buffer in the configured editor, or in the `$VISUAL` or `$EDITOR`
+    /// one. The buffer is kept if the editor fails
     fn open_editor(&mut self) -> Result<()> {
-        match &mut self.buffer_editor {
-            Some(BufferEditor {
-                ref mut command,
-                temp_file,
-            }) => {
-                {
-                    let mut file = File::create(&temp_file)?;
-                    write!(file, "{}", self.editor.get_buffer())?;
-                }
-                {
-                    let mut child = command.spawn()?;
-                    child.wait()?;
-                }
+        if self.buffer_editor.is_some() {
+            return self.run_buffer_editor();
+        }
 
-                let res = std::fs::read_to_string(temp_file)?;
-                let res = res.trim_end().to_string();
+        // The environment editor gets a new file for every edit, removed once it is read
+        self.buffer_editor = BufferEditor::from_env()?;
+        let temp_file = match &self.buffer_editor {
+            Some(buffer_editor) => buffer_editor.temp_file.clone(),
+            None => return Ok(()),
+        };
+        let result = self.run_buffer_editor();
+        self.buffer_editor = None;
+        let _ = std::fs::remove_file(temp_file);
+        result
+    }
 
-                self.editor.set_buffer(res, UndoBehavior::CreateUndoPoint);
+    fn run_buffer_editor(&mut self) -> Result<()> {
+        let BufferEditor { command, temp_file } = match &mut self.buffer_editor {
+            Some(buffer_editor) => buffer_editor,
+            None => return Ok(()),
+        };
 
-                Ok(())
-            }
-            _ => Ok(()),
+        {
+            let mut file = File::create(&temp_file)?;
+            write!(file, "{}", self.editor.get_buffer())?;
         }
-    }
 
-    /// Repaint logic for the history reverse search
-    ///
-    /// Overwrites the prompt indicator and highlights the search string
-    /// separately from the result buffer.
-    fn history_search_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        // The editor gets the terminal in the state it expects
+        let terminal = self.painter.is_terminal();
+        if terminal {
+            terminal::disable_raw_mode()?;
+        }
+        let status = command.spawn().and_then(|mut child| child.wait());
+        if terminal {
+            terminal::enable_raw_mode()?;
+        }
+
+        if status?.success() {
+            let res = std::fs::read_to_string(temp_file)?;
+            let res = res.trim_end().to_string();
+
+            self.editor.set_buffer(res, UndoBehavior::CreateUndoPoint);
+        }
+
+        Ok(())
+    }
+
+    /// Repaint logic for the history reverse search
+    ///
+    /// Overwrites the prompt indicator and highlights the search string
+    /// separately from the result buffer.
+    fn history_search_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
         let navigation = self.history_cursor.get_navigation();
 
         if let HistoryNavigationQuery::SubstringSearch(substring) = navigation {
@@ -1656,23 +3002,19 @@ impl Reedline {
     ///
     /// Includes the highlighting and hinting calls.
     fn buffer_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if self.reading_secret {
+            return self.secret_paint(prompt);
+        }
+
+        let preview = self.completion_preview();
         let cursor_position_in_buffer = self.editor.insertion_point();
         let buffer_to_paint = self.editor.get_buffer();
 
         let mut styled_text = self
             .highlighter
             .highlight(buffer_to_paint, cursor_position_in_buffer);
-        if let Some((from, to)) = self.editor.get_selection() {
-            styled_text.style_range(from, to, self.visual_selection_style);
-        }
-
-        let (before_cursor, after_cursor) = styled_text.render_around_insertion_point(
-            cursor_position_in_buffer,
-            prompt,
-            self.use_ansi_coloring,
-        );
 
-        let hint: String = if self.hints_active() {
+        let mut hint: String = if self.hints_active() {
             self.hinter.as_mut().map_or_else(String::new, |hinter| {
                 hinter.handle(
                     buffer_to_paint,
@@ -1684,6 +3026,79 @@ impl Reedline {
         } else {
             String::new()
         };
+        let styled_hint = self
+            .hinter
+            .as_ref()
+            .filter(|_| self.use_ansi_coloring && !hint.is_empty())
+            .and_then(|hinter| hinter.styled_hint());
+        if let Some(styled_hint) = styled_hint {
+            // The styles within the line are layered over its highlighting
+            let mut start = 0;
+            for (style, text) in &styled_hint.buffer {
+                let end = (start + text.len()).min(buffer_to_paint.len());
+                if start < end {
+                    styled_text.layer_range(start, end, *style);
+                }
+                start += text.len();
+            }
+            hint = styled_hint
+                .slice(buffer_to_paint.len(), start)
+                .render_simple();
+        }
+
+        if let Some((from, to)) = self.editor.get_selection() {
+            styled_text.layer_range(from, to, self.visual_selection_style);
+        }
+        let mut cursor_position_in_text = cursor_position_in_buffer;
+        if let Some(tab_width) = self.tab_width {
+            cursor_position_in_text = expand_tabs(
+                &buffer_to_paint[..cursor_position_in_buffer],
+                tab_width,
+                &mut 0,
+            )
+            .len();
+            styled_text.expand_tabs(tab_width);
+        }
+
+        // A line wider than the terminal is scrolled to the cursor, with the width left
+        // by the last line of the prompt
+        let scroll_width =
+            if self.line_overflow == LineOverflow::Scroll && !buffer_to_paint.contains('\n') {
+                let prompt_str = prompt.render_prompt_left().to_string()
+                    + &prompt.render_prompt_indicator(self.prompt_edit_mode());
+                let prompt_width = line_width(prompt_str.lines().last().unwrap_or_default());
+                let width = (self.painter.screen_width() as usize).saturating_sub(prompt_width);
+                (styled_text, cursor_position_in_text) =
+                    self.painter
+                        .scroll_line(&styled_text, cursor_position_in_text, width);
+                Some(width)
+            } else {
+                None
+            };
+
+        let (before_cursor, after_cursor) = styled_text.render_around_insertion_point(
+            cursor_position_in_text,
+            prompt,
+            self.use_ansi_coloring,
+        );
+
+        let hinted = self
+            .hinter
+            .as_ref()
+            .map_or(false, |hinter| !hinter.complete_hint().is_empty());
+        if !hinted && !preview.is_empty() {
+            hint = if self.use_ansi_coloring {
+                self.completion_preview_style.paint(preview).to_string()
+            } else {
+                preview
+            };
+        }
+        // The hint is only painted if it fits next to the line
+        if let Some(width) = scroll_width {
+            if line_width(&styled_text.raw_string()) + line_width(&hint) >= width {
+                hint.clear();
+            }
+        }
 
         // Needs to add return carriage to newlines because when not in raw mode
         // some OS don't fully return the carriage
@@ -1700,7 +3115,18 @@ impl Reedline {
         // Updating the working details of the active menu
         for menu in self.menus.iter_mut() {
             if menu.is_active() {
-                lines.prompt_indicator = menu.indicator().to_owned().into();
+                lines.prompt_indicator = if self.completer.is_pending()
+                    && matches!(menu, ReedlineMenu::EngineCompleter(_))
+                {
+                    format!(
+                        "{} {}",
+                        SPINNER_FRAMES[self.spinner_frame],
+                        menu.indicator()
+                    )
+                    .into()
+                } else {
+                    menu.indicator().to_owned().into()
+                };
                 // If the menu requires the cursor position, update it (ide menu)
                 let cursor_pos = lines.cursor_pos(self.painter.screen_width());
                 menu.set_cursor_pos(cursor_pos);
@@ -1726,6 +3152,37 @@ impl Reedline {
         )
     }
 
+    /// Repaint logic for a secret
+    ///
+    /// Only the mask is painted in place of the buffer, without highlighting, hints
+    /// or menus.
+    fn secret_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        let (before_cursor, after_cursor) = self
+            .editor
+            .get_buffer()
+            .split_at(self.editor.insertion_point());
+        let before_cursor = text_manipulation::mask_text(before_cursor, self.secret_mask);
+        let after_cursor = text_manipulation::mask_text(after_cursor, self.secret_mask);
+
+        let lines = PromptLines::new(
+            prompt,
+            self.prompt_edit_mode(),
+            None,
+            &before_cursor,
+            &after_cursor,
+            "",
+        );
+
+        self.painter.repaint_buffer(
+            prompt,
+            &lines,
+            self.prompt_edit_mode(),
+            None,
+            self.use_ansi_coloring,
+            &self.cursor_shapes,
+        )
+    }
+
     /// Adds an external printer
     ///
     /// ## Required feature:
@@ -1761,16 +3218,30 @@ impl Reedline {
     }
 
     fn submit_buffer(&mut self, prompt: &dyn Prompt) -> io::Result<EventStatus> {
-        let buffer = self.editor.get_buffer().to_string();
+        let mut buffer = self.editor.get_buffer().to_string();
         self.hide_hints = true;
         // Additional repaint to show the content without hints etc.
         if let Some(transient_prompt) = self.transient_prompt.take() {
             self.repaint(transient_prompt.as_ref())?;
             self.transient_prompt = Some(transient_prompt);
+        } else if let Some(collapsed_prompt) = CollapsedPrompt::from_prompt(prompt) {
+            self.repaint(&collapsed_prompt)?;
         } else {
             self.repaint(prompt)?;
         }
-        if !buffer.is_empty() {
+        if !self.reading_secret {
+            buffer = text_manipulation::trim_line(&buffer, self.trim_on_submit);
+        }
+        if let Some(transform) = self
+            .submit_transform
+            .as_ref()
+            .filter(|_| !self.reading_secret)
+        {
+            if let Some(transformed) = transform(&buffer) {
+                buffer = transformed;
+            }
+        }
+        if !buffer.is_empty() && !self.reading_secret {
             let mut entry = HistoryItem::from_command_line(&buffer);
             entry.session_id = self.get_history_session_id();
 
@@ -1784,20 +3255,1711 @@ impl Reedline {
                 self.history_last_run_id = entry.id;
                 self.history_excluded_item = Some(entry);
             } else {
+                self.notify_history_callback();
+                let session_id = entry.session_id;
                 entry = self.history.save(entry).expect("todo: error handling");
+                if self.history_session_callback.is_some() {
+                    // Histories without sessions don't return the session of the entry
+                    entry.session_id = entry.session_id.or(session_id);
+                    self.history_callback_entry = Some(entry.clone());
+                }
                 self.history_last_run_id = entry.id;
                 self.history_excluded_item = None;
             }
         }
-        self.run_edit_commands(&[EditCommand::Clear]);
-        self.editor.reset_undo_stack();
+        self.clear_buffer();
 
         Ok(EventStatus::Exits(Signal::Success(buffer)))
     }
+
+    // Passes the entry of the last submitted line to the history session callback
+    fn notify_history_callback(&mut self) {
+        if let (Some(entry), Some(callback)) = (
+            self.history_callback_entry.take(),
+            &self.history_session_callback,
+        ) {
+            callback(&entry);
+        }
+    }
+
+    // Submits the buffer or continues it on a new line, depending on the validator.
+    // An asynchronous validation that is not ready right away is left pending
+    fn validate_buffer(&mut self, prompt: &dyn Prompt) -> io::Result<EventStatus> {
+        if self.forced_submit {
+            return self.submit_buffer(prompt);
+        }
+
+        let result = if let Some(validator) = self.async_validator.as_mut() {
+            let buffer = self.editor.get_buffer();
+            let mut pending =
+                PendingValidation::new(buffer.to_string(), validator.validate(buffer));
+            let result = pending.poll();
+            if result.is_none() {
+                self.pending_validation = Some(pending);
+                return Ok(EventStatus::Handled);
+            }
+            result
+        } else {
+            let buffer = self.editor.get_buffer();
+            self.validator.as_mut().map(|v| v.validate(buffer))
+        };
+
+        self.apply_validation(prompt, result)
+    }
+
+    fn apply_validation(
+        &mut self,
+        prompt: &dyn Prompt,
+        result: Option<ValidationResult>,
+    ) -> io::Result<EventStatus> {
+        match result {
+            None | Some(ValidationResult::Complete) => self.submit_buffer(prompt),
+            Some(ValidationResult::Incomplete) => {
+                self.continue_input();
+
+                Ok(EventStatus::Handled)
+            }
+        }
+    }
+
+    // Starts a new line for the incomplete input, indented if enabled
+    fn continue_input(&mut self) {
+        let buffer = self.editor.get_buffer();
+        let indent = if !self.auto_indent {
+            String::new()
+        } else if let Some(validator) = &self.async_validator {
+            validator.indent_for(buffer)
+        } else if let Some(validator) = &self.validator {
+            validator.indent_for(buffer)
+        } else {
+            String::new()
+        };
+        if indent.is_empty() {
+            self.run_edit_commands(&[EditCommand::InsertNewline]);
+        } else {
+            self.run_edit_commands(&[
+                EditCommand::InsertNewline,
+                EditCommand::InsertString(indent),
+            ]);
+        }
+    }
+
+    // Whether Ctrl-D ends the input instead of deleting a character
+    fn is_eof(&self) -> bool {
+        self.ctrl_d_behavior == CtrlDBehavior::AlwaysEof || self.editor.is_empty()
+    }
+
+    // Handles Ctrl-C by returning the signal or by abandoning the line for a new prompt
+    fn interrupt(&mut self, prompt: &dyn Prompt) -> io::Result<EventStatus> {
+        if !self.cancels_line() {
+            self.clear_buffer();
+            return Ok(EventStatus::Exits(Signal::CtrlC));
+        }
+
+        // Leave the abandoned line on the screen without hints
+        self.hide_hints = true;
+        self.repaint(prompt)?;
+        self.painter.start_new_prompt()?;
+        self.hide_hints = false;
+        self.clear_buffer();
+        Ok(EventStatus::Handled)
+    }
+
+    // Whether Ctrl-C abandons the line instead of returning the signal
+    fn cancels_line(&self) -> bool {
+        match self.ctrl_c_behavior {
+            CtrlCBehavior::RawSignal => false,
+            CtrlCBehavior::CancelLine => true,
+            CtrlCBehavior::ExitOnEmpty => !self.editor.is_empty(),
+        }
+    }
+
+    // Clears the buffer and its undo history once the input is done
+    fn clear_buffer(&mut self) {
+        self.pending_validation = None;
+        if self.reading_secret {
+            self.editor.clear_secret();
+        } else {
+            self.run_edit_commands(&[EditCommand::Clear]);
+            self.editor.reset_undo_stack();
+        }
+    }
 }
 
-#[test]
-fn thread_safe() {
-    fn f<S: Send>(_: S) {}
-    f(Reedline::create());
+// Drops the events that would use the history, the menus or the buffer editor while
+// reading a secret
+fn secret_event(event: ReedlineEvent) -> ReedlineEvent {
+    match event {
+        ReedlineEvent::Multiple(events) => {
+            ReedlineEvent::Multiple(events.into_iter().map(secret_event).collect())
+        }
+        ReedlineEvent::UntilFound(events) => {
+            ReedlineEvent::UntilFound(events.into_iter().map(secret_event).collect())
+        }
+        ReedlineEvent::Menu(_)
+        | ReedlineEvent::MenuNext
+        | ReedlineEvent::MenuPrevious
+        | ReedlineEvent::MenuUp
+        | ReedlineEvent::MenuDown
+        | ReedlineEvent::MenuLeft
+        | ReedlineEvent::MenuRight
+        | ReedlineEvent::MenuPageNext
+        | ReedlineEvent::MenuPagePrevious
+        | ReedlineEvent::MenuPageGoto(_)
+        | ReedlineEvent::MenuAccept
+        | ReedlineEvent::MenuAcceptAndContinue
+        | ReedlineEvent::MenuToggleSort
+        | ReedlineEvent::PreviousHistory
+        | ReedlineEvent::NextHistory
+        | ReedlineEvent::Up
+        | ReedlineEvent::Down
+        | ReedlineEvent::SearchHistory
+        | ReedlineEvent::SearchHistoryForward
+        | ReedlineEvent::CancelHistorySearch
+        | ReedlineEvent::OpenEditor => ReedlineEvent::None,
+        event => event,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    // Painter that neither queries the terminal nor writes to it
+    fn detached_painter() -> Painter {
+        Painter::detached(io::sink())
+    }
+
+    #[test]
+    fn thread_safe() {
+        fn f<S: Send>(_: S) {}
+        f(Reedline::create());
+    }
+
+    #[test]
+    fn continuation_lines_are_indented() {
+        let mut reedline = Reedline::create()
+            .with_validator(Box::new(crate::DefaultValidator))
+            .with_auto_indent(true);
+        let prompt = crate::DefaultPrompt::default();
+        reedline
+            .editor
+            .set_buffer("if x {".to_string(), UndoBehavior::CreateUndoPoint);
+
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Handled));
+        assert_eq!(reedline.current_buffer_contents(), "if x {\n    ");
+        assert_eq!(reedline.current_insertion_point(), 11);
+
+        reedline.run_edit_commands(&[EditCommand::Backspace, EditCommand::Backspace]);
+        assert_eq!(reedline.current_buffer_contents(), "if x {\n  ");
+    }
+
+    #[test]
+    fn blocking_validator_continues_incomplete_input() {
+        let mut reedline = Reedline::create().with_async_validator(Box::new(
+            crate::BlockingValidator::new(crate::DefaultValidator),
+        ));
+        let prompt = crate::DefaultPrompt::default();
+        reedline
+            .editor
+            .set_buffer("if x {".to_string(), UndoBehavior::CreateUndoPoint);
+
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+
+        assert!(matches!(status, EventStatus::Handled));
+        assert!(reedline.pending_validation.is_none());
+        assert_eq!(reedline.current_buffer_contents(), "if x {\n");
+    }
+
+    #[test]
+    fn initial_buffer_is_submitted_unchanged() {
+        let mut reedline = Reedline::create();
+        reedline.painter = detached_painter();
+        let prompt = crate::DefaultPrompt::default();
+        reedline
+            .editor
+            .set_initial_buffer("ls -la".to_string(), Some(2));
+
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+
+        assert!(
+            matches!(status, EventStatus::Exits(Signal::Success(buffer)) if buffer == "ls -la")
+        );
+        assert!(reedline.editor.is_empty());
+    }
+
+    #[test]
+    fn history_session_callback_sees_submitted_entries() {
+        use std::sync::{Arc, Mutex};
+
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&entries);
+        let session = Some(HistorySessionId::new(42));
+        let mut reedline = Reedline::create()
+            .with_history_session_id(session)
+            .with_history_exclusion_prefix(Some(" ".to_string()))
+            .with_history_session_callback(Box::new(move |entry| {
+                observed.lock().unwrap().push(entry.clone());
+            }));
+        reedline.painter = detached_painter();
+        let prompt = crate::DefaultPrompt::default();
+
+        for (exit_status, line) in ["ls -la", " secret", "cd .."].into_iter().enumerate() {
+            reedline
+                .editor
+                .set_buffer(line.to_string(), UndoBehavior::CreateUndoPoint);
+            reedline
+                .handle_event(&prompt, ReedlineEvent::Enter)
+                .unwrap();
+            // The in-memory history can't store the context, the callback still sees it
+            let _ = reedline.update_last_command_context(&|mut entry| {
+                entry.duration = Some(Duration::from_millis(10));
+                entry.exit_status = Some(exit_status as i64);
+                entry
+            });
+        }
+        assert_eq!(entries.lock().unwrap().len(), 1);
+        reedline.reading_secret = true;
+        reedline
+            .editor
+            .set_buffer("hunter2".to_string(), UndoBehavior::CreateUndoPoint);
+        reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        drop(reedline);
+
+        let entries = entries.lock().unwrap();
+        let lines: Vec<(&str, Option<i64>)> = entries
+            .iter()
+            .map(|entry| (entry.command_line.as_str(), entry.exit_status))
+            .collect();
+        assert_eq!(lines, vec![("ls -la", Some(0)), ("cd ..", Some(2))]);
+        assert!(entries.iter().all(|entry| entry.session_id == session
+            && entry.id.is_some()
+            && entry.duration == Some(Duration::from_millis(10))));
+    }
+
+    #[test]
+    fn submit_transform_replaces_the_accepted_line() {
+        let mut reedline = Reedline::create()
+            .with_validator(Box::new(crate::DefaultValidator))
+            .with_submit_transform(Box::new(|line| {
+                line.strip_prefix("ll").map(|rest| format!("ls -l{}", rest))
+            }));
+        reedline.painter = detached_painter();
+        let prompt = crate::DefaultPrompt::default();
+
+        reedline
+            .editor
+            .set_buffer("ll (".to_string(), UndoBehavior::CreateUndoPoint);
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Handled));
+
+        reedline.run_edit_commands(&[EditCommand::InsertChar(')')]);
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(
+            matches!(status, EventStatus::Exits(Signal::Success(buffer)) if buffer == "ls -l (\n)")
+        );
+        let last_entry = reedline
+            .history
+            .search(SearchQuery::last_with_search(
+                crate::SearchFilter::anything(None),
+            ))
+            .unwrap();
+        assert_eq!(last_entry[0].command_line, "ls -l (\n)");
+
+        reedline
+            .editor
+            .set_buffer("cd".to_string(), UndoBehavior::CreateUndoPoint);
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(buffer)) if buffer == "cd"));
+    }
+
+    fn press_ctrl(reedline: &mut Reedline, buffer: &str, c: char) -> EventStatus {
+        reedline.painter = detached_painter();
+        reedline
+            .editor
+            .set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        reedline.run_edit_commands(&[EditCommand::MoveToStart { select: false }]);
+        let prompt = crate::DefaultPrompt::default();
+
+        // The default emacs bindings use Ctrl-C to copy the selection
+        let mut keybindings = crate::Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('c'),
+            ReedlineEvent::CtrlC,
+        );
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('d'),
+            ReedlineEvent::CtrlD,
+        );
+        let mut edit_mode = Emacs::new(keybindings);
+        let key = Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+        let event = edit_mode.parse_event(ReedlineRawEvent::convert_from(key).unwrap());
+        reedline.handle_event(&prompt, event).unwrap()
+    }
+
+    #[rstest]
+    #[case(CtrlCBehavior::RawSignal, "ls", true)]
+    #[case(CtrlCBehavior::RawSignal, "", true)]
+    #[case(CtrlCBehavior::CancelLine, "ls", false)]
+    #[case(CtrlCBehavior::CancelLine, "", false)]
+    #[case(CtrlCBehavior::ExitOnEmpty, "ls", false)]
+    #[case(CtrlCBehavior::ExitOnEmpty, "", true)]
+    fn ctrl_c_behavior_works(
+        #[case] behavior: CtrlCBehavior,
+        #[case] buffer: &str,
+        #[case] exits: bool,
+    ) {
+        let mut reedline = Reedline::create().with_ctrl_c_behavior(behavior);
+
+        let status = press_ctrl(&mut reedline, buffer, 'c');
+
+        if exits {
+            assert!(matches!(status, EventStatus::Exits(Signal::CtrlC)));
+        } else {
+            assert!(matches!(status, EventStatus::Handled));
+        }
+        assert!(reedline.editor.is_empty());
+    }
+
+    #[rstest]
+    #[case(CtrlDBehavior::DeleteOrEof, "ls", false, "s")]
+    #[case(CtrlDBehavior::DeleteOrEof, "", true, "")]
+    #[case(CtrlDBehavior::AlwaysEof, "ls", true, "")]
+    #[case(CtrlDBehavior::AlwaysEof, "", true, "")]
+    fn ctrl_d_behavior_works(
+        #[case] behavior: CtrlDBehavior,
+        #[case] buffer: &str,
+        #[case] exits: bool,
+        #[case] remaining: &str,
+    ) {
+        let mut reedline = Reedline::create().with_ctrl_d_behavior(behavior);
+
+        let status = press_ctrl(&mut reedline, buffer, 'd');
+
+        if exits {
+            assert!(matches!(status, EventStatus::Exits(Signal::CtrlD)));
+        } else {
+            assert!(matches!(status, EventStatus::Handled));
+        }
+        assert_eq!(reedline.current_buffer_contents(), remaining);
+    }
+
+    #[test]
+    fn ctrl_c_keeps_the_line_from_before_a_history_search() {
+        let mut reedline = Reedline::create();
+        reedline
+            .editor
+            .set_buffer("ls".to_string(), UndoBehavior::CreateUndoPoint);
+        reedline.enter_history_search();
+        reedline.run_history_commands(&[EditCommand::InsertChar('g')]);
+
+        let prompt = crate::DefaultPrompt::default();
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::CtrlC)
+            .unwrap();
+
+        assert!(matches!(status, EventStatus::Exits(Signal::CtrlC)));
+        assert_eq!(reedline.input_mode, InputMode::Regular);
+        assert_eq!(reedline.current_buffer_contents(), "ls");
+    }
+
+    fn start_pick(items: &[&str]) -> Reedline {
+        let items = items
+            .iter()
+            .map(|item| Suggestion {
+                value: item.to_string(),
+                ..Suggestion::default()
+            })
+            .collect();
+        let mut reedline = crate::picker::picker(items);
+        reedline.painter = detached_painter();
+        reedline.picker_menu = Some(crate::picker::PICKER_MENU.to_string());
+        reedline.open_picker_menu();
+        reedline
+    }
+
+    #[rstest]
+    #[case("ct", vec![ReedlineEvent::Enter], Some("cargo test"))]
+    #[case("", vec![ReedlineEvent::MenuDown, ReedlineEvent::Enter], Some("cargo test"))]
+    #[case("ct", vec![ReedlineEvent::Esc], None)]
+    #[case("ct", vec![ReedlineEvent::CtrlC], None)]
+    fn pick_returns_the_accepted_item(
+        #[case] typed: &str,
+        #[case] events: Vec<ReedlineEvent>,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut reedline = start_pick(&["cargo build", "cargo test", "git status"]);
+        let prompt = crate::DefaultPrompt::default();
+        reedline.repaint(&prompt).unwrap();
+        if !typed.is_empty() {
+            let typed = ReedlineEvent::Edit(vec![EditCommand::InsertString(typed.to_string())]);
+            reedline.handle_event(&prompt, typed).unwrap();
+            reedline.repaint(&prompt).unwrap();
+        }
+
+        let mut status = EventStatus::Inapplicable;
+        for event in events {
+            status = reedline.handle_event(&prompt, event).unwrap();
+            reedline.repaint(&prompt).unwrap();
+        }
+
+        assert!(matches!(status, EventStatus::Exits(_)));
+        let picked = reedline.picked.take().map(|suggestion| suggestion.value);
+        assert_eq!(picked.as_deref(), expected);
+        assert!(reedline.menus.iter().all(|menu| !menu.is_active()));
+    }
+
+    #[test]
+    fn pick_keeps_the_menu_open_without_matches() {
+        let mut reedline = start_pick(&["cargo build"]);
+        let prompt = crate::DefaultPrompt::default();
+        let typed = ReedlineEvent::Edit(vec![EditCommand::InsertString("xyz".to_string())]);
+        reedline.handle_event(&prompt, typed).unwrap();
+        reedline.repaint(&prompt).unwrap();
+
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Inapplicable));
+
+        let cleared = ReedlineEvent::Edit(vec![EditCommand::Clear]);
+        reedline.handle_event(&prompt, cleared).unwrap();
+        reedline.repaint(&prompt).unwrap();
+        assert!(reedline.active_menu().is_some());
+
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::Enter)
+            .unwrap();
+        assert!(
+            matches!(status, EventStatus::Exits(Signal::Success(value)) if value == "cargo build")
+        );
+    }
+
+    fn key_events(
+        edit_mode: &mut dyn EditMode,
+        keys: &[(KeyModifiers, KeyCode)],
+    ) -> Vec<ReedlineEvent> {
+        keys.iter()
+            .map(|(modifiers, code)| {
+                let key = Event::Key(KeyEvent::new(*code, *modifiers));
+                edit_mode.parse_event(ReedlineRawEvent::convert_from(key).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn processed_key_events_follow_the_keybindings() {
+        let mut reedline = Reedline::create();
+        let mut edit_mode = Emacs::default();
+        let mut keys: Vec<_> = "hi"
+            .chars()
+            .map(|c| (KeyModifiers::NONE, KeyCode::Char(c)))
+            .collect();
+        keys.push((KeyModifiers::NONE, KeyCode::Home));
+        keys.extend(
+            "echo "
+                .chars()
+                .map(|c| (KeyModifiers::NONE, KeyCode::Char(c))),
+        );
+
+        let signal = reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert!(signal.is_none());
+        assert_eq!(reedline.current_buffer_contents(), "echo hi");
+        assert_eq!(reedline.current_insertion_point(), 5);
+
+        let enter = key_events(&mut edit_mode, &[(KeyModifiers::NONE, KeyCode::Enter)]);
+        let signal = reedline.process_events(enter).unwrap();
+        assert!(matches!(signal, Some(Signal::Success(line)) if line == "echo hi"));
+        assert!(reedline.painter.is_terminal());
+    }
+
+    fn typed_keys(text: &str) -> Vec<(KeyModifiers, KeyCode)> {
+        text.chars()
+            .map(|c| (KeyModifiers::NONE, KeyCode::Char(c)))
+            .collect()
+    }
+
+    #[test]
+    fn typing_replaces_the_shift_end_selection() {
+        let mut reedline = Reedline::create();
+        let mut edit_mode = Emacs::default();
+        let mut keys = typed_keys("echo hi");
+        keys.push((KeyModifiers::NONE, KeyCode::Left));
+        keys.push((KeyModifiers::NONE, KeyCode::Left));
+        keys.push((KeyModifiers::SHIFT, KeyCode::End));
+
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert_eq!(reedline.editor.get_selection(), Some((5, 7)));
+
+        let keys = typed_keys("x");
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "echo x");
+        assert_eq!(reedline.editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(KeyCode::Left, 5)]
+    #[case(KeyCode::Right, 7)]
+    fn plain_arrows_collapse_the_selection(#[case] arrow: KeyCode, #[case] cursor: usize) {
+        let mut reedline = Reedline::create();
+        let mut edit_mode = Emacs::default();
+        let mut keys = typed_keys("echo hi");
+        keys.push((KeyModifiers::SHIFT, KeyCode::Left));
+        keys.push((KeyModifiers::SHIFT, KeyCode::Left));
+        keys.push((KeyModifiers::NONE, arrow));
+
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+
+        assert_eq!(reedline.current_insertion_point(), cursor);
+        assert_eq!(reedline.editor.get_selection(), None);
+    }
+
+    #[test]
+    fn newline_binding_starts_a_line_that_enter_submits() {
+        let mut reedline = Reedline::create()
+            .with_validator(Box::new(crate::DefaultValidator))
+            .with_forced_submit(true)
+            .with_newline_binding(KeyModifiers::ALT, KeyCode::Enter);
+        let mut keys = typed_keys("echo (");
+        keys.push((KeyModifiers::ALT, KeyCode::Enter));
+        let events: Vec<_> = keys
+            .iter()
+            .map(|(modifiers, code)| {
+                let key = Event::Key(KeyEvent::new(*code, *modifiers));
+                reedline.parse_event(ReedlineRawEvent::convert_from(key).unwrap())
+            })
+            .collect();
+
+        let signal = reedline.process_events(events).unwrap();
+        assert!(signal.is_none());
+        assert_eq!(reedline.current_buffer_contents(), "echo (\n");
+
+        let signal = reedline.process_events(vec![ReedlineEvent::Enter]).unwrap();
+        assert!(matches!(signal, Some(Signal::Success(line)) if line == "echo (\n"));
+    }
+
+    #[test]
+    fn processed_events_select_completions_in_a_menu() {
+        let completer = DefaultCompleter::new_with_wordlen(vec!["hello".into(), "help".into()], 2);
+        let menu = crate::ColumnarMenu::default().with_name("completion_menu");
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(completer))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+
+        let signal = reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("hel".to_string())]),
+                ReedlineEvent::Menu("completion_menu".to_string()),
+                ReedlineEvent::MenuNext,
+                ReedlineEvent::Enter,
+            ])
+            .unwrap();
+
+        assert!(signal.is_none());
+        assert_eq!(reedline.current_buffer_contents(), "help");
+    }
+
+    // Writer whose content stays readable once it is moved into the line editor
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn painting_goes_to_the_buffer() {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_ansi_colors(false)
+            .with_buffer(output.clone());
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "ls -la".to_string(),
+            )])])
+            .unwrap();
+
+        let frame = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let prompt = crate::DefaultPrompt::default();
+        assert!(frame.contains(&*prompt.render_prompt_indicator(PromptEditMode::Default)));
+        assert!(frame.contains("ls -la"));
+        assert!(!reedline.painter.is_terminal());
+    }
+
+    #[test]
+    fn tabs_are_painted_to_the_tab_stop() {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_ansi_colors(false)
+            .with_tab_width(4)
+            .with_buffer(output.clone());
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "abc\td".to_string(),
+            )])])
+            .unwrap();
+
+        let frame = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(frame.contains("abc d"));
+        assert!(!frame.contains('\t'));
+        assert_eq!(reedline.current_buffer_contents(), "abc\td");
+    }
+
+    #[test]
+    fn shell_integration_marks_the_prompt_the_input_and_the_output() {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_ansi_colors(false)
+            .with_shell_integration(true)
+            .with_buffer(output.clone());
+
+        let signal = reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("ls".to_string())]),
+                ReedlineEvent::Enter,
+            ])
+            .unwrap();
+        assert!(matches!(signal, Some(Signal::Success(line)) if line == "ls"));
+        reedline.report_command_finished(2).unwrap();
+
+        let frame = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let position = |marker: &str| frame.rfind(marker).unwrap();
+        assert!(position("\x1b]133;A") < position("\x1b]133;B"));
+        assert!(position("\x1b]133;B") < position("ls"));
+        assert!(position("ls") < position("\x1b]133;C"));
+        assert!(frame.ends_with("\x1b]133;D;2\x1b\\"));
+    }
+
+    #[test]
+    fn no_markers_without_shell_integration() {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create().with_buffer(output.clone());
+
+        reedline.process_events(vec![ReedlineEvent::Enter]).unwrap();
+        reedline.report_command_finished(0).unwrap();
+
+        let frame = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(!frame.contains("\x1b]133;"));
+    }
+
+    #[test]
+    fn mode_changes_set_the_cursor_shape_of_the_mode() {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_edit_mode(Box::new(crate::Vi::default()))
+            .with_cursor_config(CursorConfig {
+                vi_insert: Some(SetCursorStyle::SteadyBar),
+                vi_normal: Some(SetCursorStyle::SteadyBlock),
+                emacs: None,
+            })
+            .with_buffer(output.clone());
+        let frame = || String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let (bar, block) = ("\x1b[6 q", "\x1b[2 q");
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                'a',
+            )])])
+            .unwrap();
+        assert_eq!(frame().matches(bar).count(), 1);
+        assert!(!frame().contains(block));
+
+        let key = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        let event = reedline.parse_event(ReedlineRawEvent::convert_from(key).unwrap());
+        reedline.process_events(vec![event]).unwrap();
+        assert!(frame().rfind(block) > frame().rfind(bar));
+
+        let signal = reedline.process_events(vec![ReedlineEvent::Enter]).unwrap();
+        assert!(signal.is_some());
+        assert!(frame().ends_with("\x1b[0 q"));
+    }
+
+    // Completes the words starting with the last word of the line, followed by a space
+    struct WordCompleter(Vec<&'static str>);
+
+    impl Completer for WordCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let start = line.rfind(' ').map_or(0, |space| space + 1);
+            self.0
+                .iter()
+                .filter(|word| word.starts_with(&line[start..]))
+                .map(|word| Suggestion {
+                    value: word.to_string(),
+                    span: crate::Span::new(start, pos),
+                    append_whitespace: true,
+                    ..Suggestion::default()
+                })
+                .collect()
+        }
+    }
+
+    // Completer that records the lines it is queried with and the suggestions accepted
+    // from its values
+    #[derive(Clone)]
+    struct RecordingCompleter {
+        words: Vec<&'static str>,
+        queries: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+        accepted: std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>,
+    }
+
+    impl RecordingCompleter {
+        fn new(words: Vec<&'static str>) -> Self {
+            RecordingCompleter {
+                words,
+                queries: std::sync::Arc::default(),
+                accepted: std::sync::Arc::default(),
+            }
+        }
+    }
+
+    impl Completer for RecordingCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.queries.lock().unwrap().push(line.to_string());
+            WordCompleter(self.words.clone()).complete(line, pos)
+        }
+
+        fn on_accept(&mut self, accepted: &Suggestion, line: &str) {
+            self.accepted
+                .lock()
+                .unwrap()
+                .push((accepted.value.clone(), line.to_string()));
+        }
+    }
+
+    #[rstest]
+    #[case(vec!["hello", "world"], "hello ", false)]
+    #[case(vec!["hello", "help"], "hel", true)]
+    fn list_menu_quick_completes_a_single_value(
+        #[case] words: Vec<&'static str>,
+        #[case] buffer: &str,
+        #[case] menu_shown: bool,
+    ) {
+        let menu = crate::ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_quick_complete(true);
+        let mut reedline = Reedline::create()
+            .with_quick_completions(true)
+            .with_completer(Box::new(WordCompleter(words)))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("hel".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+            ])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), buffer);
+        assert_eq!(reedline.active_menu().is_some(), menu_shown);
+
+        // The completed word is not completed again by the next key
+        if !menu_shown {
+            reedline
+                .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                    'x',
+                )])])
+                .unwrap();
+            assert_eq!(reedline.current_buffer_contents(), "hello x");
+            assert!(reedline.active_menu().is_none());
+        }
+    }
+
+    #[rstest]
+    #[case(UndoCoalescing::PerEdit)]
+    #[case(UndoCoalescing::PerWord)]
+    #[case(UndoCoalescing::PerPause(Duration::from_secs(60)))]
+    fn accepted_completions_are_undone_on_their_own(#[case] undo_coalescing: UndoCoalescing) {
+        let typed =
+            |text: &str| ReedlineEvent::Edit(text.chars().map(EditCommand::InsertChar).collect());
+        let mut reedline = Reedline::create()
+            .with_undo_coalescing(undo_coalescing)
+            .with_completer(Box::new(WordCompleter(vec!["hello", "help"])))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                crate::ListMenu::default().with_only_buffer_difference(false),
+            )));
+
+        reedline
+            .process_events(vec![
+                typed("echo he"),
+                ReedlineEvent::Menu("search_menu".to_string()),
+                ReedlineEvent::Enter,
+                typed("x"),
+            ])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "echo hello x");
+
+        let undo = || ReedlineEvent::Edit(vec![EditCommand::Undo]);
+        reedline.process_events(vec![undo()]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "echo hello ");
+        assert_eq!(reedline.current_insertion_point(), 11);
+
+        reedline.process_events(vec![undo()]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "echo he");
+        assert_eq!(reedline.current_insertion_point(), 7);
+    }
+
+    #[test]
+    fn accepted_values_keep_the_menu_open() {
+        let menu = crate::ListMenu::default().with_only_buffer_difference(false);
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(WordCompleter(vec!["add", "commit"])))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Menu("search_menu".to_string()),
+                ReedlineEvent::MenuNext,
+                ReedlineEvent::MenuAcceptAndContinue,
+            ])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "commit ");
+        let selected = reedline
+            .active_menu()
+            .and_then(|menu| menu.selected_value());
+        assert_eq!(
+            selected.map(|suggestion| (suggestion.value, suggestion.span.start)),
+            Some(("add".to_string(), 7))
+        );
+
+        reedline
+            .process_events(vec![ReedlineEvent::MenuAcceptAndContinue])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "commit add ");
+        let selected = reedline
+            .active_menu()
+            .and_then(|menu| menu.selected_value());
+        assert_eq!(
+            selected.map(|suggestion| (suggestion.value, suggestion.span.start)),
+            Some(("add".to_string(), 11))
+        );
+    }
+
+    // Prompt recording the edit modes its indicator is rendered for
+    #[derive(Default)]
+    struct IndicatorPrompt(std::sync::Mutex<Vec<PromptEditMode>>);
+
+    impl Prompt for IndicatorPrompt {
+        fn render_prompt_left(&self) -> std::borrow::Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> std::borrow::Cow<str> {
+            self.0.lock().unwrap().push(prompt_mode);
+            "> ".into()
+        }
+
+        fn render_prompt_multiline_indicator(&self) -> std::borrow::Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_history_search_indicator(
+            &self,
+            _history_search: PromptHistorySearch,
+        ) -> std::borrow::Cow<str> {
+            "".into()
+        }
+    }
+
+    impl IndicatorPrompt {
+        fn last_mode(&self) -> Option<PromptEditMode> {
+            self.0.lock().unwrap().last().cloned()
+        }
+    }
+
+    // Two line prompt with an optional transient form
+    struct TallPrompt(Option<&'static str>);
+
+    impl Prompt for TallPrompt {
+        fn render_prompt_left(&self) -> std::borrow::Cow<str> {
+            "~/project\r\n(main)".into()
+        }
+
+        fn render_prompt_indicator(&self, _prompt_mode: PromptEditMode) -> std::borrow::Cow<str> {
+            "> ".into()
+        }
+
+        fn render_prompt_multiline_indicator(&self) -> std::borrow::Cow<str> {
+            "::: ".into()
+        }
+
+        fn render_prompt_history_search_indicator(
+            &self,
+            _history_search: PromptHistorySearch,
+        ) -> std::borrow::Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_transient(&self) -> Option<std::borrow::Cow<str>> {
+            self.0.map(Into::into)
+        }
+    }
+
+    #[rstest]
+    #[case(None, &["~/project", "(main)> ", "echo a", "::: b"], &[])]
+    #[case(Some("$ "), &["$ echo a", "::: b"], &["~/project", "(main)"])]
+    fn submitted_lines_are_repainted_with_the_transient_prompt(
+        #[case] transient: Option<&'static str>,
+        #[case] painted: &[&str],
+        #[case] hidden: &[&str],
+    ) {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_ansi_colors(false)
+            .with_buffer(output.clone());
+        reedline
+            .editor
+            .set_buffer("echo a\nb".to_string(), UndoBehavior::CreateUndoPoint);
+
+        let status = reedline
+            .handle_event(&TallPrompt(transient), ReedlineEvent::Submit)
+            .unwrap();
+
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "echo a\nb"));
+        let frame = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        for text in painted {
+            assert!(frame.contains(text), "{text:?} is not painted in {frame:?}");
+        }
+        for text in hidden {
+            assert!(!frame.contains(text), "{text:?} is painted in {frame:?}");
+        }
+    }
+
+    #[test]
+    fn vi_mode_changes_are_shown_by_the_indicator() {
+        use crate::{PromptViMode, Vi};
+
+        let prompt = IndicatorPrompt::default();
+        let mut reedline = Reedline::create()
+            .with_buffer(SharedBuffer::default())
+            .with_edit_mode(Box::<Vi>::default());
+
+        for (key, mode) in [
+            (KeyCode::Esc, PromptViMode::Normal),
+            (KeyCode::Char('i'), PromptViMode::Insert),
+        ] {
+            let key = Event::Key(KeyEvent::new(key, KeyModifiers::NONE));
+            let event = reedline.parse_event(ReedlineRawEvent::convert_from(key).unwrap());
+            reedline
+                .process_events_helper(&prompt, vec![event])
+                .unwrap();
+
+            assert_eq!(prompt.last_mode(), Some(PromptEditMode::Vi(mode)));
+        }
+    }
+
+    // Switches between the vi modes on every key without sending an event
+    #[derive(Default)]
+    struct ModeToggle(bool);
+
+    impl EditMode for ModeToggle {
+        fn parse_event(&mut self, _event: ReedlineRawEvent) -> ReedlineEvent {
+            self.0 = !self.0;
+            ReedlineEvent::None
+        }
+
+        fn edit_mode(&self) -> PromptEditMode {
+            match self.0 {
+                true => PromptEditMode::Vi(crate::PromptViMode::Normal),
+                false => PromptEditMode::Vi(crate::PromptViMode::Insert),
+            }
+        }
+    }
+
+    #[test]
+    fn mode_change_without_event_repaints_the_indicator() {
+        let prompt = IndicatorPrompt::default();
+        let mut reedline = Reedline::create()
+            .with_buffer(SharedBuffer::default())
+            .with_edit_mode(Box::<ModeToggle>::default());
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+
+        let event = reedline.parse_event(ReedlineRawEvent::convert_from(key).unwrap());
+        assert_eq!(event, ReedlineEvent::None);
+        assert!(reedline.edit_mode_changed);
+
+        reedline
+            .process_events_helper(&prompt, vec![event])
+            .unwrap();
+        assert!(!reedline.edit_mode_changed);
+        assert_eq!(
+            prompt.last_mode(),
+            Some(PromptEditMode::Vi(crate::PromptViMode::Normal))
+        );
+    }
+
+    #[test]
+    fn completion_preview_hints_the_top_suggestion() {
+        let mut reedline = Reedline::create()
+            .with_completion_preview(true)
+            .with_completer(Box::new(WordCompleter(vec!["hello", "help"])))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                crate::ListMenu::default(),
+            )));
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "echo he".to_string(),
+            )])])
+            .unwrap();
+        assert_eq!(reedline.completion_preview(), "llo");
+
+        reedline
+            .process_events(vec![ReedlineEvent::Menu("search_menu".to_string())])
+            .unwrap();
+        assert_eq!(reedline.completion_preview(), "");
+
+        reedline
+            .process_events(vec![ReedlineEvent::Esc, ReedlineEvent::HistoryHintComplete])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "echo hello");
+        assert_eq!(reedline.completion_preview(), "");
+    }
+
+    #[test]
+    fn completion_previews_are_cached_for_every_line() {
+        let completer = RecordingCompleter::new(vec!["hello", "help"]);
+        let mut reedline = Reedline::create()
+            .with_completion_preview(true)
+            .with_completer(Box::new(completer.clone()));
+
+        for command in [
+            EditCommand::InsertString("echo he".to_string()),
+            EditCommand::InsertChar('l'),
+            EditCommand::Backspace,
+            EditCommand::InsertChar('l'),
+        ] {
+            reedline
+                .process_events(vec![ReedlineEvent::Edit(vec![command])])
+                .unwrap();
+            reedline.completion_preview();
+        }
+
+        assert_eq!(reedline.completion_preview(), "lo");
+        assert_eq!(
+            *completer.queries.lock().unwrap(),
+            vec!["echo he", "echo hel"]
+        );
+    }
+
+    fn history_search_reedline() -> Reedline {
+        let mut history = FileBackedHistory::default();
+        for command_line in ["git status", "ls", "git commit", "git push"] {
+            history
+                .save(HistoryItem::from_command_line(command_line))
+                .unwrap();
+        }
+        Reedline::create().with_history(Box::new(history))
+    }
+
+    #[test]
+    fn prefix_history_search_steps_through_the_matching_entries() {
+        let mut reedline = history_search_reedline().with_prefix_history_search(true);
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "git".to_string(),
+            )])])
+            .unwrap();
+
+        let mut lines = Vec::new();
+        for event in [
+            ReedlineEvent::Up,
+            ReedlineEvent::Up,
+            ReedlineEvent::Up,
+            ReedlineEvent::Up,
+            ReedlineEvent::Down,
+            ReedlineEvent::Down,
+            ReedlineEvent::Down,
+        ] {
+            reedline.process_events(vec![event]).unwrap();
+            lines.push(reedline.current_buffer_contents().to_string());
+            assert_eq!(reedline.current_insertion_point(), 3);
+        }
+        assert_eq!(
+            lines,
+            vec![
+                "git push",
+                "git commit",
+                "git status",
+                "git status",
+                "git commit",
+                "git push",
+                "git"
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix_history_search_uses_the_text_before_the_cursor() {
+        let mut reedline = history_search_reedline().with_prefix_history_search(true);
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![
+                EditCommand::InsertString("git x".to_string()),
+                EditCommand::MoveLeft { select: false },
+                EditCommand::MoveLeft { select: false },
+            ])])
+            .unwrap();
+
+        reedline.process_events(vec![ReedlineEvent::Up]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "git push");
+        reedline.process_events(vec![ReedlineEvent::Down]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "git x");
+        assert_eq!(reedline.current_insertion_point(), 3);
+
+        // An empty buffer traverses the whole history
+        let mut reedline = history_search_reedline().with_prefix_history_search(true);
+        reedline.process_events(vec![ReedlineEvent::Up; 3]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "ls");
+    }
+
+    #[test]
+    fn incremental_history_search_steps_through_the_matches() {
+        let mut reedline = history_search_reedline();
+        let mut edit_mode = Emacs::default();
+        let mut keys = vec![(KeyModifiers::CONTROL, KeyCode::Char('r'))];
+        keys.extend(typed_keys("git"));
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert_eq!(
+            reedline.history_cursor.string_at_cursor().as_deref(),
+            Some("git push")
+        );
+
+        let previous = key_events(
+            &mut edit_mode,
+            &[
+                (KeyModifiers::CONTROL, KeyCode::Char('r')),
+                (KeyModifiers::CONTROL, KeyCode::Char('r')),
+            ],
+        );
+        reedline.process_events(previous).unwrap();
+        assert_eq!(
+            reedline.history_cursor.string_at_cursor().as_deref(),
+            Some("git status")
+        );
+
+        let next = key_events(
+            &mut edit_mode,
+            &[(KeyModifiers::CONTROL, KeyCode::Char('s'))],
+        );
+        reedline.process_events(next).unwrap();
+        assert_eq!(
+            reedline.history_cursor.string_at_cursor().as_deref(),
+            Some("git commit")
+        );
+
+        let accept = key_events(&mut edit_mode, &[(KeyModifiers::NONE, KeyCode::Enter)]);
+        let signal = reedline.process_events(accept).unwrap();
+        assert!(signal.is_none());
+        assert!(reedline.input_mode == InputMode::Regular);
+        assert_eq!(reedline.current_buffer_contents(), "git commit");
+    }
+
+    #[test]
+    fn cancelled_history_search_restores_the_buffer() {
+        let mut reedline = history_search_reedline();
+        let mut edit_mode = Emacs::default();
+        let mut keys = typed_keys("echo");
+        keys.push((KeyModifiers::CONTROL, KeyCode::Char('r')));
+        keys.extend(typed_keys("ls"));
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert_eq!(
+            reedline.history_cursor.string_at_cursor().as_deref(),
+            Some("ls")
+        );
+
+        let cancel = key_events(
+            &mut edit_mode,
+            &[(KeyModifiers::CONTROL, KeyCode::Char('g'))],
+        );
+        reedline.process_events(cancel).unwrap();
+        assert!(reedline.input_mode == InputMode::Regular);
+        assert_eq!(reedline.current_buffer_contents(), "echo");
+    }
+
+    #[test]
+    fn completers_are_told_about_the_accepted_suggestions() {
+        let completer = RecordingCompleter::new(vec!["hello", "help"]);
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(completer.clone()))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                crate::ListMenu::default().with_only_buffer_difference(false),
+            )));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("echo he".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+                ReedlineEvent::MenuNext,
+                ReedlineEvent::Enter,
+            ])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "echo help ");
+        assert_eq!(
+            *completer.accepted.lock().unwrap(),
+            vec![("help".to_string(), "echo help ".to_string())]
+        );
+    }
+
+    #[test]
+    fn menu_filter_lines_keep_the_typed_text_out_of_the_buffer() {
+        let mut reedline = history_search_reedline().with_menu(ReedlineMenu::HistoryMenu(
+            Box::new(crate::ListMenu::default().with_filter_line(true)),
+        ));
+        let mut edit_mode = Emacs::default();
+        let mut keys = typed_keys("stc");
+        keys.push((KeyModifiers::NONE, KeyCode::Backspace));
+        keys.push((KeyModifiers::NONE, KeyCode::Char('a')));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("sudo ".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+            ])
+            .unwrap();
+        reedline
+            .process_events(key_events(&mut edit_mode, &keys))
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "sudo ");
+        assert_eq!(
+            reedline
+                .menus
+                .iter()
+                .find(|menu| menu.is_active())
+                .map(|menu| menu.get_values().len()),
+            Some(1)
+        );
+
+        reedline.process_events(vec![ReedlineEvent::Enter]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "sudo git status");
+    }
+
+    #[rstest]
+    // Opening the list menu queries the number of values and then their first page
+    #[case(None, vec!["", "", "h", "he", "hel"])]
+    #[case(Some(Duration::from_secs(60)), vec!["", "", "hel"])]
+    fn debounced_completions_query_the_final_buffer(
+        #[case] debounce: Option<Duration>,
+        #[case] expected: Vec<&str>,
+    ) {
+        let completer = RecordingCompleter::new(vec!["hello", "help"]);
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(completer.clone()))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                crate::ListMenu::default().with_only_buffer_difference(false),
+            )));
+        if let Some(debounce) = debounce {
+            reedline = reedline.with_completion_debounce(debounce);
+        }
+
+        let mut events = vec![ReedlineEvent::Menu("search_menu".to_string())];
+        events.extend(
+            "hel"
+                .chars()
+                .map(|c| ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])),
+        );
+        reedline.process_events(events).unwrap();
+
+        assert_eq!(*completer.queries.lock().unwrap(), expected);
+
+        // The values match the buffer once the query is sent
+        reedline.process_events(vec![ReedlineEvent::Enter]).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "hello ");
+    }
+
+    #[test]
+    fn failed_edit_commands_ring_the_bell() {
+        let mut reedline = Reedline::create().with_visual_bell(true);
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert!(reedline.bell);
+
+        let mut reedline = Reedline::create().with_visual_bell(true);
+        reedline.run_edit_commands(&[EditCommand::InsertChar('a'), EditCommand::Undo]);
+        assert!(!reedline.bell);
+
+        let mut reedline = Reedline::create();
+        reedline.run_edit_commands(&[EditCommand::Undo]);
+        assert!(!reedline.bell);
+    }
+
+    #[rstest]
+    #[case(true, false, "\x1b[?5h", "\x07")]
+    #[case(false, true, "\x07", "\x1b[?5h")]
+    fn the_bell_is_shown_on_the_next_frame(
+        #[case] visual_bell: bool,
+        #[case] audible_bell: bool,
+        #[case] expected: &str,
+        #[case] unexpected: &str,
+    ) {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_visual_bell(visual_bell)
+            .with_audible_bell(audible_bell)
+            .with_buffer(output.clone());
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::Undo])])
+            .unwrap();
+        let painted = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(painted.contains(expected));
+        assert!(!painted.contains(unexpected));
+        assert!(!reedline.bell);
+
+        // The flash ends with the next event
+        reedline
+            .process_events(vec![ReedlineEvent::Repaint])
+            .unwrap();
+        let painted = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(painted.contains("\x1b[?5l"), visual_bell);
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    #[case(
+        "tr a-z A-Z < \"$0\" > \"$0.new\" && mv \"$0.new\" \"$0\"",
+        "ECHO HELLO",
+        10
+    )]
+    #[case("echo edited > \"$0\"; exit 1", "echo hello", 0)]
+    fn buffer_editor_replaces_the_buffer_when_it_succeeds(
+        #[case] script: &str,
+        #[case] expected: &str,
+        #[case] cursor: usize,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        let mut reedline =
+            Reedline::create().with_buffer_editor(command, temp_dir.path().join("buffer.txt"));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("echo hello".to_string())]),
+                ReedlineEvent::Edit(vec![EditCommand::MoveToStart { select: false }]),
+                ReedlineEvent::OpenEditor,
+            ])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), expected);
+        assert_eq!(reedline.current_insertion_point(), cursor);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn environment_editor_edits_a_new_file_that_is_removed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = temp_dir.path().join("editor.sh");
+        let paths = temp_dir.path().join("paths.txt");
+        std::fs::write(
+            &script,
+            format!("echo \"$1\" >> {:?}; echo edited > \"$1\"", paths),
+        )
+        .unwrap();
+        std::env::set_var("VISUAL", format!("sh {}", script.display()));
+        let mut reedline = Reedline::create();
+
+        reedline
+            .process_events(vec![ReedlineEvent::OpenEditor; 2])
+            .unwrap();
+        std::env::remove_var("VISUAL");
+
+        assert_eq!(reedline.current_buffer_contents(), "edited");
+        let paths = std::fs::read_to_string(paths).unwrap();
+        let paths: Vec<&str> = paths.lines().collect();
+        assert_eq!(paths.len(), 2);
+        assert_ne!(paths[0], paths[1]);
+        assert!(paths
+            .iter()
+            .all(|path| !std::path::Path::new(path).exists()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn temp_files_are_only_readable_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = create_temp_file().unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn accepted_snippets_are_edited_by_their_tab_stops() {
+        struct SnippetCompleter;
+
+        impl Completer for SnippetCompleter {
+            fn complete(&mut self, _line: &str, pos: usize) -> Vec<Suggestion> {
+                vec![Suggestion {
+                    value: "cp".to_string(),
+                    span: crate::Span::new(0, pos),
+                    snippet: Some("cp ${1:src} ${2:dst}".to_string()),
+                    ..Suggestion::default()
+                }]
+            }
+        }
+
+        let mut reedline = Reedline::create()
+            .with_quick_completions(true)
+            .with_completer(Box::new(SnippetCompleter))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                crate::ListMenu::default()
+                    .with_only_buffer_difference(false)
+                    .with_quick_complete(true),
+            )));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("c".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "cp src dst");
+        // The first placeholder is selected
+        assert_eq!(reedline.current_insertion_point(), 6);
+        assert_eq!(reedline.editor.get_selection(), Some((3, 6)));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![
+                    EditCommand::InsertChar('a'),
+                    EditCommand::InsertChar('b'),
+                ]),
+                ReedlineEvent::NextTabStop,
+            ])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "cp ab dst");
+        assert_eq!(reedline.editor.get_selection(), Some((6, 9)));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('c')]),
+                ReedlineEvent::PrevTabStop,
+            ])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "cp ab c");
+        assert_eq!(reedline.editor.get_selection(), Some((3, 5)));
+
+        // The snippet is left at its end
+        reedline
+            .process_events(vec![ReedlineEvent::NextTabStop, ReedlineEvent::NextTabStop])
+            .unwrap();
+        assert_eq!(reedline.current_insertion_point(), 7);
+        assert!(!reedline.editor.goto_tab_stop(true));
+    }
+
+    #[rstest]
+    #[case("gco", false, "git checkout ")]
+    #[case("sudo gco", false, "sudo gco ")]
+    #[case("sudo gco", true, "sudo git checkout ")]
+    #[case("gcob", false, "gcob ")]
+    fn abbreviations_are_expanded_on_space(
+        #[case] typed: &str,
+        #[case] anywhere: bool,
+        #[case] expected: &str,
+    ) {
+        let mut reedline = Reedline::create()
+            .with_abbreviations(HashMap::from([(
+                "gco".to_string(),
+                "git checkout".to_string(),
+            )]))
+            .with_abbreviations_anywhere(anywhere);
+
+        for c in typed.chars().chain([' ']) {
+            reedline
+                .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])])
+                .unwrap();
+        }
+
+        assert_eq!(reedline.current_buffer_contents(), expected);
+    }
+
+    #[test]
+    fn abbreviations_are_expanded_within_grouped_edits() {
+        let mut reedline = Reedline::create().with_abbreviations(HashMap::from([(
+            "gco".to_string(),
+            "git checkout".to_string(),
+        )]));
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(
+                "gco main gco"
+                    .chars()
+                    .chain([' '])
+                    .map(EditCommand::InsertChar)
+                    .collect(),
+            )])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "git checkout main gco ");
+    }
+
+    #[test]
+    fn undo_restores_the_abbreviation() {
+        let mut reedline = Reedline::create().with_abbreviations(HashMap::from([(
+            "gco".to_string(),
+            "git checkout".to_string(),
+        )]));
+
+        for c in "gco ".chars() {
+            reedline
+                .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)])])
+                .unwrap();
+        }
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::Undo])])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "git checkout");
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::Undo])])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "gco");
+        assert_eq!(reedline.current_insertion_point(), 3);
+    }
+
+    #[rstest]
+    #[case(LineOverflow::Wrap, false)]
+    #[case(LineOverflow::Scroll, true)]
+    fn long_lines_are_scrolled_to_the_cursor(
+        #[case] line_overflow: LineOverflow,
+        #[case] scrolled: bool,
+    ) {
+        let output = SharedBuffer::default();
+        let mut reedline = Reedline::create()
+            .with_line_overflow(line_overflow)
+            .with_buffer(output.clone());
+
+        reedline
+            .process_events(vec![ReedlineEvent::Edit(vec![EditCommand::InsertString(
+                "x".repeat(200),
+            )])])
+            .unwrap();
+        let painted = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let last_frame = strip_ansi_escapes::strip_str(painted.rsplit("\x1b[J").next().unwrap());
+
+        assert_eq!(last_frame.contains(&"x".repeat(200)), !scrolled);
+        assert_eq!(
+            last_frame.contains(&format!("<{}", "x".repeat(20))),
+            scrolled
+        );
+        assert_eq!(reedline.current_buffer_contents(), "x".repeat(200));
+    }
+
+    #[rstest]
+    #[case(MenuAcceptBehavior::Accept, None, "hello ")]
+    #[case(MenuAcceptBehavior::SubmitPassthrough, Some("hel"), "")]
+    fn enter_in_a_menu_follows_its_accept_behavior(
+        #[case] accept_behavior: MenuAcceptBehavior,
+        #[case] submitted: Option<&str>,
+        #[case] buffer: &str,
+    ) {
+        let menu = crate::ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_accept_behavior(accept_behavior);
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(WordCompleter(vec!["hello", "help"])))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+
+        let signal = reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("hel".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+                ReedlineEvent::Enter,
+            ])
+            .unwrap();
+
+        match submitted {
+            Some(line) => assert!(matches!(signal, Some(Signal::Success(s)) if s == line)),
+            None => assert!(signal.is_none()),
+        }
+        assert_eq!(reedline.current_buffer_contents(), buffer);
+        assert!(reedline.active_menu().is_none());
+    }
+
+    #[test]
+    fn menu_accept_inserts_the_selection() {
+        let menu = crate::ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_accept_behavior(MenuAcceptBehavior::SubmitPassthrough);
+        let mut reedline = Reedline::create()
+            .with_completer(Box::new(WordCompleter(vec!["hello", "help"])))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+
+        reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString("hel".to_string())]),
+                ReedlineEvent::Menu("search_menu".to_string()),
+                ReedlineEvent::MenuAccept,
+            ])
+            .unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "hello ");
+        assert!(reedline.active_menu().is_none());
+    }
+
+    #[rstest]
+    #[case(TrimMode::None, "ls   ", "ls   ")]
+    #[case(TrimMode::Trailing, "ls   ", "ls")]
+    #[case(TrimMode::Trailing, "echo a \\", "echo a \\")]
+    fn submitted_lines_are_trimmed(
+        #[case] trim_mode: TrimMode,
+        #[case] line: &str,
+        #[case] expected: &str,
+    ) {
+        let mut reedline = Reedline::create().with_trim_on_submit(trim_mode);
+
+        let signal = reedline
+            .process_events(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertString(line.to_string())]),
+                ReedlineEvent::Submit,
+            ])
+            .unwrap();
+
+        assert!(matches!(signal, Some(Signal::Success(submitted)) if submitted == expected));
+        let history = reedline
+            .history
+            .search(SearchQuery::everything(SearchDirection::Backward, None))
+            .unwrap();
+        assert_eq!(history[0].command_line, expected);
+    }
 }
@@ -1,8 +1,13 @@
-use crossterm::event::{Event, KeyEvent, KeyEventKind};
+use crossterm::event::{Event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 use strum_macros::EnumIter;
 
+use crate::edit_mode::KeyCombination;
+
 /// Valid ways how `Reedline::read_line()` can return
 #[derive(Debug)]
 pub enum Signal {
@@ -14,6 +19,29 @@ pub enum Signal {
     CtrlD, // End terminal session
 }
 
+/// What [`ReedlineEvent::CtrlC`] does, see `Reedline::with_ctrl_c_behavior()`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CtrlCBehavior {
+    /// Clear the line and return [`Signal::CtrlC`] to the caller
+    #[default]
+    RawSignal,
+    /// Clear the line and continue with a new prompt below it, like bash
+    CancelLine,
+    /// Return [`Signal::CtrlC`] on an empty line, otherwise behave like
+    /// [`CtrlCBehavior::CancelLine`]
+    ExitOnEmpty,
+}
+
+/// What [`ReedlineEvent::CtrlD`] does, see `Reedline::with_ctrl_d_behavior()`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CtrlDBehavior {
+    /// Delete the character under the cursor, return [`Signal::CtrlD`] on an empty line
+    #[default]
+    DeleteOrEof,
+    /// Clear the line and return [`Signal::CtrlD`]
+    AlwaysEof,
+}
+
 /// Editing actions which can be mapped to key bindings.
 ///
 /// Executed by `Reedline::run_edit_commands()`
@@ -105,6 +133,12 @@ pub enum EditCommand {
         select: bool,
     },
 
+    /// Move to the bracket matching the one at or left of the insertion point
+    MoveToMatchingBracket {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
     /// Insert a character at the current insertion point
     InsertChar(char),
 
@@ -138,6 +172,13 @@ pub enum EditCommand {
     /// Delete in-place a word from the current insertion point
     DeleteWord,
 
+    /// Delete the innermost bracket pair enclosing the cursor including the brackets,
+    /// on a bracket its pair (vi `d%`)
+    DeleteToMatchingBracket,
+
+    /// Delete the text inside of the innermost bracket pair enclosing the cursor
+    DeleteInsideBrackets,
+
     /// Clear the current buffer
     Clear,
 
@@ -186,30 +227,49 @@ pub enum EditCommand {
     /// Paste the cut buffer in front of the insertion point (vi `p`)
     PasteCutBufferAfter,
 
-    /// Upper case the current word
+    /// Replace the text inserted by the previous paste with the entry before it in the kill
+    /// ring (Emacs `M-y`), requires [`crate::Reedline::with_kill_ring`]
+    YankPop,
+
+    /// Upper case the text from the cursor to the end of the word and move behind it
     UppercaseWord,
 
-    /// Lower case the current word
+    /// Lower case the text from the cursor to the end of the word and move behind it
     LowercaseWord,
 
+    /// Capitalize the word from the cursor and move behind it
+    CapitalizeWord,
+
     /// Capitalize the current character
     CapitalizeChar,
 
-    /// Switch the case of the current character
+    /// Switch the ASCII case of the current character
     SwitchcaseChar,
 
+    /// Switch the case of the selection, or of the current character and move behind it
+    ToggleCase,
+
     /// Swap the current word with the word to the right
     SwapWords,
 
     /// Swap the current grapheme/character with the one to the right
     SwapGraphemes,
 
+    /// Swap the word before the insertion point with the word after it (emacs `M-t`)
+    TransposeWords,
+
     /// Undo the previous edit command
     Undo,
 
     /// Redo an edit command from the undo history
     Redo,
 
+    /// Switch to the next branch of the undo history, created by an edit after an undo
+    UndoTreeBranchNext,
+
+    /// Switch to the previous branch of the undo history
+    UndoTreeBranchPrev,
+
     /// CutUntil right until char
     CutRightUntil(char),
 
@@ -262,6 +322,111 @@ pub enum EditCommand {
 
     /// Copy selection
     CopySelection,
+
+    /// Cut the text object the cursor is on
+    CutTextObject(TextObject),
+
+    /// Copy the text object the cursor is on
+    CopyTextObject(TextObject),
+
+    /// Copy the current line
+    CopyCurrentLine,
+
+    /// Copy from the start of the current line to the insertion point
+    CopyFromLineStart,
+
+    /// Copy from the insertion point to the end of the current line
+    CopyToLineEnd,
+
+    /// Copy the word left of the insertion point
+    CopyWordLeft,
+
+    /// Copy the WORD left of the insertion point
+    CopyBigWordLeft,
+
+    /// Copy the word right of the insertion point
+    CopyWordRight,
+
+    /// Copy the WORD right of the insertion point
+    CopyBigWordRight,
+
+    /// Copy the word right of the insertion point and any following space
+    CopyWordRightToNext,
+
+    /// Copy the WORD right of the insertion point and any following space
+    CopyBigWordRightToNext,
+
+    /// Copy right until char
+    CopyRightUntil(char),
+
+    /// Copy right before char
+    CopyRightBefore(char),
+
+    /// Copy left until char
+    CopyLeftUntil(char),
+
+    /// Copy left before char
+    CopyLeftBefore(char),
+
+    /// Store the content of the cut buffer in the named register (vi `"a`),
+    /// uppercase register names append to the lowercase register
+    CopyToRegister(char),
+
+    /// Paste the content of the named register in front of the insertion point
+    PasteFromRegister(char),
+
+    /// Paste the content of the named register after the insertion point (vi `"ap`)
+    PasteFromRegisterAfter(char),
+
+    /// Start recording the dispatched events as a macro into the named register
+    /// (vi `q`)
+    StartRecordingMacro(char),
+
+    /// Stop recording the macro
+    StopRecordingMacro,
+
+    /// Replay the macro recorded into the named register (vi `@`)
+    ReplayMacro(char),
+
+    /// Remove the selection, keeping the insertion point
+    ClearSelection,
+
+    /// Swap the insertion point with the other end of the selection
+    SwapCursorAndAnchor,
+
+    /// Move to the start of the selection and remove it
+    MoveToSelectionStart,
+
+    /// Move to the end of the selection and remove it
+    MoveToSelectionEnd,
+}
+
+/// Text objects around the cursor that edit commands can operate on, as in vim's
+/// `iw` or `a"`
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum TextObject {
+    /// The word the cursor is on (`iw`), including the surrounding whitespace if not
+    /// `inner` (`aw`)
+    Word {
+        /// Exclude the surrounding whitespace
+        inner: bool,
+    },
+    /// The text between the `open` and `close` delimiters around the cursor (`i(`),
+    /// including the delimiters if not `inner` (`a(`)
+    Delimited {
+        /// Opening delimiter, identical to `close` for quotes
+        open: char,
+        /// Closing delimiter
+        close: char,
+        /// Exclude the delimiters
+        inner: bool,
+    },
+}
+
+impl Default for TextObject {
+    fn default() -> Self {
+        TextObject::Word { inner: true }
+    }
 }
 
 impl Display for EditCommand {
@@ -302,6 +467,9 @@ impl Display for EditCommand {
             EditCommand::MoveLeftUntil { .. } => {
                 write!(f, "MoveLeftUntil Value: <char>, Optional[select: <bool>]")
             }
+            EditCommand::MoveToMatchingBracket { .. } => {
+                write!(f, "MoveToMatchingBracket Optional[select: <bool>]")
+            }
             EditCommand::MoveLeftBefore { .. } => {
                 write!(f, "MoveLeftBefore Value: <char>, Optional[select: <bool>]")
             }
@@ -315,6 +483,8 @@ impl Display for EditCommand {
             EditCommand::CutChar => write!(f, "CutChar"),
             EditCommand::BackspaceWord => write!(f, "BackspaceWord"),
             EditCommand::DeleteWord => write!(f, "DeleteWord"),
+            EditCommand::DeleteToMatchingBracket => write!(f, "DeleteToMatchingBracket"),
+            EditCommand::DeleteInsideBrackets => write!(f, "DeleteInsideBrackets"),
             EditCommand::Clear => write!(f, "Clear"),
             EditCommand::ClearToLineEnd => write!(f, "ClearToLineEnd"),
             EditCommand::Complete => write!(f, "Complete"),
@@ -330,15 +500,21 @@ impl Display for EditCommand {
             EditCommand::CutWordRightToNext => write!(f, "CutWordRightToNext"),
             EditCommand::CutBigWordRightToNext => write!(f, "CutBigWordRightToNext"),
             EditCommand::PasteCutBufferBefore => write!(f, "PasteCutBufferBefore"),
+            EditCommand::YankPop => write!(f, "YankPop"),
             EditCommand::PasteCutBufferAfter => write!(f, "PasteCutBufferAfter"),
             EditCommand::UppercaseWord => write!(f, "UppercaseWord"),
             EditCommand::LowercaseWord => write!(f, "LowercaseWord"),
+            EditCommand::CapitalizeWord => write!(f, "CapitalizeWord"),
+            EditCommand::ToggleCase => write!(f, "ToggleCase"),
             EditCommand::SwitchcaseChar => write!(f, "SwitchcaseChar"),
             EditCommand::CapitalizeChar => write!(f, "CapitalizeChar"),
             EditCommand::SwapWords => write!(f, "SwapWords"),
             EditCommand::SwapGraphemes => write!(f, "SwapGraphemes"),
+            EditCommand::TransposeWords => write!(f, "TransposeWords"),
             EditCommand::Undo => write!(f, "Undo"),
             EditCommand::Redo => write!(f, "Redo"),
+            EditCommand::UndoTreeBranchNext => write!(f, "UndoTreeBranchNext"),
+            EditCommand::UndoTreeBranchPrev => write!(f, "UndoTreeBranchPrev"),
             EditCommand::CutRightUntil(_) => write!(f, "CutRightUntil Value: <char>"),
             EditCommand::CutRightBefore(_) => write!(f, "CutRightBefore Value: <char>"),
             EditCommand::MoveRightUntil { .. } => write!(f, "MoveRightUntil Value: <char>"),
@@ -348,6 +524,35 @@ impl Display for EditCommand {
             EditCommand::SelectAll => write!(f, "SelectAll"),
             EditCommand::CutSelection => write!(f, "CutSelection"),
             EditCommand::CopySelection => write!(f, "CopySelection"),
+            EditCommand::CutTextObject(_) => write!(f, "CutTextObject Value: <TextObject>"),
+            EditCommand::CopyTextObject(_) => write!(f, "CopyTextObject Value: <TextObject>"),
+            EditCommand::CopyCurrentLine => write!(f, "CopyCurrentLine"),
+            EditCommand::CopyFromLineStart => write!(f, "CopyFromLineStart"),
+            EditCommand::CopyToLineEnd => write!(f, "CopyToLineEnd"),
+            EditCommand::CopyWordLeft => write!(f, "CopyWordLeft"),
+            EditCommand::CopyBigWordLeft => write!(f, "CopyBigWordLeft"),
+            EditCommand::CopyWordRight => write!(f, "CopyWordRight"),
+            EditCommand::CopyBigWordRight => write!(f, "CopyBigWordRight"),
+            EditCommand::CopyWordRightToNext => write!(f, "CopyWordRightToNext"),
+            EditCommand::CopyBigWordRightToNext => write!(f, "CopyBigWordRightToNext"),
+            EditCommand::CopyRightUntil(_) => write!(f, "CopyRightUntil Value: <char>"),
+            EditCommand::CopyRightBefore(_) => write!(f, "CopyRightBefore Value: <char>"),
+            EditCommand::CopyLeftUntil(_) => write!(f, "CopyLeftUntil Value: <char>"),
+            EditCommand::CopyLeftBefore(_) => write!(f, "CopyLeftBefore Value: <char>"),
+            EditCommand::CopyToRegister(_) => write!(f, "CopyToRegister Value: <char>"),
+            EditCommand::PasteFromRegister(_) => write!(f, "PasteFromRegister Value: <char>"),
+            EditCommand::PasteFromRegisterAfter(_) => {
+                write!(f, "PasteFromRegisterAfter Value: <char>")
+            }
+            EditCommand::StartRecordingMacro(_) => {
+                write!(f, "StartRecordingMacro Value: <char>")
+            }
+            EditCommand::StopRecordingMacro => write!(f, "StopRecordingMacro"),
+            EditCommand::ReplayMacro(_) => write!(f, "ReplayMacro Value: <char>"),
+            EditCommand::ClearSelection => write!(f, "ClearSelection"),
+            EditCommand::SwapCursorAndAnchor => write!(f, "SwapCursorAndAnchor"),
+            EditCommand::MoveToSelectionStart => write!(f, "MoveToSelectionStart"),
+            EditCommand::MoveToSelectionEnd => write!(f, "MoveToSelectionEnd"),
         }
     }
 }
@@ -375,11 +580,17 @@ impl EditCommand {
             | EditCommand::MoveRightUntil { select, .. }
             | EditCommand::MoveRightBefore { select, .. }
             | EditCommand::MoveLeftUntil { select, .. }
-            | EditCommand::MoveLeftBefore { select, .. } => {
+            | EditCommand::MoveLeftBefore { select, .. }
+            | EditCommand::MoveToMatchingBracket { select, .. } => {
                 EditType::MoveCursor { select: *select }
             }
 
-            EditCommand::SelectAll => EditType::MoveCursor { select: true },
+            EditCommand::SelectAll | EditCommand::SwapCursorAndAnchor => {
+                EditType::MoveCursor { select: true }
+            }
+            EditCommand::ClearSelection
+            | EditCommand::MoveToSelectionStart
+            | EditCommand::MoveToSelectionEnd => EditType::MoveCursor { select: false },
 
             // Text edits
             EditCommand::InsertChar(_)
@@ -392,6 +603,8 @@ impl EditCommand {
             | EditCommand::ReplaceChars(_, _)
             | EditCommand::BackspaceWord
             | EditCommand::DeleteWord
+            | EditCommand::DeleteToMatchingBracket
+            | EditCommand::DeleteInsideBrackets
             | EditCommand::Clear
             | EditCommand::ClearToLineEnd
             | EditCommand::Complete
@@ -407,22 +620,50 @@ impl EditCommand {
             | EditCommand::CutWordRightToNext
             | EditCommand::CutBigWordRightToNext
             | EditCommand::PasteCutBufferBefore
+            | EditCommand::YankPop
             | EditCommand::PasteCutBufferAfter
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
+            | EditCommand::CapitalizeWord
+            | EditCommand::ToggleCase
             | EditCommand::SwitchcaseChar
             | EditCommand::CapitalizeChar
             | EditCommand::SwapWords
             | EditCommand::SwapGraphemes
+            | EditCommand::TransposeWords
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
             | EditCommand::CutLeftBefore(_)
-            | EditCommand::CutSelection => EditType::EditText,
-
-            EditCommand::Undo | EditCommand::Redo => EditType::UndoRedo,
-
-            EditCommand::CopySelection => EditType::NoOp,
+            | EditCommand::CutSelection
+            | EditCommand::CutTextObject(_)
+            | EditCommand::PasteFromRegister(_)
+            | EditCommand::PasteFromRegisterAfter(_) => EditType::EditText,
+
+            EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::UndoTreeBranchNext
+            | EditCommand::UndoTreeBranchPrev => EditType::UndoRedo,
+
+            EditCommand::CopySelection
+            | EditCommand::CopyTextObject(_)
+            | EditCommand::CopyCurrentLine
+            | EditCommand::CopyFromLineStart
+            | EditCommand::CopyToLineEnd
+            | EditCommand::CopyWordLeft
+            | EditCommand::CopyBigWordLeft
+            | EditCommand::CopyWordRight
+            | EditCommand::CopyBigWordRight
+            | EditCommand::CopyWordRightToNext
+            | EditCommand::CopyBigWordRightToNext
+            | EditCommand::CopyRightUntil(_)
+            | EditCommand::CopyRightBefore(_)
+            | EditCommand::CopyLeftUntil(_)
+            | EditCommand::CopyLeftBefore(_)
+            | EditCommand::CopyToRegister(_)
+            | EditCommand::StartRecordingMacro(_)
+            | EditCommand::StopRecordingMacro
+            | EditCommand::ReplayMacro(_) => EditType::NoOp,
         }
     }
 }
@@ -460,6 +701,9 @@ pub enum UndoBehavior {
     /// Catch-all for actions that should always form a unique undo point and never be
     /// grouped with later edits
     CreateUndoPoint,
+    /// Accepted completion, always undone on its own whatever the
+    /// [`UndoCoalescing`], back to the buffer and cursor before the completion
+    Completion,
     /// Undo/Redo actions shouldn't be reflected on the edit stack
     UndoRedo,
 }
@@ -494,6 +738,87 @@ impl UndoBehavior {
     }
 }
 
+/// How consecutive edits are grouped into one undo step, see
+/// `Reedline::with_undo_coalescing()`
+///
+/// Moving the cursor always ends a group of edits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UndoCoalescing {
+    /// Every inserted or deleted character is undone on its own
+    PerEdit,
+    /// Inserted or deleted characters are grouped until a word boundary or a newline
+    #[default]
+    PerWord,
+    /// Inserted or deleted characters are grouped until the user pauses typing for at
+    /// least the given duration
+    PerPause(Duration),
+}
+
+/// Whitespace removed from the submitted lines, see `Reedline::with_trim_on_submit()`
+///
+/// A backslash escaping the whitespace after it, e.g. a line continuation, keeps
+/// that whitespace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrimMode {
+    /// The line is submitted as it is
+    #[default]
+    None,
+    /// The whitespace at the end of the line is removed
+    Trailing,
+    /// The whitespace at the start and at the end of the line is removed
+    Both,
+    /// Like [`TrimMode::Both`], and the runs of spaces and tabs within the line are
+    /// replaced by a single space, also inside of quotes
+    Collapse,
+}
+
+/// How a line of the buffer wider than the terminal is painted, see
+/// `Reedline::with_line_overflow()`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineOverflow {
+    /// The line wraps onto the next rows of the terminal
+    #[default]
+    Wrap,
+    /// The line stays on one row and scrolls horizontally to keep the cursor visible.
+    /// The hidden parts are marked with `<` and `>` at the edges
+    Scroll,
+}
+
+/// Mouse actions that can be handled by reedline
+///
+/// Mouse events are only reported when enabled with [`Reedline::with_mouse`](crate::Reedline::with_mouse)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MouseAction {
+    /// Mouse action without special handling
+    #[default]
+    Other,
+    /// Left click at the given position of the terminal
+    Click {
+        /// Column of the click, starting from 0
+        column: u16,
+        /// Row of the click, starting from 0
+        row: u16,
+    },
+    /// Scroll wheel moved up
+    ScrollUp,
+    /// Scroll wheel moved down
+    ScrollDown,
+}
+
+impl From<MouseEvent> for MouseAction {
+    fn from(event: MouseEvent) -> Self {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => MouseAction::Click {
+                column: event.column,
+                row: event.row,
+            },
+            MouseEventKind::ScrollUp => MouseAction::ScrollUp,
+            MouseEventKind::ScrollDown => MouseAction::ScrollDown,
+            _ => MouseAction::Other,
+        }
+    }
+}
+
 /// Reedline supported actions.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, EnumIter)]
 pub enum ReedlineEvent {
@@ -504,6 +829,9 @@ pub enum ReedlineEvent {
     HistoryHintComplete,
 
     /// Complete a single token/word of the history hint
+    ///
+    /// Inserts [`Hinter::next_hint_token`](crate::Hinter::next_hint_token), the rest of
+    /// the hint stays displayed
     HistoryHintWordComplete,
 
     /// Handle EndOfLine event
@@ -544,12 +872,17 @@ pub enum ReedlineEvent {
     /// Esc event
     Esc,
 
-    /// Mouse
-    Mouse, // Fill in details later
+    /// Mouse action. Clicks select the menu values and scrolling moves
+    /// through them
+    Mouse(MouseAction),
 
     /// trigger terminal resize
     Resize(u16, u16),
 
+    /// Text pasted in bracketed paste mode, inserted as a single edit without
+    /// interpreting its newlines as Enter
+    Paste(String),
+
     /// Run these commands in the editor
     Edit(Vec<EditCommand>),
 
@@ -575,8 +908,23 @@ pub enum ReedlineEvent {
     NextHistory,
 
     /// Search the history for a string
+    ///
+    /// While searching, steps to the previous match
     SearchHistory,
 
+    /// While searching the history, steps to the next match
+    SearchHistoryForward,
+
+    /// Leaves the history search, keeping the buffer from before the search
+    CancelHistorySearch,
+
+    /// Moves to the next placeholder of an inserted completion snippet, see
+    /// [`Suggestion::snippet`](crate::Suggestion::snippet)
+    NextTabStop,
+
+    /// Moves to the previous placeholder of an inserted completion snippet
+    PrevTabStop,
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -611,6 +959,21 @@ pub enum ReedlineEvent {
     /// Move to the previous history page
     MenuPagePrevious,
 
+    /// Move to the history page with the given index, starting from 0
+    MenuPageGoto(usize),
+
+    /// Insert the selected value of the menu and close it, like [`ReedlineEvent::Enter`]
+    /// does by default, see [`MenuAcceptBehavior`](crate::MenuAcceptBehavior)
+    MenuAccept,
+
+    /// Insert the selected value of the menu like [`ReedlineEvent::Enter`] but keep the
+    /// menu open, showing the values found at the new cursor position
+    MenuAcceptAndContinue,
+
+    /// Sort the values of the menu by its next sort order, see
+    /// [`ListMenu::with_sort_orders`](crate::ListMenu::with_sort_orders)
+    MenuToggleSort,
+
     /// Way to bind the execution of a whole command (directly returning from [`crate::Reedline::read_line()`]) to a keybinding
     ExecuteHostCommand(String),
 
@@ -632,8 +995,9 @@ impl Display for ReedlineEvent {
             ReedlineEvent::Submit => write!(f, "Submit"),
             ReedlineEvent::SubmitOrNewline => write!(f, "SubmitOrNewline"),
             ReedlineEvent::Esc => write!(f, "Esc"),
-            ReedlineEvent::Mouse => write!(f, "Mouse"),
+            ReedlineEvent::Mouse(_) => write!(f, "Mouse"),
             ReedlineEvent::Resize(_, _) => write!(f, "Resize <int> <int>"),
+            ReedlineEvent::Paste(_) => write!(f, "Paste <string>"),
             ReedlineEvent::Edit(_) => write!(
                 f,
                 "Edit: <EditCommand> or Edit: <EditCommand> value: <string>"
@@ -646,6 +1010,10 @@ impl Display for ReedlineEvent {
             ReedlineEvent::Left => write!(f, "Left"),
             ReedlineEvent::NextHistory => write!(f, "NextHistory"),
             ReedlineEvent::SearchHistory => write!(f, "SearchHistory"),
+            ReedlineEvent::SearchHistoryForward => write!(f, "SearchHistoryForward"),
+            ReedlineEvent::CancelHistorySearch => write!(f, "CancelHistorySearch"),
+            ReedlineEvent::NextTabStop => write!(f, "NextTabStop"),
+            ReedlineEvent::PrevTabStop => write!(f, "PrevTabStop"),
             ReedlineEvent::Multiple(_) => write!(f, "Multiple[ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::UntilFound(_) => write!(f, "UntilFound [ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::Menu(_) => write!(f, "Menu Name: <string>"),
@@ -657,6 +1025,10 @@ impl Display for ReedlineEvent {
             ReedlineEvent::MenuRight => write!(f, "MenuRight"),
             ReedlineEvent::MenuPageNext => write!(f, "MenuPageNext"),
             ReedlineEvent::MenuPagePrevious => write!(f, "MenuPagePrevious"),
+            ReedlineEvent::MenuPageGoto(_) => write!(f, "MenuPageGoto <int>"),
+            ReedlineEvent::MenuAccept => write!(f, "MenuAccept"),
+            ReedlineEvent::MenuAcceptAndContinue => write!(f, "MenuAcceptAndContinue"),
+            ReedlineEvent::MenuToggleSort => write!(f, "MenuToggleSort"),
             ReedlineEvent::ExecuteHostCommand(_) => write!(f, "ExecuteHostCommand"),
             ReedlineEvent::OpenEditor => write!(f, "OpenEditor"),
         }
@@ -706,4 +1078,17 @@ impl ReedlineRawEvent {
     pub fn into(self) -> Event {
         self.inner
     }
+
+    /// The pressed key with its modifiers, if the event is a key press
+    pub(crate) fn key_combination(&self) -> Option<KeyCombination> {
+        match self.inner {
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => Some(KeyCombination {
+                modifier: modifiers,
+                key_code: code,
+            }),
+            _ => None,
+        }
+    }
 }
@@ -1,9 +1,13 @@
 mod example;
+mod rainbow_brackets;
+mod rules;
 mod simple_match;
 
 use crate::StyledText;
 
 pub use example::ExampleHighlighter;
+pub use rainbow_brackets::RainbowBrackets;
+pub use rules::RuleHighlighter;
 pub use simple_match::SimpleMatchHighlighter;
 /// The syntax highlighting trait. Implementers of this trait will take in the current string and then
 /// return a `StyledText` object, which represents the contents of the original line as styled strings
@@ -0,0 +1,193 @@
+use crate::highlighter::Highlighter;
+use crate::StyledText;
+use nu_ansi_term::{Color, Style};
+
+/// Color the pairs of brackets `()[]{}` by their nesting depth
+///
+/// The styles of the palette are used in turn for every level of nesting, brackets
+/// without a partner get the error style. Brackets inside of quoted strings are
+/// ignored.
+///
+/// The bracket styles are applied on top of the text styled by another highlighter,
+/// see [`RainbowBrackets::with_highlighter`].
+///
+/// Default style:
+///
+/// - unmatched brackets: White on red background
+pub struct RainbowBrackets {
+    palette: Vec<Style>,
+    error_style: Style,
+    highlighter: Option<Box<dyn Highlighter>>,
+}
+
+impl Highlighter for RainbowBrackets {
+    fn highlight(&self, line: &str, cursor: usize) -> StyledText {
+        let mut styled_text = match &self.highlighter {
+            Some(highlighter) => highlighter.highlight(line, cursor),
+            None => {
+                let mut styled_text = StyledText::new();
+                styled_text.push((Style::default(), line.to_owned()));
+                styled_text
+            }
+        };
+
+        for (index, depth) in bracket_depths(line) {
+            let style = match depth {
+                Some(depth) if !self.palette.is_empty() => self.palette[depth % self.palette.len()],
+                Some(_) => continue,
+                None => self.error_style,
+            };
+            styled_text.style_range(index, index + 1, style);
+        }
+
+        styled_text
+    }
+}
+
+impl RainbowBrackets {
+    /// Create a highlighter coloring the bracket pairs with the styles of `palette`
+    pub fn new(palette: Vec<Style>) -> Self {
+        Self {
+            palette,
+            error_style: Style::new().fg(Color::White).on(Color::Red),
+            highlighter: None,
+        }
+    }
+
+    /// Set style for the brackets without a partner
+    #[must_use]
+    pub fn with_error_style(mut self, error_style: Style) -> Self {
+        self.error_style = error_style;
+        self
+    }
+
+    /// Set the highlighter styling the text below the brackets
+    #[must_use]
+    pub fn with_highlighter(mut self, highlighter: Box<dyn Highlighter>) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+}
+
+// Byte offsets of the brackets outside of quoted strings with the nesting depth of
+// their pair, `None` for the unmatched ones
+fn bracket_depths(line: &str) -> Vec<(usize, Option<usize>)> {
+    let mut brackets = Vec::new();
+    // Position in `brackets` and kind of the open brackets
+    let mut open_brackets: Vec<(usize, char)> = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, c) in line.char_indices() {
+        if let Some(quote_char) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && quote_char == '"' {
+                escaped = true;
+            } else if c == quote_char {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '(' | '[' | '{' => {
+                open_brackets.push((brackets.len(), c));
+                brackets.push((index, None));
+            }
+            ')' | ']' | '}' => {
+                let open = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if let Some(&(position, _)) =
+                    open_brackets.last().filter(|(_, bracket)| *bracket == open)
+                {
+                    open_brackets.pop();
+                    let depth = Some(open_brackets.len());
+                    brackets[position].1 = depth;
+                    brackets.push((index, depth));
+                } else {
+                    brackets.push((index, None));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    brackets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleMatchHighlighter;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn palette() -> Vec<Style> {
+        vec![Style::new().fg(Color::Yellow), Style::new().fg(Color::Blue)]
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("a(b)", vec![(1, Some(0)), (3, Some(0))])]
+    #[case("[{}]", vec![(0, Some(0)), (1, Some(1)), (2, Some(1)), (3, Some(0))])]
+    #[case("(]", vec![(0, None), (1, None)])]
+    #[case("(()", vec![(0, None), (1, Some(1)), (2, Some(1))])]
+    #[case(r#"("(" ')')"#, vec![(0, Some(0)), (8, Some(0))])]
+    #[case(r#""\"(" )"#, vec![(6, None)])]
+    fn bracket_depths_works(#[case] line: &str, #[case] expected: Vec<(usize, Option<usize>)>) {
+        assert_eq!(bracket_depths(line), expected);
+    }
+
+    #[test]
+    fn balanced_brackets_cycle_through_the_palette() {
+        let styled = RainbowBrackets::new(palette()).highlight("((a))", 0);
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (Style::new().fg(Color::Yellow), "(".to_string()),
+                (Style::new().fg(Color::Blue), "(".to_string()),
+                (Style::default(), "a".to_string()),
+                (Style::new().fg(Color::Blue), ")".to_string()),
+                (Style::new().fg(Color::Yellow), ")".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unbalanced_brackets_get_the_error_style() {
+        let error_style = Style::new().fg(Color::Red);
+        let styled = RainbowBrackets::new(palette())
+            .with_error_style(error_style)
+            .highlight("a)", 0);
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (Style::default(), "a".to_string()),
+                (error_style, ")".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_brackets_keep_the_style_of_the_highlighter() {
+        let highlighter = SimpleMatchHighlighter::new("echo".to_string());
+        let styled = RainbowBrackets::new(palette())
+            .with_highlighter(Box::new(highlighter))
+            .highlight("echo '('", 0);
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (Style::new().fg(Color::Green), "echo".to_string()),
+                (Style::default(), " '('".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::highlighter::Highlighter;
+use crate::StyledText;
+use nu_ansi_term::Style;
+use regex::Regex;
+use std::ops::Range;
+
+/// Highlight the text matched by an ordered list of regular expressions
+///
+/// Every rule styles its matches, text that is already matched by an earlier rule keeps
+/// the style of that rule. Text that no rule matches gets the default style.
+pub struct RuleHighlighter {
+    rules: Vec<(Regex, Style)>,
+    default_style: Style,
+}
+
+impl Default for RuleHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Highlighter for RuleHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let mut spans: Vec<(Range<usize>, Style)> = vec![];
+        for (regex, style) in &self.rules {
+            for mat in regex.find_iter(line) {
+                let range = mat.range();
+                let overlaps = spans
+                    .iter()
+                    .any(|(span, _)| span.start < range.end && range.start < span.end);
+                if !range.is_empty() && !overlaps {
+                    spans.push((range, *style));
+                }
+            }
+        }
+        spans.sort_by_key(|(span, _)| span.start);
+
+        let mut styled_text = StyledText::new();
+        let mut next_idx = 0;
+        for (span, style) in spans {
+            if span.start != next_idx {
+                styled_text.push((self.default_style, line[next_idx..span.start].to_owned()));
+            }
+            next_idx = span.end;
+            styled_text.push((style, line[span].to_owned()));
+        }
+        if next_idx != line.len() {
+            styled_text.push((self.default_style, line[next_idx..].to_owned()));
+        }
+        styled_text
+    }
+}
+
+impl RuleHighlighter {
+    /// Create a highlighter without rules, that uses the default style for everything
+    pub fn new() -> Self {
+        Self {
+            rules: vec![],
+            default_style: Style::default(),
+        }
+    }
+
+    /// Add a rule styling the matches of `regex`, after the existing rules
+    #[must_use]
+    pub fn with_rule(mut self, regex: Regex, style: Style) -> Self {
+        self.rules.push((regex, style));
+        self
+    }
+
+    /// Set style for the text that no rule matches
+    #[must_use]
+    pub fn with_default_style(mut self, default_style: Style) -> Self {
+        self.default_style = default_style;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    fn highlighter() -> RuleHighlighter {
+        RuleHighlighter::new()
+            .with_rule(
+                Regex::new(r#""[^"]*""#).unwrap(),
+                Style::new().fg(Color::Green),
+            )
+            .with_rule(
+                Regex::new(r"\b(if|else)\b").unwrap(),
+                Style::new().fg(Color::Purple),
+            )
+            .with_rule(Regex::new(r"\d+").unwrap(), Style::new().fg(Color::Yellow))
+    }
+
+    #[test]
+    fn keywords_and_strings_are_styled() {
+        let styled = highlighter().highlight(r#"if x "a b" else 12"#, 0);
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (Style::new().fg(Color::Purple), "if".to_string()),
+                (Style::default(), " x ".to_string()),
+                (Style::new().fg(Color::Green), r#""a b""#.to_string()),
+                (Style::default(), " ".to_string()),
+                (Style::new().fg(Color::Purple), "else".to_string()),
+                (Style::default(), " ".to_string()),
+                (Style::new().fg(Color::Yellow), "12".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn earlier_rules_win_overlapping_matches() {
+        let styled = highlighter().highlight(r#"echo "if 1""#, 0);
+
+        assert_eq!(
+            styled.buffer,
+            vec![
+                (Style::default(), "echo ".to_string()),
+                (Style::new().fg(Color::Green), r#""if 1""#.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_text_gets_the_default_style() {
+        let style = Style::new().fg(Color::White);
+        let styled = RuleHighlighter::new()
+            .with_default_style(style)
+            .highlight("ls -la", 0);
+
+        assert_eq!(styled.buffer, vec![(style, "ls -la".to_string())]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{Hinter, History, StyledText};
+use nu_ansi_term::Style;
+use std::collections::HashMap;
+
+/// A hinter that asks several hinters in turn and shows the first hint found
+///
+/// The hinters are asked in the order they are given. Hints of a source with a style set
+/// by [`ChainedHinter::with_source_style`] are painted in that style, the others keep
+/// the style of their hinter.
+pub struct ChainedHinter {
+    hinters: Vec<Box<dyn Hinter>>,
+    source_styles: HashMap<String, Style>,
+    active_hinter: Option<usize>,
+}
+
+impl Hinter for ChainedHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: usize,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+    ) -> String {
+        self.active_hinter = None;
+
+        for (index, hinter) in self.hinters.iter_mut().enumerate() {
+            let hint = hinter.handle(line, pos, history, use_ansi_coloring);
+            let current_hint = hinter.complete_hint();
+            if current_hint.is_empty() {
+                continue;
+            }
+
+            self.active_hinter = Some(index);
+            let source_style = hinter
+                .hint_source()
+                .and_then(|source| self.source_styles.get(source));
+            return match source_style {
+                Some(style) if use_ansi_coloring => style.paint(current_hint).to_string(),
+                _ => hint,
+            };
+        }
+
+        String::new()
+    }
+
+    fn complete_hint(&self) -> String {
+        self.active()
+            .map_or_else(String::new, |hinter| hinter.complete_hint())
+    }
+
+    fn next_hint_token(&self) -> String {
+        self.active()
+            .map_or_else(String::new, |hinter| hinter.next_hint_token())
+    }
+
+    fn hint_source(&self) -> Option<&str> {
+        self.active().and_then(|hinter| hinter.hint_source())
+    }
+
+    fn styled_hint(&self) -> Option<StyledText> {
+        let hinter = self.active()?;
+        // The style of the source replaces the one of the hinter
+        match hinter.hint_source() {
+            Some(source) if self.source_styles.contains_key(source) => None,
+            _ => hinter.styled_hint(),
+        }
+    }
+}
+
+impl ChainedHinter {
+    /// Create a hinter asking the `hinters` from the first to the last
+    pub fn new(hinters: Vec<Box<dyn Hinter>>) -> Self {
+        Self {
+            hinters,
+            source_styles: HashMap::new(),
+            active_hinter: None,
+        }
+    }
+
+    /// A builder that sets the style of the hints reported with the
+    /// [`Hinter::hint_source`] `source`
+    #[must_use]
+    pub fn with_source_style(mut self, source: impl Into<String>, style: Style) -> Self {
+        self.source_styles.insert(source.into(), style);
+        self
+    }
+
+    fn active(&self) -> Option<&dyn Hinter> {
+        self.active_hinter
+            .and_then(|index| self.hinters.get(index))
+            .map(|hinter| hinter.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultHinter, FileBackedHistory, HistoryItem};
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    // Always hints the same text
+    struct StaticHinter(&'static str);
+
+    impl Hinter for StaticHinter {
+        fn handle(&mut self, _: &str, _: usize, _: &dyn History, _: bool) -> String {
+            self.0.to_string()
+        }
+
+        fn complete_hint(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn next_hint_token(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn hint_source(&self) -> Option<&str> {
+            Some("static")
+        }
+    }
+
+    #[test]
+    fn falls_through_to_the_next_hinter() {
+        let history = FileBackedHistory::default();
+        let mut hinter = ChainedHinter::new(vec![
+            Box::new(DefaultHinter::default()),
+            Box::new(StaticHinter(" --help")),
+        ]);
+
+        assert_eq!(hinter.handle("ls", 2, &history, false), " --help");
+        assert_eq!(hinter.complete_hint(), " --help");
+        assert_eq!(hinter.hint_source(), Some("static"));
+    }
+
+    #[test]
+    fn first_hint_found_is_used() {
+        let mut history = FileBackedHistory::default();
+        history
+            .save(HistoryItem::from_command_line("ls -la"))
+            .unwrap();
+        let mut hinter = ChainedHinter::new(vec![
+            Box::new(DefaultHinter::default()),
+            Box::new(StaticHinter(" --help")),
+        ]);
+
+        assert_eq!(hinter.handle("ls", 2, &history, false), " -la");
+        assert_eq!(hinter.next_hint_token(), " -");
+        assert_eq!(hinter.hint_source(), Some("history"));
+    }
+
+    #[test]
+    fn hints_are_painted_in_the_style_of_their_source() {
+        let history = FileBackedHistory::default();
+        let style = Style::new().fg(Color::Cyan);
+        let mut hinter = ChainedHinter::new(vec![Box::new(StaticHinter(" --help"))])
+            .with_source_style("static", style);
+
+        assert_eq!(
+            hinter.handle("ls", 2, &history, true),
+            style.paint(" --help").to_string()
+        );
+        assert_eq!(hinter.handle("ls", 2, &history, false), " --help");
+    }
+}
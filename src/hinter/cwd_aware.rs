@@ -80,6 +80,10 @@ impl Hinter for CwdAwareHinter {
     fn next_hint_token(&self) -> String {
         get_first_token(&self.current_hint)
     }
+
+    fn hint_source(&self) -> Option<&str> {
+        Some("history")
+    }
 }
 
 impl Default for CwdAwareHinter {
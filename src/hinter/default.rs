@@ -1,11 +1,19 @@
-use crate::{hinter::get_first_token, history::SearchQuery, Hinter, History};
+use crate::{
+    hinter::get_first_token,
+    history::{CommandLineSearch, SearchFilter, SearchQuery},
+    Hinter, History, HistoryItem, StyledText,
+};
 use nu_ansi_term::{Color, Style};
+use std::path::PathBuf;
 
 /// A hinter that uses the completions or the history to show a hint to the user
 pub struct DefaultHinter {
     style: Style,
+    match_style: Option<Style>,
+    current_line: String,
     current_hint: String,
     min_chars: usize,
+    cwd: Option<String>,
 }
 
 impl Hinter for DefaultHinter {
@@ -16,14 +24,19 @@ impl Hinter for DefaultHinter {
         history: &dyn History,
         use_ansi_coloring: bool,
     ) -> String {
+        self.current_line = line.to_string();
         self.current_hint = if line.chars().count() >= self.min_chars {
-            history
-                .search(SearchQuery::last_with_prefix(
-                    line.to_string(),
-                    history.session(),
-                ))
-                .expect("todo: error handling")
-                .first()
+            self.cwd_entry(line, history)
+                .or_else(|| {
+                    history
+                        .search(SearchQuery::last_with_prefix(
+                            line.to_string(),
+                            history.session(),
+                        ))
+                        .expect("todo: error handling")
+                        .into_iter()
+                        .next()
+                })
                 .map_or_else(String::new, |entry| {
                     entry
                         .command_line
@@ -49,14 +62,35 @@ impl Hinter for DefaultHinter {
     fn next_hint_token(&self) -> String {
         get_first_token(&self.current_hint)
     }
+
+    fn hint_source(&self) -> Option<&str> {
+        Some("history")
+    }
+
+    fn styled_hint(&self) -> Option<StyledText> {
+        let match_style = self.match_style?;
+        if self.current_hint.is_empty() {
+            return None;
+        }
+
+        Some(StyledText {
+            buffer: vec![
+                (match_style, self.current_line.clone()),
+                (self.style, self.current_hint.clone()),
+            ],
+        })
+    }
 }
 
 impl Default for DefaultHinter {
     fn default() -> Self {
         DefaultHinter {
             style: Style::new().fg(Color::LightGray),
+            match_style: None,
+            current_line: String::new(),
             current_hint: String::new(),
             min_chars: 1,
+            cwd: None,
         }
     }
 }
@@ -69,10 +103,140 @@ impl DefaultHinter {
         self
     }
 
+    /// A builder that sets the style layered over the part of the buffer the hinted
+    /// history entry matched, so the hint reads as the entry with the match pointed out
+    ///
+    /// Disabled by default, e.g. `Style::new().bold()` makes the match bold.
+    #[must_use]
+    pub fn with_match_style(mut self, match_style: Style) -> Self {
+        self.match_style = Some(match_style);
+        self
+    }
+
     /// A builder that sets the number of characters that have to be present to enable history hints
     #[must_use]
     pub fn with_min_chars(mut self, min_chars: usize) -> Self {
         self.min_chars = min_chars;
         self
     }
+
+    /// A builder that prefers the commands run in the directory `cwd`, the whole
+    /// history is used when none of them matches.
+    ///
+    /// The history has to record the directory of its entries, e.g. the
+    /// `FileBackedHistory::with_extended_format` of the `extended_history` feature
+    #[must_use]
+    pub fn with_cwd_filter(mut self, cwd: PathBuf) -> Self {
+        self.cwd = Some(cwd.to_string_lossy().to_string());
+        self
+    }
+
+    // The most recent entry starting with the line that was run in the directory.
+    // A history that doesn't filter by directory gives no entry
+    fn cwd_entry(&self, line: &str, history: &dyn History) -> Option<HistoryItem> {
+        let filter = SearchFilter::from_text_search_cwd(
+            self.cwd.clone()?,
+            CommandLineSearch::Prefix(line.to_string()),
+            history.session(),
+        );
+
+        history
+            .search(SearchQuery::last_with_search(filter))
+            .ok()?
+            .into_iter()
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileBackedHistory, HistoryItem, LineBuffer};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hint_words_are_accepted_one_at_a_time() {
+        let mut history = FileBackedHistory::default();
+        history
+            .save(HistoryItem::from_command_line("cargo test --all"))
+            .unwrap();
+        let mut hinter = DefaultHinter::default();
+
+        let mut line = "car".to_string();
+        let mut accepted = vec![];
+        loop {
+            let hint = hinter.handle(&line, line.len(), &history, false);
+            let word = hinter.next_hint_token();
+            if word.is_empty() {
+                break;
+            }
+
+            // The accepted part ends where the editor's next word movement does
+            let mut buffer = LineBuffer::from(format!("{line}{hint}").as_str());
+            buffer.set_insertion_point(line.len());
+            assert_eq!(buffer.word_right_index(), line.len() + word.len());
+
+            line.push_str(&word);
+            accepted.push(word);
+        }
+
+        assert_eq!(accepted, vec!["go", " test", " -", "-", "all"]);
+        assert_eq!(line, "cargo test --all");
+    }
+
+    #[test]
+    fn matched_prefix_is_styled_apart_from_the_hint() {
+        let mut history = FileBackedHistory::default();
+        history
+            .save(HistoryItem::from_command_line("cargo test --all"))
+            .unwrap();
+        let match_style = Style::new().bold();
+        let hint_style = Style::new().dimmed();
+        let mut hinter = DefaultHinter::default()
+            .with_style(hint_style)
+            .with_match_style(match_style);
+
+        hinter.handle("cargo", 5, &history, true);
+        assert_eq!(
+            hinter.styled_hint().unwrap().buffer,
+            vec![
+                (match_style, "cargo".to_string()),
+                (hint_style, " test --all".to_string())
+            ]
+        );
+
+        // Nothing to point out without a hint
+        hinter.handle("make", 4, &history, true);
+        assert_eq!(hinter.styled_hint(), None);
+        assert_eq!(DefaultHinter::default().styled_hint(), None);
+    }
+
+    #[cfg(feature = "extended_history")]
+    #[test]
+    fn cwd_filter_prefers_the_commands_run_in_the_directory() {
+        let mut history = FileBackedHistory::default().with_extended_format(true);
+        for (command_line, cwd) in [("make test", "/project"), ("make clean", "/other")] {
+            let mut item = HistoryItem::from_command_line(command_line);
+            item.cwd = Some(cwd.to_string());
+            history.save(item).unwrap();
+        }
+
+        let mut hinter = DefaultHinter::default().with_cwd_filter(PathBuf::from("/project"));
+        assert_eq!(hinter.handle("make", 4, &history, false), " test");
+
+        // Without a command run in the directory the most recent one is hinted
+        let mut hinter = DefaultHinter::default().with_cwd_filter(PathBuf::from("/tmp"));
+        assert_eq!(hinter.handle("make", 4, &history, false), " clean");
+    }
+
+    #[test]
+    fn cwd_filter_falls_back_without_directories_in_the_history() {
+        let mut history = FileBackedHistory::default();
+        history
+            .save(HistoryItem::from_command_line("make test"))
+            .unwrap();
+
+        let mut hinter = DefaultHinter::default().with_cwd_filter(PathBuf::from("/project"));
+        assert_eq!(hinter.handle("make", 4, &history, false), " test");
+    }
 }
@@ -1,5 +1,7 @@
+mod chained;
 mod cwd_aware;
 mod default;
+pub use chained::ChainedHinter;
 pub use cwd_aware::CwdAwareHinter;
 pub use default::DefaultHinter;
 
@@ -27,7 +29,7 @@ pub fn get_first_token(string: &str) -> String {
     result
 }
 
-use crate::History;
+use crate::{History, StyledText};
 /// A trait that's responsible for returning the hint for the current line and position
 /// Hints are often shown in-line as part of the buffer, showing the user text they can accept or ignore
 pub trait Hinter: Send {
@@ -47,5 +49,25 @@ pub trait Hinter: Send {
 
     /// Return the first semantic token of the hint
     /// for incremental completion
+    ///
+    /// The token should end where [`EditCommand::MoveWordRight`](crate::EditCommand::MoveWordRight)
+    /// would move the cursor over the hint
     fn next_hint_token(&self) -> String;
+
+    /// Return an identifier of where the current hint comes from, e.g. `"history"`
+    ///
+    /// Used by the [`ChainedHinter`] to style the hints of each source
+    fn hint_source(&self) -> Option<&str> {
+        None
+    }
+
+    /// Return the line and the current hint as styled text, e.g. to point out the part
+    /// of the line the hinted entry matched
+    ///
+    /// The styles of the text up to the length of the line are layered over the
+    /// highlighting of the buffer, the rest is painted as the hint instead of the string
+    /// returned by [`Hinter::handle`]. Only used when the output is colored.
+    fn styled_hint(&self) -> Option<StyledText> {
+        None
+    }
 }
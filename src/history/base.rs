@@ -1,5 +1,9 @@
 use super::HistoryItemId;
-use crate::{core_editor::LineBuffer, HistoryItem, HistorySessionId, Result};
+use crate::{
+    core_editor::LineBuffer,
+    result::{ReedlineError, ReedlineErrorVariants},
+    HistoryItem, HistorySessionId, Result,
+};
 use chrono::Utc;
 
 /// Browsing modes for a [`History`]
@@ -26,6 +30,15 @@ pub enum CommandLineSearch {
     ///
     /// Useful to gather statistics
     Exact(String),
+    /// Command line matches the regular expression
+    ///
+    /// Only supported by the [`SqliteBackedHistory`](crate::SqliteBackedHistory)
+    Regex(String),
+    /// Command line contains the words of the string, in the same order
+    ///
+    /// Uses the full-text index of the [`SqliteBackedHistory`](crate::SqliteBackedHistory)
+    /// and falls back to a substring search if no index is available
+    FullText(String),
 }
 
 /// Defines how to traverse the history when executing a [`SearchQuery`]
@@ -53,6 +66,15 @@ pub struct SearchFilter {
     pub exit_successful: Option<bool>,
     /// Filter on the session id
     pub session: Option<HistorySessionId>,
+    /// Only entries started at or before this time.
+    ///
+    /// Entries without a start timestamp, e.g. the ones of a plain text history file,
+    /// are excluded when the filter is set
+    pub before: Option<chrono::DateTime<Utc>>,
+    /// Only entries started at or after this time.
+    ///
+    /// Entries without a start timestamp are excluded when the filter is set
+    pub after: Option<chrono::DateTime<Utc>>,
 }
 
 impl SearchFilter {
@@ -88,6 +110,8 @@ impl SearchFilter {
             cwd_prefix: None,
             exit_successful: None,
             session,
+            before: None,
+            after: None,
         }
     }
 }
@@ -125,6 +149,20 @@ impl SearchQuery {
         }
     }
 
+    /// All the entries that are exactly the command line. [`History::count`] of this
+    /// query is the number of times the command was run
+    pub fn occurrences(command_line: String) -> SearchQuery {
+        SearchQuery {
+            direction: SearchDirection::Backward,
+            start_time: None,
+            end_time: None,
+            start_id: None,
+            end_id: None,
+            limit: None,
+            filter: SearchFilter::from_text_search(CommandLineSearch::Exact(command_line), None),
+        }
+    }
+
     /// Get the most recent entry matching [`SearchFilter`]
     pub const fn last_with_search(filter: SearchFilter) -> SearchQuery {
         SearchQuery {
@@ -218,6 +256,49 @@ pub trait History: Send {
     fn sync(&mut self) -> std::io::Result<()>;
     /// get the history session id
     fn session(&self) -> Option<HistorySessionId>;
+    /// record that the user navigated to the item, histories that order their items
+    /// by use can override it
+    fn mark_used(&mut self, #[allow(unused_variables)] id: HistoryItemId) {}
+}
+
+/// Number of items copied by [`copy_history`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HistoryCopy {
+    /// Items saved by the destination
+    pub copied: usize,
+    /// Items the destination can't represent
+    pub skipped: usize,
+}
+
+/// Copies all the items of the `src` history into the `dst` history, e.g. to migrate
+/// from a [`FileBackedHistory`](crate::FileBackedHistory) to a
+/// [`SqliteBackedHistory`](crate::SqliteBackedHistory).
+///
+/// The items are inserted from the oldest to the most recent one with new ids. Their
+/// timestamp, exit status, duration and other details are kept as far as `dst` is
+/// able to store them.
+///
+/// Returns the number of copied items and of the items that `dst` can't represent, e.g.
+/// duplicates rejected by its [`HistoryDedup`](crate::HistoryDedup) policy, which are
+/// skipped. Any other error of `dst` aborts the copy, the items saved before it are kept.
+pub fn copy_history(src: &dyn History, dst: &mut dyn History) -> Result<HistoryCopy> {
+    let items = src.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+
+    let mut count = HistoryCopy::default();
+    for item in items {
+        match dst.save(HistoryItem { id: None, ..item }) {
+            Ok(saved) if saved.id.is_some() => count.copied += 1,
+            Ok(_) | Err(ReedlineError(ReedlineErrorVariants::HistoryFeatureUnsupported { .. })) => {
+                count.skipped += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    dst.sync()
+        .map_err(|err| ReedlineError(ReedlineErrorVariants::IOError(err)))?;
+
+    Ok(count)
 }
 
 #[cfg(test)]
@@ -363,6 +444,26 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn count_occurrences() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(create_item(1, "/etc/nginx", "ls -l", 0))?;
+
+        assert_eq!(
+            history.count(SearchQuery::occurrences("ls -l".to_string()))?,
+            2
+        );
+        assert_eq!(
+            history.count(SearchQuery::occurrences("ls".to_string()))?,
+            1
+        );
+        assert_eq!(
+            history.count(SearchQuery::occurrences("rm".to_string()))?,
+            0
+        );
+        Ok(())
+    }
+
     #[test]
     fn search_includes_limit() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -379,6 +480,143 @@ mod test {
         Ok(())
     }
 
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_regex() -> Result<()> {
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        history.save(create_item(1, "/", "git status", 0))?; // 1
+        history.save(create_item(1, "/", "gitk", 0))?; // 2
+        history.save(create_item(1, "/", "echo git ", 0))?; // 3
+        history.save(create_item(1, "/", "git commit", 0))?; // 4
+
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Regex("^git ".to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&history, res, vec![1, 4])?;
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_invalid_regex_is_an_error() {
+        let history = crate::SqliteBackedHistory::in_memory().unwrap();
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Regex("(unclosed".to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        });
+
+        assert!(matches!(
+            res,
+            Err(ReedlineError(ReedlineErrorVariants::InvalidSearchRegex(_)))
+        ));
+    }
+
+    #[test]
+    fn copy_history_copies_all_items() -> Result<()> {
+        let src = create_filled_example_history()?;
+        let mut dst = crate::FileBackedHistory::default();
+
+        let count = copy_history(&*src, &mut dst)?;
+
+        assert_eq!(count.copied as i64, src.count_all()?);
+        assert_eq!(count.skipped, 0);
+        let commands = |history: &dyn History| -> Result<Vec<String>> {
+            Ok(history
+                .search(SearchQuery::everything(SearchDirection::Forward, None))?
+                .into_iter()
+                .map(|item| item.command_line)
+                .collect())
+        };
+        assert_eq!(commands(&dst)?, commands(&*src)?);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_history_skips_items_the_destination_rejects() -> Result<()> {
+        let mut src = crate::FileBackedHistory::default().with_dedup(crate::HistoryDedup::None);
+        src.save(create_item(1, "/", "ls", 0))?;
+        src.save(create_item(1, "/", "ls", 0))?;
+        src.save(create_item(1, "/", "cd /", 0))?;
+        let mut dst = crate::FileBackedHistory::default();
+
+        assert_eq!(
+            copy_history(&src, &mut dst)?,
+            HistoryCopy {
+                copied: 2,
+                skipped: 1
+            }
+        );
+        assert_eq!(dst.count_all()?, 2);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn copy_history_keeps_item_details() -> Result<()> {
+        let src = create_filled_example_history()?;
+        let mut dst = crate::SqliteBackedHistory::in_memory()?;
+
+        assert_eq!(copy_history(&*src, &mut dst)?.copied, 12);
+        let item = dst.load(HistoryItemId::new(2))?;
+        assert_eq!(item, src.load(HistoryItemId::new(2))?);
+        assert_eq!(item.exit_status, Some(1));
+        Ok(())
+    }
+
+    #[cfg(any(
+        feature = "sqlite",
+        feature = "sqlite-dynlib",
+        feature = "extended_history"
+    ))]
+    #[test]
+    fn search_time_range() -> Result<()> {
+        use chrono::TimeZone;
+
+        #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+        let mut history = crate::FileBackedHistory::default().with_extended_format(true);
+        let time = |hour| Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).single();
+        for (hour, cmd) in [(10, "ls"), (11, "cd foo"), (12, "cat x.txt")] {
+            history.save(HistoryItem {
+                start_timestamp: time(hour),
+                ..create_item(1, "/", cmd, 0)
+            })?;
+        }
+        history.save(create_item(1, "/", "no timestamp", 0))?;
+
+        let search = |before, after| {
+            history.search(SearchQuery {
+                filter: SearchFilter {
+                    before,
+                    after,
+                    ..SearchFilter::anything(None)
+                },
+                ..SearchQuery::everything(SearchDirection::Forward, None)
+            })
+        };
+        let commands = |items: Vec<HistoryItem>| -> Vec<String> {
+            items.into_iter().map(|item| item.command_line).collect()
+        };
+
+        assert_eq!(
+            commands(search(None, time(11))?),
+            vec!["cd foo", "cat x.txt"]
+        );
+        assert_eq!(commands(search(time(11), None)?), vec!["ls", "cd foo"]);
+        assert_eq!(commands(search(time(11), time(11))?), vec!["cd foo"]);
+        assert_eq!(search(None, None)?.len(), 4);
+        Ok(())
+    }
+
     #[test]
     fn clear_history() -> Result<()> {
         let mut history = create_filled_example_history()?;
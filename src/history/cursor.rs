@@ -4,6 +4,7 @@ use super::base::CommandLineSearch;
 use super::base::SearchDirection;
 use super::base::SearchFilter;
 use super::HistoryItem;
+use super::HistoryItemId;
 use super::SearchQuery;
 use crate::Result;
 
@@ -91,6 +92,11 @@ impl HistoryCursor {
         self.current.as_ref().map(|e| e.command_line.to_string())
     }
 
+    /// Returns the id (if present) of the item at the cursor
+    pub(crate) fn id_at_cursor(&self) -> Option<HistoryItemId> {
+        self.current.as_ref().and_then(|e| e.id)
+    }
+
     /// Poll the current [`HistoryNavigationQuery`] mode
     pub fn get_navigation(&self) -> HistoryNavigationQuery {
         self.query.clone()
@@ -203,6 +209,105 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dedup_none_keeps_duplicates() -> Result<()> {
+        let mut hist = FileBackedHistory::default().with_dedup(HistoryDedup::None);
+        add_text_entries(&mut hist, &["test", "test", "other", "test"]);
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["test", "test", "other", "test"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_ignore_consecutive_skips_repeated_line() -> Result<()> {
+        let mut hist = FileBackedHistory::default().with_dedup(HistoryDedup::IgnoreConsecutive);
+        add_text_entries(&mut hist, &["test", "test", "other", "test"]);
+        assert_eq!(get_all_entry_texts(&hist), vec!["test", "other", "test"]);
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_ignore_all_moves_entry_to_end() -> Result<()> {
+        let mut hist = FileBackedHistory::default().with_dedup(HistoryDedup::IgnoreAll);
+        add_text_entries(&mut hist, &["test", "other", "test", "more"]);
+        assert_eq!(get_all_entry_texts(&hist), vec!["other", "test", "more"]);
+
+        let mut cursor =
+            HistoryCursor::new(HistoryNavigationQuery::Normal(LineBuffer::default()), None);
+        cursor.back(&hist)?;
+        cursor.back(&hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("test".to_string()));
+        cursor.back(&hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("other".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn fifo_eviction_drops_the_oldest_entry() -> Result<()> {
+        let mut hist = FileBackedHistory::new(2);
+        add_text_entries(&mut hist, &["a", "b", "a", "c"]);
+        assert_eq!(get_all_entry_texts(&hist), vec!["a", "c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_unique_eviction_counts_the_distinct_commands() -> Result<()> {
+        let mut hist = FileBackedHistory::new(2).with_eviction(HistoryEviction::KeepUnique);
+        add_text_entries(&mut hist, &["a", "b", "a", "c"]);
+        assert_eq!(get_all_entry_texts(&hist), vec!["a", "a", "c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn least_recently_used_eviction_keeps_the_navigated_entries() -> Result<()> {
+        let mut hist = FileBackedHistory::new(3).with_eviction(HistoryEviction::LeastRecentlyUsed);
+        add_text_entries(&mut hist, &["a", "b", "c"]);
+
+        let mut cursor =
+            HistoryCursor::new(HistoryNavigationQuery::Normal(LineBuffer::default()), None);
+        cursor.back(&hist)?;
+        cursor.back(&hist)?;
+        cursor.back(&hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("a".to_string()));
+        hist.mark_used(cursor.id_at_cursor().unwrap());
+
+        add_text_entries(&mut hist, &["d"]);
+        assert_eq!(get_all_entry_texts(&hist), vec!["a", "c", "d"]);
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_ignore_all_removes_duplicates_from_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(10, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["test", "other", "test"]);
+        }
+
+        {
+            let mut dedup_hist = FileBackedHistory::with_file(10, histfile.clone())
+                .unwrap()
+                .with_dedup(HistoryDedup::IgnoreAll);
+            assert_eq!(get_all_entry_texts(&dedup_hist), vec!["other", "test"]);
+            add_text_entries(&mut dedup_hist, &["other"]);
+        }
+
+        let (reading_hist, _) = create_history_at(10, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["test", "other"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
     #[test]
     fn prefix_search_works() -> Result<()> {
         let (mut hist, _) = create_history();
@@ -428,6 +533,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn persists_entries_looking_like_timestamps() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let entries = vec!["#42", "ls", "\\#7", "#", "#x"];
+
+        {
+            let (mut writing_hist, _) = create_history_at(5, &histfile);
+            add_text_entries(writing_hist.as_mut(), &entries);
+        }
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+
+        let actual: Vec<_> = get_all_entry_texts(reading_hist.as_ref());
+        assert_eq!(entries, actual);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
     #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
     #[test]
     fn truncates_file_to_capacity() -> Result<()> {
@@ -604,4 +732,186 @@ mod tests {
         tmp.close().unwrap();
         Ok(())
     }
+
+    fn create_isolated_history_at(cap: usize, path: &Path) -> FileBackedHistory {
+        FileBackedHistory::with_file(cap, path.to_owned())
+            .unwrap()
+            .with_session_isolation(true)
+    }
+
+    fn save_at(hist: &mut dyn History, command_line: &str, timestamp: i64) {
+        use chrono::TimeZone;
+
+        hist.save(HistoryItem {
+            start_timestamp: chrono::Utc.timestamp_opt(timestamp, 0).single(),
+            ..HistoryItem::from_command_line(command_line)
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn isolated_sessions_merge_by_timestamp() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(10, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["initial"]);
+        }
+
+        let mut hist_a = create_isolated_history_at(10, &histfile);
+        let mut hist_b = create_isolated_history_at(10, &histfile);
+        save_at(&mut hist_a, "a 1", 100);
+        save_at(&mut hist_b, "b 1", 200);
+        save_at(&mut hist_a, "a 2", 300);
+
+        hist_a.sync().unwrap();
+        hist_b.sync().unwrap();
+        // The entries of the other session are not loaded
+        assert_eq!(get_all_entry_texts(&hist_a), vec!["initial", "a 1", "a 2"]);
+        assert_eq!(get_all_entry_texts(&hist_b), vec!["initial", "b 1"]);
+        drop(hist_a);
+        drop(hist_b);
+
+        let (reading_hist, _) = create_history_at(10, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["initial", "a 1", "b 1", "a 2"]
+        );
+        assert!(!tmp.path().join(".history.tmp").exists());
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn isolated_sessions_merge_removes_duplicates() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist_a = create_isolated_history_at(10, &histfile);
+            let mut hist_b = create_isolated_history_at(10, &histfile);
+            save_at(&mut hist_a, "ls", 100);
+            save_at(&mut hist_b, "ls", 200);
+            save_at(&mut hist_b, "cd /", 300);
+        }
+
+        let reading_hist = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+        let items = reading_hist.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+        let entries: Vec<_> = items
+            .iter()
+            .map(|item| {
+                (
+                    item.command_line.as_str(),
+                    item.start_timestamp.map(|timestamp| timestamp.timestamp()),
+                )
+            })
+            .collect();
+        assert_eq!(entries, vec![("ls", Some(100)), ("cd /", Some(300))]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[cfg(feature = "extended_history")]
+    #[test]
+    fn extended_format_keeps_item_details() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = FileBackedHistory::with_file(10, histfile.clone())
+                .unwrap()
+                .with_extended_format(true);
+            let failed = hist.save(HistoryItem {
+                cwd: Some("/home/me".to_string()),
+                ..HistoryItem::from_command_line("cd foo")
+            })?;
+            hist.update(failed.id.unwrap(), &|item| HistoryItem {
+                exit_status: Some(1),
+                duration: Some(std::time::Duration::from_millis(20)),
+                ..item
+            })?;
+            hist.save(HistoryItem {
+                exit_status: Some(0),
+                ..HistoryItem::from_command_line("ls")
+            })?;
+        }
+
+        // The format is detected without enabling it
+        let reading_hist = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+        let item = reading_hist.load(HistoryItemId::new(0))?;
+        assert_eq!(item.command_line, "cd foo");
+        assert_eq!(item.cwd, Some("/home/me".to_string()));
+        assert_eq!(item.exit_status, Some(1));
+        assert_eq!(item.duration, Some(std::time::Duration::from_millis(20)));
+
+        let successful = reading_hist.search(SearchQuery {
+            filter: SearchFilter {
+                exit_successful: Some(true),
+                ..SearchFilter::anything(None)
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        let successful: Vec<_> = successful.iter().map(|item| &item.command_line).collect();
+        assert_eq!(successful, vec!["ls"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[cfg(not(feature = "extended_history"))]
+    #[test]
+    fn extended_file_requires_the_feature() {
+        let tmp = tempfile::tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+        std::fs::write(
+            &histfile,
+            "#reedline-history:jsonl\n{\"command_line\":\"ls\"}\n",
+        )
+        .unwrap();
+
+        assert!(FileBackedHistory::with_file(10, histfile).is_err());
+    }
+
+    #[cfg(feature = "extended_history")]
+    #[test]
+    fn plain_file_is_converted_to_extended_format() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(10, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["plain"]);
+        }
+
+        {
+            let mut hist = FileBackedHistory::with_file(10, histfile.clone())
+                .unwrap()
+                .with_extended_format(true);
+            assert_eq!(get_all_entry_texts(&hist), vec!["plain"]);
+            add_text_entries(&mut hist, &["extended"]);
+        }
+
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert!(contents.starts_with("#reedline-history:jsonl\n"));
+
+        let (reading_hist, _) = create_history_at(10, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["plain", "extended"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
 }
@@ -5,18 +5,59 @@ use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
     HistorySessionId, Result,
 };
+use chrono::{TimeZone, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use std::{
-    collections::VecDeque,
-    fs::OpenOptions,
-    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 /// Default size of the [`FileBackedHistory`] used when calling [`FileBackedHistory::default()`]
 pub const HISTORY_SIZE: usize = 1000;
 pub const NEWLINE_ESCAPE: &str = "<\\n>";
+// First line of the history files in the extended format
+const EXTENDED_FORMAT_HEADER: &str = "#reedline-history:jsonl";
+
+/// Policy of the [`FileBackedHistory`] for lines that are already part of the history.
+///
+/// Mirrors the `ignoredups` and `erasedups` options of bash's `HISTCONTROL`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDedup {
+    /// Every line is added, even if it is identical to the previous one
+    None,
+    /// A line identical to the previous entry is not added again
+    #[default]
+    IgnoreConsecutive,
+    /// An existing entry identical to the line is moved to the end of the history
+    /// instead of adding a duplicate
+    IgnoreAll,
+}
+
+/// Which entries the [`FileBackedHistory`] drops once it holds as many entries as its
+/// capacity
+///
+/// The [`HistoryDedup`] policy applies before the eviction: with
+/// [`HistoryDedup::IgnoreAll`] every command line is only kept once, so
+/// [`HistoryEviction::KeepUnique`] drops the same entries as [`HistoryEviction::Fifo`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEviction {
+    /// The oldest entry is dropped
+    #[default]
+    Fifo,
+    /// The entry whose command line was saved or navigated to the longest time ago is
+    /// dropped. The entries read from the file that were not used yet are dropped first,
+    /// from the oldest one
+    LeastRecentlyUsed,
+    /// The capacity counts the distinct command lines instead of the entries, and all the
+    /// entries of the command line saved the longest time ago are dropped
+    KeepUnique,
+}
 
 /// Stateful history that allows up/down-arrow browsing with an internal cursor.
 ///
@@ -24,13 +65,42 @@ pub const NEWLINE_ESCAPE: &str = "<\\n>";
 /// Similar to bash's behavior without HISTTIMEFORMAT.
 /// (See <https://www.gnu.org/software/bash/manual/html_node/Bash-History-Facilities.html>)
 /// If the history is associated to a file all new changes within a given history capacity will be written to disk when History is dropped.
+///
+/// Entries saved with [`FileBackedHistory::with_session_isolation()`] are preceded by a
+/// `#<seconds since epoch>` line holding their timestamp, like bash does with HISTTIMEFORMAT.
+/// Entries that read like such a line themselves are written with a leading `\`.
+///
+/// With `FileBackedHistory::with_extended_format()` of the `extended_history` feature the
+/// file starts with a `#reedline-history:jsonl` line followed by one JSON record per entry.
 #[derive(Debug)]
 pub struct FileBackedHistory {
     capacity: usize,
-    entries: VecDeque<String>,
+    entries: VecDeque<Entry>,
     file: Option<PathBuf>,
     len_on_disk: usize, // Keep track what was previously written to disk
     session: Option<HistorySessionId>,
+    dedup: HistoryDedup,
+    session_isolation: bool,
+    extended_format: bool,
+    eviction: HistoryEviction,
+    // Order of the last use of the command lines, for the least recently used eviction
+    last_used: HashMap<String, u64>,
+    uses: u64,
+}
+
+// The details besides the command line are only kept in the extended format, except
+// for the timestamp of the entries saved with session isolation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Entry {
+    command_line: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_timestamp: Option<chrono::DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit_status: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
 }
 
 impl Default for FileBackedHistory {
@@ -42,33 +112,174 @@ impl Default for FileBackedHistory {
     }
 }
 
+// Entries reading like a timestamp line, optionally preceded by backslashes, get one
+// more backslash, so `#42` is not taken for the timestamp of the next entry
 fn encode_entry(s: &str) -> String {
-    s.replace('\n', NEWLINE_ESCAPE)
+    let encoded = s.replace('\n', NEWLINE_ESCAPE);
+    if parse_timestamp(encoded.trim_start_matches('\\')).is_some() {
+        format!("\\{encoded}")
+    } else {
+        encoded
+    }
 }
 
 fn decode_entry(s: &str) -> String {
+    let s = match s.strip_prefix('\\') {
+        Some(unescaped) if parse_timestamp(unescaped.trim_start_matches('\\')).is_some() => {
+            unescaped
+        }
+        _ => s,
+    };
     s.replace(NEWLINE_ESCAPE, "\n")
 }
 
+fn sibling_path(file: &Path, suffix: &str) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+fn parse_timestamp(line: &str) -> Option<i64> {
+    let digits = line.strip_prefix('#')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// Reads the entries of a history file, returning whether it uses the extended format
+fn read_entries(reader: impl Read) -> std::io::Result<(VecDeque<Entry>, bool)> {
+    let mut lines = BufReader::new(reader).lines().peekable();
+    let extended_format = matches!(lines.peek(), Some(Ok(line)) if line == EXTENDED_FORMAT_HEADER);
+
+    let mut entries = VecDeque::new();
+    if extended_format {
+        #[cfg(feature = "extended_history")]
+        for line in lines.skip(1) {
+            entries.push_back(serde_json::from_str(&line?)?);
+        }
+        #[cfg(not(feature = "extended_history"))]
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "reading the extended history format requires the `extended_history` feature",
+        ));
+    } else {
+        let mut timestamp = None;
+        for line in lines {
+            let line = line?;
+            if let Some(line_timestamp) = parse_timestamp(&line) {
+                timestamp = Utc.timestamp_opt(line_timestamp, 0).single();
+            } else {
+                entries.push_back(Entry {
+                    command_line: decode_entry(&line),
+                    start_timestamp: timestamp.take(),
+                    ..Entry::default()
+                });
+            }
+        }
+    }
+    Ok((entries, extended_format))
+}
+
+fn write_header(writer: &mut impl Write, extended_format: bool) -> std::io::Result<()> {
+    if extended_format {
+        writeln!(writer, "{EXTENDED_FORMAT_HEADER}")?;
+    }
+    Ok(())
+}
+
+fn write_entry(
+    writer: &mut impl Write,
+    entry: &Entry,
+    extended_format: bool,
+) -> std::io::Result<()> {
+    if extended_format {
+        // Without the feature no file is read or written in the extended format
+        #[cfg(feature = "extended_history")]
+        serde_json::to_writer(&mut *writer, entry)?;
+    } else {
+        if let Some(timestamp) = entry.start_timestamp {
+            writeln!(writer, "#{}", timestamp.timestamp())?;
+        }
+        writer.write_all(encode_entry(&entry.command_line).as_bytes())?;
+    }
+    writer.write_all("\n".as_bytes())
+}
+
+// Removes the entries that are part of `newer` as well as the repeated entries,
+// keeping their last occurrence
+fn remove_duplicates<'a>(
+    entries: VecDeque<Entry>,
+    newer: impl Iterator<Item = &'a Entry>,
+) -> VecDeque<Entry> {
+    let mut seen: HashSet<String> = newer.map(|entry| entry.command_line.clone()).collect();
+    let mut entries: VecDeque<Entry> = entries
+        .into_iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.command_line.clone()))
+        .collect();
+    entries.make_contiguous().reverse();
+    entries
+}
+
+// Merges the entries of this session into the entries of the file by their timestamp.
+// Entries of the file without a timestamp are considered older than all own entries
+fn merge_entries(file_entries: VecDeque<Entry>, own_entries: Vec<Entry>) -> VecDeque<Entry> {
+    let mut merged = VecDeque::with_capacity(file_entries.len() + own_entries.len());
+    let mut file_entries = file_entries.into_iter().peekable();
+    for own_entry in own_entries {
+        while let Some(file_entry) = file_entries
+            .next_if(|file_entry| file_entry.start_timestamp <= own_entry.start_timestamp)
+        {
+            merged.push_back(file_entry);
+        }
+        merged.push_back(own_entry);
+    }
+    merged.extend(file_entries);
+    merged
+}
+
 impl History for FileBackedHistory {
-    /// only saves a value if it's different than the last value
+    /// only saves a value if it's not a duplicate according to the [`HistoryDedup`] policy
     fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
-        let entry = h.command_line;
-        // Don't append if the preceding value is identical or the string empty
-        let entry_id = if self
-            .entries
-            .back()
-            .map_or(true, |previous| previous != &entry)
-            && !entry.is_empty()
-            && self.capacity > 0
-        {
-            if self.entries.len() == self.capacity {
-                // History is "full", so we delete the oldest entry first,
-                // before adding a new one.
-                self.entries.pop_front();
-                self.len_on_disk = self.len_on_disk.saturating_sub(1);
+        let entry = self.new_entry(h);
+        let is_duplicate = match self.dedup {
+            HistoryDedup::None => false,
+            HistoryDedup::IgnoreConsecutive => self.entries.back().map_or(false, |previous| {
+                previous.command_line == entry.command_line
+            }),
+            HistoryDedup::IgnoreAll => {
+                // The existing entry is removed, so that it is added again at the end
+                if let Some(idx) = self
+                    .entries
+                    .iter()
+                    .position(|existing| existing.command_line == entry.command_line)
+                {
+                    self.entries.remove(idx);
+                    if idx < self.len_on_disk {
+                        self.len_on_disk -= 1;
+                    }
+                }
+                false
+            }
+        };
+        // Don't append if the value is a duplicate or the string empty
+        let entry_id = if !is_duplicate && !entry.command_line.is_empty() && self.capacity > 0 {
+            self.mark_command_used(&entry.command_line);
+            self.entries.push_back(entry.clone());
+            // If the history is "full" the entries chosen by the eviction are
+            // deleted, the new one is kept
+            let mut entries = std::mem::take(&mut self.entries);
+            let evictable = entries.len() - 1;
+            let evicted = self.evict(&mut entries, evictable);
+            self.len_on_disk -= evicted
+                .iter()
+                .filter(|&&index| index < self.len_on_disk)
+                .count();
+            self.entries = entries;
+            if !evicted.is_empty() {
+                self.prune_last_used();
             }
-            self.entries.push_back(entry.to_string());
             Some(HistoryItemId::new((self.entries.len() - 1) as i64))
         } else {
             None
@@ -104,9 +315,10 @@ impl History for FileBackedHistory {
         }
 
         if query.filter.hostname.is_some()
-            || query.filter.cwd_exact.is_some()
-            || query.filter.cwd_prefix.is_some()
-            || query.filter.exit_successful.is_some()
+            || (!self.extended_format
+                && (query.filter.cwd_exact.is_some()
+                    || query.filter.cwd_prefix.is_some()
+                    || query.filter.exit_successful.is_some()))
         {
             return Err(ReedlineError(
                 ReedlineErrorVariants::HistoryFeatureUnsupported {
@@ -115,6 +327,15 @@ impl History for FileBackedHistory {
                 },
             ));
         }
+        if let Some(CommandLineSearch::Regex(_)) = query.filter.command_line {
+            return Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported {
+                    history: "FileBackedHistory",
+                    feature: "filtering by regular expression",
+                },
+            ));
+        }
+
         let (min_id, max_id) = {
             let start = query.start_id.map(|e| e.0);
             let end = query.end_id.map(|e| e.0);
@@ -139,12 +360,15 @@ impl History for FileBackedHistory {
         } else {
             intrinsic_limit as usize
         };
-        let filter = |(idx, cmd): (usize, &String)| {
+        let filter = |(idx, entry): (usize, &Entry)| {
+            let cmd = &entry.command_line;
             if !match &query.filter.command_line {
                 Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
-                Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
+                Some(CommandLineSearch::Substring(p) | CommandLineSearch::FullText(p)) => {
+                    cmd.contains(p)
+                }
                 Some(CommandLineSearch::Exact(p)) => cmd == p,
-                None => true,
+                Some(CommandLineSearch::Regex(_)) | None => true,
             } {
                 return None;
             }
@@ -153,9 +377,38 @@ impl History for FileBackedHistory {
                     return None;
                 }
             }
+            if let Some(cwd_exact) = &query.filter.cwd_exact {
+                if entry.cwd.as_ref() != Some(cwd_exact) {
+                    return None;
+                }
+            }
+            if let Some(cwd_prefix) = &query.filter.cwd_prefix {
+                if !entry
+                    .cwd
+                    .as_ref()
+                    .map_or(false, |cwd| cwd.starts_with(cwd_prefix))
+                {
+                    return None;
+                }
+            }
+            if let Some(before) = query.filter.before {
+                if !entry.start_timestamp.map_or(false, |start| start <= before) {
+                    return None;
+                }
+            }
+            if let Some(after) = query.filter.after {
+                if !entry.start_timestamp.map_or(false, |start| start >= after) {
+                    return None;
+                }
+            }
+            if let Some(exit_successful) = query.filter.exit_successful {
+                if entry.exit_status.map(|status| status == 0) != Some(exit_successful) {
+                    return None;
+                }
+            }
             Some(FileBackedHistory::construct_entry(
                 Some(HistoryItemId::new(idx as i64)),
-                cmd.to_string(), // todo: this copy might be a perf bottleneck
+                entry.clone(), // todo: this copy might be a perf bottleneck
             ))
         };
 
@@ -172,22 +425,41 @@ impl History for FileBackedHistory {
         }
     }
 
+    /// Only supported with the extended format and for entries that were not
+    /// written to disk yet
     fn update(
         &mut self,
-        _id: super::HistoryItemId,
-        _updater: &dyn Fn(super::HistoryItem) -> super::HistoryItem,
+        id: super::HistoryItemId,
+        updater: &dyn Fn(super::HistoryItem) -> super::HistoryItem,
     ) -> Result<()> {
-        Err(ReedlineError(
-            ReedlineErrorVariants::HistoryFeatureUnsupported {
-                history: "FileBackedHistory",
-                feature: "updating entries",
-            },
-        ))
+        let idx = id.0 as usize;
+        if !self.extended_format || idx < self.len_on_disk {
+            return Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported {
+                    history: "FileBackedHistory",
+                    feature: "updating entries",
+                },
+            ));
+        }
+
+        let entry = self.entries.get_mut(idx).ok_or(ReedlineError(
+            ReedlineErrorVariants::OtherHistoryError("Item does not exist"),
+        ))?;
+        let item = updater(FileBackedHistory::construct_entry(Some(id), entry.clone()));
+        *entry = Entry {
+            command_line: item.command_line,
+            start_timestamp: item.start_timestamp,
+            duration: item.duration,
+            exit_status: item.exit_status,
+            cwd: item.cwd,
+        };
+        Ok(())
     }
 
     fn clear(&mut self) -> Result<()> {
         self.entries.clear();
         self.len_on_disk = 0;
+        self.last_used.clear();
 
         if let Some(file) = &self.file {
             if let Err(err) = std::fs::remove_file(file) {
@@ -207,11 +479,28 @@ impl History for FileBackedHistory {
         ))
     }
 
+    fn mark_used(&mut self, id: HistoryItemId) {
+        let command_line = usize::try_from(id.0)
+            .ok()
+            .and_then(|index| self.entries.get(index))
+            .map(|entry| entry.command_line.clone());
+        if let Some(command_line) = command_line {
+            self.mark_command_used(&command_line);
+        }
+    }
+
     /// Writes unwritten history contents to disk.
     ///
     /// If file would exceed `capacity` truncates the oldest entries.
+    /// With session isolation the entries are merged into the file instead, see
+    /// [`FileBackedHistory::with_session_isolation()`].
     fn sync(&mut self) -> std::io::Result<()> {
         if let Some(fname) = &self.file {
+            if self.session_isolation {
+                let fname = fname.clone();
+                return self.merge_into_file(&fname);
+            }
+
             // The unwritten entries
             let own_entries = self.entries.range(self.len_on_disk..);
 
@@ -228,38 +517,40 @@ impl History for FileBackedHistory {
             );
             let mut writer_guard = f_lock.write()?;
             let (mut foreign_entries, truncate) = {
-                let reader = BufReader::new(writer_guard.deref());
-                let mut from_file = reader
-                    .lines()
-                    .map(|o| o.map(|i| decode_entry(&i)))
-                    .collect::<std::io::Result<VecDeque<_>>>()?;
-                if from_file.len() + own_entries.len() > self.capacity {
-                    (
-                        from_file.split_off(
-                            from_file.len() - (self.capacity.saturating_sub(own_entries.len())),
-                        ),
-                        true,
-                    )
-                } else {
-                    (from_file, false)
+                let (mut from_file, file_extended_format) = read_entries(writer_guard.deref())?;
+                // The format of an existing extended file is kept, while a plain file is
+                // rewritten when switching to the extended format
+                self.extended_format |= file_extended_format;
+                let mut rewrite = self.extended_format != file_extended_format;
+                // The file was written by other sessions or with another policy, so
+                // the entries that are also part of our own ones are dropped from it
+                if self.dedup == HistoryDedup::IgnoreAll {
+                    let len_in_file = from_file.len();
+                    from_file = remove_duplicates(from_file, own_entries.clone());
+                    rewrite |= from_file.len() != len_in_file;
                 }
+                // Only the entries of the file are evicted, the own ones are appended
+                let len_in_file = from_file.len();
+                from_file.extend(own_entries.clone().cloned());
+                let evicted = self.evict(&mut from_file, len_in_file);
+                from_file.truncate(len_in_file - evicted.len());
+                (from_file, rewrite || !evicted.is_empty())
             };
 
             {
                 let mut writer = BufWriter::new(writer_guard.deref_mut());
                 if truncate {
                     writer.rewind()?;
+                    write_header(&mut writer, self.extended_format)?;
 
-                    for line in &foreign_entries {
-                        writer.write_all(encode_entry(line).as_bytes())?;
-                        writer.write_all("\n".as_bytes())?;
+                    for entry in &foreign_entries {
+                        write_entry(&mut writer, entry, self.extended_format)?;
                     }
                 } else {
                     writer.seek(SeekFrom::End(0))?;
                 }
-                for line in own_entries {
-                    writer.write_all(encode_entry(line).as_bytes())?;
-                    writer.write_all("\n".as_bytes())?;
+                for entry in own_entries {
+                    write_entry(&mut writer, entry, self.extended_format)?;
                 }
                 writer.flush()?;
             }
@@ -272,6 +563,7 @@ impl History for FileBackedHistory {
             let own_entries = self.entries.drain(self.len_on_disk..);
             foreign_entries.extend(own_entries);
             self.entries = foreign_entries;
+            self.prune_last_used();
 
             self.len_on_disk = self.entries.len();
         }
@@ -299,12 +591,75 @@ impl FileBackedHistory {
             file: None,
             len_on_disk: 0,
             session: None,
+            dedup: HistoryDedup::default(),
+            session_isolation: false,
+            extended_format: false,
+            eviction: HistoryEviction::default(),
+            last_used: HashMap::new(),
+            uses: 0,
+        }
+    }
+
+    /// Sets the [`HistoryDedup`] policy applied when saving lines
+    ///
+    /// With [`HistoryDedup::IgnoreAll`] the duplicates already present in the
+    /// history are removed as well, keeping their most recent occurrence
+    #[must_use]
+    pub fn with_dedup(mut self, dedup: HistoryDedup) -> Self {
+        self.dedup = dedup;
+        if dedup == HistoryDedup::IgnoreAll {
+            let own_entries = self.entries.split_off(self.len_on_disk);
+            let own_entries = remove_duplicates(own_entries, std::iter::empty());
+            self.entries = remove_duplicates(std::mem::take(&mut self.entries), own_entries.iter());
+            self.len_on_disk = self.entries.len();
+            self.entries.extend(own_entries);
         }
+        self
+    }
+
+    /// Sets the [`HistoryEviction`] strategy choosing the entries dropped when the
+    /// history is full, see there for the interaction with the [`HistoryDedup`] policy
+    #[must_use]
+    pub fn with_eviction(mut self, eviction: HistoryEviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// Enables the isolation of this session from other sessions using the same file
+    ///
+    /// Other sessions' entries aren't loaded by [`History::sync()`] and only appear in
+    /// this history once it is opened again. Instead the entries of this session are merged
+    /// into the file: the timestamps decide the order, duplicates are removed according
+    /// to the [`HistoryDedup`] policy and the file is replaced atomically by renaming a
+    /// temporary file.
+    ///
+    /// All sessions sharing the file should use session isolation, as the merge is
+    /// synchronized using a separate `.lock` file.
+    #[must_use]
+    pub fn with_session_isolation(mut self, session_isolation: bool) -> Self {
+        self.session_isolation = session_isolation;
+        self
+    }
+
+    /// Enables the extended file format that keeps the start timestamp, duration,
+    /// exit status and working directory of the entries
+    ///
+    /// A plain history file is converted on the next [`History::sync()`]. The format
+    /// is detected when reading the file, so an existing extended file keeps its format
+    /// even if the extended format is not enabled.
+    ///
+    /// Requires the `extended_history` feature.
+    #[cfg(feature = "extended_history")]
+    #[must_use]
+    pub fn with_extended_format(mut self, extended_format: bool) -> Self {
+        self.extended_format = extended_format;
+        self
     }
 
     /// Creates a new history with an associated history file.
     ///
-    /// History file format: commands separated by new lines.
+    /// History file format: commands separated by new lines, or JSON records in the
+    /// extended format.
     /// If file exists file will be read otherwise empty file will be created.
     ///
     ///
@@ -320,17 +675,175 @@ impl FileBackedHistory {
         Ok(hist)
     }
 
-    // this history doesn't store any info except command line
-    fn construct_entry(id: Option<HistoryItemId>, command_line: String) -> HistoryItem {
+    // Merges the unwritten entries into the file without loading the entries of the
+    // other sessions. The merged history is written to a temporary file that replaces
+    // the history file, so that a crash can't leave a partially written history behind
+    fn merge_into_file(&mut self, fname: &Path) -> std::io::Result<()> {
+        if self.len_on_disk == self.entries.len() {
+            return Ok(());
+        }
+        let own_entries: Vec<Entry> = self.entries.range(self.len_on_disk..).cloned().collect();
+
+        if let Some(base_dir) = fname.parent() {
+            std::fs::create_dir_all(base_dir)?;
+        }
+
+        // The history file itself is replaced, so the sessions lock a separate file
+        let mut f_lock = fd_lock::RwLock::new(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(sibling_path(fname, ".lock"))?,
+        );
+        let _lock_guard = f_lock.write()?;
+
+        let file_entries = match File::open(fname) {
+            Ok(file) => {
+                let (file_entries, file_extended_format) = read_entries(file)?;
+                self.extended_format |= file_extended_format;
+                file_entries
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => VecDeque::new(),
+            Err(err) => return Err(err),
+        };
+        let mut merged = merge_entries(file_entries, own_entries);
+        merged = match self.dedup {
+            HistoryDedup::None => merged,
+            HistoryDedup::IgnoreConsecutive => merged
+                .into_iter()
+                .dedup_by(|a, b| a.command_line == b.command_line)
+                .collect(),
+            HistoryDedup::IgnoreAll => remove_duplicates(merged, std::iter::empty()),
+        };
+        let evictable = merged.len();
+        self.evict(&mut merged, evictable);
+
+        let tmp_name = sibling_path(fname, ".tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_name)?);
+        write_header(&mut writer, self.extended_format)?;
+        for entry in &merged {
+            write_entry(&mut writer, entry, self.extended_format)?;
+        }
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(&tmp_name, fname)?;
+
+        self.len_on_disk = self.entries.len();
+        Ok(())
+    }
+
+    fn mark_command_used(&mut self, command_line: &str) {
+        if self.eviction == HistoryEviction::LeastRecentlyUsed {
+            self.uses += 1;
+            self.last_used.insert(command_line.to_string(), self.uses);
+        }
+    }
+
+    // Removes the entries chosen by the eviction strategy among the first `evictable`
+    // ones until the entries fit into the capacity. Returns the indices the removed
+    // entries had before the eviction, in increasing order
+    fn evict(&self, entries: &mut VecDeque<Entry>, evictable: usize) -> Vec<usize> {
+        let excess = entries.len().saturating_sub(self.capacity);
+        let mut evicted: Vec<usize> = match self.eviction {
+            HistoryEviction::Fifo => (0..evictable.min(excess)).collect(),
+            HistoryEviction::LeastRecentlyUsed => {
+                // The oldest entry goes first among the ones last used at the same time
+                let mut candidates: Vec<usize> = (0..evictable).collect();
+                candidates.sort_by_key(|&index| {
+                    self.last_used
+                        .get(&entries[index].command_line)
+                        .copied()
+                        .unwrap_or(0)
+                });
+                candidates.truncate(excess);
+                candidates
+            }
+            HistoryEviction::KeepUnique => {
+                let mut last_index = HashMap::new();
+                for (index, entry) in entries.iter().enumerate() {
+                    last_index.insert(entry.command_line.as_str(), index);
+                }
+                // The command lines whose last entry is the oldest go first
+                let mut candidates: Vec<(&str, usize)> = last_index
+                    .iter()
+                    .map(|(command_line, index)| (*command_line, *index))
+                    .filter(|(_, index)| *index < evictable)
+                    .collect();
+                candidates.sort_by_key(|(_, index)| *index);
+                let excess = last_index.len().saturating_sub(self.capacity);
+                let dropped: HashSet<&str> = candidates
+                    .into_iter()
+                    .take(excess)
+                    .map(|(command_line, _)| command_line)
+                    .collect();
+                (0..evictable)
+                    .filter(|&index| dropped.contains(entries[index].command_line.as_str()))
+                    .collect()
+            }
+        };
+        evicted.sort_unstable();
+
+        let mut index = 0;
+        let mut removed = evicted.iter().peekable();
+        entries.retain(|_| {
+            let keep = removed.next_if_eq(&&index).is_none();
+            index += 1;
+            keep
+        });
+        evicted
+    }
+
+    // Forgets the last use of the command lines that are no longer in the history
+    fn prune_last_used(&mut self) {
+        if self.last_used.is_empty() {
+            return;
+        }
+        let command_lines: HashSet<&str> = self
+            .entries
+            .iter()
+            .map(|entry| entry.command_line.as_str())
+            .collect();
+        self.last_used
+            .retain(|command_line, _| command_lines.contains(command_line.as_str()));
+    }
+
+    // Keeps the details of the item that can be stored in the file
+    fn new_entry(&self, item: HistoryItem) -> Entry {
+        let start_timestamp = if self.extended_format || self.session_isolation {
+            item.start_timestamp
+        } else {
+            None
+        };
+        let start_timestamp = start_timestamp.or_else(|| self.session_isolation.then(Utc::now));
+
+        if self.extended_format {
+            Entry {
+                command_line: item.command_line,
+                start_timestamp,
+                duration: item.duration,
+                exit_status: item.exit_status,
+                cwd: item.cwd,
+            }
+        } else {
+            Entry {
+                command_line: item.command_line,
+                start_timestamp,
+                ..Entry::default()
+            }
+        }
+    }
+
+    // this history doesn't store the session, hostname and extra info
+    fn construct_entry(id: Option<HistoryItemId>, entry: Entry) -> HistoryItem {
         HistoryItem {
             id,
-            start_timestamp: None,
-            command_line,
+            start_timestamp: entry.start_timestamp,
+            command_line: entry.command_line,
             session_id: None,
             hostname: None,
-            cwd: None,
-            duration: None,
-            exit_status: None,
+            cwd: entry.cwd,
+            duration: entry.duration,
+            exit_status: entry.exit_status,
             more_info: None,
         }
     }
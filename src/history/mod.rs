@@ -8,9 +8,10 @@ mod sqlite_backed;
 pub use sqlite_backed::SqliteBackedHistory;
 
 pub use base::{
-    CommandLineSearch, History, HistoryNavigationQuery, SearchDirection, SearchFilter, SearchQuery,
+    copy_history, CommandLineSearch, History, HistoryCopy, HistoryNavigationQuery, SearchDirection,
+    SearchFilter, SearchQuery,
 };
 pub use cursor::HistoryCursor;
 pub use item::{HistoryItem, HistoryItemId, HistorySessionId};
 
-pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
+pub use file_backed::{FileBackedHistory, HistoryDedup, HistoryEviction, HISTORY_SIZE};
@@ -7,7 +7,8 @@ use crate::{
     Result,
 };
 use chrono::{TimeZone, Utc};
-use rusqlite::{named_params, params, Connection, ToSql};
+use regex::Regex;
+use rusqlite::{functions::FunctionFlags, named_params, params, Connection, ToSql};
 use std::{path::PathBuf, time::Duration};
 const SQLITE_APPLICATION_ID: i32 = 1151497937;
 
@@ -21,6 +22,7 @@ pub struct SqliteBackedHistory {
     db: rusqlite::Connection,
     session: Option<HistorySessionId>,
     session_timestamp: Option<chrono::DateTime<Utc>>,
+    fts_available: bool,
 }
 
 fn deserialize_history_item(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem> {
@@ -108,7 +110,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn count(&self, query: SearchQuery) -> Result<i64> {
-        let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)");
+        let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)")?;
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let result: i64 = self
             .db
@@ -120,7 +122,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
-        let (query, params) = self.construct_query(&query, "*");
+        let (query, params) = self.construct_query(&query, "*")?;
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let results: Vec<HistoryItem> = self
             .db
@@ -187,6 +189,31 @@ fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     )))
 }
 
+// SQLite only provides the syntax of the `REGEXP` operator, `X REGEXP Y` calls the
+// user function `regexp(Y, X)`. The compiled pattern is cached by SQLite for the
+// duration of the statement
+fn register_regexp_function(db: &Connection) -> Result<()> {
+    db.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let regex =
+                ctx.get_or_create_aux(0, |pattern| -> std::result::Result<_, BoxError> {
+                    Ok(Regex::new(pattern.as_str()?)?)
+                })?;
+            let text = ctx
+                .get_raw(1)
+                .as_str()
+                .map_err(|err| rusqlite::Error::UserFunctionError(err.into()))?;
+            Ok(regex.is_match(text))
+        },
+    )
+    .map_err(map_sqlite_err)
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 type BoxedNamedParams<'a> = Vec<(&'static str, Box<dyn ToSql + 'a>)>;
 
 impl SqliteBackedHistory {
@@ -267,18 +294,91 @@ impl SqliteBackedHistory {
         ",
         )
         .map_err(map_sqlite_err)?;
-        Ok(SqliteBackedHistory {
+        let fts_existed: bool = db
+            .query_row(
+                "select exists(select 1 from sqlite_master where name = 'history_fts')",
+                params![],
+                |r| r.get(0),
+            )
+            .map_err(map_sqlite_err)?;
+        // The full-text index is optional, as SQLite might be compiled without FTS5
+        let fts_available = match db.execute_batch(
+            "
+        create virtual table if not exists history_fts using fts5(
+            command_line, content='history', content_rowid='id'
+        );
+        create trigger if not exists history_fts_insert after insert on history begin
+            insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+        end;
+        create trigger if not exists history_fts_delete after delete on history begin
+            insert into history_fts(history_fts, rowid, command_line)
+                values ('delete', old.id, old.command_line);
+        end;
+        create trigger if not exists history_fts_update after update on history begin
+            insert into history_fts(history_fts, rowid, command_line)
+                values ('delete', old.id, old.command_line);
+            insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+        end;
+        ",
+        ) {
+            Ok(()) => true,
+            Err(rusqlite::Error::SqliteFailure(_, Some(message)))
+                if message.starts_with("no such module") =>
+            {
+                false
+            }
+            Err(err) => return Err(map_sqlite_err(err)),
+        };
+        register_regexp_function(&db)?;
+        let mut history = SqliteBackedHistory {
             db,
             session,
             session_timestamp,
-        })
+            fts_available,
+        };
+        // The entries of a database created before the index are indexed once
+        if fts_available && !fts_existed {
+            history.rebuild_fts_index()?;
+        }
+        Ok(history)
+    }
+
+    /// Whether the full-text index used by [`CommandLineSearch::FullText`] is available
+    ///
+    /// Without FTS5 support in the linked SQLite the full-text searches fall back to
+    /// substring searches.
+    pub fn has_fts_index(&self) -> bool {
+        self.fts_available
+    }
+
+    /// Rebuilds the full-text index from the stored entries
+    ///
+    /// New entries are indexed when they are saved and the entries of databases created
+    /// without the index are indexed when they are opened, so this is only required if
+    /// the index was changed by another program.
+    pub fn rebuild_fts_index(&mut self) -> Result<()> {
+        if !self.fts_available {
+            return Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported {
+                    history: "SqliteBackedHistory",
+                    feature: "full-text index without FTS5",
+                },
+            ));
+        }
+        self.db
+            .execute(
+                "insert into history_fts(history_fts) values ('rebuild')",
+                params![],
+            )
+            .map_err(map_sqlite_err)?;
+        Ok(())
     }
 
     fn construct_query<'a>(
         &self,
         query: &'a SearchQuery,
         select_expression: &str,
-    ) -> (String, BoxedNamedParams<'a>) {
+    ) -> Result<(String, BoxedNamedParams<'a>)> {
         // TODO: this whole function could be done with less allocs
         let (is_asc, asc) = match query.direction {
             SearchDirection::Forward => (true, "asc"),
@@ -327,13 +427,33 @@ impl SqliteBackedHistory {
         };
         if let Some(command_line) = &query.filter.command_line {
             // TODO: escape %
-            let command_line_like = match command_line {
-                CommandLineSearch::Exact(e) => e.to_string(),
-                CommandLineSearch::Prefix(prefix) => format!("{prefix}%"),
-                CommandLineSearch::Substring(cont) => format!("%{cont}%"),
+            let (condition, command_line) = match command_line {
+                CommandLineSearch::Exact(e) => ("command_line like :command_line", e.to_string()),
+                CommandLineSearch::Prefix(prefix) => {
+                    ("command_line like :command_line", format!("{prefix}%"))
+                }
+                CommandLineSearch::Substring(cont) => {
+                    ("command_line like :command_line", format!("%{cont}%"))
+                }
+                CommandLineSearch::FullText(text) if self.fts_available && !text.is_empty() => (
+                    "id in (select rowid from history_fts where history_fts match :command_line)",
+                    // Searching for the text as a phrase avoids interpreting it as a query
+                    format!("\"{}\"", text.replace('"', "\"\"")),
+                ),
+                CommandLineSearch::FullText(text) => {
+                    ("command_line like :command_line", format!("%{text}%"))
+                }
+                CommandLineSearch::Regex(pattern) => {
+                    // Compile the pattern upfront, so that an invalid one is reported even
+                    // if there are no entries for the regexp function to run on
+                    Regex::new(pattern).map_err(|err| {
+                        ReedlineError(ReedlineErrorVariants::InvalidSearchRegex(err.to_string()))
+                    })?;
+                    ("command_line regexp :command_line", pattern.to_string())
+                }
             };
-            wheres.push("command_line like :command_line");
-            params.push((":command_line", Box::new(command_line_like)));
+            wheres.push(condition);
+            params.push((":command_line", Box::new(command_line)));
         }
 
         if let Some(str) = &query.filter.not_command_line {
@@ -353,6 +473,22 @@ impl SqliteBackedHistory {
             let cwd_like = format!("{cwd_prefix}%");
             params.push((":cwd_like", Box::new(cwd_like)));
         }
+        match (query.filter.after, query.filter.before) {
+            (Some(after), Some(before)) => {
+                wheres.push("start_timestamp between :after and :before");
+                params.push((":after", Box::new(after.timestamp_millis())));
+                params.push((":before", Box::new(before.timestamp_millis())));
+            }
+            (Some(after), None) => {
+                wheres.push("start_timestamp >= :after");
+                params.push((":after", Box::new(after.timestamp_millis())));
+            }
+            (None, Some(before)) => {
+                wheres.push("start_timestamp <= :before");
+                params.push((":before", Box::new(before.timestamp_millis())));
+            }
+            (None, None) => {}
+        }
         if let Some(exit_successful) = query.filter.exit_successful {
             if exit_successful {
                 wheres.push("exit_status = 0");
@@ -384,6 +520,123 @@ impl SqliteBackedHistory {
              ORDER BY id {asc} \
              {limit}"
         );
-        (query, params)
+        Ok((query, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchFilter;
+
+    fn full_text_query(text: &str) -> SearchQuery {
+        SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::FullText(text.to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        }
+    }
+
+    fn commands(history: &SqliteBackedHistory, query: SearchQuery) -> Vec<String> {
+        history
+            .search(query)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    #[test]
+    fn full_text_search_uses_index() -> Result<()> {
+        let history = SqliteBackedHistory::in_memory()?;
+        assert!(history.has_fts_index());
+
+        let query = full_text_query("nginx");
+        let (sql, params) = history.construct_query(&query, "*")?;
+        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let plan = history
+            .db
+            .prepare(&format!("explain query plan {sql}"))
+            .map_err(map_sqlite_err)?
+            .query_map(&params_borrow[..], |row| row.get::<_, String>("detail"))
+            .map_err(map_sqlite_err)?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .map_err(map_sqlite_err)?;
+
+        assert!(
+            plan.iter()
+                .any(|detail| detail.contains("history_fts VIRTUAL TABLE INDEX")),
+            "{plan:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn full_text_search_matches_words() -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        history.save(HistoryItem::from_command_line("vim nginx.conf"))?;
+        history.save(HistoryItem::from_command_line("cat nginx.conf"))?;
+        history.save(HistoryItem::from_command_line("ls nginx"))?;
+
+        assert_eq!(
+            commands(&history, full_text_query("nginx.conf")),
+            vec!["vim nginx.conf", "cat nginx.conf"]
+        );
+        // Quotes are not interpreted as query syntax
+        assert_eq!(
+            commands(&history, full_text_query("\"cat")),
+            vec!["cat nginx.conf"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn databases_without_the_index_are_indexed_when_opened() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.sqlite3");
+        let mut history = SqliteBackedHistory::with_file(file.clone(), None, None)?;
+        history.save(HistoryItem::from_command_line("cargo test"))?;
+        // Simulate a database that was filled before the index existed
+        history
+            .db
+            .execute_batch(
+                "drop trigger history_fts_insert;
+                drop trigger history_fts_delete;
+                drop trigger history_fts_update;
+                drop table history_fts;",
+            )
+            .map_err(map_sqlite_err)?;
+        drop(history);
+
+        let history = SqliteBackedHistory::with_file(file, None, None)?;
+        assert_eq!(
+            commands(&history, full_text_query("cargo")),
+            vec!["cargo test"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rebuild_fts_index_indexes_existing_entries() -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        history.save(HistoryItem::from_command_line("cargo test"))?;
+        // Simulate a database that was filled before the index existed
+        history
+            .db
+            .execute(
+                "insert into history_fts(history_fts) values ('delete-all')",
+                params![],
+            )
+            .map_err(map_sqlite_err)?;
+        assert!(commands(&history, full_text_query("cargo")).is_empty());
+
+        history.rebuild_fts_index()?;
+        assert_eq!(
+            commands(&history, full_text_query("cargo")),
+            vec!["cargo test"]
+        );
+        Ok(())
     }
 }
@@ -177,7 +177,7 @@
 //!
 //! ## Crate features
 //!
-//! - `clipboard`: Enable support to use the `SystemClipboard`. Enabling this feature will return a `SystemClipboard` instead of a local clipboard when calling `get_default_clipboard()`.
+//! - `system_clipboard`: Enable support to use the `SystemClipboard`. Enabling this feature will return a `SystemClipboard` instead of a local clipboard when calling `get_default_clipboard()`, see [`Reedline::use_system_clipboard`].
 //! - `bashisms`: Enable support for special text sequences that recall components from the history. e.g. `!!` and `!$`. For use in shells like `bash` or [`nushell`](https://nushell.sh).
 //! - `sqlite`: Provides the `SqliteBackedHistory` to store richer information in the history. Statically links the required sqlite version.
 //! - `sqlite-dynlib`: Alternative to the feature `sqlite`. Will not statically link. Requires `sqlite >= 3.38` to link dynamically!
@@ -226,17 +226,22 @@
 #![warn(missing_docs)]
 // #![deny(warnings)]
 mod core_editor;
+pub use core_editor::AutoPairConfig;
 pub use core_editor::Editor;
 pub use core_editor::LineBuffer;
+pub use core_editor::{CharClass, WordBoundary};
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, ReedlineRawEvent, Signal, UndoBehavior};
+pub use enums::{
+    CtrlCBehavior, CtrlDBehavior, EditCommand, LineOverflow, MouseAction, ReedlineEvent,
+    ReedlineRawEvent, Signal, TextObject, TrimMode, UndoBehavior, UndoCoalescing,
+};
 
 mod painting;
 pub use painting::{Painter, StyledText};
 
 mod engine;
-pub use engine::Reedline;
+pub use engine::{HistorySessionCallback, Reedline, SubmitTransform};
 
 mod result;
 pub use result::{ReedlineError, ReedlineErrorVariants, Result};
@@ -245,42 +250,56 @@ mod history;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 pub use history::SqliteBackedHistory;
 pub use history::{
-    CommandLineSearch, FileBackedHistory, History, HistoryItem, HistoryItemId,
-    HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchFilter, SearchQuery,
-    HISTORY_SIZE,
+    copy_history, CommandLineSearch, FileBackedHistory, History, HistoryCopy, HistoryDedup,
+    HistoryEviction, HistoryItem, HistoryItemId, HistoryNavigationQuery, HistorySessionId,
+    SearchDirection, SearchFilter, SearchQuery, HISTORY_SIZE,
 };
 
 mod prompt;
 pub use prompt::{
-    DefaultPrompt, DefaultPromptSegment, Prompt, PromptEditMode, PromptHistorySearch,
-    PromptHistorySearchStatus, PromptViMode,
+    AsyncPrompt, DefaultPrompt, DefaultPromptSegment, Prompt, PromptEditMode, PromptFuture,
+    PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
 };
 
 mod edit_mode;
 pub use edit_mode::{
-    default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    CursorConfig, EditMode, Emacs, Keybindings, Vi,
+    default_emacs_keybindings, default_kakoune_insert_keybindings,
+    default_kakoune_normal_keybindings, default_vi_insert_keybindings,
+    default_vi_normal_keybindings, CursorConfig, EditMode, Emacs, Kakoune, KeyCombination,
+    Keybindings, Vi,
 };
 
 mod highlighter;
-pub use highlighter::{ExampleHighlighter, Highlighter, SimpleMatchHighlighter};
+pub use highlighter::{
+    ExampleHighlighter, Highlighter, RainbowBrackets, RuleHighlighter, SimpleMatchHighlighter,
+};
 
 mod completion;
-pub use completion::{Completer, DefaultCompleter, Span, Suggestion};
+pub use completion::{
+    AsyncCompleter, BlockingCompleter, Completer, CompletionFuture, DefaultCompleter, Description,
+    Span, Suggestion,
+};
 
 mod hinter;
 pub use hinter::CwdAwareHinter;
-pub use hinter::{DefaultHinter, Hinter};
+pub use hinter::{ChainedHinter, DefaultHinter, Hinter};
 
 mod validator;
-pub use validator::{DefaultValidator, ValidationResult, Validator};
+pub use validator::{
+    AsyncValidator, BlockingValidator, DefaultValidator, ValidationFuture, ValidationResult,
+    Validator,
+};
 
 mod menu;
 pub use menu::{
-    menu_functions, ColumnarMenu, DescriptionMode, IdeMenu, ListMenu, Menu, MenuEvent,
-    MenuTextStyle, ReedlineMenu,
+    menu_functions, ColumnarMenu, DescriptionMode, DescriptionPanePosition, HistoryOrdering,
+    IdeMenu, ListMenu, MatchConfig, Menu, MenuAcceptBehavior, MenuEvent, MenuSort, MenuTextStyle,
+    MenuTheme, Overflow, ReedlineMenu,
 };
 
+mod picker;
+pub use picker::pick;
+
 mod terminal_extensions;
 pub use terminal_extensions::kitty_protocol_available;
 
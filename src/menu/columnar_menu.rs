@@ -1,9 +1,17 @@
-use super::{menu_functions::find_common_string, Menu, MenuEvent, MenuTextStyle};
+use super::{
+    menu_functions::{
+        center_menu, find_common_string, glyph_cell, glyph_column_width, menu_width,
+        replace_in_buffer, truncate_with_ellipsis,
+    },
+    Menu, MenuAcceptBehavior, MenuEvent, MenuSort, MenuTheme,
+};
 use crate::{
-    core_editor::Editor, menu_functions::string_difference, painting::Painter, Completer,
+    core_editor::Editor, menu_functions::string_difference, painting::Painter, Completer, Span,
     Suggestion, UndoBehavior,
 };
 use nu_ansi_term::{ansi::RESET, Style};
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthStr;
 
 /// Default values used as reference for the menu. These values are set during
 /// the initial declaration of the menu and are always kept as reference for the
@@ -35,17 +43,24 @@ struct ColumnDetails {
     pub columns: u16,
     /// Column width
     pub col_width: usize,
+    /// Maximum width of the values before they are truncated
+    pub value_width: Option<usize>,
 }
 
 /// Menu to present suggestions in a columnar fashion
 /// It presents a description of the suggestion if available
+///
+/// When the rows don't fit next to the prompt, the grid is split into pages of the
+/// rows that fit, with a banner line showing the page. The arrows move from one page
+/// to the next at the edges of the page, [`MenuEvent::NextPage`] and
+/// [`MenuEvent::PreviousPage`] cycle through the pages.
 pub struct ColumnarMenu {
     /// Menu name
     name: String,
     /// Columnar menu active status
     active: bool,
-    /// Menu coloring
-    color: MenuTextStyle,
+    /// Styles and markers of the menu
+    theme: MenuTheme,
     /// Default column details that are set when creating the menu
     /// These values are the reference for the working details
     default_details: DefaultColumnDetails,
@@ -60,8 +75,6 @@ pub struct ColumnarMenu {
     col_pos: u16,
     /// row position in the menu. Starts from 0
     row_pos: u16,
-    /// Menu marker when active
-    marker: String,
     /// Event sent to the menu
     event: Option<MenuEvent>,
     /// Longest suggestion found in the values
@@ -71,6 +84,26 @@ pub struct ColumnarMenu {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// Order applied to the values collected from the completer
+    sort: MenuSort,
+    /// Orders cycled through by [`MenuEvent::ToggleSort`]
+    sort_orders: Vec<MenuSort>,
+    /// What enter does while the menu is open
+    accept_behavior: MenuAcceptBehavior,
+    /// Show the glyph of the suggestions in an aligned column before the value
+    glyph_column: bool,
+    /// Width of the glyph column calculated from the cached values
+    glyph_width: usize,
+    /// Rows shown on each page, `None` when all the rows fit
+    page_rows: Option<u16>,
+    /// Terminal lines used by each row of the menu
+    row_lines: u16,
+    /// Maximum number of columns used by the menu
+    max_width: Option<u16>,
+    /// Center the menu in the terminal when it's narrower than the terminal
+    centered: bool,
+    /// Terminal width the menu was laid out for
+    screen_width: u16,
 }
 
 impl Default for ColumnarMenu {
@@ -78,18 +111,27 @@ impl Default for ColumnarMenu {
         Self {
             name: "columnar_menu".to_string(),
             active: false,
-            color: MenuTextStyle::default(),
+            theme: MenuTheme::with_marker("| "),
             default_details: DefaultColumnDetails::default(),
             min_rows: 3,
             working_details: ColumnDetails::default(),
             values: Vec::new(),
             col_pos: 0,
             row_pos: 0,
-            marker: "| ".to_string(),
             event: None,
             longest_suggestion: 0,
             input: None,
             only_buffer_difference: false,
+            sort: MenuSort::default(),
+            sort_orders: Vec::new(),
+            accept_behavior: MenuAcceptBehavior::default(),
+            glyph_column: false,
+            glyph_width: 0,
+            page_rows: None,
+            row_lines: 1,
+            max_width: None,
+            centered: false,
+            screen_width: 0,
         }
     }
 }
@@ -106,21 +148,21 @@ impl ColumnarMenu {
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_text_style(mut self, text_style: Style) -> Self {
-        self.color.text_style = text_style;
+        self.theme.styles.text_style = text_style;
         self
     }
 
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_selected_text_style(mut self, selected_text_style: Style) -> Self {
-        self.color.selected_text_style = selected_text_style;
+        self.theme.styles.selected_text_style = selected_text_style;
         self
     }
 
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_description_text_style(mut self, description_text_style: Style) -> Self {
-        self.color.description_style = description_text_style;
+        self.theme.styles.description_style = description_text_style;
         self
     }
 
@@ -132,13 +174,16 @@ impl ColumnarMenu {
     }
 
     /// Menu builder with new column width value
+    ///
+    /// When `None` the width is calculated from the longest value. When `Some`
+    /// the columns have the given width and longer values are truncated
     #[must_use]
     pub fn with_column_width(mut self, col_width: Option<usize>) -> Self {
         self.default_details.col_width = col_width;
         self
     }
 
-    /// Menu builder with new column width value
+    /// Menu builder with new column padding value
     #[must_use]
     pub fn with_column_padding(mut self, col_padding: usize) -> Self {
         self.default_details.col_padding = col_padding;
@@ -148,7 +193,7 @@ impl ColumnarMenu {
     /// Menu builder with marker
     #[must_use]
     pub fn with_marker(mut self, marker: String) -> Self {
-        self.marker = marker;
+        self.theme.marker = marker;
         self
     }
 
@@ -158,6 +203,56 @@ impl ColumnarMenu {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder with new sort order for the values
+    #[must_use]
+    pub fn with_sort(mut self, sort: MenuSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Menu builder with the sort orders cycled through by [`MenuEvent::ToggleSort`],
+    /// e.g. bound to [`ReedlineEvent::MenuToggleSort`](crate::ReedlineEvent::MenuToggleSort).
+    /// Without them the sort of the menu is fixed
+    #[must_use]
+    pub fn with_sort_orders(mut self, sort_orders: Vec<MenuSort>) -> Self {
+        self.sort_orders = sort_orders;
+        self
+    }
+
+    /// Menu builder with new accept behavior, by default enter accepts the selection
+    #[must_use]
+    pub fn with_accept_behavior(mut self, accept_behavior: MenuAcceptBehavior) -> Self {
+        self.accept_behavior = accept_behavior;
+        self
+    }
+
+    /// Menu builder with new glyph column value
+    #[must_use]
+    pub fn with_glyph_column(mut self, glyph_column: bool) -> Self {
+        self.glyph_column = glyph_column;
+        self
+    }
+
+    /// Menu builder with new max width value
+    ///
+    /// When `Some`, the columns are laid out in at most the given number of terminal
+    /// columns instead of the whole width of the terminal
+    #[must_use]
+    pub fn with_max_width(mut self, max_width: Option<u16>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Menu builder with new centered value
+    ///
+    /// When active, a menu narrower than the terminal, see
+    /// [`ColumnarMenu::with_max_width`], is centered instead of left aligned
+    #[must_use]
+    pub fn with_centered(mut self, centered: bool) -> Self {
+        self.centered = centered;
+        self
+    }
 }
 
 // Menu functionality
@@ -290,6 +385,67 @@ impl ColumnarMenu {
         }
     }
 
+    /// Splits the rows into pages that fit into the available lines, keeping a line
+    /// for the banner
+    fn update_pages(&mut self, screen_width: u16, available_lines: u16) {
+        let screen_width = screen_width.max(1) as usize;
+        // Values wider than the terminal wrap into several lines
+        self.row_lines = ((self.get_width() + screen_width - 1) / screen_width).max(1) as u16;
+
+        // Without any lines the size of the terminal isn't known
+        self.page_rows =
+            if available_lines > 0 && self.get_rows() * self.row_lines > available_lines {
+                Some((available_lines.saturating_sub(1) / self.row_lines).max(1))
+            } else {
+                None
+            };
+        self.clamp_position();
+    }
+
+    /// Index of the page showing the selected row
+    fn page(&self) -> u16 {
+        self.page_rows
+            .map_or(0, |page_rows| self.row_pos / page_rows)
+    }
+
+    /// Number of pages needed to show all the rows
+    fn page_count(&self) -> u16 {
+        self.page_rows
+            .map_or(1, |page_rows| (self.get_rows() + page_rows - 1) / page_rows)
+    }
+
+    /// Moves to the first row of the page, keeping the column, the pages wrap around
+    fn goto_page(&mut self, page: i32) {
+        if let Some(page_rows) = self.page_rows {
+            let page = page.rem_euclid(self.page_count() as i32) as u16;
+            self.row_pos = page * page_rows;
+            self.clamp_position();
+        }
+    }
+
+    /// Moves the position onto the last value if it is past the values
+    fn clamp_position(&mut self) {
+        let values = self.get_values().len();
+        if values > 0 && self.index() >= values {
+            self.row_pos = ((values - 1) / self.get_cols() as usize) as u16;
+            self.col_pos = ((values - 1) % self.get_cols() as usize) as u16;
+        }
+    }
+
+    fn banner_message(&self, use_ansi_coloring: bool) -> String {
+        let banner = format!("page {}/{}", self.page() + 1, self.page_count());
+        if use_ansi_coloring {
+            format!(
+                "{}{}{}",
+                self.theme.styles.selected_text_style.prefix(),
+                banner,
+                RESET
+            )
+        } else {
+            banner
+        }
+    }
+
     /// Returns working details col width
     fn get_width(&self) -> usize {
         self.working_details.col_width
@@ -306,7 +462,7 @@ impl ColumnarMenu {
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
-                self.color.selected_text_style.prefix(),
+                self.theme.styles.selected_text_style.prefix(),
                 msg,
                 RESET
             )
@@ -320,6 +476,11 @@ impl ColumnarMenu {
         self.working_details.columns.max(1)
     }
 
+    /// Number of terminal columns the menu is laid out in
+    fn menu_width(&self) -> u16 {
+        menu_width(self.screen_width, self.max_width)
+    }
+
     /// End of line for menu
     fn end_of_line(&self, column: u16) -> &str {
         if column == self.get_cols().saturating_sub(1) {
@@ -329,6 +490,83 @@ impl ColumnarMenu {
         }
     }
 
+    /// Calculates the number of columns and their width based on the values
+    /// and the screen width
+    fn update_layout(&mut self, screen_width: u16) {
+        // If there is at least one suggestion that contains a description, then the layout
+        // is changed to one column to fit the description
+        let exist_description = self
+            .get_values()
+            .iter()
+            .any(|suggestion| suggestion.description.is_some());
+
+        self.glyph_width = if self.glyph_column {
+            glyph_column_width(self.get_values())
+        } else {
+            0
+        };
+
+        if exist_description {
+            self.working_details.columns = 1;
+            self.working_details.col_width = screen_width as usize;
+            self.working_details.value_width = None;
+
+            self.longest_suggestion = self.get_values().iter().fold(0, |prev, suggestion| {
+                if prev >= suggestion.value.len() {
+                    prev
+                } else {
+                    suggestion.value.len()
+                }
+            });
+        } else {
+            if let Some(col_width) = self.default_details.col_width {
+                // With a fixed column width the values are truncated to fit in their cell
+                self.working_details.col_width = col_width.max(1);
+                self.working_details.value_width = Some(
+                    col_width.saturating_sub(self.default_details.col_padding + self.glyph_width),
+                );
+            } else {
+                let max_width = self.get_values().iter().fold(0, |acc, suggestion| {
+                    let str_len = suggestion.value.width()
+                        + self.glyph_width
+                        + self.default_details.col_padding;
+                    if str_len > acc {
+                        str_len
+                    } else {
+                        acc
+                    }
+                });
+
+                // If no default width is found, then the total screen width is used to estimate
+                // the column width based on the default number of columns
+                let default_width = (screen_width / self.default_details.columns.max(1)) as usize;
+
+                // Adjusting the working width of the column based the max line width found
+                // in the menu values
+                self.working_details.col_width = max_width.max(default_width).max(1);
+                self.working_details.value_width = None;
+            }
+
+            // The working columns is adjusted based on possible number of columns
+            // that could be fitted in the screen with the calculated column width
+            let possible_cols = screen_width / self.working_details.col_width as u16;
+            if possible_cols > self.default_details.columns {
+                self.working_details.columns = self.default_details.columns.max(1);
+            } else {
+                self.working_details.columns = possible_cols;
+            }
+        }
+    }
+
+    /// Value of the suggestion as shown in its cell. It is truncated when the
+    /// columns have a fixed width
+    fn cell_value<'a>(&self, suggestion: &'a Suggestion) -> Cow<'a, str> {
+        match self.working_details.value_width {
+            Some(width) => truncate_with_ellipsis(&suggestion.value, width),
+            None => Cow::Borrowed(&suggestion.value),
+        }
+    }
+
     /// Creates default string that represents one suggestion from the menu
     fn create_string(
         &self,
@@ -338,16 +576,22 @@ impl ColumnarMenu {
         empty_space: usize,
         use_ansi_coloring: bool,
     ) -> String {
+        let glyph = glyph_cell(suggestion, self.glyph_width);
+        let value = self.cell_value(suggestion);
         if use_ansi_coloring {
             if index == self.index() {
                 if let Some(description) = &suggestion.description {
                     let left_text_size = self.longest_suggestion + self.default_details.col_padding;
-                    let right_text_size = self.get_width().saturating_sub(left_text_size);
+                    let right_text_size = self
+                        .get_width()
+                        .saturating_sub(left_text_size + self.glyph_width);
                     format!(
-                        "{}{:max$}{}{}{}",
-                        self.color.selected_text_style.prefix(),
-                        &suggestion.value,
+                        "{}{}{:max$}{}{}{}",
+                        self.theme.styles.selected_text_style.prefix(),
+                        glyph,
+                        value,
                         description
+                            .text()
                             .chars()
                             .take(right_text_size)
                             .collect::<String>()
@@ -358,9 +602,10 @@ impl ColumnarMenu {
                     )
                 } else {
                     format!(
-                        "{}{}{}{:>empty$}{}",
-                        self.color.selected_text_style.prefix(),
-                        &suggestion.value,
+                        "{}{}{}{}{:>empty$}{}",
+                        self.theme.styles.selected_text_style.prefix(),
+                        glyph,
+                        value,
                         RESET,
                         "",
                         self.end_of_line(column),
@@ -369,14 +614,18 @@ impl ColumnarMenu {
                 }
             } else if let Some(description) = &suggestion.description {
                 let left_text_size = self.longest_suggestion + self.default_details.col_padding;
-                let right_text_size = self.get_width().saturating_sub(left_text_size);
+                let right_text_size = self
+                    .get_width()
+                    .saturating_sub(left_text_size + self.glyph_width);
                 format!(
-                    "{}{:max$}{}{}{}{}{}",
-                    self.color.text_style.prefix(),
-                    &suggestion.value,
+                    "{}{}{:max$}{}{}{}{}{}",
+                    self.theme.styles.text_style.prefix(),
+                    glyph,
+                    value,
                     RESET,
-                    self.color.description_style.prefix(),
+                    self.theme.styles.description_style.prefix(),
                     description
+                        .text()
                         .chars()
                         .take(right_text_size)
                         .collect::<String>()
@@ -387,11 +636,12 @@ impl ColumnarMenu {
                 )
             } else {
                 format!(
-                    "{}{}{}{}{:>empty$}{}{}",
-                    self.color.text_style.prefix(),
-                    &suggestion.value,
+                    "{}{}{}{}{}{:>empty$}{}{}",
+                    self.theme.styles.text_style.prefix(),
+                    glyph,
+                    value,
                     RESET,
-                    self.color.description_style.prefix(),
+                    self.theme.styles.description_style.prefix(),
                     "",
                     RESET,
                     self.end_of_line(column),
@@ -404,10 +654,12 @@ impl ColumnarMenu {
 
             let line = if let Some(description) = &suggestion.description {
                 format!(
-                    "{}{:max$}{}{}",
+                    "{}{}{:max$}{}{}",
                     marker,
-                    &suggestion.value,
+                    glyph,
+                    value,
                     description
+                        .text()
                         .chars()
                         .take(empty_space)
                         .collect::<String>()
@@ -421,9 +673,10 @@ impl ColumnarMenu {
                 )
             } else {
                 format!(
-                    "{}{}{:>empty$}{}",
+                    "{}{}{}{:>empty$}{}",
                     marker,
-                    &suggestion.value,
+                    glyph,
+                    value,
                     "",
                     self.end_of_line(column),
                     empty = empty_space.saturating_sub(marker.len()),
@@ -447,7 +700,16 @@ impl Menu for ColumnarMenu {
 
     /// Menu indicator
     fn indicator(&self) -> &str {
-        self.marker.as_str()
+        self.theme.marker.as_str()
+    }
+
+    /// The styles and markers of the menu are replaced by the theme
+    fn apply_theme(&mut self, theme: &MenuTheme) {
+        self.theme = theme.clone();
+    }
+
+    fn accept_behavior(&self) -> MenuAcceptBehavior {
+        self.accept_behavior
     }
 
     /// Deactivates context menu
@@ -478,24 +740,25 @@ impl Menu for ColumnarMenu {
         if let (Some(Suggestion { value, span, .. }), Some(index)) = find_common_string(values) {
             let index = index.min(value.len());
             let matching = &value[0..index];
+            let Span { start, end } = span.clamp_to(editor.line_buffer().len());
 
             // make sure that the partial completion does not overwrite user entered input
-            let extends_input = matching.starts_with(&editor.get_buffer()[span.start..span.end]);
+            let extends_input = matching.starts_with(&editor.get_buffer()[start..end]);
 
             if !matching.is_empty() && extends_input {
                 let mut line_buffer = editor.line_buffer().clone();
-                line_buffer.replace_range(span.start..span.end, matching);
+                line_buffer.replace_range(start..end, matching);
 
-                let offset = if matching.len() < (span.end - span.start) {
+                let offset = if matching.len() < (end - start) {
                     line_buffer
                         .insertion_point()
-                        .saturating_sub((span.end - span.start) - matching.len())
+                        .saturating_sub((end - start) - matching.len())
                 } else {
-                    line_buffer.insertion_point() + matching.len() - (span.end - span.start)
+                    line_buffer.insertion_point() + matching.len() - (end - start)
                 };
 
                 line_buffer.set_insertion_point(offset);
-                editor.set_line_buffer(line_buffer, UndoBehavior::CreateUndoPoint);
+                editor.set_line_buffer(line_buffer, UndoBehavior::Completion);
 
                 // The values need to be updated because the spans need to be
                 // recalculated for accurate replacement in the string
@@ -550,9 +813,26 @@ impl Menu for ColumnarMenu {
             )
         };
 
+        self.sort.sort(&mut self.values);
         self.reset_position();
     }
 
+    /// Updates menu values keeping the selected value
+    fn refresh_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        let selected = self.get_value();
+        self.update_values(editor, completer);
+
+        if let Some(index) = selected.and_then(|selected| {
+            self.values
+                .iter()
+                .position(|suggestion| suggestion.value == selected.value)
+        }) {
+            let index = index as u16;
+            self.row_pos = index / self.get_cols();
+            self.col_pos = index % self.get_cols();
+        }
+    }
+
     /// The working details for the menu changes based on the size of the lines
     /// collected from the completer
     fn update_working_details(
@@ -565,60 +845,8 @@ impl Menu for ColumnarMenu {
             // The working value for the menu are updated first before executing any of the
             // menu events
             //
-            // If there is at least one suggestion that contains a description, then the layout
-            // is changed to one column to fit the description
-            let exist_description = self
-                .get_values()
-                .iter()
-                .any(|suggestion| suggestion.description.is_some());
-
-            if exist_description {
-                self.working_details.columns = 1;
-                self.working_details.col_width = painter.screen_width() as usize;
-
-                self.longest_suggestion = self.get_values().iter().fold(0, |prev, suggestion| {
-                    if prev >= suggestion.value.len() {
-                        prev
-                    } else {
-                        suggestion.value.len()
-                    }
-                });
-            } else {
-                let max_width = self.get_values().iter().fold(0, |acc, suggestion| {
-                    let str_len = suggestion.value.len() + self.default_details.col_padding;
-                    if str_len > acc {
-                        str_len
-                    } else {
-                        acc
-                    }
-                });
-
-                // If no default width is found, then the total screen width is used to estimate
-                // the column width based on the default number of columns
-                let default_width = if let Some(col_width) = self.default_details.col_width {
-                    col_width
-                } else {
-                    let col_width = painter.screen_width() / self.default_details.columns;
-                    col_width as usize
-                };
-
-                // Adjusting the working width of the column based the max line width found
-                // in the menu values
-                if max_width > default_width {
-                    self.working_details.col_width = max_width;
-                } else {
-                    self.working_details.col_width = default_width;
-                };
-
-                // The working columns is adjusted based on possible number of columns
-                // that could be fitted in the screen with the calculated column width
-                let possible_cols = painter.screen_width() / self.working_details.col_width as u16;
-                if possible_cols > self.default_details.columns {
-                    self.working_details.columns = self.default_details.columns.max(1);
-                } else {
-                    self.working_details.columns = possible_cols;
-                }
-            }
+            self.screen_width = painter.screen_width();
+            self.update_layout(self.menu_width());
 
             match event {
                 MenuEvent::Activate(updated) => {
@@ -649,36 +877,27 @@ impl Menu for ColumnarMenu {
                 MenuEvent::MoveDown => self.move_down(),
                 MenuEvent::MoveLeft => self.move_left(),
                 MenuEvent::MoveRight => self.move_right(),
-                MenuEvent::PreviousPage | MenuEvent::NextPage => {
-                    // The columnar menu doest have the concept of pages, yet
+                MenuEvent::NextPage => self.goto_page(self.page() as i32 + 1),
+                MenuEvent::PreviousPage => self.goto_page(self.page() as i32 - 1),
+                MenuEvent::GotoPage(page) => {
+                    let last_page = self.page_count().saturating_sub(1) as usize;
+                    self.goto_page(page.min(last_page) as i32);
+                }
+                MenuEvent::ToggleSort => {
+                    self.sort = self.sort.toggle(&self.sort_orders);
+                    self.refresh_values(editor, completer);
                 }
             }
+            self.update_pages(
+                self.menu_width(),
+                painter.menu_available_lines(self.min_rows()),
+            );
         }
     }
 
     /// The buffer gets replaced in the Span location
     fn replace_in_buffer(&self, editor: &mut Editor) {
-        if let Some(Suggestion {
-            mut value,
-            span,
-            append_whitespace,
-            ..
-        }) = self.get_value()
-        {
-            let start = span.start.min(editor.line_buffer().len());
-            let end = span.end.min(editor.line_buffer().len());
-            if append_whitespace {
-                value.push(' ');
-            }
-            let mut line_buffer = editor.line_buffer().clone();
-            line_buffer.replace_range(start..end, &value);
-
-            let mut offset = line_buffer.insertion_point();
-            offset = offset.saturating_add(value.len());
-            offset = offset.saturating_sub(end.saturating_sub(start));
-            line_buffer.set_insertion_point(offset);
-            editor.set_line_buffer(line_buffer, UndoBehavior::CreateUndoPoint);
-        }
+        replace_in_buffer(self.get_value(), editor);
     }
 
     /// Minimum rows that should be displayed by the menu
@@ -691,13 +910,45 @@ impl Menu for ColumnarMenu {
         &self.values
     }
 
+    fn selected_index(&self) -> Option<usize> {
+        (self.active && !self.values.is_empty()).then(|| self.index())
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
-        self.get_rows()
+        match self.page_rows {
+            // The rows of a page and the banner
+            Some(page_rows) => page_rows.min(self.get_rows()) * self.row_lines + 1,
+            None => self.get_rows(),
+        }
     }
 
     fn menu_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
-        if self.get_values().is_empty() {
+        let menu = if self.get_values().is_empty() {
             self.no_records_msg(use_ansi_coloring)
+        } else if let Some(page_rows) = self.page_rows {
+            let skip_values = (self.page() * page_rows * self.get_cols()) as usize;
+            let page_values = (page_rows * self.get_cols()) as usize;
+            let mut menu: String = self
+                .get_values()
+                .iter()
+                .enumerate()
+                .skip(skip_values)
+                .take(page_values)
+                .map(|(index, suggestion)| {
+                    let column = index as u16 % self.get_cols();
+                    let empty_space = self
+                        .get_width()
+                        .saturating_sub(self.cell_value(suggestion).width() + self.glyph_width);
+
+                    self.create_string(suggestion, index, column, empty_space, use_ansi_coloring)
+                })
+                .collect();
+
+            // The last row of the grid can be incomplete
+            if !menu.ends_with("\r\n") {
+                menu.push_str("\r\n");
+            }
+            menu + &self.banner_message(use_ansi_coloring)
         } else {
             // The skip values represent the number of lines that should be skipped
             // while printing the menu
@@ -721,11 +972,19 @@ impl Menu for ColumnarMenu {
                     // Correcting the enumerate index based on the number of skipped values
                     let index = index + skip_values;
                     let column = index as u16 % self.get_cols();
-                    let empty_space = self.get_width().saturating_sub(suggestion.value.len());
+                    let empty_space = self
+                        .get_width()
+                        .saturating_sub(self.cell_value(suggestion).width() + self.glyph_width);
 
                     self.create_string(suggestion, index, column, empty_space, use_ansi_coloring)
                 })
                 .collect()
+        };
+
+        if self.centered {
+            center_menu(menu, self.menu_width(), self.screen_width)
+        } else {
+            menu
         }
     }
 
@@ -820,7 +1079,301 @@ mod tests {
             extra: None,
             span: Span { start: 0, end: pos },
             append_whitespace: false,
+            ..Suggestion::default()
+        }
+    }
+
+    #[test]
+    fn test_selected_index() {
+        let mut completer = FakeCompleter::new(&["file1.txt", "file2.txt"]);
+        let mut menu = ColumnarMenu::default().with_name("testmenu");
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(menu.selected_index(), None);
+
+        menu.menu_event(MenuEvent::Activate(false));
+        assert_eq!(menu.selected_index(), Some(0));
+
+        menu.move_next();
+        assert_eq!(menu.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn test_fixed_column_width_fits_screen() {
+        let mut completer = FakeCompleter::new(&[
+            "short",
+            "a_very_long_file_name.txt",
+            "漢字漢字漢字漢字",
+            "📁📁📁📁📁📁📁",
+            "last",
+        ]);
+        let mut menu = ColumnarMenu::default()
+            .with_columns(3)
+            .with_column_width(Some(10))
+            .with_column_padding(2);
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+        menu.update_layout(30);
+        assert_eq!(menu.get_cols(), 3);
+
+        let output = menu.menu_string(2, false);
+        let rows: Vec<&str> = output.split("\r\n").collect();
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            assert!(row.width() <= 30, "row too wide: {row:?}");
+        }
+
+        assert_eq!(
+            output,
+            ">SHORT    a_very_…  漢字漢…   \r\n📁📁📁…   last      "
+        );
+    }
+
+    #[test]
+    fn test_auto_column_width_is_not_truncated() {
+        let mut completer = FakeCompleter::new(&["short", "a_very_long_file_name.txt"]);
+        let mut menu = ColumnarMenu::default().with_columns(3);
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+        menu.update_layout(30);
+
+        assert_eq!(menu.get_cols(), 1);
+        assert!(menu
+            .menu_string(2, false)
+            .contains("a_very_long_file_name.txt"));
+    }
+
+    #[test]
+    fn test_glyph_column() {
+        let mut completer = FakeCompleter::new(&["src", "build.rs"]);
+        let mut menu = ColumnarMenu::default()
+            .with_column_width(Some(15))
+            .with_glyph_column(true);
+        let mut editor = Editor::default();
+        let painter = Painter::detached(std::io::sink());
+
+        menu.update_values(&mut editor, &mut completer);
+        menu.values[0].glyph = Some("📁".to_string());
+        menu.menu_event(MenuEvent::Activate(true));
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+
+        assert_eq!(menu.menu_string(2, false), ">📁 SRC           build.rs    ");
+    }
+
+    fn paginated_menu(values: usize) -> (ColumnarMenu, Editor, FakeCompleter, Painter) {
+        let names: Vec<String> = (0..values)
+            .map(|value| format!("value{value:02}"))
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut completer = FakeCompleter::new(&names);
+        let mut menu = ColumnarMenu::default();
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(80, 10);
+
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+        (menu, editor, completer, painter)
+    }
+
+    fn send_event(
+        menu: &mut ColumnarMenu,
+        editor: &mut Editor,
+        completer: &mut FakeCompleter,
+        painter: &Painter,
+        event: MenuEvent,
+    ) {
+        menu.menu_event(event);
+        menu.update_working_details(editor, completer, painter);
+    }
+
+    #[test]
+    fn columns_are_laid_out_in_the_max_width() {
+        let names: Vec<String> = (0..20).map(|value| format!("value{value:02}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut completer = FakeCompleter::new(&names);
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(200, 50);
+
+        let mut wide_menu = ColumnarMenu::default().with_columns(20);
+        // The layout is calculated for the values found by the previous event
+        for event in [MenuEvent::Activate(false), MenuEvent::Edit(false)] {
+            send_event(&mut wide_menu, &mut editor, &mut completer, &painter, event);
+        }
+        assert_eq!(wide_menu.menu_required_lines(200), 1);
+
+        let mut menu = ColumnarMenu::default()
+            .with_columns(20)
+            .with_max_width(Some(80))
+            .with_centered(true);
+        // The layout is calculated for the values found by the previous event
+        for event in [MenuEvent::Activate(false), MenuEvent::Edit(false)] {
+            send_event(&mut menu, &mut editor, &mut completer, &painter, event);
+        }
+        assert_eq!(menu.menu_required_lines(200), 3);
+
+        let menu_string = menu.menu_string(10, false);
+        let lines: Vec<&str> = menu_string.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            assert!(line.starts_with(&" ".repeat(60)));
+            assert!(line.trim_start().width() <= 80);
+        }
+    }
+
+    #[test]
+    fn rows_are_split_into_pages_fitting_the_terminal() {
+        let (mut menu, mut editor, mut completer, painter) = paginated_menu(50);
+
+        // 4 columns of 13 rows, 8 rows and the banner fit below the prompt
+        assert_eq!(menu.get_cols(), 4);
+        assert_eq!(menu.page_rows, Some(8));
+        assert_eq!(menu.page_count(), 2);
+        assert_eq!(menu.menu_required_lines(80), 9);
+
+        let first_page = menu.menu_string(9, false);
+        assert_eq!(first_page.lines().count(), 9);
+        assert!(first_page.ends_with("page 1/2"));
+        assert!(first_page.contains("value31") && !first_page.contains("value32"));
+
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::NextPage,
+        );
+        let second_page = menu.menu_string(9, false);
+        assert!(second_page.ends_with("page 2/2"));
+        assert!(second_page.contains("VALUE32") && second_page.contains("value49"));
+
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::NextPage,
+        );
+        assert_eq!(menu.get_value().unwrap().value, "value00");
+    }
+
+    #[test]
+    fn arrows_move_across_the_page_edges() {
+        let (mut menu, mut editor, mut completer, painter) = paginated_menu(50);
+        for _ in 0..7 {
+            send_event(
+                &mut menu,
+                &mut editor,
+                &mut completer,
+                &painter,
+                MenuEvent::MoveDown,
+            );
         }
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::MoveRight,
+        );
+        assert_eq!(menu.get_value().unwrap().value, "value29");
+        assert_eq!(menu.page(), 0);
+
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::MoveDown,
+        );
+        assert_eq!(menu.get_value().unwrap().value, "value33");
+        assert!(menu.menu_string(9, false).ends_with("page 2/2"));
+
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::MoveUp,
+        );
+        assert_eq!(menu.get_value().unwrap().value, "value29");
+        assert_eq!(menu.page(), 0);
+
+        // The last page only has the values left
+        send_event(
+            &mut menu,
+            &mut editor,
+            &mut completer,
+            &painter,
+            MenuEvent::PreviousPage,
+        );
+        assert_eq!(menu.page(), 1);
+        assert_eq!(menu.get_value().unwrap().value, "value33");
+    }
+
+    #[test]
+    fn test_sort_by_description() {
+        let mut completer = FakeCompleter::new(&["c", "b", "a"]);
+        let mut menu = ColumnarMenu::default().with_sort(MenuSort::ByDescription);
+        let mut editor = Editor::default();
+
+        let mut values = completer.complete("", 0);
+        values[0].description = Some("first".into());
+        menu.sort.sort(&mut values);
+
+        let values: Vec<&str> = values.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, vec!["a", "b", "c"]);
+
+        menu.update_values(&mut editor, &mut completer);
+        let values: Vec<&str> = menu.values.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_refresh_values_keeps_selection() {
+        let mut completer = FakeCompleter::new(&["file1.txt", "file2.txt"]);
+        let mut menu = ColumnarMenu::default().with_name("testmenu");
+        let mut editor = Editor::default();
+
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_values(&mut editor, &mut completer);
+        menu.move_next();
+        assert_eq!(menu.get_value().unwrap().value, "file2.txt");
+
+        let mut completer = FakeCompleter::new(&["file0.txt", "file1.txt", "file2.txt"]);
+        menu.refresh_values(&mut editor, &mut completer);
+        assert_eq!(menu.get_value().unwrap().value, "file2.txt");
+
+        let mut completer = FakeCompleter::new(&["file3.txt", "file4.txt"]);
+        menu.refresh_values(&mut editor, &mut completer);
+        assert_eq!(menu.get_value().unwrap().value, "file3.txt");
+    }
+
+    #[test]
+    fn toggling_the_sort_keeps_the_selection() {
+        let mut completer = FakeCompleter::new(&["c", "a", "b"]);
+        let mut menu =
+            ColumnarMenu::default().with_sort_orders(vec![MenuSort::None, MenuSort::Alphabetical]);
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(80, 10);
+
+        for event in [
+            MenuEvent::Activate(false),
+            MenuEvent::NextElement,
+            MenuEvent::NextElement,
+            MenuEvent::ToggleSort,
+        ] {
+            send_event(&mut menu, &mut editor, &mut completer, &painter, event);
+        }
+
+        let values: Vec<&str> = menu.values.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, vec!["a", "b", "c"]);
+        assert_eq!(menu.get_value().unwrap().value, "b");
     }
 
     #[test]
@@ -1,7 +1,12 @@
-use super::{menu_functions::find_common_string, Menu, MenuEvent, MenuTextStyle};
+use super::{
+    menu_functions::{
+        description_lines, find_common_string, render_description_line, replace_in_buffer,
+    },
+    Menu, MenuAcceptBehavior, MenuEvent, MenuSort, MenuTheme,
+};
 use crate::{
     core_editor::Editor, menu_functions::string_difference, painting::Painter, Completer,
-    Suggestion, UndoBehavior,
+    Description, Span, StyledText, Suggestion, UndoBehavior,
 };
 use itertools::{
     EitherOrBoth::{Both, Left, Right},
@@ -122,8 +127,8 @@ pub struct IdeMenu {
     name: String,
     /// Ide menu active status
     active: bool,
-    /// Menu coloring
-    color: MenuTextStyle,
+    /// Styles and markers of the menu
+    theme: MenuTheme,
     /// Default ide menu details that are set when creating the menu
     /// These values are the reference for the working details
     default_details: DefaultIdeMenuDetails,
@@ -133,8 +138,6 @@ pub struct IdeMenu {
     values: Vec<Suggestion>,
     /// Selected value. Starts at 0
     selected: u16,
-    /// Menu marker when active
-    marker: String,
     /// Event sent to the menu
     event: Option<MenuEvent>,
     /// Longest suggestion found in the values
@@ -144,6 +147,12 @@ pub struct IdeMenu {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// Order applied to the values collected from the completer
+    sort: MenuSort,
+    /// Orders cycled through by [`MenuEvent::ToggleSort`]
+    sort_orders: Vec<MenuSort>,
+    /// What enter does while the menu is open
+    accept_behavior: MenuAcceptBehavior,
 }
 
 impl Default for IdeMenu {
@@ -151,16 +160,18 @@ impl Default for IdeMenu {
         Self {
             name: "ide_completion_menu".to_string(),
             active: false,
-            color: MenuTextStyle::default(),
+            theme: MenuTheme::with_marker("| "),
             default_details: DefaultIdeMenuDetails::default(),
             working_details: IdeMenuDetails::default(),
             values: Vec::new(),
             selected: 0,
-            marker: "| ".to_string(),
             event: None,
             longest_suggestion: 0,
             input: None,
             only_buffer_difference: false,
+            sort: MenuSort::default(),
+            sort_orders: Vec::new(),
+            accept_behavior: MenuAcceptBehavior::default(),
         }
     }
 }
@@ -176,21 +187,21 @@ impl IdeMenu {
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_text_style(mut self, text_style: Style) -> Self {
-        self.color.text_style = text_style;
+        self.theme.styles.text_style = text_style;
         self
     }
 
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_selected_text_style(mut self, selected_text_style: Style) -> Self {
-        self.color.selected_text_style = selected_text_style;
+        self.theme.styles.selected_text_style = selected_text_style;
         self
     }
 
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_description_text_style(mut self, description_text_style: Style) -> Self {
-        self.color.description_style = description_text_style;
+        self.theme.styles.description_style = description_text_style;
         self
     }
 
@@ -261,7 +272,7 @@ impl IdeMenu {
     /// Menu builder with marker
     #[must_use]
     pub fn with_marker(mut self, marker: String) -> Self {
-        self.marker = marker;
+        self.theme.marker = marker;
         self
     }
 
@@ -272,6 +283,29 @@ impl IdeMenu {
         self
     }
 
+    /// Menu builder with new sort order for the values
+    #[must_use]
+    pub fn with_sort(mut self, sort: MenuSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Menu builder with the sort orders cycled through by [`MenuEvent::ToggleSort`],
+    /// e.g. bound to [`ReedlineEvent::MenuToggleSort`](crate::ReedlineEvent::MenuToggleSort).
+    /// Without them the sort of the menu is fixed
+    #[must_use]
+    pub fn with_sort_orders(mut self, sort_orders: Vec<MenuSort>) -> Self {
+        self.sort_orders = sort_orders;
+        self
+    }
+
+    /// Menu builder with new accept behavior, by default enter accepts the selection
+    #[must_use]
+    pub fn with_accept_behavior(mut self, accept_behavior: MenuAcceptBehavior) -> Self {
+        self.accept_behavior = accept_behavior;
+        self
+    }
+
     /// Menu builder with new description mode
     #[must_use]
     pub fn with_description_mode(mut self, description_mode: DescriptionMode) -> Self {
@@ -380,7 +414,7 @@ impl IdeMenu {
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
-                self.color.selected_text_style.prefix(),
+                self.theme.styles.selected_text_style.prefix(),
                 msg,
                 RESET
             )
@@ -391,7 +425,7 @@ impl IdeMenu {
 
     fn create_description(
         &self,
-        description: String,
+        description: Description,
         use_ansi_coloring: bool,
         available_width: u16,
         available_height: u16,
@@ -410,44 +444,57 @@ impl IdeMenu {
         let content_width = available_width.saturating_sub(border_width);
         let content_height = available_height.saturating_sub(border_width);
 
-        let mut description_lines = split_string(&description, content_width as usize);
-
-        // panic!("{:?}", description_lines);
+        let mut styled_lines = description_lines(&description, content_width as usize);
 
-        if description_lines.len() > content_height as usize {
-            description_lines.truncate(content_height as usize);
-            truncate_string_list(&mut description_lines, "...");
+        if styled_lines.len() > content_height as usize {
+            styled_lines.truncate(content_height as usize);
+            truncate_styled_lines(&mut styled_lines, "...");
         }
 
-        let content_width = description_lines
+        let content_width = styled_lines
             .iter()
-            .map(|s| s.width())
+            .map(|line| line.raw_string().width())
             .max()
             .unwrap_or_default()
             .max(min_width.saturating_sub(border_width) as usize);
 
         // let needs_padding = description_lines.len() > 1
 
-        if let Some(border) = &self.default_details.border {
-            let horizontal_border = border.horizontal.to_string().repeat(content_width);
-
-            for line in &mut description_lines {
-                let padding = " ".repeat(content_width.saturating_sub(line.width()));
+        let vertical_border = self
+            .default_details
+            .border
+            .as_ref()
+            .map(|border| border.vertical.to_string())
+            .unwrap_or_default();
+        let mut description_lines: Vec<String> = styled_lines
+            .iter()
+            .map(|line| {
+                let padding = " ".repeat(content_width.saturating_sub(line.raw_string().width()));
 
                 if use_ansi_coloring {
-                    *line = format!(
+                    format!(
                         "{}{}{}{}{}{}",
-                        border.vertical,
-                        self.color.description_style.prefix(),
-                        line,
+                        vertical_border,
+                        self.theme.styles.description_style.prefix(),
+                        render_description_line(line, self.theme.styles.description_style),
                         padding,
                         RESET,
-                        border.vertical
-                    );
+                        vertical_border
+                    )
                 } else {
-                    *line = format!("{}{}{}{}", border.vertical, line, padding, border.vertical);
+                    format!(
+                        "{}{}{}{}",
+                        vertical_border,
+                        line.raw_string(),
+                        padding,
+                        vertical_border
+                    )
                 }
-            }
+            })
+            .collect();
+
+        if let Some(border) = &self.default_details.border {
+            let horizontal_border = border.horizontal.to_string().repeat(content_width);
 
             description_lines.insert(
                 0,
@@ -460,22 +507,6 @@ impl IdeMenu {
                 "{}{}{}",
                 border.bottom_left, horizontal_border, border.bottom_right
             ));
-        } else {
-            for line in &mut description_lines {
-                let padding = " ".repeat(content_width.saturating_sub(line.width()));
-
-                if use_ansi_coloring {
-                    *line = format!(
-                        "{}{}{}{}",
-                        self.color.description_style.prefix(),
-                        line,
-                        padding,
-                        RESET
-                    );
-                } else {
-                    *line = format!("{}{}", line, padding);
-                }
-            }
         }
 
         description_lines
@@ -484,7 +515,7 @@ impl IdeMenu {
     /// Returns width and height of the description, including the border
     fn description_dims(
         &self,
-        description: String,
+        description: Description,
         max_width: u16,
         max_height: u16,
         min_width: u16,
@@ -542,7 +573,7 @@ impl IdeMenu {
                 format!(
                     "{}{}{}{}{}{}{}",
                     vertical_border,
-                    self.color.selected_text_style.prefix(),
+                    self.theme.styles.selected_text_style.prefix(),
                     " ".repeat(padding),
                     string,
                     " ".repeat(padding_right),
@@ -553,7 +584,7 @@ impl IdeMenu {
                 format!(
                     "{}{}{}{}{}{}{}",
                     vertical_border,
-                    self.color.text_style.prefix(),
+                    self.theme.styles.text_style.prefix(),
                     " ".repeat(padding),
                     string,
                     " ".repeat(padding_right),
@@ -585,7 +616,16 @@ impl Menu for IdeMenu {
 
     /// Menu indicator
     fn indicator(&self) -> &str {
-        self.marker.as_str()
+        self.theme.marker.as_str()
+    }
+
+    /// The styles and markers of the menu are replaced by the theme
+    fn apply_theme(&mut self, theme: &MenuTheme) {
+        self.theme = theme.clone();
+    }
+
+    fn accept_behavior(&self) -> MenuAcceptBehavior {
+        self.accept_behavior
     }
 
     /// Deactivates context menu
@@ -614,24 +654,25 @@ impl Menu for IdeMenu {
         if let (Some(Suggestion { value, span, .. }), Some(index)) = find_common_string(values) {
             let index = index.min(value.len());
             let matching = &value[0..index];
+            let Span { start, end } = span.clamp_to(editor.line_buffer().len());
 
             // make sure that the partial completion does not overwrite user entered input
-            let extends_input = matching.starts_with(&editor.get_buffer()[span.start..span.end]);
+            let extends_input = matching.starts_with(&editor.get_buffer()[start..end]);
 
             if !matching.is_empty() && extends_input {
                 let mut line_buffer = editor.line_buffer().clone();
-                line_buffer.replace_range(span.start..span.end, matching);
+                line_buffer.replace_range(start..end, matching);
 
-                let offset = if matching.len() < (span.end - span.start) {
+                let offset = if matching.len() < (end - start) {
                     line_buffer
                         .insertion_point()
-                        .saturating_sub((span.end - span.start) - matching.len())
+                        .saturating_sub((end - start) - matching.len())
                 } else {
-                    line_buffer.insertion_point() + matching.len() - (span.end - span.start)
+                    line_buffer.insertion_point() + matching.len() - (end - start)
                 };
 
                 line_buffer.set_insertion_point(offset);
-                editor.set_line_buffer(line_buffer, UndoBehavior::CreateUndoPoint);
+                editor.set_line_buffer(line_buffer, UndoBehavior::Completion);
 
                 // The values need to be updated because the spans need to be
                 // recalculated for accurate replacement in the string
@@ -686,9 +727,24 @@ impl Menu for IdeMenu {
             )
         };
 
+        self.sort.sort(&mut self.values);
         self.reset_position();
     }
 
+    /// Update menu values keeping the selected value
+    fn refresh_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        let selected = self.get_value();
+        self.update_values(editor, completer);
+
+        if let Some(index) = selected.and_then(|selected| {
+            self.values
+                .iter()
+                .position(|suggestion| suggestion.value == selected.value)
+        }) {
+            self.selected = index as u16;
+        }
+    }
+
     /// The working details for the menu changes based on the size of the lines
     /// collected from the completer
     fn update_working_details(
@@ -724,10 +780,15 @@ impl Menu for IdeMenu {
                 }
                 MenuEvent::NextElement | MenuEvent::MoveDown => self.move_next(),
                 MenuEvent::PreviousElement | MenuEvent::MoveUp => self.move_previous(),
+                MenuEvent::ToggleSort => {
+                    self.sort = self.sort.toggle(&self.sort_orders);
+                    self.refresh_values(editor, completer);
+                }
                 MenuEvent::MoveLeft
                 | MenuEvent::MoveRight
                 | MenuEvent::PreviousPage
-                | MenuEvent::NextPage => {}
+                | MenuEvent::NextPage
+                | MenuEvent::GotoPage(_) => {}
             }
 
             self.longest_suggestion = self.get_values().iter().fold(0, |prev, suggestion| {
@@ -845,27 +906,7 @@ impl Menu for IdeMenu {
 
     /// The buffer gets replaced in the Span location
     fn replace_in_buffer(&self, editor: &mut Editor) {
-        if let Some(Suggestion {
-            mut value,
-            span,
-            append_whitespace,
-            ..
-        }) = self.get_value()
-        {
-            let start = span.start.min(editor.line_buffer().len());
-            let end = span.end.min(editor.line_buffer().len());
-            if append_whitespace {
-                value.push(' ');
-            }
-            let mut line_buffer = editor.line_buffer().clone();
-            line_buffer.replace_range(start..end, &value);
-
-            let mut offset = line_buffer.insertion_point();
-            offset = offset.saturating_add(value.len());
-            offset = offset.saturating_sub(end.saturating_sub(start));
-            line_buffer.set_insertion_point(offset);
-            editor.set_line_buffer(line_buffer, UndoBehavior::CreateUndoPoint);
-        }
+        replace_in_buffer(self.get_value(), editor);
     }
 
     /// Minimum rows that should be displayed by the menu
@@ -877,6 +918,10 @@ impl Menu for IdeMenu {
         &self.values
     }
 
+    fn selected_index(&self) -> Option<usize> {
+        (self.active && !self.values.is_empty()).then(|| self.index())
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
         self.get_rows()
     }
@@ -1047,62 +1092,6 @@ impl Menu for IdeMenu {
     }
 }
 
-/// Split the input into strings that are at most `max_length` (in columns, not in chars) long
-/// The split is done at whitespace if possible
-fn split_string(input_str: &str, max_length: usize) -> Vec<String> {
-    let whitespace_split = input_str.split_whitespace();
-    let mut words = Vec::new();
-
-    for word in whitespace_split {
-        let word_len_cols = word.width();
-
-        if word_len_cols > max_length {
-            let mut width = 0;
-            let mut substring = String::new();
-            for grapheme in word.graphemes(true) {
-                let grapheme_width = grapheme.width();
-                // Some unicode characters can have a width of multiple rows
-                if grapheme_width > max_length {
-                    continue;
-                }
-                if width + grapheme_width > max_length {
-                    words.push(substring);
-                    substring = String::from(grapheme);
-                    width = grapheme_width;
-                } else {
-                    substring.push_str(grapheme);
-                    width += grapheme_width;
-                }
-            }
-            if !substring.is_empty() {
-                words.push(substring);
-            }
-        } else {
-            words.push(word.to_string());
-        }
-    }
-
-    let mut result = Vec::new();
-    let mut string = String::new();
-
-    for word in words {
-        if string.width() + word.width() > max_length {
-            result.push(string.trim_end().to_string());
-            string = word;
-            string.push(' ');
-        } else {
-            string.push_str(&word);
-            string.push(' ');
-        }
-    }
-
-    if !string.trim_end().is_empty() {
-        result.push(string.trim_end().to_string());
-    }
-
-    result
-}
-
 /// Truncate a list of strings using the provided truncation characters
 fn truncate_string_list(list: &mut [String], truncation_chars: &str) {
     let truncation_chars: Vec<char> = truncation_chars.chars().rev().collect();
@@ -1130,9 +1119,30 @@ fn truncate_string_list(list: &mut [String], truncation_chars: &str) {
     }
 }
 
+/// Truncates the lines like [`truncate_string_list`], keeping the styles of the text
+/// that is not replaced
+fn truncate_styled_lines(lines: &mut [StyledText], truncation_chars: &str) {
+    let originals: Vec<String> = lines.iter().map(StyledText::raw_string).collect();
+    let mut truncated = originals.clone();
+    truncate_string_list(&mut truncated, truncation_chars);
+
+    for ((line, original), truncated) in lines.iter_mut().zip(originals).zip(truncated) {
+        if original != truncated {
+            let kept = original
+                .char_indices()
+                .zip(truncated.chars())
+                .find(|((_, original), truncated)| original != truncated)
+                .map_or(original.len().min(truncated.len()), |((index, _), _)| index);
+            let mut new_line = line.slice(0, kept);
+            new_line.push((Style::new(), truncated[kept..].to_string()));
+            *line = new_line;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::Span;
+    use crate::{menu_functions::split_string, Span};
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -1440,9 +1450,50 @@ mod tests {
             extra: None,
             span: Span { start: 0, end: pos },
             append_whitespace: false,
+            ..Suggestion::default()
         }
     }
 
+    #[test]
+    fn test_selected_index() {
+        let mut completer = FakeCompleter::new(&["file1.txt", "file2.txt"]);
+        let mut menu = IdeMenu::default().with_name("testmenu");
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(menu.selected_index(), None);
+
+        menu.menu_event(MenuEvent::Activate(false));
+        assert_eq!(menu.selected_index(), Some(0));
+
+        menu.move_next();
+        assert_eq!(menu.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn toggling_the_sort_keeps_the_selection() {
+        let mut completer = FakeCompleter::new(&["c", "a", "b"]);
+        let mut menu =
+            IdeMenu::default().with_sort_orders(vec![MenuSort::None, MenuSort::Alphabetical]);
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(80, 10);
+
+        for event in [
+            MenuEvent::Activate(false),
+            MenuEvent::NextElement,
+            MenuEvent::NextElement,
+            MenuEvent::ToggleSort,
+        ] {
+            menu.menu_event(event);
+            menu.update_working_details(&mut editor, &mut completer, &painter);
+        }
+
+        let values: Vec<&str> = menu.values.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, vec!["a", "b", "c"]);
+        assert_eq!(menu.get_value().unwrap().value, "b");
+    }
+
     #[test]
     fn test_menu_replace_backtick() {
         // https://github.com/nushell/nushell/issues/7885
@@ -1,20 +1,32 @@
 use {
     super::{
-        menu_functions::{parse_selection_char, string_difference},
-        Menu, MenuEvent, MenuTextStyle,
+        menu_functions::{
+            center_menu, common_prefix, description_lines, fuzzy_match_with, glyph_cell,
+            glyph_column_width, hard_wrap, menu_width, parse_selection_char,
+            render_description_line, replace_in_buffer, scroll_to_end, string_difference,
+            truncate_with_marker,
+        },
+        MatchConfig, Menu, MenuAcceptBehavior, MenuEvent, MenuSort, MenuTheme,
     },
     crate::{
         core_editor::Editor,
-        painting::{estimate_single_line_wraps, Painter},
-        Completer, Suggestion, UndoBehavior,
+        painting::{estimate_single_line_wraps, line_width, Painter},
+        Completer, EditCommand, Span, StyledText, Suggestion, UndoBehavior,
     },
     nu_ansi_term::{ansi::RESET, Style},
-    std::{fmt::Write, iter::Sum},
+    std::{cmp::Ordering, collections::HashMap, fmt::Write, iter::Sum, ops::Range},
+    unicode_segmentation::UnicodeSegmentation,
     unicode_width::UnicodeWidthStr,
 };
 
-const SELECTION_CHAR: char = '!';
+const DEFAULT_SELECTION_CHAR: char = '!';
+// Number of more recent values after which a use of a value counts half as much
+// for the `HistoryOrdering::FrequencyRecency` score
+const FRECENCY_HALF_LIFE: f64 = 50.0;
+const SCROLLBAR_TRACK: char = '│';
+const SCROLLBAR_THUMB: char = '█';
 
+#[derive(Clone)]
 struct Page {
     size: usize,
     full: bool,
@@ -38,17 +50,147 @@ impl<'a> Sum<&'a Page> for Page {
     }
 }
 
+/// Pages of values fetched from the completer for the same input, so going back to
+/// a page doesn't query the completer again
+#[derive(Default)]
+struct PageCache {
+    /// Input and position the pages were fetched for
+    query: Option<(String, usize)>,
+    total: Option<usize>,
+    /// Values of each page index, together with the number of values requested
+    pages: HashMap<usize, (usize, Vec<Suggestion>)>,
+}
+
+impl PageCache {
+    fn fetch(
+        &mut self,
+        completer: &mut dyn Completer,
+        input: &str,
+        pos: usize,
+        page: usize,
+        skip: usize,
+        take: usize,
+    ) -> (Option<usize>, Vec<Suggestion>) {
+        let query = (input.to_string(), pos);
+        if self.query.as_ref() != Some(&query) {
+            self.query = Some(query);
+            self.total = completer.total_completions(input, pos);
+            self.pages.clear();
+        }
+
+        let values = match self.pages.get(&page) {
+            Some((size, values)) if *size == take => values.clone(),
+            _ => {
+                let values = completer.partial_complete(input, pos, skip, take);
+                self.pages.insert(page, (take, values.clone()));
+                values
+            }
+        };
+
+        (self.total, values)
+    }
+}
+
+/// Position of the menu stored when it is deactivated
+struct SavedPosition {
+    /// Line buffer when the menu was deactivated
+    buffer: String,
+    page: usize,
+    row_position: u16,
+    pages: Vec<Page>,
+}
+
+/// Number of values available when they are collected page by page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuerySize {
+    /// Total number of values reported by the completer
+    Known(usize),
+    /// The completer doesn't know its number of values. It is exhausted once a
+    /// page returned fewer values than requested
+    Unknown { exhausted: bool },
+}
+
+/// Position of the pane that shows the description of the selected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptionPanePosition {
+    /// The pane is shown below the values
+    Bottom,
+    /// The pane is shown at the right of the values, using one line per value
+    Side,
+}
+
+/// How the menu shows the values that don't fit, see [`ListMenu::with_overflow`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Values with more lines than the max entry lines are cut, the last line shown
+    /// is the ellipsis
+    #[default]
+    TruncateLines,
+    /// Values are shown on a single line, cut to the given number of columns and
+    /// ended with the ellipsis
+    TruncateChars(u16),
+    /// Values are shown on a single line cut at the terminal width, the selected
+    /// value is scrolled to show its end
+    Scroll,
+}
+
+/// Order of the values of a history search menu, see [`ListMenu::with_history_ordering`]
+///
+/// The values are expected from the most recent to the oldest, like a
+/// [`ReedlineMenu::HistoryMenu`](crate::ReedlineMenu::HistoryMenu) returns them. The
+/// uses of a command are the values identical to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryOrdering {
+    /// Keep the order of the history, repeated commands are shown for every use
+    #[default]
+    Recency,
+    /// Every command is shown once, the most used first. Commands used equally
+    /// often keep the order of the history
+    Frequency,
+    /// Every command is shown once, ranked by a score adding up its uses. A use
+    /// counts half as much for every 50 more recent values, so a command used
+    /// often outranks a command used once more recently, but not forever
+    FrequencyRecency,
+}
+
+impl HistoryOrdering {
+    /// Orders the values, which are given from the most recent to the oldest
+    pub fn order(&self, values: Vec<Suggestion>) -> Vec<Suggestion> {
+        if *self == HistoryOrdering::Recency {
+            return values;
+        }
+
+        // The commands in the order of their most recent use, with their score
+        let mut commands: Vec<(Suggestion, f64)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (age, value) in values.into_iter().enumerate() {
+            let weight = match self {
+                HistoryOrdering::Recency | HistoryOrdering::Frequency => 1.0,
+                HistoryOrdering::FrequencyRecency => 0.5f64.powf(age as f64 / FRECENCY_HALF_LIFE),
+            };
+            match positions.get(&value.value) {
+                Some(&position) => commands[position].1 += weight,
+                None => {
+                    positions.insert(value.value.clone(), commands.len());
+                    commands.push((value, weight));
+                }
+            }
+        }
+
+        commands.sort_by(|(_, lhs), (_, rhs)| rhs.partial_cmp(lhs).unwrap_or(Ordering::Equal));
+        commands.into_iter().map(|(command, _)| command).collect()
+    }
+}
+
 /// Struct to store the menu style
 /// Context menu definition
 pub struct ListMenu {
     /// Menu name
     name: String,
-    /// Menu coloring
-    color: MenuTextStyle,
+    /// Styles and markers of the menu
+    theme: MenuTheme,
     /// Number of records pulled until page is full
     page_size: usize,
-    /// Menu marker displayed when the menu is active
-    marker: String,
     /// Menu active status
     active: bool,
     /// Cached values collected when querying the completer.
@@ -60,11 +202,13 @@ pub struct ListMenu {
     /// row position in the menu. Starts from 0
     row_position: u16,
     /// Max size of the suggestions when querying without a search buffer
-    query_size: Option<usize>,
+    query_size: Option<QuerySize>,
     /// Max number of lines that are shown with large suggestions entries
     max_lines: u16,
-    /// Multiline marker
-    multiline_marker: String,
+    /// How the values that don't fit are shown
+    overflow: Overflow,
+    /// Marker shown where a value is cut
+    ellipsis: String,
     /// Registry of the number of entries per page that have been displayed
     pages: Vec<Page>,
     /// Page index
@@ -76,26 +220,106 @@ pub struct ListMenu {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// Order applied to the values collected from the completer
+    sort: MenuSort,
+    /// Orders cycled through by [`MenuEvent::ToggleSort`]
+    sort_orders: Vec<MenuSort>,
+    /// What enter does while the menu is open
+    accept_behavior: MenuAcceptBehavior,
+    /// Order of the values of a history search
+    history_ordering: HistoryOrdering,
+    /// Apply the only value found when the menu is activated instead of showing it
+    quick_complete: bool,
+    /// Compare the case of the values to find the prefix of a partial completion
+    case_sensitive: bool,
+    /// Ranks the values using a fuzzy match against the typed string instead
+    /// of relying on the completer to filter them
+    fuzzy_matching: bool,
+    /// Comparison of the characters by the fuzzy matching
+    match_config: MatchConfig,
+    /// String typed after the menu was activated. It is used to rank the values
+    /// when fuzzy matching is active and to highlight the matches
+    query: Option<String>,
+    /// Moving past the last or first entry jumps to the other end of the menu
+    wrap_around: bool,
+    /// Character used to select an entry by its index, e.g. `!3`
+    selection_char: char,
+    /// Show a scrollbar on the right edge of the menu
+    scrollbar: bool,
+    /// Width of the menu, used to place the scrollbar
+    terminal_width: u16,
+    /// Terminal width the menu is centered in
+    screen_width: u16,
+    /// Maximum number of columns used by the menu
+    max_width: Option<u16>,
+    /// Center the menu in the terminal when it's narrower than the terminal
+    centered: bool,
+    /// Show the glyph of the suggestions before the row number
+    glyph_column: bool,
+    /// Width of the glyph column calculated from the cached values
+    glyph_width: usize,
+    /// Pane used to show the description of the selected value instead of
+    /// showing the descriptions next to the values
+    description_pane: Option<DescriptionPanePosition>,
+    /// Restore the position of the menu when it is activated again with the
+    /// same line buffer
+    remember_position: bool,
+    /// Line buffer used the last time the values were updated
+    last_buffer: String,
+    /// Position stored when the menu was deactivated
+    saved_position: Option<SavedPosition>,
+    /// Pages already fetched from the completer, if they are cached
+    page_cache: Option<PageCache>,
+    /// Line edited instead of the line buffer to filter the values, if the menu
+    /// has its own filter line
+    filter_line: Option<Editor>,
+    /// Counts the values of each source of the page in the banner
+    source_breakdown: bool,
 }
 
 impl Default for ListMenu {
     fn default() -> Self {
         Self {
             name: "search_menu".to_string(),
-            color: MenuTextStyle::default(),
+            theme: MenuTheme::with_marker("? "),
             page_size: 10,
             active: false,
             values: Vec::new(),
             row_position: 0,
             page: 0,
             query_size: None,
-            marker: "? ".to_string(),
             max_lines: 5,
-            multiline_marker: ":::".to_string(),
+            overflow: Overflow::default(),
+            ellipsis: "...".to_string(),
             pages: Vec::new(),
             event: None,
             input: None,
             only_buffer_difference: true,
+            sort: MenuSort::default(),
+            sort_orders: Vec::new(),
+            accept_behavior: MenuAcceptBehavior::default(),
+            history_ordering: HistoryOrdering::default(),
+            quick_complete: false,
+            case_sensitive: true,
+            fuzzy_matching: false,
+            match_config: MatchConfig::default(),
+            query: None,
+            wrap_around: true,
+            selection_char: DEFAULT_SELECTION_CHAR,
+            scrollbar: false,
+            terminal_width: 0,
+            screen_width: 0,
+            max_width: None,
+            centered: false,
+            glyph_column: false,
+            glyph_width: 0,
+            description_pane: None,
+            remember_position: false,
+            last_buffer: String::new(),
+            saved_position: None,
+            page_cache: None,
+            filter_line: None,
+            source_breakdown: false,
         }
     }
 }
@@ -112,21 +336,35 @@ impl ListMenu {
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_text_style(mut self, text_style: Style) -> Self {
-        self.color.text_style = text_style;
+        self.theme.styles.text_style = text_style;
         self
     }
 
     /// Menu builder with new value for text style
     #[must_use]
     pub fn with_selected_text_style(mut self, selected_text_style: Style) -> Self {
-        self.color.selected_text_style = selected_text_style;
+        self.theme.styles.selected_text_style = selected_text_style;
         self
     }
 
     /// Menu builder with new value for description style
     #[must_use]
     pub fn with_description_text_style(mut self, description_text_style: Style) -> Self {
-        self.color.description_style = description_text_style;
+        self.theme.styles.description_style = description_text_style;
+        self
+    }
+
+    /// Menu builder with new value for the scrollbar style
+    #[must_use]
+    pub fn with_scrollbar_style(mut self, scrollbar_style: Style) -> Self {
+        self.theme.styles.scrollbar_style = scrollbar_style;
+        self
+    }
+
+    /// Menu builder with new value for the style of the matched text
+    #[must_use]
+    pub fn with_match_text_style(mut self, match_text_style: Style) -> Self {
+        self.theme.styles.match_style = match_text_style;
         self
     }
 
@@ -137,12 +375,206 @@ impl ListMenu {
         self
     }
 
+    /// Menu builder with new page cache value, by default `false`
+    ///
+    /// With it, the pages collected from [`Completer::partial_complete`] are kept
+    /// until the input changes or the menu is activated again, so an expensive
+    /// completer is queried once per page when moving back and forth
+    #[must_use]
+    pub fn with_page_cache(mut self, page_cache: bool) -> Self {
+        self.page_cache = page_cache.then(PageCache::default);
+        self
+    }
+
+    /// Menu builder with new filter line value, by default `false`
+    ///
+    /// With it, the text typed while the menu is active goes to a filter line shown
+    /// in the banner of the menu instead of the line buffer. The values are queried
+    /// with the filter line and the selected value is inserted at the insertion point,
+    /// so searching doesn't change the line that is edited
+    #[must_use]
+    pub fn with_filter_line(mut self, filter_line: bool) -> Self {
+        self.filter_line = filter_line.then(Editor::default);
+        self
+    }
+
+    /// Menu builder with new source breakdown value, by default `false`
+    ///
+    /// With it, the banner counts the values of the page by their
+    /// [`Suggestion::source`], e.g. `12 files, 3 commands`. The banner is unchanged
+    /// when no value has a source
+    #[must_use]
+    pub fn with_source_breakdown(mut self, source_breakdown: bool) -> Self {
+        self.source_breakdown = source_breakdown;
+        self
+    }
+
     /// Menu builder with new only buffer difference
     #[must_use]
     pub fn with_only_buffer_difference(mut self, only_buffer_difference: bool) -> Self {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder with new sort order for the values
+    #[must_use]
+    pub fn with_sort(mut self, sort: MenuSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Menu builder with the sort orders cycled through by [`MenuEvent::ToggleSort`],
+    /// e.g. bound to [`ReedlineEvent::MenuToggleSort`](crate::ReedlineEvent::MenuToggleSort).
+    /// Without them the sort of the menu is fixed
+    #[must_use]
+    pub fn with_sort_orders(mut self, sort_orders: Vec<MenuSort>) -> Self {
+        self.sort_orders = sort_orders;
+        self
+    }
+
+    /// Menu builder with new accept behavior, by default enter accepts the selection
+    #[must_use]
+    pub fn with_accept_behavior(mut self, accept_behavior: MenuAcceptBehavior) -> Self {
+        self.accept_behavior = accept_behavior;
+        self
+    }
+
+    /// Menu builder with new order of the values of a history search
+    ///
+    /// Ordered by frequency, the menu requests every value from the completer
+    /// instead of a page at a time, to count the uses of the commands. The ordering
+    /// is applied before the [`MenuSort`] and the fuzzy matching rank
+    #[must_use]
+    pub fn with_history_ordering(mut self, history_ordering: HistoryOrdering) -> Self {
+        self.history_ordering = history_ordering;
+        self
+    }
+
+    /// Menu builder with new fuzzy matching value
+    ///
+    /// When active, the menu requests every value from the completer and ranks
+    /// them using the typed string. Values that do not match are removed
+    #[must_use]
+    pub fn with_fuzzy_matching(mut self, fuzzy_matching: bool) -> Self {
+        self.fuzzy_matching = fuzzy_matching;
+        self
+    }
+
+    /// Menu builder with the case sensitivity and accent folding of the fuzzy matching,
+    /// by default it ignores the case and keeps the accents
+    #[must_use]
+    pub fn with_match_config(mut self, match_config: MatchConfig) -> Self {
+        self.match_config = match_config;
+        self
+    }
+
+    /// Menu builder with new wrap around value
+    ///
+    /// When disabled, moving past the first or last entry keeps the selection
+    /// at that entry instead of jumping to the other end of the menu
+    #[must_use]
+    pub fn with_wrap_around(mut self, wrap_around: bool) -> Self {
+        self.wrap_around = wrap_around;
+        self
+    }
+
+    /// Menu builder with new quick complete value
+    ///
+    /// When active and the quick completions of the line editor are on, see
+    /// [`Reedline::with_quick_completions`](crate::Reedline::with_quick_completions),
+    /// the menu isn't shown if the query finds a single value, which is inserted in
+    /// the buffer right away like in the [`ColumnarMenu`](crate::ColumnarMenu)
+    #[must_use]
+    pub fn with_quick_complete(mut self, quick_complete: bool) -> Self {
+        self.quick_complete = quick_complete;
+        self
+    }
+
+    /// Menu builder with new case sensitive value, by default `true`
+    ///
+    /// Without it, a partial completion finds the prefix shared by the values ignoring
+    /// their case and the typed string gets the case of the first value, see
+    /// [`Reedline::with_partial_completions`](crate::Reedline::with_partial_completions)
+    #[must_use]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Menu builder with new remember position value
+    ///
+    /// When active, the menu opens at the page and row it had when it was closed,
+    /// as long as the line buffer hasn't changed since then
+    #[must_use]
+    pub fn with_remember_position(mut self, remember_position: bool) -> Self {
+        self.remember_position = remember_position;
+        self
+    }
+
+    /// Menu builder with new scrollbar value
+    ///
+    /// The scrollbar shows the position of the current page among all the values.
+    /// It is only rendered when ansi coloring is active
+    #[must_use]
+    pub fn with_scrollbar(mut self, scrollbar: bool) -> Self {
+        self.scrollbar = scrollbar;
+        self
+    }
+
+    /// Menu builder with a description pane
+    ///
+    /// The pane shows the full description of the selected value, wrapped to the
+    /// terminal width and limited to `max_lines`. The pane keeps its size when the
+    /// selected value has no description
+    #[must_use]
+    pub fn with_description_pane(mut self, position: DescriptionPanePosition) -> Self {
+        self.description_pane = Some(position);
+        self
+    }
+
+    /// Menu builder with new glyph column value
+    ///
+    /// When active, the glyph of each suggestion is shown in an aligned column
+    /// before the row number
+    #[must_use]
+    pub fn with_glyph_column(mut self, glyph_column: bool) -> Self {
+        self.glyph_column = glyph_column;
+        self
+    }
+
+    /// Menu builder with new max width value
+    ///
+    /// When `Some`, the values are wrapped or cut at the given number of terminal
+    /// columns instead of the width of the terminal
+    #[must_use]
+    pub fn with_max_width(mut self, max_width: Option<u16>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Menu builder with new centered value
+    ///
+    /// When active, a menu narrower than the terminal, see
+    /// [`ListMenu::with_max_width`], is centered instead of left aligned
+    #[must_use]
+    pub fn with_centered(mut self, centered: bool) -> Self {
+        self.centered = centered;
+        self
+    }
+
+    /// Menu builder with new selection character
+    ///
+    /// # Panics
+    /// If the character is a whitespace
+    #[must_use]
+    pub fn with_selection_char(mut self, selection_char: char) -> Self {
+        assert!(
+            !selection_char.is_whitespace(),
+            "The selection character can't be a whitespace"
+        );
+        self.selection_char = selection_char;
+        self
+    }
 }
 
 // Menu functionality
@@ -150,7 +582,7 @@ impl ListMenu {
     /// Menu builder with menu marker
     #[must_use]
     pub fn with_marker(mut self, marker: String) -> Self {
-        self.marker = marker;
+        self.theme.marker = marker;
         self
     }
 
@@ -161,6 +593,30 @@ impl ListMenu {
         self
     }
 
+    /// Menu builder with new overflow behavior for the values that don't fit
+    #[must_use]
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Menu builder with new multiline marker
+    ///
+    /// The marker starts the continuation lines of values, with a single line
+    /// [`Overflow`] it separates the lines instead
+    #[must_use]
+    pub fn with_multiline_marker(mut self, multiline_marker: &str) -> Self {
+        self.theme.multiline_marker = multiline_marker.to_string();
+        self
+    }
+
+    /// Menu builder with new ellipsis shown where a value is cut
+    #[must_use]
+    pub fn with_ellipsis(mut self, ellipsis: &str) -> Self {
+        self.ellipsis = ellipsis.to_string();
+        self
+    }
+
     fn update_row_pos(&mut self, new_pos: Option<usize>) {
         if let (Some(row), Some(page)) = (new_pos, self.pages.get(self.page)) {
             let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
@@ -173,17 +629,114 @@ impl ListMenu {
 
     /// The number of rows an entry from the menu can take considering wrapping
     fn number_of_lines(&self, entry: &str, terminal_columns: u16) -> u16 {
-        number_of_lines(entry, self.max_lines as usize, terminal_columns)
+        let terminal_columns = terminal_columns.saturating_sub(self.glyph_width as u16);
+        match self.overflow {
+            Overflow::TruncateLines => {
+                number_of_lines(entry, self.max_lines as usize, terminal_columns)
+            }
+            Overflow::TruncateChars(columns) => {
+                let line = self.single_line(entry);
+                let line = truncate_with_marker(&line, columns as usize, &self.ellipsis);
+                1 + estimate_single_line_wraps(&line, terminal_columns) as u16
+            }
+            Overflow::Scroll => 1,
+        }
+    }
+
+    /// Joins the lines of a value with the multiline marker
+    fn single_line(&self, value: &str) -> String {
+        value
+            .lines()
+            .collect::<Vec<&str>>()
+            .join(&self.theme.multiline_marker)
+    }
+
+    /// The lines of the value up to the max entry lines, continued with the multiline
+    /// marker
+    fn printable_value_lines(&self, value: &str) -> String {
+        if value.lines().count() > self.max_lines as usize {
+            let lines = value.lines().take(self.max_lines as usize).fold(
+                String::new(),
+                |mut out_string, string| {
+                    let _ = write!(out_string, "{}\r\n{}", string, self.theme.multiline_marker);
+                    out_string
+                },
+            );
+
+            lines + &self.ellipsis
+        } else {
+            value.replace('\n', &format!("\r\n{}", self.theme.multiline_marker))
+        }
+    }
+
+    /// The value as it is printed after the row number, depending on the overflow
+    fn printable_value(&self, value: &str, index: usize, row_number: &str) -> String {
+        match self.overflow {
+            // The terminal wraps the lines at its width, narrower menus wrap them
+            Overflow::TruncateLines if self.max_width.is_some() => {
+                // The last column is kept for the selection marker
+                let columns = (self.terminal_width as usize).saturating_sub(
+                    self.indicator().width() + count_digits(index) as usize + self.glyph_width + 1,
+                );
+                self.printable_value_lines(value)
+                    .split("\r\n")
+                    .map(|line| hard_wrap(line, columns.max(1)).join("\r\n"))
+                    .collect::<Vec<String>>()
+                    .join("\r\n")
+            }
+            Overflow::TruncateLines => self.printable_value_lines(value),
+            Overflow::TruncateChars(columns) => {
+                truncate_with_marker(&self.single_line(value), columns as usize, &self.ellipsis)
+                    .into_owned()
+            }
+            Overflow::Scroll => {
+                // The last column is kept free to avoid wrapping. The row number holds the
+                // glyph cell of the value
+                let columns = (self.terminal_width as usize)
+                    .saturating_sub(self.indicator().width() + line_width(row_number) + 1);
+                let line = self.single_line(value);
+                if index == self.index() {
+                    scroll_to_end(&line, columns, &self.ellipsis).into_owned()
+                } else {
+                    truncate_with_marker(&line, columns, &self.ellipsis).into_owned()
+                }
+            }
+        }
     }
 
+    /// Number of values of the menu. When the completer doesn't know its size,
+    /// these are the values pulled so far
     fn total_values(&self) -> usize {
-        self.query_size.unwrap_or(self.values.len())
+        match self.query_size {
+            Some(QuerySize::Known(size)) => size,
+            Some(QuerySize::Unknown { .. }) => {
+                self.pages.iter().take(self.page).sum::<Page>().size + self.values.len()
+            }
+            None => self.values.len(),
+        }
+    }
+
+    /// Whether every value of the query is cached, not only a page of them
+    fn all_values_cached(&self) -> bool {
+        match self.query_size {
+            Some(QuerySize::Known(size)) => self.page == 0 && size <= self.values.len(),
+            Some(QuerySize::Unknown { exhausted }) => self.page == 0 && exhausted,
+            None => true,
+        }
     }
 
     fn values_until_current_page(&self) -> usize {
         self.pages.iter().take(self.page + 1).sum::<Page>().size
     }
 
+    /// Checks if there are no more values after the current page
+    fn is_last_page(&self) -> bool {
+        match self.query_size {
+            Some(QuerySize::Unknown { exhausted }) => exhausted,
+            _ => self.values_until_current_page() > self.total_values().saturating_sub(1),
+        }
+    }
+
     fn set_actual_page_size(&mut self, printable_entries: usize) {
         if let Some(page) = self.pages.get_mut(self.page) {
             page.full = page.size > printable_entries || page.full;
@@ -201,6 +754,33 @@ impl ListMenu {
         self.get_values().get(self.index()).cloned()
     }
 
+    /// Moves to the given page, clamped to the pages required to show all the values.
+    /// Pages that haven't been displayed yet are registered with the default page size
+    fn goto_page(&mut self, page: usize) {
+        let known_values = self.pages.iter().sum::<Page>().size;
+        let remaining_values = self.total_values().saturating_sub(known_values);
+        let page_size = self.page_size.max(1);
+        let remaining_pages = (remaining_values + page_size - 1) / page_size;
+        let last_page = (self.pages.len() + remaining_pages).saturating_sub(1);
+        let page = page.min(last_page);
+
+        while self.pages.len() <= page {
+            self.pages.push(Page {
+                size: self.page_size,
+                full: false,
+            });
+        }
+
+        // Pages before the selected one are complete, so moving forward from
+        // them doesn't extend their size
+        for previous in self.pages.iter_mut().take(page) {
+            previous.full = true;
+        }
+
+        self.page = page;
+        self.row_position = 0;
+    }
+
     /// Reset menu position
     fn reset_position(&mut self) {
         self.page = 0;
@@ -209,9 +789,10 @@ impl ListMenu {
     }
 
     fn printable_entries(&self, painter: &Painter) -> usize {
-        // The number 2 comes from the prompt line and the banner printed at the bottom
-        // of the menu
-        let available_lines = painter.screen_height().saturating_sub(2);
+        // The banner is printed at the bottom of the menu
+        let available_lines = painter
+            .menu_available_lines(self.min_rows())
+            .saturating_sub(1 + self.bottom_pane_lines());
         let (printable_entries, _) =
             self.get_values()
                 .iter()
@@ -224,9 +805,10 @@ impl ListMenu {
                                 + self.number_of_lines(
                                     &suggestion.value,
                                     //  to account for the index and the indicator e.g. 0: XXXX
-                                    painter.screen_width().saturating_sub(
-                                        self.indicator().width() as u16 + count_digits(lines),
-                                    ),
+                                    menu_width(painter.screen_width(), self.max_width)
+                                        .saturating_sub(
+                                            self.indicator().width() as u16 + count_digits(lines),
+                                        ),
                                 );
 
                             if new_total_lines < available_lines {
@@ -241,12 +823,20 @@ impl ListMenu {
         printable_entries
     }
 
+    /// Lines used by the description pane when it is shown below the values
+    fn bottom_pane_lines(&self) -> u16 {
+        match self.description_pane {
+            Some(DescriptionPanePosition::Bottom) => self.max_lines,
+            _ => 0,
+        }
+    }
+
     fn no_page_msg(&self, use_ansi_coloring: bool) -> String {
         let msg = "PAGE NOT FOUND";
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
-                self.color.selected_text_style.prefix(),
+                self.theme.styles.selected_text_style.prefix(),
                 msg,
                 RESET
             )
@@ -265,25 +855,221 @@ impl ListMenu {
         };
 
         let full_page = if page.full { "[FULL]" } else { "" };
-        let status_bar = format!(
+        let total = match self.query_size {
+            Some(QuerySize::Unknown { exhausted: false }) => "?".to_string(),
+            _ => self.total_values().to_string(),
+        };
+        let mut status_bar = format!(
             "Page {}: records {} - {}  total: {}  {}",
             self.page + 1,
             value_before,
             values_until,
-            self.total_values(),
+            total,
             full_page,
         );
+        if self.source_breakdown {
+            let breakdown = self.source_breakdown(page);
+            if !breakdown.is_empty() {
+                if !full_page.is_empty() {
+                    status_bar.push_str("  ");
+                }
+                status_bar.push_str(&breakdown);
+            }
+        }
+        if !self.sort_orders.is_empty() {
+            status_bar.push_str(&format!("  sort: {}", self.sort));
+        }
+
+        let filter = self
+            .filter_line
+            .as_ref()
+            .map_or_else(String::new, |filter_line| {
+                self.filter_line_string(filter_line, use_ansi_coloring)
+            });
 
         if use_ansi_coloring {
             format!(
-                "{}{}{}",
-                self.color.selected_text_style.prefix(),
+                "{}{}{}{}",
+                filter,
+                self.theme.styles.selected_text_style.prefix(),
                 status_bar,
                 RESET,
             )
         } else {
-            status_bar
+            format!("{filter}{status_bar}")
+        }
+    }
+
+    /// Number of values of each source on the page, in the order the sources appear
+    fn source_breakdown(&self, page: &Page) -> String {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for source in self
+            .get_values()
+            .iter()
+            .take(page.size)
+            .filter_map(|value| value.source.as_deref())
+        {
+            match counts.iter_mut().find(|(counted, _)| *counted == source) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((source, 1)),
+            }
+        }
+
+        counts
+            .iter()
+            .map(|(source, count)| format!("{count} {source}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The filter line shown at the start of the banner, the insertion point is
+    /// painted in reverse video
+    fn filter_line_string(&self, filter_line: &Editor, use_ansi_coloring: bool) -> String {
+        let buffer = filter_line.get_buffer().replace('\n', " ");
+        if !use_ansi_coloring {
+            return format!("{}{}  ", self.theme.marker, buffer);
+        }
+
+        let (before, after) = buffer.split_at(filter_line.insertion_point());
+        let mut graphemes = after.graphemes(true);
+        let cursor = graphemes.next().unwrap_or(" ");
+        format!(
+            "{}{}{}{}{}{}  ",
+            self.theme.marker,
+            before,
+            Style::new().reverse().prefix(),
+            cursor,
+            RESET,
+            graphemes.as_str(),
+        )
+    }
+
+    /// Rows of the current page covered by the scrollbar thumb
+    fn scrollbar_thumb(&self, rows: usize) -> Range<usize> {
+        let total = self.total_values().max(1);
+        let values_until = self.values_until_current_page().min(total);
+        let values_before = self.pages.iter().take(self.page).sum::<Page>().size;
+
+        let start = (values_before * rows / total).min(rows.saturating_sub(1));
+        let end = ((values_until * rows + total - 1) / total).clamp(start + 1, rows.max(1));
+
+        start..end
+    }
+
+    /// Adds the scrollbar cell at the right edge of the first line of an entry.
+    /// Entries that would need the last column of the terminal are left untouched
+    /// so the scrollbar doesn't push them into wrapping
+    fn add_scrollbar_cell(&self, entry: String, thumb: bool) -> String {
+        let (first_line, rest) = entry
+            .split_once(Self::end_of_line())
+            .unwrap_or((&entry, ""));
+        let available_width = (self.terminal_width as usize).saturating_sub(1);
+        let width = line_width(first_line);
+
+        if width >= available_width {
+            return entry;
+        }
+
+        let cell = if thumb {
+            SCROLLBAR_THUMB
+        } else {
+            SCROLLBAR_TRACK
+        };
+
+        format!(
+            "{}{}{}{}{}{}{}",
+            first_line,
+            " ".repeat(available_width - width),
+            self.theme.styles.scrollbar_style.prefix(),
+            cell,
+            RESET,
+            Self::end_of_line(),
+            rest
+        )
+    }
+
+    /// Lines of the description pane for the selected value wrapped to the given width.
+    /// The pane always has `max_lines` lines so the layout doesn't jump between values
+    fn description_pane_lines(&self, width: usize) -> Vec<StyledText> {
+        let mut lines = self
+            .get_value()
+            .and_then(|suggestion| suggestion.description)
+            .map(|description| description_lines(&description, width))
+            .unwrap_or_default();
+
+        lines.resize(self.max_lines as usize, StyledText::new());
+        lines
+    }
+
+    /// A line of the description pane, painted in the description style
+    fn description_pane_line(&self, line: &StyledText, use_ansi_coloring: bool) -> String {
+        if use_ansi_coloring {
+            format!(
+                "{}{}{}",
+                self.theme.styles.description_style.prefix(),
+                render_description_line(line, self.theme.styles.description_style),
+                RESET
+            )
+        } else {
+            line.raw_string()
+        }
+    }
+
+    /// Lines of the description pane shown below the values
+    fn bottom_description_pane(&self, use_ansi_coloring: bool) -> String {
+        self.description_pane_lines(self.terminal_width as usize)
+            .into_iter()
+            .map(|line| {
+                if line.buffer.is_empty() {
+                    Self::end_of_line().to_string()
+                } else {
+                    format!(
+                        "{}{}",
+                        self.description_pane_line(&line, use_ansi_coloring),
+                        Self::end_of_line()
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// Width of the values and of the description pane when it is shown at their side.
+    /// The last column is kept free for the scrollbar
+    fn side_pane_widths(&self) -> (usize, usize) {
+        let available_width = (self.terminal_width as usize).saturating_sub(1);
+        let values_width = available_width / 2;
+
+        (values_width, available_width - values_width - 1)
+    }
+
+    /// Adds a line of the side description pane to the first line of an entry.
+    /// Entries wider than the space reserved for the values are left untouched
+    fn add_side_pane_cell(
+        &self,
+        entry: String,
+        pane_line: &StyledText,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let (first_line, rest) = entry
+            .split_once(Self::end_of_line())
+            .unwrap_or((&entry, ""));
+        let (values_width, _) = self.side_pane_widths();
+        let width = line_width(first_line);
+
+        if width >= values_width || pane_line.buffer.is_empty() {
+            return entry;
         }
+
+        let pane_line = self.description_pane_line(pane_line, use_ansi_coloring);
+
+        format!(
+            "{}{} {}{}{}",
+            first_line,
+            " ".repeat(values_width - width),
+            pane_line,
+            Self::end_of_line(),
+            rest
+        )
     }
 
     /// End of line for menu
@@ -294,12 +1080,59 @@ impl ListMenu {
     /// Text style for menu
     fn text_style(&self, index: usize) -> String {
         if index == self.index() {
-            self.color.selected_text_style.prefix().to_string()
+            self.theme.styles.selected_text_style.prefix().to_string()
         } else {
-            self.color.text_style.prefix().to_string()
+            self.theme.styles.text_style.prefix().to_string()
         }
     }
 
+    /// Highlights the characters matched by the fuzzy query with the selected text style
+    fn highlight_fuzzy_match(&self, line: &str, index: usize) -> String {
+        let matched = self
+            .query
+            .as_ref()
+            .filter(|_| self.fuzzy_matching)
+            .and_then(|query| fuzzy_match_with(line, query, self.match_config));
+
+        match matched {
+            Some(matched) => line.char_indices().fold(
+                String::with_capacity(line.len()),
+                |mut out_string, (byte_index, c)| {
+                    if matched.indices.contains(&byte_index) {
+                        let _ = write!(
+                            out_string,
+                            "{}{}{}{}",
+                            self.theme.styles.selected_text_style.prefix(),
+                            c,
+                            RESET,
+                            self.text_style(index)
+                        );
+                    } else {
+                        out_string.push(c);
+                    }
+                    out_string
+                },
+            ),
+            None => line.to_string(),
+        }
+    }
+
+    /// Byte range of the line that matches the typed string.
+    /// Fuzzy matches are highlighted character by character instead
+    fn substring_match(&self, line: &str) -> Option<Range<usize>> {
+        if self.fuzzy_matching {
+            return None;
+        }
+
+        let query = self.query.as_ref()?;
+        // Lowercasing ASCII characters keeps the byte offsets valid in the original line
+        let start = line
+            .find(query.as_str())
+            .or_else(|| line.to_ascii_lowercase().find(&query.to_ascii_lowercase()))?;
+
+        Some(start..start + query.len())
+    }
+
     /// Creates default string that represents one line from a menu
     fn create_string(
         &self,
@@ -307,13 +1140,14 @@ impl ListMenu {
         description: Option<&str>,
         index: usize,
         row_number: &str,
+        match_range: Option<Range<usize>>,
         use_ansi_coloring: bool,
     ) -> String {
         let description = description.map_or("".to_string(), |desc| {
             if use_ansi_coloring {
                 format!(
                     "{}({}) {}",
-                    self.color.description_style.prefix(),
+                    self.theme.styles.description_style.prefix(),
                     desc,
                     RESET
                 )
@@ -323,16 +1157,40 @@ impl ListMenu {
         });
 
         if use_ansi_coloring {
+            let line = match match_range {
+                Some(range) => format!(
+                    "{}{}{}{}{}{}",
+                    &line[..range.start],
+                    self.theme.styles.match_style.prefix(),
+                    &line[range.clone()],
+                    RESET,
+                    self.text_style(index),
+                    &line[range.end..],
+                ),
+                None => self.highlight_fuzzy_match(line, index),
+            };
+
             format!(
                 "{}{}{}{}{}{}",
                 row_number,
                 description,
                 self.text_style(index),
-                &line,
+                line,
                 RESET,
                 Self::end_of_line(),
             )
         } else {
+            // Without ansi coloring the match is surrounded with markers
+            let line = match match_range {
+                Some(range) => format!(
+                    "{}*{}*{}",
+                    &line[..range.start],
+                    &line[range.clone()],
+                    &line[range.end..],
+                ),
+                None => line.to_string(),
+            };
+
             // If no ansi coloring is found, then the selection word is
             // the line in uppercase
             let line_str = if index == self.index() {
@@ -354,7 +1212,16 @@ impl Menu for ListMenu {
 
     /// Menu indicator
     fn indicator(&self) -> &str {
-        self.marker.as_str()
+        self.theme.marker.as_str()
+    }
+
+    /// The styles and markers of the menu are replaced by the theme
+    fn apply_theme(&mut self, theme: &MenuTheme) {
+        self.theme = theme.clone();
+    }
+
+    fn accept_behavior(&self) -> MenuAcceptBehavior {
+        self.accept_behavior
     }
 
     /// Deactivates context menu
@@ -362,29 +1229,89 @@ impl Menu for ListMenu {
         self.active
     }
 
-    /// There is no use for quick complete for the menu
+    /// Quick complete is only used if it was turned on
     fn can_quick_complete(&self) -> bool {
-        false
+        self.quick_complete
     }
 
-    /// The menu should not try to auto complete to avoid comparing
-    /// all registered values
+    /// The prefix shared by the values is inserted if it extends the typed string.
+    /// Values fetched a page at a time are not compared, because the prefix of a
+    /// page could differ from the prefix of all the values
     fn can_partially_complete(
         &mut self,
-        _values_updated: bool,
-        _editor: &mut Editor,
-        _completer: &mut dyn Completer,
+        values_updated: bool,
+        editor: &mut Editor,
+        completer: &mut dyn Completer,
     ) -> bool {
-        false
+        // If the values were already updated (e.g. quick completions are true)
+        // there is no need to update the values from the menu
+        if !values_updated {
+            self.update_values(editor, completer);
+        }
+        if !self.all_values_cached() {
+            return false;
+        }
+
+        let (span, prefix) = match self
+            .values
+            .first()
+            .zip(common_prefix(&self.values, self.case_sensitive))
+        {
+            Some((first, prefix)) => (first.span, prefix.to_string()),
+            None => return false,
+        };
+        let Span { start, end } = span.clamp_to(editor.line_buffer().len());
+        let typed = &editor.get_buffer()[start..end];
+
+        // make sure that the partial completion does not overwrite user entered input
+        let extends_input = prefix.len() > typed.len()
+            && prefix.get(..typed.len()).map_or(false, |start| {
+                start == typed
+                    || (!self.case_sensitive && start.to_lowercase() == typed.to_lowercase())
+            });
+        if !extends_input {
+            return false;
+        }
+
+        let mut line_buffer = editor.line_buffer().clone();
+        let offset = line_buffer.insertion_point() + prefix.len() - (end - start);
+        line_buffer.replace_range(start..end, &prefix);
+        line_buffer.set_insertion_point(offset);
+        editor.set_line_buffer(line_buffer, UndoBehavior::Completion);
+
+        // The values need to be updated because the spans need to be
+        // recalculated for accurate replacement in the string
+        self.update_values(editor, completer);
+
+        true
     }
 
     /// Selects what type of event happened with the menu
     fn menu_event(&mut self, event: MenuEvent) {
         match &event {
-            MenuEvent::Activate(_) => self.active = true,
+            MenuEvent::Activate(_) => {
+                // The values can be used before the menu is painted, by a quick complete
+                self.active = true;
+                self.reset_position();
+                if let Some(filter_line) = self.filter_line.as_mut() {
+                    *filter_line = Editor::default();
+                }
+                if let Some(page_cache) = self.page_cache.as_mut() {
+                    *page_cache = PageCache::default();
+                }
+            }
             MenuEvent::Deactivate => {
                 self.active = false;
                 self.input = None;
+
+                if self.remember_position {
+                    self.saved_position = Some(SavedPosition {
+                        buffer: std::mem::take(&mut self.last_buffer),
+                        page: self.page,
+                        row_position: self.row_position,
+                        pages: self.pages.clone(),
+                    });
+                }
             }
             _ => {}
         }
@@ -394,8 +1321,18 @@ impl Menu for ListMenu {
 
     /// Collecting the value from the completer to be shown in the menu
     fn update_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        if self.remember_position {
+            self.last_buffer = editor.get_buffer().to_string();
+        }
+
         let line_buffer = editor.line_buffer();
-        let (pos, input) = if self.only_buffer_difference {
+        let filter = self
+            .filter_line
+            .as_ref()
+            .map(|filter_line| filter_line.get_buffer().to_string());
+        let (pos, input) = if let Some(filter) = &filter {
+            (filter.len(), filter.as_str())
+        } else if self.only_buffer_difference {
             match &self.input {
                 Some(old_string) => {
                     let (start, input) = string_difference(line_buffer.get_buffer(), old_string);
@@ -414,7 +1351,7 @@ impl Menu for ListMenu {
             )
         };
 
-        let parsed = parse_selection_char(input, SELECTION_CHAR);
+        let parsed = parse_selection_char(input, self.selection_char);
         self.update_row_pos(parsed.index);
 
         // If there are no row selector and the menu has an Edit event, this clears
@@ -423,9 +1360,18 @@ impl Menu for ListMenu {
             self.reset_position();
         }
 
-        self.values = if parsed.remainder.is_empty() {
-            self.query_size = Some(completer.total_completions(parsed.remainder, pos));
+        self.query = if parsed.remainder.is_empty() {
+            None
+        } else {
+            Some(parsed.remainder.to_string())
+        };
+
+        // The selection marker is not sent to the completer so it can't be confused
+        // with the searched string. The spans are later extended to replace it
+        let query_pos = pos.saturating_sub(input.len() - parsed.remainder.len());
 
+        let paged = self.history_ordering == HistoryOrdering::Recency;
+        self.values = if parsed.remainder.is_empty() && paged {
             let skip = self.pages.iter().take(self.page).sum::<Page>().size;
             let take = self
                 .pages
@@ -433,11 +1379,95 @@ impl Menu for ListMenu {
                 .map(|page| page.size)
                 .unwrap_or(self.page_size);
 
-            completer.partial_complete(input, pos, skip, take)
+            let (total, mut values) = match self.page_cache.as_mut() {
+                Some(page_cache) => page_cache.fetch(
+                    completer,
+                    parsed.remainder,
+                    query_pos,
+                    self.page,
+                    skip,
+                    take,
+                ),
+                None => (
+                    completer.total_completions(parsed.remainder, query_pos),
+                    completer.partial_complete(parsed.remainder, query_pos, skip, take),
+                ),
+            };
+            self.query_size = Some(match total {
+                Some(total) => QuerySize::Known(total),
+                None => QuerySize::Unknown {
+                    exhausted: values.len() < take,
+                },
+            });
+
+            self.sort.sort(&mut values);
+            values
+        } else if self.fuzzy_matching && !parsed.remainder.is_empty() {
+            self.query_size = None;
+
+            // The values returned for the typed string are ranked by their fuzzy
+            // score, the ones that don't match it are dropped
+            let mut values = self
+                .history_ordering
+                .order(completer.complete(parsed.remainder, query_pos))
+                .into_iter()
+                .filter_map(|suggestion| {
+                    fuzzy_match_with(&suggestion.value, parsed.remainder, self.match_config)
+                        .map(|res| (res.score, suggestion))
+                })
+                .collect::<Vec<(usize, Suggestion)>>();
+
+            values.sort_by(|(lhs_score, lhs), (rhs_score, rhs)| {
+                rhs_score
+                    .cmp(lhs_score)
+                    .then_with(|| self.sort.compare(lhs, rhs))
+            });
+            values
+                .into_iter()
+                .map(|(_, suggestion)| suggestion)
+                .collect()
         } else {
             self.query_size = None;
-            completer.complete(input, pos)
+            let mut values = self
+                .history_ordering
+                .order(completer.complete(parsed.remainder, query_pos));
+            self.sort.sort(&mut values);
+            values
+        };
+
+        if self.fuzzy_matching || query_pos != pos {
+            for suggestion in self.values.iter_mut() {
+                suggestion.span = Span::new(suggestion.span.start.min(pos), pos);
+            }
+        }
+
+        // The filter is not part of the buffer, the values are inserted at the cursor
+        if self.filter_line.is_some() {
+            let insertion_point = editor.insertion_point();
+            for suggestion in self.values.iter_mut() {
+                suggestion.span = Span::new(insertion_point, insertion_point);
+            }
         }
+
+        self.glyph_width = if self.glyph_column {
+            glyph_column_width(&self.values)
+        } else {
+            0
+        };
+    }
+
+    /// Collecting the value from the completer keeping the selected value
+    fn refresh_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        let selected = self.get_value();
+        self.update_values(editor, completer);
+
+        self.row_position = selected
+            .and_then(|selected| {
+                self.get_values()
+                    .iter()
+                    .position(|suggestion| suggestion.value == selected.value)
+            })
+            .unwrap_or(0) as u16;
     }
 
     /// Gets values from cached values that will be displayed in the menu
@@ -467,28 +1497,68 @@ impl Menu for ListMenu {
         }
     }
 
+    fn selected_index(&self) -> Option<usize> {
+        if !self.active || self.get_values().is_empty() {
+            return None;
+        }
+
+        let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
+        Some(values_before_page + self.index())
+    }
+
+    /// Only the values of the current page are cached
+    fn selected_value(&self) -> Option<Suggestion> {
+        if self.active {
+            self.get_value()
+        } else {
+            None
+        }
+    }
+
     /// The buffer gets cleared with the actual value
     fn replace_in_buffer(&self, editor: &mut Editor) {
-        if let Some(Suggestion {
-            mut value,
-            span,
-            append_whitespace,
-            ..
-        }) = self.get_value()
-        {
-            let buffer_len = editor.line_buffer().len();
-            let start = span.start.min(buffer_len);
-            let end = span.end.min(buffer_len);
-            if append_whitespace {
-                value.push(' ');
+        replace_in_buffer(self.get_value(), editor);
+    }
+
+    /// The commands edit the filter line if the menu has one
+    fn edit_filter(&mut self, commands: &[EditCommand]) -> bool {
+        match self.filter_line.as_mut() {
+            Some(filter_line) => {
+                for command in commands {
+                    filter_line.run_edit_command(command);
+                }
+                true
             }
-            let mut line_buffer = editor.line_buffer().clone();
-            line_buffer.replace_range(start..end, &value);
+            None => false,
+        }
+    }
 
-            let mut offset = line_buffer.insertion_point();
-            offset += value.len().saturating_sub(end.saturating_sub(start));
-            line_buffer.set_insertion_point(offset);
-            editor.set_line_buffer(line_buffer, UndoBehavior::CreateUndoPoint);
+    /// Selects the value painted at the given line of the menu
+    fn select_line(&mut self, line: u16) -> bool {
+        let page_size = self.pages.get(self.page).map_or(0, |page| page.size);
+        let mut first_line = 0;
+        let index = self
+            .get_values()
+            .iter()
+            .take(page_size)
+            .enumerate()
+            .position(|(index, suggestion)| {
+                //  to account for the index and the indicator e.g. 0: XXXX
+                first_line += self.number_of_lines(
+                    &suggestion.value,
+                    self.terminal_width
+                        .saturating_sub(self.indicator().width() as u16 + count_digits(index)),
+                );
+                line < first_line
+            });
+
+        match index {
+            Some(index) => {
+                let selected = self.index() == index;
+                self.row_position = index as u16;
+                selected
+            }
+            None => false,
         }
     }
 
@@ -498,6 +1568,9 @@ impl Menu for ListMenu {
         completer: &mut dyn Completer,
         painter: &Painter,
     ) {
+        self.screen_width = painter.screen_width();
+        self.terminal_width = menu_width(self.screen_width, self.max_width);
+
         if let Some(event) = self.event.clone() {
             match event {
                 MenuEvent::Activate(_) => {
@@ -509,12 +1582,27 @@ impl Menu for ListMenu {
                         None
                     };
 
-                    self.update_values(editor, completer);
+                    // The stored position is only valid if the buffer is the same
+                    // used to collect the values that were shown
+                    let saved_position = self
+                        .saved_position
+                        .take()
+                        .filter(|saved| saved.buffer == editor.get_buffer());
 
-                    self.pages.push(Page {
-                        size: self.printable_entries(painter),
-                        full: false,
-                    });
+                    if let Some(saved) = saved_position {
+                        self.page = saved.page;
+                        self.row_position = saved.row_position;
+                        self.pages = saved.pages;
+
+                        self.update_values(editor, completer);
+                    } else {
+                        self.update_values(editor, completer);
+
+                        self.pages.push(Page {
+                            size: self.printable_entries(painter),
+                            full: false,
+                        });
+                    }
                 }
                 MenuEvent::Deactivate => {
                     self.active = false;
@@ -532,8 +1620,10 @@ impl Menu for ListMenu {
 
                     if let Some(page) = self.pages.get(self.page) {
                         if new_pos >= page.size as u16 {
-                            self.event = Some(MenuEvent::NextPage);
-                            self.update_working_details(editor, completer, painter);
+                            if self.wrap_around || !self.is_last_page() {
+                                self.event = Some(MenuEvent::NextPage);
+                                self.update_working_details(editor, completer, painter);
+                            }
                         } else {
                             self.row_position = new_pos;
                         }
@@ -542,7 +1632,7 @@ impl Menu for ListMenu {
                 MenuEvent::PreviousElement | MenuEvent::MoveUp | MenuEvent::MoveLeft => {
                     if let Some(new_pos) = self.row_position.checked_sub(1) {
                         self.row_position = new_pos;
-                    } else {
+                    } else if self.wrap_around || self.page > 0 {
                         let page = if let Some(page) = self.page.checked_sub(1) {
                             self.pages.get(page)
                         } else {
@@ -558,7 +1648,7 @@ impl Menu for ListMenu {
                     }
                 }
                 MenuEvent::NextPage => {
-                    if self.values_until_current_page() <= self.total_values().saturating_sub(1) {
+                    if !self.is_last_page() {
                         if let Some(page) = self.pages.get_mut(self.page) {
                             if page.full {
                                 self.row_position = 0;
@@ -576,18 +1666,32 @@ impl Menu for ListMenu {
 
                         self.update_values(editor, completer);
                         self.set_actual_page_size(self.printable_entries(painter));
-                    } else {
+                    } else if self.wrap_around {
                         self.row_position = 0;
                         self.page = 0;
                         self.update_values(editor, completer);
                     }
                 }
-                MenuEvent::PreviousPage => {
-                    match self.page.checked_sub(1) {
-                        Some(page_num) => self.page = page_num,
-                        None => self.page = self.pages.len().saturating_sub(1),
+                MenuEvent::PreviousPage => match self.page.checked_sub(1) {
+                    Some(page_num) => {
+                        self.page = page_num;
+                        self.update_values(editor, completer);
+                    }
+                    None if self.wrap_around => {
+                        self.page = self.pages.len().saturating_sub(1);
+                        self.update_values(editor, completer);
                     }
+                    None => {}
+                },
+                MenuEvent::GotoPage(page) => {
+                    self.goto_page(page);
                     self.update_values(editor, completer);
+                    self.set_actual_page_size(self.printable_entries(painter));
+                }
+                MenuEvent::ToggleSort => {
+                    self.sort = self.sort.toggle(&self.sort_orders);
+                    self.refresh_values(editor, completer);
+                    self.set_actual_page_size(self.printable_entries(painter));
                 }
             }
 
@@ -598,6 +1702,7 @@ impl Menu for ListMenu {
     /// Calculates the real required lines for the menu considering how many lines
     /// wrap the terminal and if an entry is larger than the remaining lines
     fn menu_required_lines(&self, terminal_columns: u16) -> u16 {
+        let terminal_columns = menu_width(terminal_columns, self.max_width);
         let mut entry_index = 0;
         self.get_values().iter().fold(0, |total_lines, suggestion| {
             //  to account for the the index and the indicator e.g. 0: XXXX
@@ -611,58 +1716,93 @@ impl Menu for ListMenu {
             entry_index += 1;
             ret
         }) + 1
+            + self.bottom_pane_lines()
     }
 
     /// Creates the menu representation as a string which will be painted by the painter
     fn menu_string(&self, _available_lines: u16, use_ansi_coloring: bool) -> String {
         let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
-        match self.pages.get(self.page) {
+        let menu = match self.pages.get(self.page) {
             Some(page) => {
+                let rows = self.get_values().iter().take(page.size).count();
+                let thumb = self.scrollbar_thumb(rows);
+                let side_pane = match self.description_pane {
+                    Some(DescriptionPanePosition::Side) => {
+                        self.description_pane_lines(self.side_pane_widths().1)
+                    }
+                    _ => Vec::new(),
+                };
+
                 let lines_string = self
                     .get_values()
                     .iter()
                     .take(page.size)
                     .enumerate()
                     .map(|(index, suggestion)| {
+                        let row_number = format!(
+                            "{}{}: ",
+                            glyph_cell(suggestion, self.glyph_width),
+                            index + values_before_page
+                        );
+
                         // Final string with colors
-                        let line = &suggestion.value;
-                        let line = if line.lines().count() > self.max_lines as usize {
-                            let lines = line.lines().take(self.max_lines as usize).fold(
-                                String::new(),
-                                |mut out_string, string| {
-                                    let _ = write!(
-                                        out_string,
-                                        "{}\r\n{}",
-                                        string, self.multiline_marker
-                                    );
-                                    out_string
-                                },
-                            );
-
-                            lines + "..."
+                        let line = self.printable_value(&suggestion.value, index, &row_number);
+
+                        // The descriptions are shown in the pane when there is one
+                        let description = if self.description_pane.is_some() {
+                            None
                         } else {
-                            line.replace('\n', &format!("\r\n{}", self.multiline_marker))
+                            suggestion
+                                .description
+                                .as_ref()
+                                .map(|description| description.text().replace('\n', " "))
                         };
 
-                        let row_number = format!("{}: ", index + values_before_page);
-
-                        self.create_string(
+                        let entry = self.create_string(
                             &line,
-                            suggestion.description.as_deref(),
+                            description.as_deref(),
                             index,
                             &row_number,
+                            self.substring_match(&line),
                             use_ansi_coloring,
-                        )
+                        );
+
+                        let entry = match side_pane.get(index) {
+                            Some(pane_line) => {
+                                self.add_side_pane_cell(entry, pane_line, use_ansi_coloring)
+                            }
+                            None => entry,
+                        };
+
+                        if self.scrollbar && use_ansi_coloring {
+                            self.add_scrollbar_cell(entry, thumb.contains(&index))
+                        } else {
+                            entry
+                        }
                     })
                     .collect::<String>();
 
+                let bottom_pane = match self.description_pane {
+                    Some(DescriptionPanePosition::Bottom) => {
+                        self.bottom_description_pane(use_ansi_coloring)
+                    }
+                    _ => String::new(),
+                };
+
                 format!(
-                    "{}{}",
+                    "{}{}{}",
                     lines_string,
+                    bottom_pane,
                     self.banner_message(page, use_ansi_coloring)
                 )
             }
             None => self.no_page_msg(use_ansi_coloring),
+        };
+
+        if self.centered {
+            center_menu(menu, self.terminal_width, self.screen_width)
+        } else {
+            menu
         }
     }
 
@@ -715,6 +1855,1133 @@ fn count_digits(mut n: usize) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{DefaultCompleter, Description, MenuTextStyle, UndoBehavior};
+    use nu_ansi_term::Color;
+    use rstest::rstest;
+
+    struct FakeCompleter {
+        completions: Vec<String>,
+        filtered: bool,
+    }
+
+    impl FakeCompleter {
+        fn new(completions: &[&str]) -> Self {
+            Self {
+                completions: completions.iter().map(|c| c.to_string()).collect(),
+                filtered: true,
+            }
+        }
+
+        /// Completer returning all its values whatever the line, leaving the
+        /// matching to the menu
+        fn unfiltered(completions: &[&str]) -> Self {
+            Self {
+                filtered: false,
+                ..Self::new(completions)
+            }
+        }
+    }
+
+    impl Completer for FakeCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.completions
+                .iter()
+                .filter(|c| !self.filtered || c.contains(line))
+                .map(|c| Suggestion {
+                    value: c.to_string(),
+                    description: None,
+                    extra: None,
+                    span: Span::new(pos - line.len(), pos),
+                    append_whitespace: false,
+                    ..Suggestion::default()
+                })
+                .collect()
+        }
+    }
+
+    fn menu_values(menu: &ListMenu) -> Vec<&str> {
+        menu.get_values()
+            .iter()
+            .map(|suggestion| suggestion.value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn filter_line_edits_query_the_values() {
+        let mut completer = FakeCompleter::new(&["git commit", "cargo test", "git checkout"]);
+        let mut menu = ListMenu::default().with_filter_line(true);
+        let mut editor = Editor::default();
+        editor.set_buffer("echo ".to_string(), UndoBehavior::CreateUndoPoint);
+        menu.menu_event(MenuEvent::Activate(false));
+
+        assert!(menu.edit_filter(&[
+            EditCommand::InsertString("git cx".to_string()),
+            EditCommand::Backspace,
+            EditCommand::InsertChar('h'),
+        ]));
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(editor.get_buffer(), "echo ");
+        assert_eq!(menu_values(&menu), vec!["git checkout"]);
+        assert_eq!(menu.get_values()[0].span, Span::new(5, 5));
+        assert!(menu
+            .banner_message(
+                &Page {
+                    size: 1,
+                    full: false
+                },
+                false
+            )
+            .starts_with("? git ch  "));
+    }
+
+    #[test]
+    fn menus_without_filter_line_leave_the_edits_to_the_buffer() {
+        let mut menu = ListMenu::default();
+
+        assert!(!menu.edit_filter(&[EditCommand::InsertChar('a')]));
+    }
+
+    #[test]
+    fn menus_are_rendered_with_the_applied_theme() {
+        let mut completer = FakeCompleter::new(&["git\ncommit", "git push"]);
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(80, 20);
+        let theme = MenuTheme {
+            styles: MenuTextStyle {
+                selected_text_style: Color::Red.bold(),
+                text_style: Color::Blue.normal(),
+                ..MenuTextStyle::default()
+            },
+            marker: "> ".to_string(),
+            multiline_marker: "~ ".to_string(),
+        };
+        let mut menu = ListMenu::default().with_only_buffer_difference(false);
+
+        menu.apply_theme(&theme);
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+        let menu_string = menu.menu_string(10, true);
+
+        assert_eq!(menu.indicator(), "> ");
+        assert!(menu_string.contains(&format!("{}git\r\n~ commit", Color::Red.bold().prefix())));
+        assert!(menu_string.contains(&format!("{}git push", Color::Blue.normal().prefix())));
+    }
+
+    #[test]
+    fn values_wrap_at_the_max_width() {
+        let long_value = "a".repeat(150);
+        let mut completer = FakeCompleter::new(&[&long_value]);
+        let mut editor = Editor::default();
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(200, 50);
+
+        let mut wide_menu = ListMenu::default();
+        wide_menu.menu_event(MenuEvent::Activate(false));
+        wide_menu.update_working_details(&mut editor, &mut completer, &painter);
+        assert_eq!(wide_menu.menu_required_lines(200), 2);
+
+        let mut menu = ListMenu::default().with_max_width(Some(80));
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_working_details(&mut editor, &mut completer, &painter);
+        assert_eq!(menu.menu_required_lines(200), 3);
+
+        let menu_string = menu.menu_string(10, false);
+        let lines: Vec<&str> = menu_string.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            assert!(line_width(line) <= 80, "{line} is too wide");
+        }
+    }
+
+    #[test]
+    fn fuzzy_matching_ranks_values() {
+        let mut completer =
+            FakeCompleter::unfiltered(&["git commit", "cargo test", "git checkout"]);
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_fuzzy_matching(true);
+        let mut editor = Editor::default();
+        editor.set_buffer("gco".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu_values(&menu), vec!["git commit", "git checkout"]);
+        assert_eq!(menu.total_values(), 2);
+        assert!(menu
+            .get_values()
+            .iter()
+            .all(|suggestion| suggestion.span == Span::new(0, 3)));
+    }
+
+    #[rstest]
+    #[case(true, vec!["résumé.pdf"])]
+    #[case(false, vec![])]
+    fn fuzzy_matching_folds_accents(#[case] fold_accents: bool, #[case] expected: Vec<&str>) {
+        let mut completer = FakeCompleter::unfiltered(&["résumé.pdf", "notes.txt"]);
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_fuzzy_matching(true)
+            .with_match_config(MatchConfig {
+                fold_accents,
+                ..MatchConfig::default()
+            });
+        let mut editor = Editor::default();
+        editor.set_buffer("resume".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu_values(&menu), expected);
+    }
+
+    #[test]
+    fn fuzzy_matching_ranks_the_values_of_the_default_completer() {
+        let mut completer = DefaultCompleter::new_with_wordlen(
+            ["cargo", "carpet", "cat", "scarf"]
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+            2,
+        );
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_fuzzy_matching(true);
+        let mut editor = Editor::default();
+        editor.set_buffer("cargof".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+        assert!(menu_values(&menu).is_empty());
+
+        editor.set_buffer("car".to_string(), UndoBehavior::CreateUndoPoint);
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu_values(&menu), vec!["cargo", "carpet"]);
+        assert!(menu
+            .get_values()
+            .iter()
+            .all(|suggestion| suggestion.span == Span::new(0, 3)));
+    }
+
+    #[test]
+    fn fuzzy_matching_disabled_uses_completer() {
+        let mut completer = FakeCompleter::new(&["git commit", "cargo test", "git checkout"]);
+        let mut menu = ListMenu::default().with_only_buffer_difference(false);
+        let mut editor = Editor::default();
+        editor.set_buffer("gco".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert!(menu_values(&menu).is_empty());
+    }
+
+    #[test]
+    fn substring_match_highlight_without_ansi() {
+        let mut completer = FakeCompleter::new(&["git commit", "recommend"]);
+        let mut menu = ListMenu::default().with_only_buffer_difference(false);
+        let mut editor = Editor::default();
+        editor.set_buffer("comm".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+        menu.pages.push(Page {
+            size: 10,
+            full: false,
+        });
+
+        assert_eq!(
+            menu.menu_string(10, false),
+            "0: >GIT *COMM*IT\r\n1: re*comm*end\r\nPage 1: records 0 - 9  total: 2  "
+        );
+    }
+
+    struct GlyphCompleter(FakeCompleter);
+
+    impl Completer for GlyphCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let mut values = self.0.complete(line, pos);
+            for suggestion in values.iter_mut() {
+                if suggestion.value.ends_with('/') {
+                    suggestion.glyph = Some("📁".to_string());
+                }
+            }
+            values
+        }
+    }
+
+    #[test]
+    fn glyph_column_is_aligned_before_row_number() {
+        let mut completer = GlyphCompleter(FakeCompleter::new(&["src/", "build.rs"]));
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_glyph_column(true);
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+        menu.pages.push(Page {
+            size: 10,
+            full: false,
+        });
+
+        assert_eq!(menu.glyph_width, 3);
+        assert_eq!(
+            menu.menu_string(10, false),
+            "📁 0: >SRC/\r\n   1: build.rs\r\nPage 1: records 0 - 9  total: 2  "
+        );
+        assert_eq!(menu.number_of_lines("0: build.rs", 12), 2);
+    }
+
+    #[test]
+    fn glyph_column_disabled_by_default() {
+        let mut completer = GlyphCompleter(FakeCompleter::new(&["src/", "build.rs"]));
+        let mut menu = ListMenu::default().with_only_buffer_difference(false);
+        let mut editor = Editor::default();
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu.glyph_width, 0);
+        assert_eq!(menu.number_of_lines("0: build.rs", 12), 1);
+    }
+
+    #[rstest]
+    #[case::completer_order(MenuSort::None, vec!["git pull", "git add", "git pull", "git add"])]
+    #[case::alphabetical(MenuSort::Alphabetical, vec!["git add", "git add", "git pull", "git pull"])]
+    fn sort_is_deterministic(#[case] sort: MenuSort, #[case] expected: Vec<&str>) {
+        let mut completer = FakeCompleter::new(&["git pull", "git add", "git pull", "git add"]);
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_sort(sort);
+        let mut editor = Editor::default();
+        editor.set_buffer("git".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu_values(&menu), expected);
+    }
+
+    #[test]
+    fn sort_by_source_groups_the_values() {
+        let value = |value: &str, source: Option<&str>| Suggestion {
+            source: source.map(str::to_string),
+            ..Suggestion::new(value, Span::default())
+        };
+        let mut values = vec![
+            value("git pull", Some("git")),
+            value("ls", Some("files")),
+            value("echo", None),
+            value("git add", Some("git")),
+            value("cd", Some("files")),
+        ];
+
+        MenuSort::BySource.sort(&mut values);
+
+        let values: Vec<&str> = values.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, vec!["echo", "ls", "cd", "git pull", "git add"]);
+    }
+
+    #[test]
+    fn sort_breaks_fuzzy_score_ties() {
+        let mut completer = FakeCompleter::unfiltered(&["gc b", "gc a", "git commit", "gc c"]);
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_fuzzy_matching(true)
+            .with_sort(MenuSort::Alphabetical);
+        let mut editor = Editor::default();
+        editor.set_buffer("gc".to_string(), UndoBehavior::CreateUndoPoint);
+
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(
+            menu_values(&menu),
+            vec!["gc a", "gc b", "gc c", "git commit"]
+        );
+    }
+
+    #[test]
+    fn substring_match_multibyte() {
+        let menu = ListMenu {
+            query: Some("ｓｈ".to_string()),
+            ..Default::default()
+        };
+
+        let line = "ｎｕｓｈｅｌｌ";
+        let range = menu.substring_match(line).unwrap();
+
+        assert_eq!(&line[range], "ｓｈ");
+    }
+
+    #[test]
+    fn substring_match_ignores_ascii_case() {
+        let menu = ListMenu {
+            query: Some("git".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(menu.substring_match("Git status"), Some(0..3));
+        assert_eq!(menu.substring_match("cargo"), None);
+    }
+
+    #[rstest]
+    #[case(HistoryOrdering::Frequency, vec!["git log", "git status", "git diff", "git stash"])]
+    #[case(HistoryOrdering::FrequencyRecency, vec!["git log", "git status", "git stash", "git diff"])]
+    fn history_ordering_ranks_commands_by_use(
+        #[case] history_ordering: HistoryOrdering,
+        #[case] expected: Vec<&str>,
+    ) {
+        // From the most recent to the oldest command
+        let mut history = vec!["git stash", "git status", "git status", "git status"];
+        history.extend(vec!["git log"; 200]);
+        history.extend(vec!["git diff"; 2]);
+        let mut completer = FakeCompleter::new(&history);
+        let mut editor = Editor::default();
+
+        let menu = setup_menu(
+            ListMenu::default().with_history_ordering(history_ordering),
+            &mut completer,
+            &mut editor,
+        );
+
+        assert_eq!(menu_values(&menu), expected);
+    }
+
+    #[test]
+    fn frequently_used_older_command_outranks_newer_one() {
+        let mut completer = FakeCompleter::new(&["git stash", "git status", "git status"]);
+        let mut editor = Editor::default();
+
+        let recency = setup_menu(ListMenu::default(), &mut completer, &mut editor);
+        assert_eq!(
+            menu_values(&recency),
+            vec!["git stash", "git status", "git status"]
+        );
+
+        let frecency = setup_menu(
+            ListMenu::default().with_history_ordering(HistoryOrdering::FrequencyRecency),
+            &mut completer,
+            &mut editor,
+        );
+        assert_eq!(menu_values(&frecency), vec!["git status", "git stash"]);
+    }
+
+    fn setup_menu(menu: ListMenu, completer: &mut FakeCompleter, editor: &mut Editor) -> ListMenu {
+        let mut menu = menu.with_only_buffer_difference(false);
+        editor.set_buffer("git".to_string(), UndoBehavior::CreateUndoPoint);
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_values(editor, completer);
+        menu.pages.push(Page {
+            size: menu.values.len(),
+            full: false,
+        });
+        menu
+    }
+
+    fn send_event(
+        menu: &mut ListMenu,
+        event: MenuEvent,
+        editor: &mut Editor,
+        completer: &mut FakeCompleter,
+    ) {
+        let painter = Painter::detached(std::io::sink());
+        menu.menu_event(event);
+        menu.update_working_details(editor, completer, &painter);
+    }
+
+    #[test]
+    fn wrap_around_disabled_stops_at_first_entry() {
+        let mut completer = FakeCompleter::new(&["git add", "git commit", "git push"]);
+        let mut editor = Editor::default();
+        let menu = ListMenu::default().with_wrap_around(false);
+        let mut menu = setup_menu(menu, &mut completer, &mut editor);
+
+        send_event(&mut menu, MenuEvent::MoveUp, &mut editor, &mut completer);
+
+        assert_eq!(menu.row_position, 0);
+        assert_eq!(menu.page, 0);
+    }
+
+    #[test]
+    fn wrap_around_disabled_stops_at_last_entry() {
+        let mut completer = FakeCompleter::new(&["git add", "git commit", "git push"]);
+        let mut editor = Editor::default();
+        let menu = ListMenu::default().with_wrap_around(false);
+        let mut menu = setup_menu(menu, &mut completer, &mut editor);
+
+        for _ in 0..3 {
+            send_event(&mut menu, MenuEvent::MoveDown, &mut editor, &mut completer);
+        }
+
+        assert_eq!(menu.row_position, 2);
+        assert_eq!(menu.page, 0);
+    }
+
+    #[test]
+    fn wrap_around_enabled_jumps_to_other_end() {
+        let mut completer = FakeCompleter::new(&["git add", "git commit", "git push"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(ListMenu::default(), &mut completer, &mut editor);
+
+        send_event(&mut menu, MenuEvent::MoveUp, &mut editor, &mut completer);
+        assert_eq!(menu.row_position, 2);
+
+        send_event(&mut menu, MenuEvent::MoveDown, &mut editor, &mut completer);
+        assert_eq!(menu.row_position, 0);
+        assert_eq!(menu.page, 0);
+    }
+
+    #[test]
+    fn goto_page_creates_intermediate_pages() {
+        let mut completer = FakeCompleter::new(&["git 1", "git 2", "git 3", "git 4", "git 5"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu::default().with_page_size(2),
+            &mut completer,
+            &mut editor,
+        );
+        menu.pages[0].size = 2;
+        menu.row_position = 1;
+
+        menu.goto_page(2);
+
+        assert_eq!(menu.page, 2);
+        assert_eq!(menu.row_position, 0);
+        assert_eq!(menu.pages.len(), 3);
+        assert!(menu.pages[0].full && menu.pages[1].full);
+        assert_eq!(menu.values_until_current_page(), 6);
+        assert_eq!(menu_values(&menu), vec!["git 5"]);
+    }
+
+    #[test]
+    fn goto_page_is_clamped() {
+        let mut completer = FakeCompleter::new(&["git 1", "git 2", "git 3", "git 4", "git 5"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu::default().with_page_size(2),
+            &mut completer,
+            &mut editor,
+        );
+        menu.pages[0].size = 2;
+
+        menu.goto_page(10);
+        assert_eq!(menu.page, 2);
+
+        menu.goto_page(0);
+        assert_eq!(menu.page, 0);
+        assert_eq!(menu.pages.len(), 3);
+        assert_eq!(menu_values(&menu), vec!["git 1", "git 2"]);
+    }
+
+    #[rstest]
+    #[case::default_char(ListMenu::default(), "git!3")]
+    #[case::custom_char(ListMenu::default().with_selection_char('@'), "git@3")]
+    fn selection_char_selects_row(#[case] menu: ListMenu, #[case] input: &str) {
+        let mut completer = FakeCompleter::new(&["git 1", "git 2", "git 3", "git 4", "git 5"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(menu, &mut completer, &mut editor);
+
+        editor.set_buffer(input.to_string(), UndoBehavior::CreateUndoPoint);
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu.row_position, 3);
+        assert_eq!(
+            menu.get_value()
+                .map(|suggestion| (suggestion.value, suggestion.span)),
+            Some(("git 4".to_string(), Span::new(0, 5)))
+        );
+    }
+
+    #[test]
+    fn custom_selection_char_ignores_default() {
+        let mut completer = FakeCompleter::new(&["git!3"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu::default().with_selection_char('@'),
+            &mut completer,
+            &mut editor,
+        );
+
+        editor.set_buffer("git!3".to_string(), UndoBehavior::CreateUndoPoint);
+        menu.update_values(&mut editor, &mut completer);
+
+        assert_eq!(menu_values(&menu), vec!["git!3"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn whitespace_selection_char_panics() {
+        let _ = ListMenu::default().with_selection_char(' ');
+    }
+
+    #[test]
+    fn selected_index_accounts_for_previous_pages() {
+        let mut completer = FakeCompleter::new(&["git 1", "git 2", "git 3", "git 4", "git 5"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu::default().with_page_size(2),
+            &mut completer,
+            &mut editor,
+        );
+        menu.pages[0].size = 2;
+        assert_eq!(menu.selected_index(), Some(0));
+
+        menu.goto_page(1);
+        menu.row_position = 1;
+        assert_eq!(menu.selected_index(), Some(3));
+        assert_eq!(menu.get_value().map(|s| s.value), Some("git 4".to_string()));
+
+        menu.menu_event(MenuEvent::Deactivate);
+        assert_eq!(menu.selected_index(), None);
+    }
+
+    #[test]
+    fn scrollbar_thumb_follows_page() {
+        let values = [
+            "git 0", "git 1", "git 2", "git 3", "git 4", "git 5", "git 6", "git 7",
+        ];
+        let mut completer = FakeCompleter::new(&values);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu::default().with_page_size(2),
+            &mut completer,
+            &mut editor,
+        );
+        menu.pages[0].size = 2;
+
+        assert_eq!(menu.scrollbar_thumb(2), 0..1);
+
+        menu.goto_page(3);
+        assert_eq!(menu.scrollbar_thumb(2), 1..2);
+
+        menu.goto_page(0);
+        menu.pages[0].size = 8;
+        assert_eq!(menu.scrollbar_thumb(8), 0..8);
+    }
+
+    #[test]
+    fn scrollbar_cell_is_placed_at_right_edge() {
+        let menu = ListMenu {
+            terminal_width: 10,
+            ..Default::default()
+        };
+
+        let entry = menu.add_scrollbar_cell("0: ab\r\n:::cd\r\n".to_string(), true);
+        assert_eq!(
+            entry,
+            format!(
+                "0: ab    {}█{}\r\n:::cd\r\n",
+                menu.theme.styles.scrollbar_style.prefix(),
+                RESET
+            )
+        );
+
+        let long_entry = "0: abcdefghijk\r\n".to_string();
+        assert_eq!(
+            menu.add_scrollbar_cell(long_entry.clone(), false),
+            long_entry
+        );
+    }
+
+    fn described_menu(position: DescriptionPanePosition) -> ListMenu {
+        let suggestion = |value: &str, description: Option<&str>| Suggestion {
+            value: value.to_string(),
+            description: description.map(Description::from),
+            extra: None,
+            span: Span::new(0, 0),
+            append_whitespace: false,
+            ..Suggestion::default()
+        };
+
+        ListMenu {
+            values: vec![
+                suggestion("ls", Some("list the contents of a directory")),
+                suggestion("cd", None),
+            ],
+            pages: vec![Page {
+                size: 2,
+                full: false,
+            }],
+            max_lines: 2,
+            terminal_width: 30,
+            ..Default::default()
+        }
+        .with_description_pane(position)
+    }
+
+    fn overflowing_menu(overflow: Overflow) -> ListMenu {
+        let suggestion = |value: &str| Suggestion {
+            value: value.to_string(),
+            description: None,
+            extra: None,
+            span: Span::new(0, 0),
+            append_whitespace: false,
+            ..Suggestion::default()
+        };
+
+        ListMenu {
+            values: vec![
+                suggestion("git commit\n-m fix\n--amend"),
+                suggestion("git status --short --branch"),
+            ],
+            pages: vec![Page {
+                size: 2,
+                full: false,
+            }],
+            max_lines: 1,
+            terminal_width: 20,
+            ..Default::default()
+        }
+        .with_overflow(overflow)
+        .with_multiline_marker("|")
+    }
+
+    #[rstest]
+    #[case(
+        Overflow::TruncateLines,
+        "0: >GIT COMMIT\r\n|…\r\n1: git status --short --branch\r\nPage 1: records 0 - 1  total: 2  ",
+        5
+    )]
+    #[case(
+        Overflow::TruncateChars(12),
+        "0: >GIT COMMIT|…\r\n1: git status …\r\nPage 1: records 0 - 1  total: 2  ",
+        3
+    )]
+    #[case(
+        Overflow::Scroll,
+        "0: >…M FIX|--AMEND\r\n1: git status --…\r\nPage 1: records 0 - 1  total: 2  ",
+        3
+    )]
+    fn overflow_decides_how_values_are_cut(
+        #[case] overflow: Overflow,
+        #[case] expected: &str,
+        #[case] required_lines: u16,
+    ) {
+        let menu = overflowing_menu(overflow).with_ellipsis("…");
+
+        assert_eq!(menu.menu_string(10, false), expected);
+        assert_eq!(menu.menu_required_lines(20), required_lines);
+    }
+
+    #[test]
+    fn scrolled_values_leave_room_for_the_indicator_and_the_glyph() {
+        let mut menu = overflowing_menu(Overflow::Scroll)
+            .with_ellipsis("…")
+            .with_marker("menu> ".to_string());
+        menu.values[1].glyph = Some("#".to_string());
+        menu.glyph_width = 1;
+
+        assert_eq!(
+            menu.menu_string(10, false),
+            " 0: >…|--AMEND\r\n#1: git stat…\r\nPage 1: records 0 - 1  total: 2  "
+        );
+    }
+
+    #[test]
+    fn bottom_description_pane_shows_selected_description() {
+        let mut menu = described_menu(DescriptionPanePosition::Bottom);
+
+        assert_eq!(
+            menu.menu_string(10, false),
+            "0: >LS\r\n1: cd\r\nlist the contents of a\r\ndirectory\r\nPage 1: records 0 - 1  total: 2  "
+        );
+        assert_eq!(menu.menu_required_lines(30), 5);
+
+        // The pane keeps its lines when the selected value has no description
+        menu.row_position = 1;
+        assert_eq!(
+            menu.menu_string(10, false),
+            "0: ls\r\n1: >CD\r\n\r\n\r\nPage 1: records 0 - 1  total: 2  "
+        );
+    }
+
+    #[test]
+    fn bottom_description_pane_respects_max_lines() {
+        let menu = ListMenu {
+            terminal_width: 10,
+            ..described_menu(DescriptionPanePosition::Bottom)
+        };
+
+        let lines: Vec<String> = menu
+            .description_pane_lines(10)
+            .iter()
+            .map(StyledText::raw_string)
+            .collect();
+        assert_eq!(lines, vec!["list the", "contents"]);
+    }
+
+    #[test]
+    fn description_pane_keeps_the_lines_and_styles_of_the_description() {
+        let flag = Style::new().fg(Color::Yellow);
+        let description = StyledText {
+            buffer: vec![
+                (Style::new(), "list with ".into()),
+                (flag, "-l".into()),
+                (Style::new(), "\nshow all files".into()),
+            ],
+        };
+        let mut menu = ListMenu {
+            max_lines: 3,
+            terminal_width: 12,
+            ..described_menu(DescriptionPanePosition::Bottom)
+        };
+        menu.values[0].description = Some(description.into());
+        let description_style = menu.theme.styles.description_style;
+
+        assert_eq!(
+            menu.bottom_description_pane(true),
+            format!(
+                "{}list with {}{}{}\r\n{}show all{}\r\n{}files{}\r\n",
+                description_style.prefix(),
+                flag.paint("-l"),
+                description_style.prefix(),
+                RESET,
+                description_style.prefix(),
+                RESET,
+                description_style.prefix(),
+                RESET,
+            )
+        );
+        assert_eq!(
+            menu.bottom_description_pane(false),
+            "list with -l\r\nshow all\r\nfiles\r\n"
+        );
+    }
+
+    #[test]
+    fn side_description_pane_shows_selected_description() {
+        let menu = described_menu(DescriptionPanePosition::Side);
+
+        assert_eq!(menu.side_pane_widths(), (14, 14));
+        assert_eq!(
+            menu.menu_string(10, false),
+            "0: >LS         list the\r\n1: cd          contents of a\r\nPage 1: records 0 - 1  total: 2  "
+        );
+    }
+
+    #[test]
+    fn select_line_maps_to_entry() {
+        let mut completer = FakeCompleter::new(&["git add", "git commit\n-m", "git push"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(
+            ListMenu {
+                terminal_width: 40,
+                ..Default::default()
+            },
+            &mut completer,
+            &mut editor,
+        );
+
+        assert!(!menu.select_line(2));
+        assert_eq!(menu.row_position, 1);
+
+        // A click on the selected value reports it so it can be accepted
+        assert!(menu.select_line(1));
+        assert_eq!(menu.row_position, 1);
+
+        assert!(!menu.select_line(3));
+        assert_eq!(menu.row_position, 2);
+
+        // Lines after the values are ignored
+        assert!(!menu.select_line(4));
+        assert_eq!(menu.row_position, 2);
+    }
+
+    struct UncountedCompleter(FakeCompleter);
+
+    impl Completer for UncountedCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.0.complete(line, pos)
+        }
+
+        fn total_completions(&mut self, _line: &str, _pos: usize) -> Option<usize> {
+            None
+        }
+    }
+
+    #[test]
+    fn unknown_total_pulls_pages_until_exhausted() {
+        let mut completer = UncountedCompleter(FakeCompleter::new(&["a", "b", "c", "d", "e"]));
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_page_size(2);
+        let mut editor = Editor::default();
+
+        for page in 0..3 {
+            menu.pages.push(Page {
+                size: 2,
+                full: true,
+            });
+            menu.page = page;
+            menu.update_values(&mut editor, &mut completer);
+
+            if page < 2 {
+                assert!(!menu.is_last_page());
+                assert!(menu
+                    .banner_message(&menu.pages[page], false)
+                    .contains("total: ?"));
+            }
+        }
+
+        assert_eq!(menu_values(&menu), vec!["e"]);
+        assert!(menu.is_last_page());
+        assert_eq!(menu.total_values(), 5);
+        assert!(menu
+            .banner_message(&menu.pages[2], false)
+            .contains("total: 5"));
+    }
+
+    #[test]
+    fn toggling_the_sort_reorders_the_values() {
+        let mut completer = FakeCompleter::new(&["git pull", "git add", "git commit"]);
+        let mut editor = Editor::default();
+        editor.set_buffer("git".to_string(), UndoBehavior::CreateUndoPoint);
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_sort_orders(vec![MenuSort::None, MenuSort::Alphabetical]);
+        let mut painter = Painter::detached(std::io::sink());
+        painter.handle_resize(80, 20);
+        let mut send_event = |menu: &mut ListMenu, event| {
+            menu.menu_event(event);
+            menu.update_working_details(&mut editor, &mut completer, &painter);
+        };
+
+        send_event(&mut menu, MenuEvent::Activate(false));
+        send_event(&mut menu, MenuEvent::NextElement);
+        assert_eq!(
+            menu_values(&menu),
+            vec!["git pull", "git add", "git commit"]
+        );
+        assert!(menu
+            .banner_message(&menu.pages[0], false)
+            .ends_with("sort: completer order"));
+
+        send_event(&mut menu, MenuEvent::ToggleSort);
+        assert_eq!(
+            menu_values(&menu),
+            vec!["git add", "git commit", "git pull"]
+        );
+        assert_eq!(menu.get_value().unwrap().value, "git add");
+        assert!(menu
+            .banner_message(&menu.pages[0], false)
+            .ends_with("sort: alphabetical"));
+
+        send_event(&mut menu, MenuEvent::ToggleSort);
+        assert_eq!(
+            menu_values(&menu),
+            vec!["git pull", "git add", "git commit"]
+        );
+        assert_eq!(menu.get_value().unwrap().value, "git add");
+    }
+
+    #[test]
+    fn banner_counts_the_values_of_each_source() {
+        let value = |value: &str, source: Option<&str>| Suggestion {
+            value: value.to_string(),
+            source: source.map(str::to_string),
+            ..Suggestion::default()
+        };
+        let mut menu = ListMenu::default().with_source_breakdown(true);
+        menu.values = vec![
+            value("src", Some("files")),
+            value("ls", Some("commands")),
+            value("Cargo.toml", Some("files")),
+            value("other", None),
+            value("next page", Some("variables")),
+        ];
+        menu.pages.push(Page {
+            size: 4,
+            full: false,
+        });
+
+        assert_eq!(
+            menu.banner_message(&menu.pages[0], false),
+            "Page 1: records 0 - 3  total: 5  2 files, 1 commands"
+        );
+
+        // Without sources the banner stays the same
+        menu.values.iter_mut().for_each(|value| value.source = None);
+        assert_eq!(
+            menu.banner_message(&menu.pages[0], false),
+            "Page 1: records 0 - 3  total: 5  "
+        );
+    }
+
+    // Counts the queries of each page, identified by the number of skipped values
+    struct CountingCompleter {
+        completer: FakeCompleter,
+        totals: usize,
+        pages: HashMap<usize, usize>,
+    }
+
+    impl Completer for CountingCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            self.completer.complete(line, pos)
+        }
+
+        fn partial_complete(
+            &mut self,
+            line: &str,
+            pos: usize,
+            start: usize,
+            offset: usize,
+        ) -> Vec<Suggestion> {
+            *self.pages.entry(start).or_default() += 1;
+            self.complete(line, pos)
+                .into_iter()
+                .skip(start)
+                .take(offset)
+                .collect()
+        }
+
+        fn total_completions(&mut self, line: &str, pos: usize) -> Option<usize> {
+            self.totals += 1;
+            Some(self.complete(line, pos).len())
+        }
+    }
+
+    #[test]
+    fn page_cache_queries_each_page_once() {
+        let mut completer = CountingCompleter {
+            completer: FakeCompleter::new(&["a", "b", "c", "d", "e"]),
+            totals: 0,
+            pages: HashMap::new(),
+        };
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_page_size(2)
+            .with_page_cache(true);
+        let mut editor = Editor::default();
+        menu.menu_event(MenuEvent::Activate(false));
+
+        for page in [0, 1, 2, 1, 0, 1] {
+            if page >= menu.pages.len() {
+                menu.pages.push(Page {
+                    size: 2,
+                    full: true,
+                });
+            }
+            menu.page = page;
+            menu.update_values(&mut editor, &mut completer);
+        }
+
+        assert_eq!(menu_values(&menu), vec!["c", "d"]);
+        assert_eq!(menu.total_values(), 5);
+        assert_eq!(completer.totals, 1);
+        assert_eq!(completer.pages, HashMap::from([(0, 1), (2, 1), (4, 1)]));
+
+        // The values can change while the menu is closed
+        menu.menu_event(MenuEvent::Deactivate);
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(completer.totals, 2);
+        assert_eq!(completer.pages[&0], 2);
+    }
+
+    #[test]
+    fn remember_position_restores_page_and_row() {
+        let mut completer = FakeCompleter::new(&["a", "b", "c", "d", "e"]);
+        let mut editor = Editor::default();
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_remember_position(true);
+
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.pages = vec![
+            Page {
+                size: 2,
+                full: true,
+            },
+            Page {
+                size: 2,
+                full: false,
+            },
+        ];
+        menu.page = 1;
+        menu.row_position = 1;
+        menu.update_values(&mut editor, &mut completer);
+        assert_eq!(menu.get_value().unwrap().value, "d");
+
+        menu.menu_event(MenuEvent::Deactivate);
+        send_event(
+            &mut menu,
+            MenuEvent::Activate(false),
+            &mut editor,
+            &mut completer,
+        );
+        assert_eq!((menu.page, menu.row_position), (1, 1));
+        assert_eq!(menu.get_value().unwrap().value, "d");
+
+        // A different buffer falls back to the first page
+        menu.menu_event(MenuEvent::Deactivate);
+        editor.set_buffer("x".to_string(), UndoBehavior::CreateUndoPoint);
+        send_event(
+            &mut menu,
+            MenuEvent::Activate(false),
+            &mut editor,
+            &mut completer,
+        );
+        assert_eq!((menu.page, menu.row_position), (0, 0));
+    }
+
+    #[test]
+    fn position_is_reset_by_default() {
+        let mut completer = FakeCompleter::new(&["a", "b", "c"]);
+        let mut editor = Editor::default();
+        let mut menu = setup_menu(ListMenu::default(), &mut completer, &mut editor);
+        menu.row_position = 2;
+
+        menu.menu_event(MenuEvent::Deactivate);
+        send_event(
+            &mut menu,
+            MenuEvent::Activate(false),
+            &mut editor,
+            &mut completer,
+        );
+        assert_eq!(menu.row_position, 0);
+    }
+
+    // Completes the values starting with the line, whatever their case
+    struct PrefixCompleter(FakeCompleter);
+
+    impl Completer for PrefixCompleter {
+        fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let mut values = self.0.complete("", pos);
+            values.retain(|suggestion| {
+                suggestion
+                    .value
+                    .to_lowercase()
+                    .starts_with(&line.to_lowercase())
+            });
+            for suggestion in values.iter_mut() {
+                suggestion.span = Span::new(pos - line.len(), pos);
+            }
+            values
+        }
+    }
+
+    #[rstest]
+    #[case(&["foobar", "foobaz"], "fo", true, "fooba")]
+    #[case(&["foobar"], "fo", true, "foobar")]
+    #[case(&["foobar", "foobaz"], "fooba", true, "fooba")]
+    #[case(&["Makefile", "makedirs"], "ma", true, "ma")]
+    #[case(&["Makefile", "makedirs"], "ma", false, "Make")]
+    fn partial_completion_inserts_the_common_prefix(
+        #[case] completions: &[&str],
+        #[case] typed: &str,
+        #[case] case_sensitive: bool,
+        #[case] expected: &str,
+    ) {
+        let mut completer = PrefixCompleter(FakeCompleter::new(completions));
+        let mut menu = ListMenu::default()
+            .with_only_buffer_difference(false)
+            .with_case_sensitive(case_sensitive);
+        let mut editor = Editor::default();
+        editor.set_buffer(typed.to_string(), UndoBehavior::CreateUndoPoint);
+
+        let completed = menu.can_partially_complete(false, &mut editor, &mut completer);
+
+        assert_eq!(completed, expected != typed);
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected.len());
+        assert_eq!(menu.get_values().len(), completions.len());
+    }
 
     #[test]
     fn number_of_lines_test() {
@@ -725,6 +2992,15 @@ mod tests {
         assert_eq!(res, 3);
     }
 
+    #[test]
+    fn emoji_sequences_and_cjk_wrap_like_the_terminal() {
+        // A family emoji takes two columns, every CJK character two as well: 2 + 1 + 8
+        let input = "👨\u{200d}👩\u{200d}👧 你好世界";
+
+        assert_eq!(number_of_lines(input, 5, 11), 1);
+        assert_eq!(number_of_lines(input, 5, 10), 2);
+    }
+
     #[test]
     fn number_one_line_test() {
         let input = "let a: another";
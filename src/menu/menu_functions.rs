@@ -1,5 +1,13 @@
 //! Collection of common functions that can be used to create menus
-use crate::Suggestion;
+use crate::{
+    core_editor::Snippet,
+    painting::{grapheme_width, line_width},
+    Description, Editor, MatchConfig, Span, StyledText, Suggestion, UndoBehavior,
+};
+use nu_ansi_term::Style;
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Index result obtained from parsing a string with an index marker
 /// For example, the next string:
@@ -138,6 +146,139 @@ pub fn parse_selection_char(buffer: &str, marker: char) -> ParseResult {
     }
 }
 
+/// Replaces the span of the suggestion in the buffer with its value and applies its
+/// [`Suggestion::extra_edits`]. The cursor is moved by the length difference of the
+/// value and of the edits before it. Extra edits overlapping the span of the value or
+/// an edit before them are dropped.
+///
+/// A [`Suggestion::snippet`] is inserted instead of the value, with the cursor on its
+/// first tab stop
+pub fn replace_in_buffer(suggestion: Option<Suggestion>, editor: &mut Editor) {
+    if let Some(Suggestion {
+        value,
+        span,
+        append_whitespace,
+        extra_edits,
+        snippet,
+        cursor_offset,
+        ..
+    }) = suggestion
+    {
+        let (mut value, mut tab_stops) = match snippet {
+            Some(body) => {
+                let snippet = Snippet::parse(&body);
+                (snippet.text, Some(snippet.tab_stops))
+            }
+            None => (value, None),
+        };
+        let extra_edits = extra_edits.unwrap_or_default();
+        let buffer_len = editor.line_buffer().len();
+        // Invalid spans are clamped to the buffer, unless strict completions are enabled
+        if editor.strict_completions() {
+            for span in extra_edits.iter().map(|(span, _)| span).chain([&span]) {
+                check_span(*span, buffer_len);
+            }
+        }
+        let Span { start, end } = span.clamp_to(buffer_len);
+        if append_whitespace {
+            // A final tab stop at the end of the snippet moves past the whitespace
+            if let Some(last) = tab_stops.as_mut().and_then(|ranges| ranges.last_mut()) {
+                if last.start == last.end && last.end == value.len() {
+                    *last = value.len() + 1..value.len() + 1;
+                }
+            }
+            value.push(' ');
+        }
+        let mut line_buffer = editor.line_buffer().clone();
+
+        let mut offset = line_buffer.insertion_point();
+        offset = offset.saturating_add(value.len());
+        offset = offset.saturating_sub(end.saturating_sub(start));
+
+        let mut extra_edits: Vec<(usize, usize, String)> = extra_edits
+            .into_iter()
+            .map(|(span, text)| {
+                let span = span.clamp_to(buffer_len);
+                (span.start, span.end, text)
+            })
+            .collect();
+        extra_edits.sort_by_key(|(edit_start, edit_end, _)| (*edit_start, *edit_end));
+        let mut edits: Vec<(usize, usize, String)> = Vec::with_capacity(extra_edits.len() + 1);
+        for (edit_start, edit_end, text) in extra_edits {
+            let previous = edits.last().map(|(start, end, _)| (*start, *end));
+            if overlap((edit_start, edit_end), (start, end))
+                || previous.map_or(false, |previous| overlap((edit_start, edit_end), previous))
+            {
+                assert!(
+                    !editor.strict_completions(),
+                    "The completer returned the edit {edit_start}..{edit_end} overlapping another edit"
+                );
+                continue;
+            }
+            edits.push((edit_start, edit_end, text));
+        }
+        let (mut inserted, mut removed) = (0, 0);
+        for (edit_start, edit_end, text) in edits.iter() {
+            if *edit_end <= start {
+                inserted += text.len();
+                removed += edit_end - edit_start;
+            }
+        }
+        offset = offset.saturating_add(inserted).saturating_sub(removed);
+        // Start of the value once the edits before it are applied
+        let value_start = start.saturating_add(inserted).saturating_sub(removed);
+        let value_len = value.len();
+        edits.push((start, end, value));
+
+        // Applied from the right to the left, so the spans still point to the
+        // original text
+        edits.sort_by_key(|(edit_start, _, _)| std::cmp::Reverse(*edit_start));
+        for (edit_start, edit_end, text) in edits {
+            line_buffer.replace_range(edit_start..edit_end.max(edit_start), &text);
+        }
+
+        // The tab stops of a snippet place the cursor instead
+        if let Some(cursor_offset) = cursor_offset.filter(|_| tab_stops.is_none()) {
+            offset = value_start + cursor_offset.min(value_len);
+            while !line_buffer.get_buffer().is_char_boundary(offset) {
+                offset -= 1;
+            }
+        }
+        line_buffer.set_insertion_point(offset);
+        editor.set_line_buffer(line_buffer, UndoBehavior::Completion);
+
+        if let Some(tab_stops) = tab_stops {
+            editor.start_tab_stops(
+                tab_stops
+                    .into_iter()
+                    .map(|range| value_start + range.start..value_start + range.end)
+                    .collect(),
+            );
+        }
+    }
+}
+
+// Whether the edits of the ranges `start..end` change the same text. Edits starting at
+// the same position overlap, as their order is ambiguous
+fn overlap(lhs: (usize, usize), rhs: (usize, usize)) -> bool {
+    lhs.0 == rhs.0 || (lhs.0 < rhs.1 && rhs.0 < lhs.1)
+}
+
+fn span_fits(span: Span, buffer_len: usize) -> bool {
+    span.start <= span.end && span.end <= buffer_len
+}
+
+// Panics with the offending span, for `Reedline::with_strict_completions`
+fn check_span(span: Span, buffer_len: usize) {
+    assert!(
+        span_fits(span, buffer_len),
+        "The completer returned the span {}..{} for a buffer of {} bytes",
+        span.start,
+        span.end,
+        buffer_len
+    );
+}
+
 /// Finds index for the common string in a list of suggestions
 pub fn find_common_string(values: &[Suggestion]) -> (Option<&Suggestion>, Option<usize>) {
     let first = values.iter().next();
@@ -174,6 +315,375 @@ pub fn find_common_string(values: &[Suggestion]) -> (Option<&Suggestion>, Option
     (first, index)
 }
 
+/// Finds the longest prefix shared by the values of the suggestions, which have to
+/// replace spans starting at the same position. Without `case_sensitive`, characters
+/// that only differ by their case are the same and the prefix has the case of the
+/// first value.
+///
+/// # Example
+/// ```
+/// use reedline::{menu_functions::common_prefix, Span, Suggestion};
+///
+/// let values: Vec<Suggestion> = ["foobar", "Foobaz"]
+///     .iter()
+///     .map(|value| Suggestion::new(*value, Span::default()))
+///     .collect();
+///
+/// assert_eq!(common_prefix(&values, true), Some(""));
+/// assert_eq!(common_prefix(&values, false), Some("fooba"));
+/// ```
+pub fn common_prefix(values: &[Suggestion], case_sensitive: bool) -> Option<&str> {
+    let (first, rest) = values.split_first()?;
+    let same_char = |lhs: char, rhs: char| {
+        if case_sensitive {
+            lhs == rhs
+        } else {
+            lhs.to_lowercase().eq(rhs.to_lowercase())
+        }
+    };
+
+    let mut len = first.value.len();
+    for suggestion in rest {
+        if suggestion.span.start != first.span.start {
+            return None;
+        }
+
+        let mut matching = 0;
+        for ((index, lhs), rhs) in first.value[..len]
+            .char_indices()
+            .zip(suggestion.value.chars())
+        {
+            if !same_char(lhs, rhs) {
+                break;
+            }
+            matching = index + lhs.len_utf8();
+        }
+        len = matching;
+    }
+
+    Some(&first.value[..len])
+}
+
+/// Truncates the string to the given display width, ending it with `…` when it
+/// doesn't fit. The string is cut between graphemes so wide characters are
+/// never split
+///
+/// # Example
+/// ```
+/// use reedline::menu_functions::truncate_with_ellipsis;
+///
+/// assert_eq!(truncate_with_ellipsis("reedline", 5), "reed…");
+/// assert_eq!(truncate_with_ellipsis("reed", 5), "reed");
+/// ```
+pub fn truncate_with_ellipsis(value: &str, max_width: usize) -> Cow<'_, str> {
+    truncate_with_marker(value, max_width, "…")
+}
+
+/// Truncates the string to the given display width like [`truncate_with_ellipsis`],
+/// ending it with `marker` when it doesn't fit
+///
+/// # Example
+/// ```
+/// use reedline::menu_functions::truncate_with_marker;
+///
+/// assert_eq!(truncate_with_marker("reedline", 6, "..."), "ree...");
+/// ```
+pub fn truncate_with_marker<'a>(value: &'a str, max_width: usize, marker: &str) -> Cow<'a, str> {
+    if line_width(value) <= max_width {
+        return Cow::Borrowed(value);
+    }
+
+    let available_width = max_width.saturating_sub(line_width(marker));
+    let mut width = 0;
+    let mut truncated = value
+        .graphemes(true)
+        .take_while(|grapheme| {
+            width += grapheme_width(grapheme);
+            width <= available_width
+        })
+        .collect::<String>();
+
+    if max_width > 0 {
+        truncated.push_str(marker);
+    }
+
+    Cow::Owned(truncated)
+}
+
+/// Shows the end of the string in the given display width, starting it with `marker`
+/// when the beginning is cut off
+pub(crate) fn scroll_to_end<'a>(value: &'a str, max_width: usize, marker: &str) -> Cow<'a, str> {
+    if line_width(value) <= max_width {
+        return Cow::Borrowed(value);
+    }
+
+    let available_width = max_width.saturating_sub(line_width(marker));
+    let mut width = 0;
+    let mut end = value
+        .graphemes(true)
+        .rev()
+        .take_while(|grapheme| {
+            width += grapheme_width(grapheme);
+            width <= available_width
+        })
+        .collect::<Vec<&str>>();
+    end.reverse();
+
+    Cow::Owned(format!("{}{}", marker, end.concat()))
+}
+
+/// Width of a menu shown in a terminal `screen_width` columns wide and limited to
+/// `max_width` columns
+pub(crate) fn menu_width(screen_width: u16, max_width: Option<u16>) -> u16 {
+    max_width.map_or(screen_width, |max_width| max_width.min(screen_width))
+}
+
+/// Indents the lines of a menu `menu_width` columns wide to center it in a terminal
+/// `screen_width` columns wide
+pub(crate) fn center_menu(menu: String, menu_width: u16, screen_width: u16) -> String {
+    let indent = " ".repeat(screen_width.saturating_sub(menu_width) as usize / 2);
+    if indent.is_empty() {
+        return menu;
+    }
+
+    menu.split_inclusive('\n')
+        .fold(String::new(), |mut centered, line| {
+            centered.push_str(&indent);
+            centered.push_str(line);
+            centered
+        })
+}
+
+/// Splits the line into pieces at most `width` columns wide, without splitting
+/// graphemes
+pub(crate) fn hard_wrap(line: &str, width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut piece_width = 0;
+    for (index, grapheme) in line.grapheme_indices(true) {
+        let grapheme_width = grapheme_width(grapheme);
+        if piece_width + grapheme_width > width && index > start {
+            pieces.push(&line[start..index]);
+            start = index;
+            piece_width = 0;
+        }
+        piece_width += grapheme_width;
+    }
+    pieces.push(&line[start..]);
+
+    pieces
+}
+
+/// Split the input into strings that are at most `max_length` (in columns, not in chars) long
+/// The split is done at whitespace if possible
+pub(crate) fn split_string(input_str: &str, max_length: usize) -> Vec<String> {
+    let whitespace_split = input_str.split_whitespace();
+    let mut words = Vec::new();
+
+    for word in whitespace_split {
+        let word_len_cols = word.width();
+
+        if word_len_cols > max_length {
+            let mut width = 0;
+            let mut substring = String::new();
+            for grapheme in word.graphemes(true) {
+                let grapheme_width = grapheme.width();
+                // Some unicode characters can have a width of multiple rows
+                if grapheme_width > max_length {
+                    continue;
+                }
+                if width + grapheme_width > max_length {
+                    words.push(substring);
+                    substring = String::from(grapheme);
+                    width = grapheme_width;
+                } else {
+                    substring.push_str(grapheme);
+                    width += grapheme_width;
+                }
+            }
+            if !substring.is_empty() {
+                words.push(substring);
+            }
+        } else {
+            words.push(word.to_string());
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut string = String::new();
+
+    for word in words {
+        if string.width() + word.width() > max_length {
+            result.push(string.trim_end().to_string());
+            string = word;
+            string.push(' ');
+        } else {
+            string.push_str(&word);
+            string.push(' ');
+        }
+    }
+
+    if !string.trim_end().is_empty() {
+        result.push(string.trim_end().to_string());
+    }
+
+    result
+}
+
+/// Lines of the description wrapped to `max_length` columns, keeping the lines of the
+/// description. The lines of a [`Description::Text`] have the default style
+pub(crate) fn description_lines(description: &Description, max_length: usize) -> Vec<StyledText> {
+    match description {
+        Description::Text(text) => text
+            .split('\n')
+            .flat_map(|line| split_string(line, max_length))
+            .map(|line| StyledText {
+                buffer: vec![(Style::new(), line)],
+            })
+            .collect(),
+        Description::Styled(styled_text) => styled_text.wrap(max_length.max(1)),
+    }
+}
+
+/// Renders a line of a description for a menu painting the descriptions in the
+/// `description_style`. The styled parts keep their style and the style of the menu
+/// is set again after them
+pub(crate) fn render_description_line(line: &StyledText, description_style: Style) -> String {
+    line.buffer
+        .iter()
+        .map(|(style, text)| {
+            if *style == Style::new() {
+                text.clone()
+            } else {
+                format!("{}{}", style.paint(text), description_style.prefix())
+            }
+        })
+        .collect()
+}
+
+/// Width of the column required to show the glyphs of the suggestions aligned.
+/// It includes a space that separates the glyph from the value, and it is 0
+/// when none of the suggestions has a glyph
+pub fn glyph_column_width(values: &[Suggestion]) -> usize {
+    values
+        .iter()
+        .filter_map(|suggestion| suggestion.glyph.as_deref())
+        .map(|glyph| glyph.width())
+        .max()
+        .map_or(0, |width| width + 1)
+}
+
+/// Glyph of the suggestion padded to the width of the glyph column
+pub fn glyph_cell(suggestion: &Suggestion, column_width: usize) -> String {
+    if column_width == 0 {
+        return String::new();
+    }
+
+    let glyph = suggestion.glyph.as_deref().unwrap_or_default();
+    format!(
+        "{}{}",
+        glyph,
+        " ".repeat(column_width.saturating_sub(glyph.width()))
+    )
+}
+
+/// Result obtained from fuzzy matching a pattern against a string
+#[derive(Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Score of the match. Higher values indicate a better match
+    pub score: usize,
+    /// Byte positions of the matched characters in the string
+    pub indices: Vec<usize>,
+}
+
+/// Fuzzy matches a pattern against a string
+///
+/// The characters of the pattern have to be found in order in the string, but
+/// not necessarily next to each other. Characters that follow a previously
+/// matched character receive a bonus so contiguous matches rank higher.
+/// The comparison ignores the case, see [`fuzzy_match_with`] to configure it.
+///
+/// ## Example usage
+/// ```
+/// use reedline::menu_functions::fuzzy_match;
+///
+/// let res = fuzzy_match("git commit", "gcm").expect("pattern is a subsequence");
+/// assert_eq!(res.indices, vec![0, 4, 6]);
+///
+/// assert!(fuzzy_match("git commit", "mg").is_none());
+/// ```
+pub fn fuzzy_match(value: &str, pattern: &str) -> Option<FuzzyMatch> {
+    fuzzy_match_with(value, pattern, MatchConfig::default())
+}
+
+/// Fuzzy matches a pattern against a string like [`fuzzy_match`], comparing the
+/// characters as configured by the [`MatchConfig`]
+///
+/// ## Example usage
+/// ```
+/// use reedline::{menu_functions::fuzzy_match_with, MatchConfig};
+///
+/// let folding = MatchConfig {
+///     fold_accents: true,
+///     ..MatchConfig::default()
+/// };
+/// assert!(fuzzy_match_with("café", "cafe", folding).is_some());
+/// assert!(fuzzy_match_with("café", "cafe", MatchConfig::default()).is_none());
+/// ```
+pub fn fuzzy_match_with(value: &str, pattern: &str, config: MatchConfig) -> Option<FuzzyMatch> {
+    let pattern = pattern
+        .chars()
+        .filter_map(|c| config.fold(c))
+        .collect::<Vec<char>>();
+    let first = *pattern.first()?;
+    // The ignored characters can't be matched, the byte positions stay the ones of the value
+    let chars = value
+        .char_indices()
+        .filter_map(|(byte_index, c)| config.fold(c).map(|c| (byte_index, c)))
+        .collect::<Vec<(usize, char)>>();
+
+    // Every position where the first character of the pattern appears is a
+    // candidate start. The best scoring candidate is kept
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, c))| *c == first)
+        .filter_map(|(start, _)| {
+            let mut score = 0;
+            let mut streak = 0;
+            let mut indices = Vec::with_capacity(pattern.len());
+            let mut last_match: Option<usize> = None;
+            let mut pattern_chars = pattern.iter().peekable();
+
+            for (pos, (byte_index, c)) in chars.iter().enumerate().skip(start) {
+                match pattern_chars.peek() {
+                    Some(p) if c == *p => {
+                        streak = match last_match {
+                            Some(last) if last + 1 == pos => streak + 1,
+                            _ => 0,
+                        };
+                        score += 1 + 2 * streak;
+                        indices.push(*byte_index);
+                        last_match = Some(pos);
+                        pattern_chars.next();
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+
+            pattern_chars
+                .peek()
+                .is_none()
+                .then_some(FuzzyMatch { score, indices })
+        })
+        .max_by(|lhs, rhs| {
+            lhs.score
+                .cmp(&rhs.score)
+                .then(rhs.indices.cmp(&lhs.indices))
+        })
+}
+
 /// Finds different string between two strings
 ///
 /// ## Example usage
@@ -248,6 +758,181 @@ pub fn string_difference<'a>(new_string: &'a str, old_string: &str) -> (usize, &
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Span;
+    use rstest::rstest;
+
+    #[test]
+    fn replace_in_buffer_applies_the_extra_edits() {
+        let mut editor = Editor::default();
+        editor.set_buffer(
+            "cat ./src/mian.rs".to_string(),
+            UndoBehavior::CreateUndoPoint,
+        );
+        let suggestion = Suggestion {
+            value: "main.rs".to_string(),
+            span: Span::new(10, 17),
+            extra_edits: Some(vec![
+                (Span::new(4, 6), String::new()),
+                (Span::new(0, 3), "bat".to_string()),
+            ]),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "bat src/main.rs");
+        assert_eq!(editor.insertion_point(), 15);
+    }
+
+    #[test]
+    fn edits_after_the_value_keep_the_cursor() {
+        let mut editor = Editor::default();
+        editor.set_initial_buffer("ls fo bar".to_string(), Some(5));
+        let suggestion = Suggestion {
+            value: "foo".to_string(),
+            span: Span::new(3, 5),
+            append_whitespace: true,
+            extra_edits: Some(vec![(Span::new(6, 9), "baz/".to_string())]),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "ls foo  baz/");
+        assert_eq!(editor.insertion_point(), 7);
+    }
+
+    #[test]
+    fn clamped_edits_before_the_value_move_it() {
+        let mut editor = Editor::default();
+        editor.set_buffer("ls fo".to_string(), UndoBehavior::CreateUndoPoint);
+        let suggestion = Suggestion {
+            value: "${1:foo}".to_string(),
+            snippet: Some("${1:foo}".to_string()),
+            span: Span::new(7, 9),
+            extra_edits: Some(vec![(Span::new(3, 8), "x".to_string())]),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "ls xfoo");
+        assert_eq!(editor.get_selection(), Some((4, 7)));
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[should_panic(expected = "edit 5..8 overlapping another edit")]
+    #[case(true)]
+    fn overlapping_extra_edits_are_dropped(#[case] strict: bool) {
+        let mut editor = Editor::default();
+        editor.set_strict_completions(strict);
+        editor.set_buffer(
+            "cat ./src/mian.rs".to_string(),
+            UndoBehavior::CreateUndoPoint,
+        );
+        let suggestion = Suggestion {
+            value: "main.rs".to_string(),
+            span: Span::new(10, 17),
+            extra_edits: Some(vec![
+                (Span::new(12, 14), "value".to_string()),
+                (Span::new(5, 8), "previous".to_string()),
+                (Span::new(4, 6), String::new()),
+            ]),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "cat src/main.rs");
+        assert_eq!(editor.insertion_point(), 15);
+    }
+
+    #[rstest]
+    #[case(Span::new(2, 4), 10, Span::new(2, 4))]
+    #[case(Span::new(2, 14), 10, Span::new(2, 10))]
+    #[case(Span::new(12, 14), 10, Span::new(10, 10))]
+    fn spans_are_clamped_to_the_buffer(
+        #[case] span: Span,
+        #[case] len: usize,
+        #[case] expected: Span,
+    ) {
+        assert_eq!(span.clamp_to(len), expected);
+    }
+
+    #[test]
+    fn snippets_ignore_the_cursor_offset() {
+        let mut editor = Editor::default();
+        editor.set_buffer("pri".to_string(), UndoBehavior::CreateUndoPoint);
+        let suggestion = Suggestion {
+            value: "print()".to_string(),
+            snippet: Some("print($1)".to_string()),
+            span: Span::new(0, 3),
+            cursor_offset: Some(0),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "print()");
+        assert_eq!(editor.insertion_point(), 6);
+    }
+
+    #[rstest]
+    #[case(true, "print() ")]
+    #[case(false, "print()")]
+    fn cursor_offset_places_the_cursor_inside_the_value(
+        #[case] append_whitespace: bool,
+        #[case] expected: &str,
+    ) {
+        let mut editor = Editor::default();
+        editor.set_buffer("echo pri".to_string(), UndoBehavior::CreateUndoPoint);
+        let suggestion = Suggestion {
+            value: "print()".to_string(),
+            span: Span::new(5, 8),
+            append_whitespace,
+            cursor_offset: Some(6),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), format!("echo {expected}"));
+        assert_eq!(editor.insertion_point(), 11);
+
+        // Offsets past the value stop at its end
+        editor.set_buffer("pri".to_string(), UndoBehavior::CreateUndoPoint);
+        let suggestion = Suggestion {
+            value: "print()".to_string(),
+            span: Span::new(0, 3),
+            append_whitespace,
+            cursor_offset: Some(20),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.insertion_point(), expected.len());
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[should_panic(expected = "span 3..9 for a buffer of 5 bytes")]
+    #[case(true)]
+    fn strict_completions_reject_spans_past_the_buffer(#[case] strict: bool) {
+        let mut editor = Editor::default();
+        editor.set_strict_completions(strict);
+        editor.set_buffer("ls fo".to_string(), UndoBehavior::CreateUndoPoint);
+        let suggestion = Suggestion {
+            value: "foo".to_string(),
+            span: Span::new(3, 9),
+            ..Suggestion::default()
+        };
+
+        replace_in_buffer(Some(suggestion), &mut editor);
+
+        assert_eq!(editor.get_buffer(), "ls foo");
+    }
 
     #[test]
     fn parse_row_test() {
@@ -389,6 +1074,149 @@ mod tests {
         assert!(matches!(res.action, ParseAction::BackwardSearch));
     }
 
+    #[test]
+    fn fuzzy_match_subsequence() {
+        let res = fuzzy_match("cargo build", "cbd");
+
+        assert_eq!(
+            res,
+            Some(FuzzyMatch {
+                score: 3,
+                indices: vec![0, 6, 10]
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("résumé", "resume", true, false, true)]
+    #[case("résumé", "resume", false, false, false)]
+    // The decomposed form of the accented letters
+    #[case("re\u{301}sume\u{301}", "resume", true, false, true)]
+    #[case("résumé", "résumé", true, false, true)]
+    #[case("Résumé", "resume", true, true, false)]
+    #[case("Résumé", "resume", true, false, true)]
+    fn fuzzy_match_folds_accents(
+        #[case] value: &str,
+        #[case] pattern: &str,
+        #[case] fold_accents: bool,
+        #[case] case_sensitive: bool,
+        #[case] matches: bool,
+    ) {
+        let config = MatchConfig {
+            case_sensitive,
+            fold_accents,
+        };
+
+        assert_eq!(fuzzy_match_with(value, pattern, config).is_some(), matches);
+    }
+
+    #[test]
+    fn folded_matches_keep_the_byte_positions() {
+        let config = MatchConfig {
+            fold_accents: true,
+            ..MatchConfig::default()
+        };
+        let res = fuzzy_match_with("café", "fe", config).unwrap();
+
+        assert_eq!(res.indices, vec![2, 3]);
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_contiguous() {
+        let scattered = fuzzy_match("a_b_c", "abc").unwrap();
+        let contiguous = fuzzy_match("xx_abc", "abc").unwrap();
+
+        assert!(contiguous.score > scattered.score);
+        assert_eq!(contiguous.indices, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn fuzzy_match_picks_best_start() {
+        let res = fuzzy_match("ab_abc", "abc").unwrap();
+
+        assert_eq!(res.indices, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn fuzzy_match_ignores_case() {
+        let res = fuzzy_match("ReadMe.md", "rme");
+
+        assert!(matches!(res, Some(FuzzyMatch { indices, .. }) if indices == vec![0, 4, 5]));
+    }
+
+    #[test]
+    fn fuzzy_match_no_match() {
+        assert!(fuzzy_match("cargo", "cx").is_none());
+        assert!(fuzzy_match("cargo", "").is_none());
+        assert!(fuzzy_match("", "c").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_multibyte() {
+        let res = fuzzy_match("ｎｕｓｈｅｌｌ", "ｎｈ").unwrap();
+
+        assert_eq!(res.indices, vec![0, 9]);
+    }
+
+    #[test]
+    fn glyph_column_aligns_glyphs() {
+        let values: Vec<_> = [Some("d"), None, Some("📁")]
+            .into_iter()
+            .map(|glyph| Suggestion {
+                glyph: glyph.map(String::from),
+                ..Default::default()
+            })
+            .collect();
+
+        let width = glyph_column_width(&values);
+        assert_eq!(width, 3);
+
+        let cells: Vec<_> = values.iter().map(|s| glyph_cell(s, width)).collect();
+        assert_eq!(cells, vec!["d  ", "   ", "📁 "]);
+    }
+
+    #[test]
+    fn glyph_column_without_glyphs() {
+        let values = vec![Suggestion::default()];
+
+        assert_eq!(glyph_column_width(&values), 0);
+        assert_eq!(glyph_cell(&values[0], 0), "");
+    }
+
+    #[test]
+    fn truncate_with_ellipsis_ascii() {
+        assert_eq!(truncate_with_ellipsis("reedline", 8), "reedline");
+        assert_eq!(truncate_with_ellipsis("reedline", 7), "reedli…");
+        assert_eq!(truncate_with_ellipsis("reedline", 1), "…");
+        assert_eq!(truncate_with_ellipsis("reedline", 0), "");
+    }
+
+    #[test]
+    fn truncate_with_marker_works() {
+        assert_eq!(truncate_with_marker("reedline", 8, "..."), "reedline");
+        assert_eq!(truncate_with_marker("reedline", 7, "..."), "reed...");
+        assert_eq!(truncate_with_marker("漢字漢字", 6, "~"), "漢字~");
+    }
+
+    #[test]
+    fn scroll_to_end_works() {
+        assert_eq!(scroll_to_end("reedline", 8, "..."), "reedline");
+        assert_eq!(scroll_to_end("reedline", 7, "..."), "...line");
+        assert_eq!(scroll_to_end("漢字漢字", 6, "~"), "~漢字");
+    }
+
+    #[test]
+    fn truncate_with_ellipsis_wide_characters() {
+        // Wide characters are not split, so the result may be narrower than the limit
+        assert_eq!(truncate_with_ellipsis("漢字漢字", 6), "漢字…");
+        assert_eq!(truncate_with_ellipsis("漢字漢字", 5), "漢字…");
+        assert_eq!(truncate_with_ellipsis("📁📁📁", 4), "📁…");
+        assert_eq!(
+            truncate_with_ellipsis("e\u{301}e\u{301}e\u{301}", 2),
+            "e\u{301}…"
+        );
+    }
+
     #[test]
     fn string_difference_test() {
         let new_string = "this is a new string";
@@ -500,6 +1328,7 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                ..Suggestion::default()
             })
             .collect();
         let res = find_common_string(&input);
@@ -519,6 +1348,7 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                ..Suggestion::default()
             })
             .collect();
         let res = find_common_string(&input);
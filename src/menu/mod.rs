@@ -5,14 +5,20 @@ pub mod menu_functions;
 
 use crate::core_editor::Editor;
 use crate::History;
-use crate::{completion::history::HistoryCompleter, painting::Painter, Completer, Suggestion};
+use crate::{
+    completion::history::HistoryCompleter, painting::Painter, Completer, Description, EditCommand,
+    Suggestion,
+};
 pub use columnar_menu::ColumnarMenu;
 pub use ide_menu::DescriptionMode;
 pub use ide_menu::IdeMenu;
-pub use list_menu::ListMenu;
+pub use list_menu::{DescriptionPanePosition, HistoryOrdering, ListMenu, Overflow};
 use nu_ansi_term::{Color, Style};
+use std::cmp::Ordering;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
 
 /// Struct to store the menu style
+#[derive(Clone)]
 pub struct MenuTextStyle {
     /// Text style for selected text in a menu
     pub selected_text_style: Style,
@@ -20,6 +26,10 @@ pub struct MenuTextStyle {
     pub text_style: Style,
     /// Text style for the item description
     pub description_style: Style,
+    /// Text style for the part of an item that matches the typed string
+    pub match_style: Style,
+    /// Text style for the menu scrollbar
+    pub scrollbar_style: Style,
 }
 
 impl Default for MenuTextStyle {
@@ -28,6 +38,161 @@ impl Default for MenuTextStyle {
             selected_text_style: Color::Green.bold().reverse(),
             text_style: Color::DarkGray.normal(),
             description_style: Color::Yellow.normal(),
+            match_style: Style::new().underline(),
+            scrollbar_style: Color::DarkGray.normal(),
+        }
+    }
+}
+
+/// Styles and markers of a menu, see [`Menu::apply_theme`]
+///
+/// A theme defined once can be applied to all the menus, also while the line editor
+/// is running with [`Reedline::apply_menu_theme`](crate::Reedline::apply_menu_theme),
+/// e.g. to switch between a light and a dark theme
+#[derive(Clone)]
+pub struct MenuTheme {
+    /// Styles of the values, their descriptions and matches
+    pub styles: MenuTextStyle,
+    /// Marker shown in the prompt while the menu is active
+    pub marker: String,
+    /// Marker starting the continuation lines of a value with several lines
+    pub multiline_marker: String,
+}
+
+impl Default for MenuTheme {
+    fn default() -> Self {
+        Self {
+            styles: MenuTextStyle::default(),
+            marker: "| ".to_string(),
+            multiline_marker: ":::".to_string(),
+        }
+    }
+}
+
+impl MenuTheme {
+    // Default theme of a menu with the given marker
+    pub(crate) fn with_marker(marker: &str) -> Self {
+        Self {
+            marker: marker.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Order applied to the suggestions collected by a menu
+///
+/// The sort is stable, so suggestions that compare equal keep the order
+/// returned by the completer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MenuSort {
+    /// Keep the order returned by the completer
+    #[default]
+    None,
+    /// Sort the suggestions by their value
+    Alphabetical,
+    /// Sort the suggestions by their description, using the value to break ties.
+    /// Suggestions without description are placed first
+    ByDescription,
+    /// Group the suggestions by their [`Suggestion::source`], keeping the order of the
+    /// completer within a source. Suggestions without source are placed first
+    BySource,
+}
+
+impl std::fmt::Display for MenuSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MenuSort::None => write!(f, "completer order"),
+            MenuSort::Alphabetical => write!(f, "alphabetical"),
+            MenuSort::ByDescription => write!(f, "by description"),
+            MenuSort::BySource => write!(f, "by source"),
+        }
+    }
+}
+
+impl MenuSort {
+    /// Compares two suggestions according to the sort order
+    pub fn compare(&self, lhs: &Suggestion, rhs: &Suggestion) -> Ordering {
+        match self {
+            MenuSort::None => Ordering::Equal,
+            MenuSort::Alphabetical => lhs.value.cmp(&rhs.value),
+            MenuSort::ByDescription => lhs
+                .description
+                .as_ref()
+                .map(Description::text)
+                .cmp(&rhs.description.as_ref().map(Description::text))
+                .then_with(|| lhs.value.cmp(&rhs.value)),
+            MenuSort::BySource => lhs.source.cmp(&rhs.source),
+        }
+    }
+
+    /// The order following this one in the orders cycled through by
+    /// [`MenuEvent::ToggleSort`], the first one if this order is not part of them
+    pub fn toggle(&self, orders: &[MenuSort]) -> MenuSort {
+        let next = orders
+            .iter()
+            .position(|sort| sort == self)
+            .map_or(0, |index| index + 1);
+        orders
+            .get(next)
+            .or_else(|| orders.first())
+            .copied()
+            .unwrap_or(*self)
+    }
+
+    /// Sorts the suggestions in place
+    pub fn sort(&self, values: &mut [Suggestion]) {
+        if *self != MenuSort::None {
+            values.sort_by(|lhs, rhs| self.compare(lhs, rhs));
+        }
+    }
+}
+
+/// What [`ReedlineEvent::Enter`](crate::ReedlineEvent::Enter) does while a menu is open
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAcceptBehavior {
+    /// Enter inserts the selected value and closes the menu
+    #[default]
+    Accept,
+    /// Enter closes the menu without inserting the value and submits the buffer. The
+    /// value is inserted with [`ReedlineEvent::MenuAccept`](crate::ReedlineEvent::MenuAccept),
+    /// e.g. bound to tab
+    SubmitPassthrough,
+}
+
+/// How the menus match the typed string against the suggestions when they filter them
+/// themselves, e.g. the fuzzy matching of the [`ListMenu`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MatchConfig {
+    /// Distinguish upper and lower case letters
+    pub case_sensitive: bool,
+    /// Ignore the accents of the letters, so that `cafe` matches `café`. The letters are
+    /// decomposed and their combining marks are dropped
+    pub fold_accents: bool,
+}
+
+impl MatchConfig {
+    /// The character as it is compared, `None` for the combining marks that are ignored
+    pub(crate) fn fold(&self, c: char) -> Option<char> {
+        let c = if self.fold_accents {
+            if is_combining_mark(c) {
+                return None;
+            }
+            let mut base = None;
+            decompose_canonical(c, |decomposed| {
+                if base.is_none() && !is_combining_mark(decomposed) {
+                    base = Some(decomposed);
+                }
+            });
+            base.unwrap_or(c)
+        } else {
+            c
+        };
+
+        if self.case_sensitive {
+            Some(c)
+        } else {
+            c.to_lowercase().next()
         }
     }
 }
@@ -59,6 +224,10 @@ pub enum MenuEvent {
     NextPage,
     /// Move to previous page
     PreviousPage,
+    /// Move to the page with the given index, starting from 0
+    GotoPage(usize),
+    /// Sort the values by the next of the configured sort orders
+    ToggleSort,
 }
 
 /// Trait that defines how a menu will be printed by the painter
@@ -95,6 +264,13 @@ pub trait Menu: Send {
     /// immediately
     fn update_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer);
 
+    /// Updates the values presented in the menu when the completer has new values
+    /// without the line buffer being edited, e.g. when an asynchronous completion
+    /// resolves. The selected value is kept if it is still present
+    fn refresh_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        self.update_values(editor, completer);
+    }
+
     /// The working details of a menu are values that could change based on
     /// the menu conditions before it being printed, such as the number or size
     /// of columns, etc.
@@ -107,9 +283,32 @@ pub trait Menu: Send {
         painter: &Painter,
     );
 
+    /// Replaces the styles and markers of the menu with the ones of the theme.
+    /// Menus without styles ignore the theme
+    fn apply_theme(&mut self, _theme: &MenuTheme) {}
+
+    /// What enter does while the menu is open, by default it accepts the selection
+    fn accept_behavior(&self) -> MenuAcceptBehavior {
+        MenuAcceptBehavior::Accept
+    }
+
     /// Indicates how to replace in the line buffer the selected value from the menu
     fn replace_in_buffer(&self, editor: &mut Editor);
 
+    /// Applies the edit commands to the filter line of the menu instead of the line
+    /// buffer. Returns false, leaving the commands to the line buffer, when the menu
+    /// filters its values with the buffer
+    fn edit_filter(&mut self, #[allow(unused_variables)] commands: &[EditCommand]) -> bool {
+        false
+    }
+
+    /// Selects the value painted at the given line of the menu, where 0 is the
+    /// first line of the menu. Returns true when the value was already selected
+    /// so it can be accepted. Menus without mouse support ignore the line
+    fn select_line(&mut self, _line: u16) -> bool {
+        false
+    }
+
     /// Calculates the real required lines for the menu considering how many lines
     /// wrap the terminal or if entries have multiple lines
     fn menu_required_lines(&self, terminal_columns: u16) -> u16;
@@ -122,6 +321,20 @@ pub trait Menu: Send {
 
     /// Gets cached values from menu that will be displayed
     fn get_values(&self) -> &[Suggestion];
+
+    /// Index of the selected value in the complete list of suggestions.
+    /// Returns `None` when the menu is not active or has no values
+    fn selected_index(&self) -> Option<usize> {
+        None
+    }
+
+    /// The selected value, `None` when the menu is not active or has no values
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.selected_index()
+            .and_then(|index| self.get_values().get(index))
+            .cloned()
+    }
+
     /// Sets the position of the cursor (currently only required by the IDE menu)
     fn set_cursor_pos(&mut self, pos: (u16, u16));
 }
@@ -201,6 +414,24 @@ impl ReedlineMenu {
         }
     }
 
+    // Applies the selected value to the buffer and tells the completer that produced it
+    pub(crate) fn accept_selection(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        let accepted = self.as_ref().selected_value();
+        self.as_ref().replace_in_buffer(editor);
+
+        if let Some(accepted) = accepted {
+            let line = editor.get_buffer();
+            match self {
+                Self::EngineCompleter(_) => completer.on_accept(&accepted, line),
+                Self::HistoryMenu(_) => {}
+                Self::WithCompleter {
+                    completer: own_completer,
+                    ..
+                } => own_completer.on_accept(&accepted, line),
+            }
+        }
+    }
+
     pub(crate) fn update_working_details(
         &mut self,
         editor: &mut Editor,
@@ -278,6 +509,20 @@ impl Menu for ReedlineMenu {
         }
     }
 
+    fn refresh_values(&mut self, editor: &mut Editor, completer: &mut dyn Completer) {
+        match self {
+            Self::EngineCompleter(menu) | Self::HistoryMenu(menu) => {
+                menu.refresh_values(editor, completer);
+            }
+            Self::WithCompleter {
+                menu,
+                completer: own_completer,
+            } => {
+                menu.refresh_values(editor, own_completer.as_mut());
+            }
+        }
+    }
+
     fn update_working_details(
         &mut self,
         editor: &mut Editor,
@@ -301,6 +546,22 @@ impl Menu for ReedlineMenu {
         self.as_ref().replace_in_buffer(editor);
     }
 
+    fn apply_theme(&mut self, theme: &MenuTheme) {
+        self.as_mut().apply_theme(theme);
+    }
+
+    fn accept_behavior(&self) -> MenuAcceptBehavior {
+        self.as_ref().accept_behavior()
+    }
+
+    fn edit_filter(&mut self, commands: &[EditCommand]) -> bool {
+        self.as_mut().edit_filter(commands)
+    }
+
+    fn select_line(&mut self, line: u16) -> bool {
+        self.as_mut().select_line(line)
+    }
+
     fn menu_required_lines(&self, terminal_columns: u16) -> u16 {
         self.as_ref().menu_required_lines(terminal_columns)
     }
@@ -318,6 +579,14 @@ impl Menu for ReedlineMenu {
         self.as_ref().get_values()
     }
 
+    fn selected_index(&self) -> Option<usize> {
+        self.as_ref().selected_index()
+    }
+
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.as_ref().selected_value()
+    }
+
     fn set_cursor_pos(&mut self, pos: (u16, u16)) {
         self.as_mut().set_cursor_pos(pos);
     }
@@ -1,19 +1,22 @@
 use crate::{CursorConfig, PromptEditMode, PromptViMode};
 
 use {
-    super::utils::{coerce_crlf, line_width},
+    super::utils::{coerce_crlf, grapheme_width, line_width},
     crate::{
         menu::{Menu, ReedlineMenu},
-        painting::PromptLines,
+        painting::{PromptLines, StyledText},
+        terminal_extensions::semantic_prompt::SemanticPromptMarker,
         Prompt,
     },
     crossterm::{
-        cursor::{self, MoveTo, RestorePosition, SavePosition},
+        cursor::{self, MoveTo, RestorePosition, SavePosition, SetCursorStyle},
         style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
         terminal::{self, Clear, ClearType},
         QueueableCommand,
     },
+    nu_ansi_term::Style,
     std::io::{Result, Write},
+    unicode_segmentation::UnicodeSegmentation,
 };
 #[cfg(feature = "external_printer")]
 use {crate::LineBuffer, crossterm::cursor::MoveUp};
@@ -47,29 +50,162 @@ fn skip_buffer_lines(string: &str, skip: usize, offset: Option<usize>) -> &str {
 }
 
 /// the type used by crossterm operations
-pub type W = std::io::BufWriter<std::io::Stderr>;
+pub type W = Box<dyn Write + Send>;
 
 /// Implementation of the output to the terminal
 pub struct Painter {
     // Stdout
     stdout: W,
+    // Whether the output is the terminal, whose size and cursor position are queried
+    terminal: bool,
+    // Row the cursor was moved to, in place of the cursor position of the terminal
+    detached_row: u16,
     prompt_start_row: u16,
     terminal_size: (u16, u16),
     last_required_lines: u16,
     large_buffer: bool,
+    menu_start_row: u16,
+    cursor_row: u16,
+    // Whether the OSC 133 markers are written around the prompt and the command
+    shell_integration: bool,
+    // Edit mode whose cursor shape was set last, until the default shape is restored
+    cursor_shape_mode: Option<PromptEditMode>,
+    // The colors of the terminal are inverted by the visual bell
+    reverse_video: bool,
+    // First column of a line scrolled horizontally that is painted, see `scroll_line`
+    horizontal_offset: usize,
 }
 
 impl Painter {
-    pub(crate) fn new(stdout: W) -> Self {
+    pub(crate) fn new(stdout: impl Write + Send + 'static) -> Self {
         Painter {
-            stdout,
+            stdout: Box::new(stdout),
+            terminal: true,
+            detached_row: 0,
             prompt_start_row: 0,
             terminal_size: (0, 0),
             last_required_lines: 0,
             large_buffer: false,
+            menu_start_row: 0,
+            cursor_row: 0,
+            shell_integration: false,
+            cursor_shape_mode: None,
+            reverse_video: false,
+            horizontal_offset: 0,
         }
     }
 
+    /// Painter writing to `output` instead of the terminal, for a terminal of the
+    /// default size
+    pub(crate) fn detached(output: impl Write + Send + 'static) -> Self {
+        Painter {
+            terminal: false,
+            terminal_size: (80, 24),
+            ..Painter::new(output)
+        }
+    }
+
+    /// Whether the painter writes to the terminal
+    pub(crate) fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+
+    /// Whether the OSC 133 semantic prompt markers are written
+    pub(crate) fn shell_integration(&self) -> bool {
+        self.shell_integration
+    }
+
+    pub(crate) fn set_shell_integration(&mut self, shell_integration: bool) {
+        self.shell_integration = shell_integration;
+    }
+
+    /// Part of the line that fits in `width` columns with the cursor visible, for a line
+    /// that scrolls horizontally instead of wrapping. The hidden parts are replaced by
+    /// `<` and `>` at the edges. Returns the line with the new cursor index.
+    ///
+    /// The offset of the visible part is kept while the cursor stays in view
+    pub(crate) fn scroll_line(
+        &mut self,
+        line: &StyledText,
+        cursor: usize,
+        width: usize,
+    ) -> (StyledText, usize) {
+        let raw = line.raw_string();
+        // Start column, end column and byte index of the graphemes
+        let mut graphemes = Vec::new();
+        let mut column = 0;
+        for (index, grapheme) in raw.grapheme_indices(true) {
+            let end = column + grapheme_width(grapheme);
+            graphemes.push((column, end, index));
+            column = end;
+        }
+        let cursor_column = graphemes
+            .iter()
+            .find(|(_, _, index)| *index >= cursor)
+            .map_or(column, |(start, _, _)| *start);
+
+        // One more column for the cursor after the end of the line
+        if column < width {
+            self.horizontal_offset = 0;
+            return (line.clone(), cursor);
+        }
+
+        // The cursor stays between the markers, without scrolling past the end of the
+        // line that has no right marker
+        let visible = width.saturating_sub(2).max(1);
+        self.horizontal_offset = self
+            .horizontal_offset
+            .clamp((cursor_column + 1).saturating_sub(visible), cursor_column)
+            .min((column + 2).saturating_sub(width));
+
+        let left_marker = self.horizontal_offset > 0;
+        let mut end_column = self.horizontal_offset + width - usize::from(left_marker);
+        let right_marker = end_column < column;
+        if right_marker {
+            end_column -= 1;
+        }
+        let shown = |(start, end, _): &&(usize, usize, usize)| {
+            *start >= self.horizontal_offset && *end <= end_column
+        };
+        let from = graphemes
+            .iter()
+            .find(shown)
+            .map_or(raw.len(), |(_, _, index)| *index);
+        let to = graphemes
+            .iter()
+            .rev()
+            .find(shown)
+            .map_or(from, |(_, _, index)| {
+                index + raw[*index..].graphemes(true).next().map_or(0, str::len)
+            });
+
+        let mut scrolled = StyledText::new();
+        if left_marker {
+            scrolled.push((Style::new(), "<".to_string()));
+        }
+        scrolled.buffer.extend(line.slice(from, to).buffer);
+        if right_marker {
+            scrolled.push((Style::new(), ">".to_string()));
+        }
+
+        (
+            scrolled,
+            cursor.clamp(from, to) - from + usize::from(left_marker),
+        )
+    }
+
+    /// Row of the terminal where the menu was last painted
+    pub(crate) fn menu_start_row(&self) -> u16 {
+        self.menu_start_row
+    }
+
+    /// Rows a menu can use next to the cursor line, once the prompt is scrolled up to
+    /// make room for it
+    pub(crate) fn menu_available_lines(&self, min_rows: u16) -> u16 {
+        let cursor_distance = self.cursor_row.saturating_sub(self.prompt_start_row);
+        menu_layout(cursor_distance, self.screen_height(), min_rows).available_lines()
+    }
+
     /// Height of the current terminal window
     pub fn screen_height(&self) -> u16 {
         self.terminal_size.1
@@ -91,19 +227,25 @@ impl Painter {
     /// Not to be used for resizes during a running line editor, use
     /// [`Painter::handle_resize()`] instead
     pub(crate) fn initialize_prompt_position(&mut self) -> Result<()> {
-        // Update the terminal size
-        self.terminal_size = {
-            let size = terminal::size()?;
-            // if reported size is 0, 0 -
-            // use a default size to avoid divide by 0 panics
-            if size == (0, 0) {
-                (80, 24)
-            } else {
-                size
-            }
+        // Without a terminal the size stays as it is and the prompt starts where the
+        // painter left the cursor
+        let (column, row) = if self.terminal {
+            // Update the terminal size
+            self.terminal_size = {
+                let size = terminal::size()?;
+                // if reported size is 0, 0 -
+                // use a default size to avoid divide by 0 panics
+                if size == (0, 0) {
+                    (80, 24)
+                } else {
+                    size
+                }
+            };
+            // Cursor positions are 0 based here.
+            cursor::position()?
+        } else {
+            (0, self.detached_row)
         };
-        // Cursor positions are 0 based here.
-        let (column, row) = cursor::position()?;
         // Assumption: if the cursor is not on the zeroth column,
         // there is content we want to leave intact, thus advance to the next row
         let new_row = if column > 0 { row + 1 } else { row };
@@ -152,12 +294,15 @@ impl Painter {
         self.large_buffer = required_lines >= screen_height;
 
         // This might not be terribly performant. Testing it out
-        let is_reset = || match cursor::position() {
-            // when output something without newline, the cursor position is at current line.
-            // but the prompt_start_row is next line.
-            // in this case we don't want to reset, need to `add 1` to handle for such case.
-            Ok(position) => position.1 + 1 < self.prompt_start_row,
-            Err(_) => false,
+        let is_reset = || {
+            self.terminal
+                && match cursor::position() {
+                    // when output something without newline, the cursor position is at current line.
+                    // but the prompt_start_row is next line.
+                    // in this case we don't want to reset, need to `add 1` to handle for such case.
+                    Ok(position) => position.1 + 1 < self.prompt_start_row,
+                    Err(_) => false,
+                }
         };
 
         // Moving the start position of the cursor based on the size of the required lines
@@ -169,6 +314,11 @@ impl Painter {
             self.prompt_start_row = self.prompt_start_row.saturating_sub(extra);
         }
 
+        self.cursor_row = self
+            .prompt_start_row
+            .saturating_add(lines.distance_from_prompt(screen_width))
+            .min(screen_height.saturating_sub(1));
+
         // Moving the cursor to the start of the prompt
         // from this position everything will be printed
         self.stdout
@@ -194,8 +344,12 @@ impl Painter {
                 PromptEditMode::Vi(PromptViMode::Normal) => shapes.vi_normal,
                 _ => None,
             };
+            // The shape is only set when the mode changes
             if let Some(shape) = shape {
-                self.stdout.queue(shape)?;
+                if self.cursor_shape_mode.as_ref() != Some(&prompt_mode) {
+                    self.stdout.queue(shape)?;
+                    self.cursor_shape_mode = Some(prompt_mode);
+                }
             }
         }
         self.stdout.queue(cursor::Show)?;
@@ -204,6 +358,9 @@ impl Painter {
     }
 
     fn print_right_prompt(&mut self, lines: &PromptLines) -> Result<()> {
+        if lines.prompt_str_right.is_empty() {
+            return Ok(());
+        }
         let prompt_length_right = line_width(&lines.prompt_str_right);
         let start_position = self
             .screen_width()
@@ -227,30 +384,32 @@ impl Painter {
         Ok(())
     }
 
-    fn print_menu(
-        &mut self,
-        menu: &dyn Menu,
-        lines: &PromptLines,
-        use_ansi_coloring: bool,
-    ) -> Result<()> {
-        let screen_width = self.screen_width();
-        let screen_height = self.screen_height();
-        let cursor_distance = lines.distance_from_prompt(screen_width);
-
-        // If there is not enough space to print the menu, then the starting
-        // drawing point for the menu will overwrite the last rows in the buffer
-        let starting_row = if cursor_distance >= screen_height.saturating_sub(1) {
-            screen_height.saturating_sub(menu.min_rows())
-        } else {
-            self.prompt_start_row + cursor_distance + 1
-        };
-
-        let remaining_lines = screen_height.saturating_sub(starting_row);
-        let menu_string = menu.menu_string(remaining_lines, use_ansi_coloring);
-        self.stdout
-            .queue(cursor::MoveTo(0, starting_row))?
-            .queue(Clear(ClearType::FromCursorDown))?
-            .queue(Print(menu_string.trim_end_matches('\n')))?;
+    fn print_menu(&mut self, menu: &dyn Menu, use_ansi_coloring: bool) -> Result<()> {
+        let layout = menu_layout(self.cursor_row, self.screen_height(), menu.min_rows());
+        let menu_string = menu.menu_string(layout.available_lines(), use_ansi_coloring);
+
+        match layout {
+            MenuLayout::Below { .. } => {
+                self.menu_start_row = self.cursor_row + 1;
+                self.stdout
+                    .queue(cursor::MoveTo(0, self.menu_start_row))?
+                    .queue(Clear(ClearType::FromCursorDown))?
+                    .queue(Print(menu_string.trim_end_matches('\n')))?;
+            }
+            MenuLayout::Above { available_lines } => {
+                // The menu covers the lines above the cursor, which are painted again
+                // once the menu is closed
+                let menu_lines: Vec<&str> =
+                    menu_string.lines().take(available_lines as usize).collect();
+                self.menu_start_row = self.cursor_row.saturating_sub(menu_lines.len() as u16);
+                for (row, line) in (self.menu_start_row..).zip(menu_lines) {
+                    self.stdout
+                        .queue(cursor::MoveTo(0, row))?
+                        .queue(Clear(ClearType::CurrentLine))?
+                        .queue(Print(line))?;
+                }
+            }
+        }
 
         Ok(())
     }
@@ -262,6 +421,8 @@ impl Painter {
         menu: Option<&ReedlineMenu>,
         use_ansi_coloring: bool,
     ) -> Result<()> {
+        self.queue_marker(SemanticPromptMarker::PromptStart)?;
+
         // print our prompt with color
         if use_ansi_coloring {
             self.stdout
@@ -278,6 +439,7 @@ impl Painter {
 
         self.stdout
             .queue(Print(&coerce_crlf(&lines.prompt_indicator)))?;
+        self.queue_marker(SemanticPromptMarker::CommandStart)?;
 
         if use_ansi_coloring {
             self.stdout
@@ -298,7 +460,7 @@ impl Painter {
             .queue(Print(&lines.after_cursor))?;
 
         if let Some(menu) = menu {
-            self.print_menu(menu, lines, use_ansi_coloring)?;
+            self.print_menu(menu, use_ansi_coloring)?;
         } else {
             self.stdout.queue(Print(&lines.hint))?;
         }
@@ -330,6 +492,8 @@ impl Painter {
         // Extra rows represent how many rows are "above" the visible area in the terminal
         let extra_rows = (total_lines_before).saturating_sub(screen_height as usize);
 
+        self.queue_marker(SemanticPromptMarker::PromptStart)?;
+
         // print our prompt with color
         if use_ansi_coloring {
             self.stdout
@@ -350,30 +514,15 @@ impl Painter {
 
         let indicator_skipped = skip_buffer_lines(&lines.prompt_indicator, extra_rows, None);
         self.stdout.queue(Print(&coerce_crlf(indicator_skipped)))?;
+        self.queue_marker(SemanticPromptMarker::CommandStart)?;
 
         if use_ansi_coloring {
             self.stdout.queue(ResetColor)?;
         }
 
-        // The minimum number of lines from the menu are removed from the buffer if there is no more
-        // space to print the menu. This will only happen if the cursor is at the last line and
-        // it is a large buffer
-        let offset = menu.and_then(|menu| {
-            if cursor_distance >= screen_height.saturating_sub(1) {
-                let rows = lines
-                    .before_cursor
-                    .lines()
-                    .count()
-                    .saturating_sub(extra_rows)
-                    .saturating_sub(menu.min_rows() as usize);
-                Some(rows)
-            } else {
-                None
-            }
-        });
-
-        // Selecting the lines before the cursor that will be printed
-        let before_cursor_skipped = skip_buffer_lines(&lines.before_cursor, extra_rows, offset);
+        // Selecting the lines before the cursor that will be printed, a menu without room
+        // below the cursor is painted above it
+        let before_cursor_skipped = skip_buffer_lines(&lines.before_cursor, extra_rows, None);
         self.stdout.queue(Print(before_cursor_skipped))?;
         self.stdout.queue(SavePosition)?;
 
@@ -386,7 +535,7 @@ impl Painter {
             } else {
                 self.stdout.queue(Print(&lines.after_cursor))?;
             }
-            self.print_menu(menu, lines, use_ansi_coloring)?;
+            self.print_menu(menu, use_ansi_coloring)?;
         } else {
             // Selecting lines for the hint
             // The -1 subtraction is done because the remaining lines consider the line where the
@@ -419,6 +568,9 @@ impl Painter {
         //
         // I assume this is a bug with the position() call but haven't figured that
         // out yet.
+        if !self.terminal {
+            return;
+        }
         if let Ok(position) = cursor::position() {
             self.prompt_start_row = position.1;
         }
@@ -444,12 +596,17 @@ impl Painter {
     /// other output back at the first line of the terminal.
     pub(crate) fn clear_screen(&mut self) -> Result<()> {
         self.stdout.queue(cursor::Hide)?;
-        let (_, num_lines) = terminal::size()?;
+        let num_lines = if self.terminal {
+            terminal::size()?.1
+        } else {
+            self.screen_height()
+        };
         for _ in 0..2 * num_lines {
             self.stdout.queue(Print("\n"))?;
         }
         self.stdout.queue(MoveTo(0, 0))?;
         self.stdout.queue(cursor::Show)?;
+        self.detached_row = 0;
 
         self.stdout.flush()?;
         self.initialize_prompt_position()
@@ -461,6 +618,7 @@ impl Painter {
             .queue(crossterm::terminal::Clear(ClearType::Purge))?
             .queue(cursor::MoveTo(0, 0))?
             .flush()?;
+        self.detached_row = 0;
         self.initialize_prompt_position()
     }
 
@@ -473,12 +631,72 @@ impl Painter {
         if scroll != 0 {
             self.queue_universal_scroll(scroll)?;
         }
-        self.stdout
-            .queue(MoveTo(0, final_row.min(self.screen_height() - 1)))?;
+        self.detached_row = final_row.min(self.screen_height() - 1);
+        self.stdout.queue(MoveTo(0, self.detached_row))?;
+
+        self.stdout.flush()
+    }
+
+    /// Moves the cursor below the input like [`Painter::move_cursor_to_end`] once the
+    /// line editor returns, marking the start of the command output
+    ///
+    /// The cursor shape of the user is restored for the command.
+    pub(crate) fn finish_input(&mut self) -> Result<()> {
+        self.move_cursor_to_end()?;
+        if self.cursor_shape_mode.take().is_some() {
+            self.stdout.queue(SetCursorStyle::DefaultUserShape)?;
+        }
+        if self.reverse_video {
+            self.set_reverse_video(false)?;
+        }
+        self.queue_marker(SemanticPromptMarker::OutputStart)?;
+
+        self.stdout.flush()
+    }
+
+    /// Rings the bell of the terminal
+    pub(crate) fn ring_bell(&mut self) -> Result<()> {
+        self.stdout.queue(Print('\x07'))?;
 
         self.stdout.flush()
     }
 
+    /// Inverts the colors of the terminal, to flash it as a visual bell
+    pub(crate) fn set_reverse_video(&mut self, reverse: bool) -> Result<()> {
+        self.reverse_video = reverse;
+        let mode = if reverse { "\x1b[?5h" } else { "\x1b[?5l" };
+        self.stdout.queue(Print(mode))?;
+
+        self.stdout.flush()
+    }
+
+    /// Whether the colors of the terminal are inverted by the visual bell
+    pub(crate) fn reverse_video(&self) -> bool {
+        self.reverse_video
+    }
+
+    /// Marks the end of the command output with the exit code of the command
+    pub(crate) fn finish_command(&mut self, exit_code: i32) -> Result<()> {
+        self.queue_marker(SemanticPromptMarker::CommandFinished(exit_code))?;
+
+        self.stdout.flush()
+    }
+
+    fn queue_marker(&mut self, marker: SemanticPromptMarker) -> Result<()> {
+        if self.shell_integration {
+            self.stdout.queue(Print(marker))?;
+        }
+        Ok(())
+    }
+
+    /// Starts a new prompt below the painted lines, which stay on the screen
+    pub(crate) fn start_new_prompt(&mut self) -> Result<()> {
+        self.move_cursor_to_end()?;
+        let final_row = self.prompt_start_row + self.last_required_lines;
+        self.prompt_start_row = final_row.min(self.screen_height().saturating_sub(1));
+        Ok(())
+    }
+
     /// Prints an external message
     ///
     /// This function doesn't flush the buffer. So buffer should be flushed
@@ -491,7 +709,7 @@ impl Painter {
         prompt: &dyn Prompt,
     ) -> Result<()> {
         // adding 3 seems to be right for first line-wrap
-        let prompt_len = prompt.render_prompt_right().len() + 3;
+        let prompt_len = line_width(&prompt.render_prompt_right()) + 3;
         let mut buffer_num_lines = 0_u16;
         for (i, line) in line_buffer.get_buffer().lines().enumerate() {
             let screen_lines = match i {
@@ -555,10 +773,136 @@ impl Painter {
     }
 }
 
+/// Rows of the terminal a menu is painted in, relative to the line of the cursor
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum MenuLayout {
+    /// From the row below the cursor line down
+    Below {
+        /// Rows left for the menu
+        available_lines: u16,
+    },
+    /// In the rows right above the cursor line
+    Above {
+        /// Rows left for the menu
+        available_lines: u16,
+    },
+}
+
+impl MenuLayout {
+    /// Rows left for the menu
+    fn available_lines(&self) -> u16 {
+        match self {
+            MenuLayout::Below { available_lines } | MenuLayout::Above { available_lines } => {
+                *available_lines
+            }
+        }
+    }
+}
+
+// Chooses where the menu goes: below the cursor line if there are at least `min_rows` rows
+// left, otherwise on the side with more room. With too little room on both sides the
+// menu only gets the rows of that side
+fn menu_layout(cursor_row: u16, screen_height: u16, min_rows: u16) -> MenuLayout {
+    let cursor_row = cursor_row.min(screen_height.saturating_sub(1));
+    let below = screen_height.saturating_sub(cursor_row + 1);
+    let above = cursor_row;
+
+    if below >= min_rows || below >= above {
+        MenuLayout::Below {
+            available_lines: below,
+        }
+    } else {
+        MenuLayout::Above {
+            available_lines: above,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(200, 0, "", 79, ">")]
+    #[case(200, 200, "<", 78, "")]
+    #[case(200, 100, "<", 78, ">")]
+    #[case(50, 50, "", 50, "")]
+    fn scrolled_lines_show_the_cursor_and_the_edge_markers(
+        #[case] length: usize,
+        #[case] cursor: usize,
+        #[case] left: &str,
+        #[case] visible: usize,
+        #[case] right: &str,
+    ) {
+        let mut painter = Painter::detached(std::io::sink());
+        let line: String = ('a'..='z').cycle().take(length).collect();
+        let mut styled_line = StyledText::new();
+        styled_line.push((Style::new(), line.clone()));
+
+        let (scrolled, scrolled_cursor) = painter.scroll_line(&styled_line, cursor, 80);
+        let scrolled = scrolled.raw_string();
+
+        assert!(scrolled.starts_with(left));
+        assert!(scrolled.ends_with(right));
+        assert!(line_width(&scrolled) <= 80);
+        let shown = &scrolled[left.len()..scrolled.len() - right.len()];
+        assert_eq!(shown.len(), visible);
+        // The cursor is on the same character as in the full line
+        let start = cursor - (scrolled_cursor - left.len());
+        assert_eq!(&line[start..start + visible], shown);
+    }
+
+    #[test]
+    fn scrolled_lines_keep_their_offset_while_the_cursor_is_visible() {
+        let mut painter = Painter::detached(std::io::sink());
+        let mut line = StyledText::new();
+        line.push((Style::new(), "x".repeat(200)));
+
+        let (_, cursor) = painter.scroll_line(&line, 200, 80);
+        assert_eq!(cursor, 79);
+        // Moving left only moves the cursor until it reaches the edge
+        let (_, cursor) = painter.scroll_line(&line, 150, 80);
+        assert_eq!(cursor, 29);
+        let (scrolled, cursor) = painter.scroll_line(&line, 100, 80);
+        assert_eq!(cursor, 1);
+        assert_eq!(scrolled.raw_string(), format!("<{}>", "x".repeat(78)));
+    }
+
+    #[test]
+    fn detached_painter_starts_the_prompt_where_it_left_the_cursor() {
+        let mut painter = Painter::detached(std::io::sink());
+        painter.initialize_prompt_position().unwrap();
+        assert_eq!(painter.prompt_start_row, 0);
+
+        painter.last_required_lines = 2;
+        painter.move_cursor_to_end().unwrap();
+        painter.initialize_prompt_position().unwrap();
+        assert_eq!(painter.prompt_start_row, 2);
+
+        painter.clear_screen().unwrap();
+        assert_eq!(painter.prompt_start_row, 0);
+        assert_eq!(painter.screen_height(), 24);
+    }
+
+    #[rstest]
+    #[case(0, 24, 5, MenuLayout::Below { available_lines: 23 })]
+    #[case(18, 24, 5, MenuLayout::Below { available_lines: 5 })]
+    #[case(19, 24, 5, MenuLayout::Above { available_lines: 19 })]
+    #[case(23, 24, 5, MenuLayout::Above { available_lines: 23 })]
+    #[case(30, 24, 5, MenuLayout::Above { available_lines: 23 })]
+    #[case(3, 6, 5, MenuLayout::Above { available_lines: 3 })]
+    #[case(2, 6, 5, MenuLayout::Below { available_lines: 3 })]
+    #[case(0, 1, 5, MenuLayout::Below { available_lines: 0 })]
+    fn menu_layout_works(
+        #[case] cursor_row: u16,
+        #[case] screen_height: u16,
+        #[case] min_rows: u16,
+        #[case] expected: MenuLayout,
+    ) {
+        assert_eq!(menu_layout(cursor_row, screen_height, min_rows), expected);
+    }
 
     #[test]
     fn test_skip_lines() {
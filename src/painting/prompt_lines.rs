@@ -252,4 +252,29 @@ mod tests {
 
         assert_eq!(pos, expected);
     }
+
+    #[rstest]
+    #[case("~/path/", "❯ ", "ls", false, 11)]
+    #[case("\x1b[32m~/path/\x1b[0m", "\x1b[1m❯\x1b[0m ", "ls", false, 11)]
+    #[case("~/path/\n$", "❯ ", "ls", false, 7)]
+    #[case("~/path/\n$", "❯ ", "ls\nmore input", true, 5)]
+    fn test_estimate_right_prompt_line_width(
+        #[case] prompt_str_left: &str,
+        #[case] prompt_indicator: &str,
+        #[case] before_cursor: &str,
+        #[case] right_prompt_on_last_line: bool,
+        #[case] expected: u16,
+    ) {
+        let prompt_lines = PromptLines {
+            prompt_str_left: Cow::Borrowed(prompt_str_left),
+            prompt_str_right: Cow::Borrowed("\x1b[33m12:00\x1b[0m"),
+            prompt_indicator: Cow::Borrowed(prompt_indicator),
+            before_cursor: Cow::Borrowed(before_cursor),
+            after_cursor: Cow::Borrowed(""),
+            hint: Cow::Borrowed(""),
+            right_prompt_on_last_line,
+        };
+
+        assert_eq!(prompt_lines.estimate_right_prompt_line_width(100), expected);
+    }
 }
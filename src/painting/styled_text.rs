@@ -1,11 +1,12 @@
 use nu_ansi_term::Style;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::Prompt;
 
-use super::utils::strip_ansi;
+use super::utils::{expand_tabs, grapheme_width, line_width, strip_ansi};
 
 /// A representation of a buffer with styling, used for doing syntax highlighting
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StyledText {
     /// The component, styled parts of the text
     pub buffer: Vec<(Style, String)>,
@@ -48,7 +49,7 @@ impl StyledText {
             } else {
                 Position::In
             };
-            let end_position = if end_idx < from {
+            let end_position = if end_idx <= from {
                 Position::Before
             } else if end_idx > to {
                 Position::After
@@ -58,7 +59,7 @@ impl StyledText {
             match (start_position, end_position) {
                 (Position::Before, Position::After) => {
                     let mut in_range = pair.1.split_off(from - current_idx);
-                    let after_range = in_range.split_off(to - current_idx - from);
+                    let after_range = in_range.split_off(to - from);
                     let in_range = (new_style, in_range);
                     let after_range = (pair.0, after_range);
                     self.buffer.insert(pair_idx + 1, in_range);
@@ -89,6 +90,112 @@ impl StyledText {
         }
     }
 
+    /// Layer the style over the styles of the range, e.g. to show a selection without
+    /// losing the highlighting: the colors and attributes set by `layer` replace the
+    /// ones of the text, the others are kept
+    pub fn layer_range(&mut self, from: usize, to: usize, layer: Style) {
+        let (from, to) = if from > to { (to, from) } else { (from, to) };
+        let mut current_idx = 0;
+        let mut layered = Vec::with_capacity(self.buffer.len() + 2);
+        for (style, text) in self.buffer.drain(..) {
+            let end_idx = current_idx + text.len();
+            let start = from.clamp(current_idx, end_idx) - current_idx;
+            let end = to.clamp(current_idx, end_idx) - current_idx;
+            let parts = [
+                (style, 0..start),
+                (layered_style(style, layer), start..end),
+                (style, end..text.len()),
+            ];
+            for (part_style, range) in parts {
+                if !range.is_empty() {
+                    layered.push((part_style, text[range].to_string()));
+                }
+            }
+            current_idx = end_idx;
+        }
+        self.buffer = layered;
+    }
+
+    /// Replace the tabs with spaces up to the next tab stop, every `tab_width` columns
+    /// from the start of the line
+    pub(crate) fn expand_tabs(&mut self, tab_width: usize) {
+        let mut column = 0;
+        for (_, text) in &mut self.buffer {
+            *text = expand_tabs(text, tab_width, &mut column);
+        }
+    }
+
+    /// The styled text of the byte range `from..to`
+    pub(crate) fn slice(&self, from: usize, to: usize) -> StyledText {
+        let mut current_idx = 0;
+        let mut sliced = StyledText::new();
+        for (style, text) in &self.buffer {
+            let end_idx = current_idx + text.len();
+            let start = from.clamp(current_idx, end_idx) - current_idx;
+            let end = to.clamp(current_idx, end_idx) - current_idx;
+            if start < end {
+                sliced.push((*style, text[start..end].to_string()));
+            }
+            current_idx = end_idx;
+        }
+        sliced
+    }
+
+    /// Split the text into its lines, wrapped at the words to fit in `width` columns.
+    /// Words longer than the width are split, the whitespace at the wraps is dropped
+    pub(crate) fn wrap(&self, width: usize) -> Vec<StyledText> {
+        let width = width.max(1);
+        let raw = self.raw_string();
+        let mut lines = Vec::new();
+        let mut line_offset = 0;
+
+        for line in raw.split('\n') {
+            // Byte range of the wrapped line and its width, without trailing whitespace
+            let mut start = line_offset;
+            let mut end = line_offset;
+            let mut column = 0;
+            for (index, word) in whitespace_runs(line) {
+                let index = line_offset + index;
+                let is_whitespace = word.trim().is_empty();
+                let word_width = line_width(word);
+                if column > 0 && column + word_width > width {
+                    lines.push(self.slice(start, end));
+                    column = 0;
+                    start = index;
+                    end = index;
+                    if is_whitespace {
+                        start = index + word.len();
+                        end = start;
+                        continue;
+                    }
+                }
+
+                if word_width > width {
+                    for (grapheme_index, grapheme) in word.grapheme_indices(true) {
+                        let grapheme_width = grapheme_width(grapheme);
+                        if column > 0 && column + grapheme_width > width {
+                            lines.push(self.slice(start, end));
+                            start = end;
+                            column = 0;
+                        }
+                        column += grapheme_width;
+                        end = index + grapheme_index + grapheme.len();
+                    }
+                    continue;
+                }
+
+                column += word_width;
+                if !is_whitespace {
+                    end = index + word.len();
+                }
+            }
+            lines.push(self.slice(start, end));
+            line_offset += line.len() + 1;
+        }
+
+        lines
+    }
+
     /// Render the styled string. We use the insertion point to render around so that
     /// we can properly write out the styled string to the screen and find the correct
     /// place to put the cursor. This assumes a logic that prints the first part of the
@@ -156,6 +263,25 @@ impl StyledText {
     }
 }
 
+// The runs of whitespace and of the other characters of the line, with their byte offsets
+fn whitespace_runs(line: &str) -> Vec<(usize, &str)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (index, c) in line.char_indices() {
+        let is_whitespace = c.is_whitespace();
+        if previous.map_or(false, |previous| previous != is_whitespace) {
+            runs.push((start, &line[start..index]));
+            start = index;
+        }
+        previous = Some(is_whitespace);
+    }
+    if start < line.len() {
+        runs.push((start, &line[start..]));
+    }
+    runs
+}
+
 fn render_as_string(
     renderable: &(Style, String),
     prompt_style: &Style,
@@ -172,6 +298,23 @@ fn render_as_string(
     rendered
 }
 
+// The style with the colors and attributes of the layer set over it
+fn layered_style(style: Style, layer: Style) -> Style {
+    Style {
+        foreground: layer.foreground.or(style.foreground),
+        background: layer.background.or(style.background),
+        is_bold: style.is_bold || layer.is_bold,
+        is_dimmed: style.is_dimmed || layer.is_dimmed,
+        is_italic: style.is_italic || layer.is_italic,
+        is_underline: style.is_underline || layer.is_underline,
+        is_blink: style.is_blink || layer.is_blink,
+        is_reverse: style.is_reverse || layer.is_reverse,
+        is_hidden: style.is_hidden || layer.is_hidden,
+        is_strikethrough: style.is_strikethrough || layer.is_strikethrough,
+        prefix_with_reset: style.prefix_with_reset,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use nu_ansi_term::{Color, Style};
@@ -235,6 +378,18 @@ mod test {
         assert_eq!(styled_text.buffer[4], (before_style, "cc".into()));
     }
     #[test]
+    fn style_range_inside_later_part() {
+        let (styled_text_template, before_style, after_style) = get_styled_text_template();
+        let mut styled_text = styled_text_template;
+        styled_text.style_range(4, 5, after_style);
+        assert_eq!(styled_text.buffer[0], (before_style, "aaa".into()));
+        assert_eq!(styled_text.buffer[1], (before_style, "b".into()));
+        assert_eq!(styled_text.buffer[2], (after_style, "b".into()));
+        assert_eq!(styled_text.buffer[3], (before_style, "b".into()));
+        assert_eq!(styled_text.buffer[4], (before_style, "ccc".into()));
+        assert_eq!(styled_text.buffer.len(), 5);
+    }
+    #[test]
     fn style_range_last_letter() {
         let (_, before_style, after_style) = get_styled_text_template();
         let mut styled_text = StyledText {
@@ -255,4 +410,48 @@ mod test {
         assert_eq!(styled_text.buffer[1], (after_style, "d".into()));
         assert_eq!(styled_text.buffer[2], (before_style, "f".into()));
     }
+    #[test]
+    fn layer_range_keeps_the_styles_not_set_by_the_layer() {
+        let keyword = Style::new().fg(Color::Green).bold();
+        let selection = Style::new().on(Color::LightGray);
+        let mut styled_text = StyledText {
+            buffer: vec![(keyword, "let".into()), (Style::new(), " a\nb".into())],
+        };
+        styled_text.layer_range(5, 1, selection);
+        assert_eq!(
+            styled_text.buffer,
+            vec![
+                (keyword, "l".into()),
+                (keyword.on(Color::LightGray), "et".into()),
+                (selection, " a".into()),
+                (Style::new(), "\nb".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_splits_the_lines_and_keeps_the_styles() {
+        let flag = Style::new().fg(Color::Yellow);
+        let styled_text = StyledText {
+            buffer: vec![
+                (Style::new(), "Lists the ".into()),
+                (flag, "--all".into()),
+                (Style::new(), " files\nverylongword".into()),
+            ],
+        };
+        let lines: Vec<_> = styled_text
+            .wrap(11)
+            .into_iter()
+            .map(|line| line.buffer)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                vec![(Style::new(), "Lists the".into())],
+                vec![(flag, "--all".into()), (Style::new(), " files".into())],
+                vec![(Style::new(), "verylongwor".into())],
+                vec![(Style::new(), "d".into())],
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 /// Ensures input uses CRLF line endings.
@@ -65,9 +66,71 @@ pub(crate) fn estimate_single_line_wraps(line: &str, terminal_columns: u16) -> u
     estimated_line_count.saturating_sub(1)
 }
 
+/// Columns between the tab stops of a terminal that were not configured otherwise
+const TERMINAL_TAB_WIDTH: usize = 8;
+
 /// Compute the line width for ANSI escaped text
+///
+/// Tabs advance to the next tab stop of the terminal
 pub(crate) fn line_width(line: &str) -> usize {
-    strip_ansi(line).width()
+    // The tabs are split off first as stripping the escape codes removes them
+    line.split('\t')
+        .enumerate()
+        .fold(0, |width, (index, part)| {
+            let width = if index == 0 {
+                width
+            } else {
+                next_tab_stop(width, TERMINAL_TAB_WIDTH)
+            };
+            width
+                + strip_ansi(part)
+                    .graphemes(true)
+                    .map(grapheme_width)
+                    .sum::<usize>()
+        })
+}
+
+fn next_tab_stop(column: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    column + tab_width - column % tab_width
+}
+
+/// Replaces the tabs with the spaces up to the next tab stop, every `tab_width`
+/// columns from the start of the line.
+///
+/// `column` is the column the text starts at, it is left at the column the text ends at
+/// so that the following text can be expanded.
+pub(crate) fn expand_tabs(text: &str, tab_width: usize, column: &mut usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    for grapheme in text.graphemes(true) {
+        match grapheme {
+            "\t" => {
+                let tab_stop = next_tab_stop(*column, tab_width);
+                expanded.extend(std::iter::repeat(' ').take(tab_stop - *column));
+                *column = tab_stop;
+            }
+            "\n" | "\r\n" => {
+                expanded.push_str(grapheme);
+                *column = 0;
+            }
+            _ => {
+                expanded.push_str(grapheme);
+                *column += grapheme_width(grapheme);
+            }
+        }
+    }
+    expanded
+}
+
+/// Width of a grapheme cluster in the terminal
+///
+/// Emoji joined by zero width joiners (e.g. 👨‍👩‍👧) are rendered as a single emoji
+pub(crate) fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme.contains('\u{200d}') {
+        2
+    } else {
+        grapheme.width()
+    }
 }
 
 #[cfg(test)]
@@ -94,4 +157,51 @@ mod test {
             "Unnecessary allocation"
         )
     }
+
+    #[rstest]
+    #[case("", 0)]
+    #[case("ls -la", 6)]
+    #[case("你好", 4)]
+    #[case("e\u{301}", 1)]
+    #[case("👨\u{200d}👩\u{200d}👧", 2)]
+    #[case("\x1b[32m👨\u{200d}👩\u{200d}👧 你好\x1b[0m", 7)]
+    #[case("abc\td", 9)]
+    #[case("\t\t", 16)]
+    fn line_width_works(#[case] line: &str, #[case] expected: usize) {
+        assert_eq!(line_width(line), expected);
+    }
+
+    #[rstest]
+    #[case("abc\td", 4, "abc d")]
+    #[case("abcd\te", 4, "abcd    e")]
+    #[case("\tx\n\ty", 2, "  x\n  y")]
+    #[case("你\tx", 4, "你  x")]
+    #[case("no tabs", 4, "no tabs")]
+    fn expand_tabs_works(#[case] text: &str, #[case] tab_width: usize, #[case] expected: &str) {
+        assert_eq!(expand_tabs(text, tab_width, &mut 0), expected);
+    }
+
+    #[test]
+    fn expansion_continues_from_the_column() {
+        let mut column = 0;
+        let mut expanded = expand_tabs("ab", 4, &mut column);
+        expanded.push_str(&expand_tabs("c\td", 4, &mut column));
+
+        assert_eq!(expanded, "abc d");
+        assert_eq!(column, 5);
+    }
+
+    #[rstest]
+    #[case("abc\tdefg", 4, 0)]
+    #[case("abc\tdefgh", 4, 1)]
+    #[case("abcdefg\th", 4, 1)]
+    fn wrap_estimation_matches_the_expanded_tabs(
+        #[case] line: &str,
+        #[case] tab_width: usize,
+        #[case] wraps: usize,
+    ) {
+        let expanded = expand_tabs(line, tab_width, &mut 0);
+
+        assert_eq!(estimate_single_line_wraps(&expanded, 8), wraps);
+    }
 }
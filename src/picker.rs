@@ -0,0 +1,67 @@
+use std::io;
+
+use crate::{
+    default_emacs_keybindings, Completer, Emacs, KeyCode, KeyModifiers, ListMenu, Prompt, Reedline,
+    ReedlineEvent, ReedlineMenu, Suggestion,
+};
+
+// Name of the menu showing the items of the picker
+pub(crate) const PICKER_MENU: &str = "picker";
+
+/// Lets the user pick one of the `items` in a [`ListMenu`], like a fuzzy finder.
+///
+/// The typed text filters the items with a fuzzy match, the arrow keys move the
+/// selection and enter returns the selected item. Escape and `Ctrl-C` cancel the
+/// pick and return `None`.
+///
+/// ```no_run
+/// use reedline::{pick, DefaultPrompt, Span, Suggestion};
+///
+/// let items = ["main", "develop", "release"]
+///     .iter()
+///     .map(|branch| Suggestion::new(*branch, Span::default()))
+///     .collect();
+///
+/// if let Some(branch) = pick(items, &DefaultPrompt::default())? {
+///     println!("Checking out {}", branch.value);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn pick(items: Vec<Suggestion>, prompt: &dyn Prompt) -> io::Result<Option<Suggestion>> {
+    picker(items).read_pick(prompt, PICKER_MENU)
+}
+
+// Line editor whose buffer filters the items in the menu of the picker
+pub(crate) fn picker(items: Vec<Suggestion>) -> Reedline {
+    let menu = ListMenu::default()
+        .with_name(PICKER_MENU)
+        .with_only_buffer_difference(false)
+        .with_fuzzy_matching(true);
+
+    // The emacs bindings copy the selection with Ctrl-C and open the history search
+    // and the external editor, which have no use in a picker
+    let mut keybindings = default_emacs_keybindings();
+    keybindings.add_binding(
+        KeyModifiers::CONTROL,
+        KeyCode::Char('c'),
+        ReedlineEvent::CtrlC,
+    );
+    keybindings.remove_binding(KeyModifiers::CONTROL, KeyCode::Char('r'));
+    keybindings.remove_binding(KeyModifiers::CONTROL, KeyCode::Char('o'));
+
+    Reedline::create()
+        .with_completer(Box::new(ItemCompleter { items }))
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)))
+        .with_edit_mode(Box::new(Emacs::new(keybindings)))
+}
+
+// Completer returning all the items, the menu filters them with the typed text
+struct ItemCompleter {
+    items: Vec<Suggestion>,
+}
+
+impl Completer for ItemCompleter {
+    fn complete(&mut self, _line: &str, _pos: usize) -> Vec<Suggestion> {
+        self.items.clone()
+    }
+}
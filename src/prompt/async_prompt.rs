@@ -0,0 +1,57 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{utils::polling::poll_once, Prompt};
+
+/// Future returned by an [`AsyncPrompt`] that resolves to the complete prompt
+pub type PromptFuture = Pin<Box<dyn Future<Output = Box<dyn Prompt>> + Send>>;
+
+/// A trait that defines how to compute an expensive prompt without blocking the editor,
+/// e.g. a prompt showing the git status.
+///
+/// [`Reedline`](crate::Reedline) requests a new future for every call to
+/// [`Reedline::read_line`](crate::Reedline::read_line) and shows the prompt given
+/// to `read_line` as placeholder until the future resolves. The line is then
+/// repainted with the computed prompt, keeping the buffer and the cursor position
+/// the user is editing.
+///
+/// The future is polled and cancelled like the one of an
+/// [`AsyncCompleter`](crate::AsyncCompleter): if `read_line` returns before it
+/// resolves, the submitted line keeps the placeholder and the future is dropped.
+pub trait AsyncPrompt: Send {
+    /// Starts computing the prompt for the next line
+    fn prompt(&mut self) -> PromptFuture;
+}
+
+// Prompt future of the current line, polled by the engine while it waits for events
+pub(crate) struct PendingPrompt {
+    future: PromptFuture,
+}
+
+impl PendingPrompt {
+    pub fn new(future: PromptFuture) -> Self {
+        Self { future }
+    }
+
+    pub fn poll(&mut self) -> Option<Box<dyn Prompt>> {
+        poll_once(self.future.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::polling::Delayed, DefaultPrompt, DefaultPromptSegment};
+
+    #[test]
+    fn pending_prompt_resolves_once_ready() {
+        let prompt: Box<dyn Prompt> = Box::new(DefaultPrompt::new(
+            DefaultPromptSegment::Basic("main".to_string()),
+            DefaultPromptSegment::Empty,
+        ));
+        let mut pending = PendingPrompt::new(Box::pin(Delayed::new(1, prompt)));
+
+        assert!(pending.poll().is_none());
+        let prompt = pending.poll().expect("prompt is ready");
+        assert_eq!(prompt.render_prompt_left(), "main");
+    }
+}
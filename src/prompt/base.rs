@@ -43,7 +43,7 @@ impl PromptHistorySearch {
 }
 
 /// Modes that the prompt can be in
-#[derive(Serialize, Deserialize, Clone, Debug, EnumIter)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, EnumIter)]
 pub enum PromptEditMode {
     /// The default mode
     Default,
@@ -59,7 +59,7 @@ pub enum PromptEditMode {
 }
 
 /// The vi-specific modes that the prompt can be in
-#[derive(Serialize, Deserialize, Clone, Debug, EnumIter, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, EnumIter, Default)]
 pub enum PromptViMode {
     /// The default mode
     #[default]
@@ -86,8 +86,11 @@ impl Display for PromptEditMode {
 pub trait Prompt: Send {
     /// Provide content of the left full prompt
     fn render_prompt_left(&self) -> Cow<str>;
-    /// Provide content of the right full prompt
-    fn render_prompt_right(&self) -> Cow<str>;
+    /// Provide content of the right full prompt, aligned to the right edge of the first
+    /// prompt line and hidden while the input would overlap it. Empty by default
+    fn render_prompt_right(&self) -> Cow<str> {
+        Cow::Borrowed("")
+    }
     /// Render the prompt indicator (Last part of the prompt that changes based on the editor mode)
     fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<str>;
     /// Indicator to show before explicit new lines
@@ -118,4 +121,12 @@ pub trait Prompt: Send {
     fn right_prompt_on_last_line(&self) -> bool {
         false
     }
+
+    /// Compact prompt that replaces the prompt of a line once it is submitted, to keep
+    /// the scrollback short with tall prompts. Without it the prompt stays unchanged.
+    ///
+    /// A prompt set with [`crate::Reedline::with_transient_prompt`] takes precedence.
+    fn render_prompt_transient(&self) -> Option<Cow<str>> {
+        None
+    }
 }
@@ -1,8 +1,13 @@
+mod async_prompt;
 mod base;
 mod default;
+mod transient;
 
+pub(crate) use async_prompt::PendingPrompt;
+pub use async_prompt::{AsyncPrompt, PromptFuture};
 pub use base::{
     Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
 };
 
 pub use default::{DefaultPrompt, DefaultPromptSegment};
+pub(crate) use transient::CollapsedPrompt;
@@ -0,0 +1,57 @@
+use {
+    super::{Prompt, PromptEditMode, PromptHistorySearch},
+    crossterm::style::Color,
+    std::borrow::Cow,
+};
+
+/// Prompt painted in place of the prompt of a submitted line, showing the compact
+/// form of [`Prompt::render_prompt_transient`] without right prompt and indicator
+pub(crate) struct CollapsedPrompt {
+    prompt: String,
+    multiline_indicator: String,
+    prompt_color: Color,
+    multiline_color: nu_ansi_term::Color,
+}
+
+impl CollapsedPrompt {
+    /// Collapse the prompt if it provides a transient form
+    pub(crate) fn from_prompt(prompt: &dyn Prompt) -> Option<Self> {
+        prompt
+            .render_prompt_transient()
+            .map(|transient| CollapsedPrompt {
+                prompt: transient.into_owned(),
+                multiline_indicator: prompt.render_prompt_multiline_indicator().into_owned(),
+                prompt_color: prompt.get_prompt_color(),
+                multiline_color: prompt.get_prompt_multiline_color(),
+            })
+    }
+}
+
+impl Prompt for CollapsedPrompt {
+    fn render_prompt_left(&self) -> Cow<str> {
+        Cow::Borrowed(&self.prompt)
+    }
+
+    fn render_prompt_indicator(&self, _prompt_mode: PromptEditMode) -> Cow<str> {
+        Cow::Borrowed("")
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<str> {
+        Cow::Borrowed(&self.multiline_indicator)
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        _history_search: PromptHistorySearch,
+    ) -> Cow<str> {
+        Cow::Borrowed("")
+    }
+
+    fn get_prompt_color(&self) -> Color {
+        self.prompt_color
+    }
+
+    fn get_prompt_multiline_color(&self) -> nu_ansi_term::Color {
+        self.multiline_color
+    }
+}
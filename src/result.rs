@@ -10,6 +10,11 @@ pub enum ReedlineErrorVariants {
     #[error("error within history database: {0}")]
     HistoryDatabaseError(String),
 
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    /// Regular expression of a history search that failed to compile
+    #[error("invalid regular expression in history search: {0}")]
+    InvalidSearchRegex(String),
+
     /// Error within history
     #[error("error within history: {0}")]
     OtherHistoryError(&'static str),
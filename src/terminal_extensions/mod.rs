@@ -1,5 +1,7 @@
 pub(crate) mod bracketed_paste;
 pub(crate) mod kitty;
+pub(crate) mod mouse;
+pub(crate) mod semantic_prompt;
 
 /// Return if the terminal supports the kitty keyboard enhancement protocol
 ///
@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// Markers of the OSC 133 semantic prompt sequences, used by terminals to find the
+/// prompts, the commands and their output, e.g. to jump between commands
+///
+/// The markers are written by the painter and take no columns on the screen.
+///
+/// Read more: <https://gitlab.freedesktop.org/Per_Bothner/specifications/blob/master/proposals/semantic-prompts.md>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SemanticPromptMarker {
+    /// `A`: the prompt starts
+    PromptStart,
+    /// `B`: the prompt ends and the command input starts
+    CommandStart,
+    /// `C`: the command is submitted and its output starts
+    OutputStart,
+    /// `D`: the command finished with the exit code
+    CommandFinished(i32),
+}
+
+impl fmt::Display for SemanticPromptMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticPromptMarker::PromptStart => write!(f, "\x1b]133;A\x1b\\"),
+            SemanticPromptMarker::CommandStart => write!(f, "\x1b]133;B\x1b\\"),
+            SemanticPromptMarker::OutputStart => write!(f, "\x1b]133;C\x1b\\"),
+            SemanticPromptMarker::CommandFinished(exit_code) => {
+                write!(f, "\x1b]133;D;{exit_code}\x1b\\")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painting::line_width;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(SemanticPromptMarker::PromptStart, "\x1b]133;A\x1b\\")]
+    #[case(SemanticPromptMarker::CommandFinished(127), "\x1b]133;D;127\x1b\\")]
+    fn markers_are_osc_133_sequences(#[case] marker: SemanticPromptMarker, #[case] text: &str) {
+        assert_eq!(marker.to_string(), text);
+    }
+
+    #[test]
+    fn markers_take_no_columns() {
+        let prompt = format!(
+            "{}~> {}ls",
+            SemanticPromptMarker::PromptStart,
+            SemanticPromptMarker::CommandStart
+        );
+
+        assert_eq!(line_width(&prompt), 5);
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod polling;
 mod query;
 pub(crate) mod text_manipulation;
 
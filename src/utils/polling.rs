@@ -0,0 +1,77 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+// The futures of the async completers, validators and prompts are polled by the engine
+// while it waits for events, so their waker has nothing to do
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    // Safe because the vtable functions never access the data pointer
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
+
+/// Polls the future once, returning its output if it is ready
+pub(crate) fn poll_once<F: Future + ?Sized>(future: Pin<&mut F>) -> Option<F::Output> {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+
+    match future.poll(&mut context) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Future that is ready after being polled the given number of times, counting its
+/// drops to check the cancellations
+#[cfg(test)]
+pub(crate) struct Delayed<T> {
+    polls: usize,
+    value: Option<T>,
+    drops: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(test)]
+impl<T> Delayed<T> {
+    pub fn new(polls: usize, value: T) -> Self {
+        Self {
+            polls,
+            value: Some(value),
+            drops: std::sync::Arc::default(),
+        }
+    }
+
+    /// Counts the drops of the future in `drops`
+    pub fn with_drops(mut self, drops: std::sync::Arc<std::sync::atomic::AtomicUsize>) -> Self {
+        self.drops = drops;
+        self
+    }
+}
+
+#[cfg(test)]
+impl<T: Unpin> Future for Delayed<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.polls == 0 {
+            Poll::Ready(self.value.take().expect("the future was already ready"))
+        } else {
+            self.polls -= 1;
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T> Drop for Delayed<T> {
+    fn drop(&mut self) {
+        self.drops.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
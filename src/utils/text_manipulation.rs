@@ -1,3 +1,4 @@
+use crate::TrimMode;
 use unicode_segmentation::UnicodeSegmentation;
 
 pub fn remove_last_grapheme(string: &str) -> &str {
@@ -10,10 +11,85 @@ pub fn remove_last_grapheme(string: &str) -> &str {
     }
 }
 
+/// Normalizes the line endings of pasted text to `\n` and strips the control
+/// characters (including newlines) surrounding it
+pub fn clean_pasted_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .trim_matches(char::is_control)
+        .to_string()
+}
+
+/// Replaces every grapheme of the text with the mask, without a mask nothing is left
+pub fn mask_text(text: &str, mask: Option<char>) -> String {
+    mask.map_or_else(String::new, |mask| {
+        text.graphemes(true).map(|_| mask).collect()
+    })
+}
+
+/// Removes the whitespace of the line according to the mode. The whitespace escaped by
+/// a backslash is kept
+pub fn trim_line(line: &str, mode: TrimMode) -> String {
+    let mut trimmed = match mode {
+        TrimMode::None => return line.to_string(),
+        TrimMode::Trailing => line.to_string(),
+        TrimMode::Both => line.trim_start().to_string(),
+        TrimMode::Collapse => collapse_blanks(line.trim_start()),
+    };
+
+    let end = trimmed.trim_end().len();
+    let escaped = trimmed[..end]
+        .chars()
+        .rev()
+        .take_while(|c| *c == '\\')
+        .count()
+        % 2
+        == 1;
+    let keep = match trimmed[end..].chars().next() {
+        Some(c) if escaped => end + c.len_utf8(),
+        _ => end,
+    };
+    trimmed.truncate(keep);
+
+    trimmed
+}
+
+// Replaces the runs of spaces and tabs by a single space, keeping the escaped ones
+fn collapse_blanks(line: &str) -> String {
+    let mut collapsed = String::with_capacity(line.len());
+    let mut backslashes = 0;
+    let mut after_blank = false;
+    for c in line.chars() {
+        let blank = c == ' ' || c == '\t';
+        if blank && backslashes % 2 == 1 {
+            collapsed.push(c);
+        } else if blank {
+            if !after_blank {
+                collapsed.push(' ');
+            }
+        } else {
+            collapsed.push(c);
+        }
+        after_blank = blank && backslashes % 2 == 0;
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+    }
+
+    collapsed
+}
+
+/// Overwrites the contents of the string in place before clearing it
+pub fn zeroize(string: &mut String) {
+    let len = string.len();
+    string.clear();
+    string.extend(std::iter::repeat('\0').take(len));
+    string.clear();
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
     fn remove_last_char_works_with_empty_string() {
@@ -35,4 +111,49 @@ mod test {
 
         assert_eq!(remove_last_grapheme(string), "this is a 😞");
     }
+
+    #[test]
+    fn clean_pasted_text_keeps_inner_newlines() {
+        let text = "\x1b\techo a\r\necho\tb\r\n\n";
+
+        assert_eq!(clean_pasted_text(text), "echo a\necho\tb");
+    }
+
+    #[rstest]
+    #[case("", Some('*'), "")]
+    #[case("secret", Some('*'), "******")]
+    #[case("a😞b", Some('•'), "•••")]
+    #[case("secret", None, "")]
+    fn mask_text_replaces_graphemes(
+        #[case] text: &str,
+        #[case] mask: Option<char>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(mask_text(text, mask), expected);
+    }
+
+    #[rstest]
+    #[case("ls   ", TrimMode::None, "ls   ")]
+    #[case("  ls   ", TrimMode::Trailing, "  ls")]
+    #[case("  ls -a\t\n", TrimMode::Both, "ls -a")]
+    #[case("  ls  -a \t b  ", TrimMode::Collapse, "ls -a b")]
+    #[case("echo a\\", TrimMode::Trailing, "echo a\\")]
+    #[case("echo a\\  ", TrimMode::Trailing, "echo a\\ ")]
+    #[case("echo a\\\\  ", TrimMode::Trailing, "echo a\\\\")]
+    #[case("cd my\\  dir", TrimMode::Collapse, "cd my\\  dir")]
+    #[case("   ", TrimMode::Both, "")]
+    fn trim_line_works(#[case] line: &str, #[case] mode: TrimMode, #[case] expected: &str) {
+        assert_eq!(trim_line(line, mode), expected);
+    }
+
+    #[test]
+    fn zeroize_keeps_the_allocation() {
+        let mut string = String::from("secret");
+        let capacity = string.capacity();
+
+        zeroize(&mut string);
+
+        assert!(string.is_empty());
+        assert_eq!(string.capacity(), capacity);
+    }
 }
@@ -0,0 +1,104 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{utils::polling::poll_once, ValidationResult, Validator};
+
+/// Future returned by an [`AsyncValidator`] that resolves to the validation of the line
+pub type ValidationFuture = Pin<Box<dyn Future<Output = ValidationResult> + Send>>;
+
+/// A trait that defines how to validate the input without blocking the editor, e.g. by
+/// parsing it in an external process.
+///
+/// The validation starts when the user presses enter. While the future is pending the
+/// line stays in the editor as if it was incomplete. It is submitted once the future
+/// resolves to [`ValidationResult::Complete`], for [`ValidationResult::Incomplete`] a
+/// new line is started, like with a [`Validator`].
+///
+/// Enter is handled by an open menu to accept its selection, so the validation never
+/// runs while a menu is open. A menu opened during the validation cancels it.
+///
+/// The future is polled and cancelled like the one of an
+/// [`AsyncCompleter`](crate::AsyncCompleter): editing the line before it resolves drops
+/// it, and the line is only validated again on the next enter.
+pub trait AsyncValidator: Send {
+    /// Starts validating the line
+    fn validate(&mut self, line: &str) -> ValidationFuture;
+
+    /// The indentation of the line continuing an incomplete input, see
+    /// [`Validator::indent_for`]
+    fn indent_for(&self, #[allow(unused_variables)] line: &str) -> String {
+        String::new()
+    }
+}
+
+/// Bridge that lets a synchronous [`Validator`] be used as an [`AsyncValidator`].
+///
+/// The line is validated when the future is requested, so the validator still
+/// blocks the editor while it runs.
+pub struct BlockingValidator<T: Validator> {
+    validator: T,
+}
+
+impl<T: Validator> BlockingValidator<T> {
+    /// Wraps the synchronous validator
+    pub fn new(validator: T) -> Self {
+        Self { validator }
+    }
+}
+
+impl<T: Validator> AsyncValidator for BlockingValidator<T> {
+    fn validate(&mut self, line: &str) -> ValidationFuture {
+        Box::pin(std::future::ready(self.validator.validate(line)))
+    }
+
+    fn indent_for(&self, line: &str) -> String {
+        self.validator.indent_for(line)
+    }
+}
+
+// Validation of a submitted line, polled by the engine while it waits for events
+pub(crate) struct PendingValidation {
+    line: String,
+    future: ValidationFuture,
+}
+
+impl PendingValidation {
+    pub fn new(line: String, future: ValidationFuture) -> Self {
+        Self { line, future }
+    }
+
+    /// The line that is validated
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn poll(&mut self) -> Option<ValidationResult> {
+        poll_once(self.future.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::polling::Delayed, DefaultValidator};
+
+    #[test]
+    fn pending_validation_resolves_once_ready() {
+        let mut pending = PendingValidation::new(
+            "ls".to_string(),
+            Box::pin(Delayed::new(1, ValidationResult::Complete)),
+        );
+
+        assert!(pending.poll().is_none());
+        assert!(matches!(pending.poll(), Some(ValidationResult::Complete)));
+        assert_eq!(pending.line(), "ls");
+    }
+
+    #[test]
+    fn blocking_validator_is_ready_immediately() {
+        let mut validator = BlockingValidator::new(DefaultValidator);
+        let mut pending = PendingValidation::new("(".to_string(), validator.validate("("));
+
+        assert!(matches!(pending.poll(), Some(ValidationResult::Incomplete)));
+        assert_eq!(validator.indent_for("("), "    ");
+    }
+}
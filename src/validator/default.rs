@@ -1,6 +1,8 @@
 use crate::{ValidationResult, Validator};
 
 /// A default validator which checks for mismatched quotes and brackets
+///
+/// Continuation lines are indented by four spaces for every open bracket.
 pub struct DefaultValidator;
 
 impl Validator for DefaultValidator {
@@ -11,9 +13,18 @@ impl Validator for DefaultValidator {
             ValidationResult::Complete
         }
     }
+
+    fn indent_for(&self, line: &str) -> String {
+        "    ".repeat(open_brackets(line))
+    }
 }
 
 fn incomplete_brackets(line: &str) -> bool {
+    open_brackets(line) > 0
+}
+
+// Number of brackets that are still open at the end of the line
+fn open_brackets(line: &str) -> usize {
     let mut balance: Vec<char> = Vec::new();
 
     for c in line.chars() {
@@ -32,7 +43,7 @@ fn incomplete_brackets(line: &str) -> bool {
         }
     }
 
-    !balance.is_empty()
+    balance.len()
 }
 
 #[cfg(test)]
@@ -50,4 +61,13 @@ mod test {
 
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case("ls", "")]
+    #[case("if x {", "    ")]
+    #[case("if x {\n    if y {", "        ")]
+    #[case("if x {\n    [1, 2]\n}", "")]
+    fn test_indent_for_open_brackets(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(DefaultValidator.indent_for(input), expected);
+    }
 }
@@ -1,4 +1,7 @@
+mod async_validator;
 mod default;
+pub(crate) use async_validator::PendingValidation;
+pub use async_validator::{AsyncValidator, BlockingValidator, ValidationFuture};
 pub use default::DefaultValidator;
 
 /// The syntax validation trait. Implementers of this trait will check to see if the current input
@@ -6,6 +9,14 @@ pub use default::DefaultValidator;
 pub trait Validator: Send {
     /// The action that will handle the current buffer as a line and return the corresponding validation
     fn validate(&self, line: &str) -> ValidationResult;
+
+    /// The indentation of the line continuing an [`ValidationResult::Incomplete`] input
+    ///
+    /// Only used with [`Reedline::with_auto_indent`](crate::Reedline::with_auto_indent),
+    /// by default no indentation is inserted
+    fn indent_for(&self, #[allow(unused_variables)] line: &str) -> String {
+        String::new()
+    }
 }
 
 #[derive(Clone, Copy)]